@@ -0,0 +1,585 @@
+//! `AbortController` and `AbortSignal` for cancelling asynchronous operations.
+//!
+//! [`install`] adds the `AbortController` and `AbortSignal` classes to the global object of a
+//! context. An [`AbortSignal`] can be taken as an argument by Rust host functions, which can use
+//! [`AbortSignal::run`] to stop a future once JavaScript calls `controller.abort()`.
+//!
+//! ```
+//! # use rquickjs::{AsyncRuntime, AsyncContext, CatchResultExt, Function, Promise, Result};
+//! # use rquickjs::{abort::{self, AbortSignal}, function::Async};
+//! # use std::future::pending;
+//! # async fn run() {
+//! let rt = AsyncRuntime::new().unwrap();
+//! let ctx = AsyncContext::full(&rt).await.unwrap();
+//!
+//! async fn forever(signal: AbortSignal<'_>) -> Result<()> {
+//!     signal.run(pending()).await
+//! }
+//!
+//! ctx.with(|ctx| {
+//!     abort::install(&ctx).unwrap();
+//!     let forever = Function::new(ctx.clone(), Async(forever)).unwrap();
+//!     ctx.globals().set("forever", forever).unwrap();
+//!     ctx.eval::<(), _>(r#"
+//!         const controller = new AbortController();
+//!         globalThis.result = forever(controller.signal).catch((e) => e.name);
+//!         controller.abort();
+//!     "#)
+//!     .unwrap();
+//! })
+//! .await;
+//!
+//! rt.idle().await;
+//! ctx.with(|ctx| {
+//!     let result: Promise = ctx.globals().get("result").unwrap();
+//!     assert_eq!(result.finish::<String>().unwrap(), "AbortError");
+//! })
+//! .await;
+//! # }
+//! ```
+
+use alloc::vec::Vec;
+use core::{
+    future::{poll_fn, Future},
+    mem,
+    pin::{pin, Pin},
+    task::{Context, Poll, Waker},
+};
+
+use crate::{
+    class::{JsClass, Trace, Tracer, Writable},
+    convert::Coerced,
+    function::{Opt, This},
+    object::Accessor,
+    value::Constructor,
+    Class, Ctx, Error, Exception, FromJs, Function, IntoJs, JsLifetime, Object, Result, StdString,
+    Value,
+};
+
+/// Add the `AbortController` and `AbortSignal` classes to the global object.
+pub fn install(ctx: &Ctx<'_>) -> Result<()> {
+    let globals = ctx.globals();
+    Class::<AbortController>::define(&globals)?;
+    Class::<SignalState>::define(&globals)?;
+    Ok(())
+}
+
+/// Create the default abort reason, an error with the name `AbortError`.
+fn abort_error<'js>(ctx: &Ctx<'js>) -> Result<Value<'js>> {
+    let error = Exception::from_message(ctx.clone(), "This operation was aborted")?;
+    error.set("name", "AbortError")?;
+    Ok(error.into_value())
+}
+
+/// Report the pending exception by rethrowing it from a job.
+fn report<'js>(ctx: &Ctx<'js>) -> Result<()> {
+    let exception = ctx.catch();
+    let rethrow = Function::new(ctx.clone(), |ctx: Ctx<'js>, exception: Value<'js>| {
+        Err::<(), _>(ctx.throw(exception))
+    })?;
+    rethrow.defer((exception,))
+}
+
+/// A controller which aborts its [`AbortSignal`], the `AbortController` class in JavaScript.
+pub struct AbortController<'js> {
+    signal: AbortSignal<'js>,
+}
+
+impl<'js> AbortController<'js> {
+    /// Create a new controller with a signal which is not yet aborted.
+    pub fn new(ctx: &Ctx<'js>) -> Result<Self> {
+        let state = SignalState {
+            reason: None,
+            onabort: None,
+            listeners: Vec::new(),
+            next_waker: 0,
+            wakers: Vec::new(),
+        };
+        Ok(AbortController {
+            signal: AbortSignal(Class::instance(ctx.clone(), state)?),
+        })
+    }
+
+    /// Returns the signal of this controller.
+    pub fn signal(&self) -> &AbortSignal<'js> {
+        &self.signal
+    }
+
+    /// Abort the signal of this controller, see [`AbortSignal::abort`].
+    pub fn abort(&self, reason: Option<Value<'js>>) -> Result<()> {
+        self.signal.abort(reason)
+    }
+}
+
+impl<'js> Trace<'js> for AbortController<'js> {
+    fn trace<'a>(&self, tracer: Tracer<'a, 'js>) {
+        self.signal.0.trace(tracer)
+    }
+}
+
+unsafe impl<'js> JsLifetime<'js> for AbortController<'js> {
+    type Changed<'to> = AbortController<'to>;
+}
+
+impl<'js> JsClass<'js> for AbortController<'js> {
+    const NAME: &'static str = "AbortController";
+
+    type Mutable = Writable;
+
+    fn prototype(ctx: &Ctx<'js>) -> Result<Option<Object<'js>>> {
+        fn signal<'js>(this: This<Class<'js, AbortController<'js>>>) -> AbortSignal<'js> {
+            this.borrow().signal.clone()
+        }
+
+        fn abort<'js>(
+            this: This<Class<'js, AbortController<'js>>>,
+            reason: Opt<Value<'js>>,
+        ) -> Result<()> {
+            let signal = this.borrow().signal.clone();
+            signal.abort(reason.0.filter(|x| !x.is_undefined()))
+        }
+
+        let proto = Object::new(ctx.clone())?;
+        proto.prop("signal", Accessor::new_get(signal))?;
+        proto.set(
+            "abort",
+            Function::new(ctx.clone(), abort)?.with_name("abort")?,
+        )?;
+        Ok(Some(proto))
+    }
+
+    fn constructor(ctx: &Ctx<'js>) -> Result<Option<Constructor<'js>>> {
+        let constructor =
+            Constructor::new_class::<AbortController, _, _>(ctx.clone(), |ctx: Ctx<'js>| {
+                Class::instance(ctx.clone(), AbortController::new(&ctx)?)
+            })?;
+        Ok(Some(constructor))
+    }
+}
+
+struct SignalState<'js> {
+    reason: Option<Value<'js>>,
+    onabort: Option<Function<'js>>,
+    listeners: Vec<Function<'js>>,
+    next_waker: u64,
+    /// The wakers of pending [`Aborted`] futures, by the id of the future.
+    wakers: Vec<(u64, Waker)>,
+}
+
+impl<'js> Trace<'js> for SignalState<'js> {
+    fn trace<'a>(&self, tracer: Tracer<'a, 'js>) {
+        self.reason.trace(tracer);
+        self.onabort.trace(tracer);
+        self.listeners.trace(tracer);
+    }
+}
+
+unsafe impl<'js> JsLifetime<'js> for SignalState<'js> {
+    type Changed<'to> = SignalState<'to>;
+}
+
+impl<'js> JsClass<'js> for SignalState<'js> {
+    const NAME: &'static str = "AbortSignal";
+
+    type Mutable = Writable;
+
+    fn prototype(ctx: &Ctx<'js>) -> Result<Option<Object<'js>>> {
+        fn aborted(this: This<AbortSignal<'_>>) -> bool {
+            this.is_aborted()
+        }
+
+        fn reason<'js>(this: This<AbortSignal<'js>>) -> Option<Value<'js>> {
+            this.reason()
+        }
+
+        fn get_onabort<'js>(this: This<AbortSignal<'js>>) -> Option<Function<'js>> {
+            this.0 .0.borrow().onabort.clone()
+        }
+
+        fn set_onabort<'js>(this: This<AbortSignal<'js>>, onabort: Option<Function<'js>>) {
+            this.0 .0.borrow_mut().onabort = onabort;
+        }
+
+        fn throw_if_aborted<'js>(ctx: Ctx<'js>, this: This<AbortSignal<'js>>) -> Result<()> {
+            match this.reason() {
+                Some(reason) => Err(ctx.throw(reason)),
+                None => Ok(()),
+            }
+        }
+
+        fn add_event_listener<'js>(
+            this: This<AbortSignal<'js>>,
+            kind: Coerced<StdString>,
+            listener: Option<Function<'js>>,
+        ) {
+            let mut state = this.0 .0.borrow_mut();
+            if let Some(listener) = listener.filter(|_| kind.0 == "abort") {
+                if !state.listeners.contains(&listener) {
+                    state.listeners.push(listener);
+                }
+            }
+        }
+
+        fn remove_event_listener<'js>(
+            this: This<AbortSignal<'js>>,
+            kind: Coerced<StdString>,
+            listener: Option<Function<'js>>,
+        ) {
+            if let Some(listener) = listener.filter(|_| kind.0 == "abort") {
+                this.0 .0.borrow_mut().listeners.retain(|x| *x != listener);
+            }
+        }
+
+        let proto = Object::new(ctx.clone())?;
+        proto.prop("aborted", Accessor::new_get(aborted))?;
+        proto.prop("reason", Accessor::new_get(reason))?;
+        proto.prop("onabort", Accessor::new(get_onabort, set_onabort))?;
+        proto.set(
+            "throwIfAborted",
+            Function::new(ctx.clone(), throw_if_aborted)?.with_name("throwIfAborted")?,
+        )?;
+        proto.set(
+            "addEventListener",
+            Function::new(ctx.clone(), add_event_listener)?.with_name("addEventListener")?,
+        )?;
+        proto.set(
+            "removeEventListener",
+            Function::new(ctx.clone(), remove_event_listener)?.with_name("removeEventListener")?,
+        )?;
+        Ok(Some(proto))
+    }
+
+    fn constructor(ctx: &Ctx<'js>) -> Result<Option<Constructor<'js>>> {
+        fn abort<'js>(ctx: Ctx<'js>, reason: Opt<Value<'js>>) -> Result<AbortSignal<'js>> {
+            let controller = AbortController::new(&ctx)?;
+            controller.abort(reason.0.filter(|x| !x.is_undefined()))?;
+            Ok(controller.signal)
+        }
+
+        let constructor =
+            Constructor::new_class::<SignalState, _, _>(ctx.clone(), |ctx: Ctx<'js>| {
+                Err::<Value, _>(Exception::throw_type(&ctx, "Illegal constructor"))
+            })?;
+        constructor.set(
+            "abort",
+            Function::new(ctx.clone(), abort)?.with_name("abort")?,
+        )?;
+        Ok(Some(constructor))
+    }
+}
+
+/// A signal which is aborted by its [`AbortController`], the `AbortSignal` class in JavaScript.
+///
+/// Rust host functions can take a signal as an argument to stop their work once it is aborted.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct AbortSignal<'js>(Class<'js, SignalState<'js>>);
+
+impl<'js> AbortSignal<'js> {
+    /// Returns whether the signal was aborted.
+    pub fn is_aborted(&self) -> bool {
+        self.0.borrow().reason.is_some()
+    }
+
+    /// Returns the reason with which the signal was aborted, if it was.
+    pub fn reason(&self) -> Option<Value<'js>> {
+        self.0.borrow().reason.clone()
+    }
+
+    /// Abort the signal.
+    ///
+    /// Without a reason the signal is aborted with an error named `AbortError`. Calls the
+    /// `onabort` handler and the `abort` event listeners of the signal and wakes all futures
+    /// waiting on it. Does nothing if the signal was already aborted.
+    ///
+    /// An exception thrown by a listener does not stop the remaining listeners. Like in
+    /// browsers, it is reported instead: it is rethrown by a job, like an exception thrown by a
+    /// promise reaction.
+    pub fn abort(&self, reason: Option<Value<'js>>) -> Result<()> {
+        let ctx = self.0.ctx();
+        let reason = match reason {
+            Some(reason) => reason,
+            None => abort_error(ctx)?,
+        };
+        let (onabort, listeners) = {
+            let mut state = self.0.borrow_mut();
+            if state.reason.is_some() {
+                return Ok(());
+            }
+            state.reason = Some(reason);
+            mem::take(&mut state.wakers)
+                .into_iter()
+                .for_each(|(_, waker)| waker.wake());
+            (state.onabort.clone(), state.listeners.clone())
+        };
+
+        let event = Object::new(ctx.clone())?;
+        event.set("type", "abort")?;
+        event.set("target", self.clone())?;
+        for listener in onabort.into_iter().chain(listeners) {
+            match listener.call::<_, ()>((This(self.clone()), event.clone())) {
+                Err(Error::Exception) => report(ctx)?,
+                res => res?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns a future which completes with the abort reason once the signal is aborted.
+    pub fn aborted(&self) -> Aborted<'js> {
+        Aborted {
+            signal: self.clone(),
+            waker: None,
+        }
+    }
+
+    /// Run a future until it completes or the signal is aborted.
+    ///
+    /// If the signal is aborted first, the future is dropped and the abort reason is thrown.
+    pub async fn run<F, T>(&self, future: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        let mut future = pin!(future);
+        let mut aborted = self.aborted();
+        poll_fn(|cx| {
+            if let Poll::Ready(reason) = Pin::new(&mut aborted).poll(cx) {
+                return Poll::Ready(Err(self.0.ctx().throw(reason)));
+            }
+            future.as_mut().poll(cx)
+        })
+        .await
+    }
+}
+
+impl<'js> FromJs<'js> for AbortSignal<'js> {
+    fn from_js(ctx: &Ctx<'js>, value: Value<'js>) -> Result<Self> {
+        Class::from_js(ctx, value).map(AbortSignal)
+    }
+}
+
+impl<'js> IntoJs<'js> for AbortSignal<'js> {
+    fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
+        self.0.into_js(ctx)
+    }
+}
+
+/// A future which completes once an [`AbortSignal`] is aborted, see [`AbortSignal::aborted`].
+///
+/// The waker of the future is registered with the signal until the future completes or is
+/// dropped.
+pub struct Aborted<'js> {
+    signal: AbortSignal<'js>,
+    /// The id of the registered waker, once polled.
+    waker: Option<u64>,
+}
+
+impl<'js> Future for Aborted<'js> {
+    type Output = Value<'js>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Value<'js>> {
+        let this = self.get_mut();
+        let mut state = this.signal.0.borrow_mut();
+        if let Some(reason) = &state.reason {
+            return Poll::Ready(reason.clone());
+        }
+        let registered = this
+            .waker
+            .and_then(|id| state.wakers.iter_mut().find(|x| x.0 == id));
+        match registered {
+            Some((_, waker)) => {
+                if !waker.will_wake(cx.waker()) {
+                    *waker = cx.waker().clone();
+                }
+            }
+            None => {
+                let id = state.next_waker;
+                state.next_waker += 1;
+                state.wakers.push((id, cx.waker().clone()));
+                this.waker = Some(id);
+            }
+        }
+        Poll::Pending
+    }
+}
+
+impl Drop for Aborted<'_> {
+    fn drop(&mut self) {
+        if let Some(id) = self.waker {
+            if let Ok(mut state) = self.signal.0.try_borrow_mut() {
+                state.wakers.retain(|x| x.0 != id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::{sync::Arc, task::Wake};
+    use core::{
+        future::Future,
+        pin::Pin,
+        sync::atomic::{AtomicBool, Ordering},
+        task::{Context as TaskContext, Waker},
+    };
+
+    use super::{install, AbortController, AbortSignal};
+    use crate::{
+        function::Async, AsyncContext, AsyncRuntime, Context, Function, Object, Promise, Result,
+        Runtime,
+    };
+
+    #[test]
+    fn controller_and_signal() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            install(&ctx).unwrap();
+            let res: String = ctx
+                .eval(
+                    r#"
+                    const log = [];
+                    const controller = new AbortController();
+                    const signal = controller.signal;
+                    const removed = () => log.push("removed");
+                    signal.onabort = (e) => log.push(`onabort ${e.type} ${e.target === signal}`);
+                    signal.addEventListener("abort", () => log.push("listener"));
+                    signal.addEventListener("abort", removed);
+                    signal.removeEventListener("abort", removed);
+                    log.push(`${signal.aborted}`);
+                    signal.throwIfAborted();
+                    controller.abort();
+                    controller.abort("ignored");
+                    log.push(`${signal.aborted} ${signal.reason.name}`);
+                    try {
+                        signal.throwIfAborted();
+                    } catch (e) {
+                        log.push(`${e === signal.reason} ${e instanceof Error}`);
+                    }
+                    try {
+                        new AbortSignal();
+                    } catch (e) {
+                        log.push(e.name);
+                    }
+                    log.push(AbortSignal.abort("reason").reason);
+                    log.join(", ")
+                "#,
+                )
+                .unwrap();
+            assert_eq!(
+                res,
+                "false, onabort abort true, listener, true AbortError, true true, TypeError, reason"
+            );
+        })
+    }
+
+    #[test]
+    fn throwing_listener() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            install(&ctx).unwrap();
+            let res: String = ctx
+                .eval(
+                    r#"
+                    const log = [];
+                    const controller = new AbortController();
+                    controller.signal.onabort = () => { throw new Error("onabort") };
+                    controller.signal.addEventListener("abort", () => log.push("listener"));
+                    controller.abort();
+                    log.join(", ")
+                "#,
+                )
+                .unwrap();
+            assert_eq!(res, "listener");
+
+            // The exception is rethrown by a job.
+            assert!(ctx.execute_pending_job());
+            let message: String = ctx.catch().get::<Object>().unwrap().get("message").unwrap();
+            assert_eq!(message, "onabort");
+            assert!(!ctx.execute_pending_job());
+        });
+    }
+
+    struct Noop;
+
+    impl Wake for Noop {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    #[test]
+    fn aborted_wakers() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            let waker = Waker::from(Arc::new(Noop));
+            let mut cx = TaskContext::from_waker(&waker);
+            let controller = AbortController::new(&ctx).unwrap();
+            let signal = controller.signal();
+            let wakers = || signal.0.borrow().wakers.len();
+
+            let mut first = signal.aborted();
+            let mut second = signal.aborted();
+            for _ in 0..3 {
+                assert!(Pin::new(&mut first).poll(&mut cx).is_pending());
+            }
+            assert!(Pin::new(&mut second).poll(&mut cx).is_pending());
+            assert_eq!(wakers(), 2);
+            drop(first);
+            assert_eq!(wakers(), 1);
+
+            controller.abort(None).unwrap();
+            assert_eq!(wakers(), 0);
+            assert!(Pin::new(&mut second).poll(&mut cx).is_ready());
+        });
+    }
+
+    static DROPPED: AtomicBool = AtomicBool::new(false);
+
+    struct SetOnDrop;
+
+    impl Drop for SetOnDrop {
+        fn drop(&mut self) {
+            DROPPED.store(true, Ordering::SeqCst);
+        }
+    }
+
+    async fn forever(signal: AbortSignal<'_>) -> Result<()> {
+        signal
+            .run(async {
+                let _guard = SetOnDrop;
+                core::future::pending().await
+            })
+            .await
+    }
+
+    #[tokio::test]
+    async fn abort_host_future() {
+        let rt = AsyncRuntime::new().unwrap();
+        let ctx = AsyncContext::full(&rt).await.unwrap();
+        ctx.with(|ctx| {
+            install(&ctx).unwrap();
+            let forever = Function::new(ctx.clone(), Async(forever)).unwrap();
+            ctx.globals().set("forever", forever).unwrap();
+            let tick = Function::new(ctx.clone(), Async(tokio::task::yield_now)).unwrap();
+            ctx.globals().set("tick", tick).unwrap();
+            ctx.eval::<(), _>(
+                r#"
+                const controller = new AbortController();
+                globalThis.result = forever(controller.signal)
+                    .catch((e) => `${e.name} ${e === controller.signal.reason}`);
+                tick().then(() => controller.abort());
+            "#,
+            )
+            .unwrap();
+        })
+        .await;
+
+        rt.idle().await;
+        assert!(DROPPED.load(Ordering::SeqCst));
+        ctx.with(|ctx| {
+            let result: Promise = ctx.globals().get("result").unwrap();
+            assert_eq!(result.finish::<String>().unwrap(), "AbortError true");
+        })
+        .await;
+    }
+}
@@ -2,11 +2,16 @@
 
 use crate::qjs;
 
+mod account;
 mod rust;
 
 use alloc::boxed::Box;
+use core::ptr::{self, NonNull};
 pub use rust::RustAllocator;
 
+pub(crate) use account::{Account, Accounting};
+use account::{Header, HEADER_SIZE};
+
 /// The allocator interface
 ///
 /// # Safety
@@ -54,8 +59,14 @@ pub unsafe trait Allocator {
 
 type DynAllocator = Box<dyn Allocator>;
 
+/// The allocator together with the memory accounting of the runtime.
+struct AllocatorState {
+    allocator: DynAllocator,
+    accounting: Accounting,
+}
+
 #[derive(Debug)]
-pub(crate) struct AllocatorHolder(*mut DynAllocator);
+pub(crate) struct AllocatorHolder(*mut AllocatorState);
 
 impl Drop for AllocatorHolder {
     fn drop(&mut self) {
@@ -82,11 +93,34 @@ impl AllocatorHolder {
     where
         A: Allocator + 'static,
     {
-        Self(Box::into_raw(Box::new(Box::new(allocator))))
+        Self(Box::into_raw(Box::new(AllocatorState {
+            allocator: Box::new(allocator),
+            accounting: Accounting::default(),
+        })))
+    }
+
+    pub(crate) fn opaque_ptr(&self) -> *mut qjs::c_void {
+        self.0.cast()
+    }
+
+    pub(crate) fn accounting(&self) -> NonNull<Accounting> {
+        unsafe { NonNull::from(&(*self.0).accounting) }
     }
 
-    pub(crate) fn opaque_ptr(&self) -> *mut DynAllocator {
-        self.0
+    /// Attribute a new allocation to the current account and write its header, returns the
+    /// pointer handed out to QuickJS.
+    unsafe fn finish_alloc<A>(account: Option<NonNull<Account>>, ptr: *mut u8) -> *mut qjs::c_void
+    where
+        A: Allocator,
+    {
+        if ptr.is_null() {
+            return ptr::null_mut();
+        }
+        ptr.cast::<Header>().write(Header { account });
+        if let Some(account) = account {
+            Account::add(account, A::usable_size(ptr));
+        }
+        ptr.add(HEADER_SIZE).cast()
     }
 
     unsafe extern "C" fn calloc<A>(
@@ -97,19 +131,40 @@ impl AllocatorHolder {
     where
         A: Allocator,
     {
-        let allocator = &mut *(opaque as *mut DynAllocator);
+        let state = opaque as *mut AllocatorState;
         let rust_size: usize = size.try_into().expect(qjs::SIZE_T_ERROR);
         let rust_count: usize = count.try_into().expect(qjs::SIZE_T_ERROR);
-        allocator.calloc(rust_count, rust_size) as *mut qjs::c_void
+        let Some(total_size) = rust_count
+            .checked_mul(rust_size)
+            .and_then(|x| x.checked_add(HEADER_SIZE))
+        else {
+            return ptr::null_mut();
+        };
+
+        let account = (*state).accounting.current();
+        if account.is_some_and(|x| x.as_ref().exceeds_limit(total_size)) {
+            return ptr::null_mut();
+        }
+        let ptr = (*state).allocator.calloc(1, total_size);
+        Self::finish_alloc::<A>(account, ptr)
     }
 
     unsafe extern "C" fn malloc<A>(opaque: *mut qjs::c_void, size: qjs::size_t) -> *mut qjs::c_void
     where
         A: Allocator,
     {
-        let allocator = &mut *(opaque as *mut DynAllocator);
+        let state = opaque as *mut AllocatorState;
         let rust_size: usize = size.try_into().expect(qjs::SIZE_T_ERROR);
-        allocator.alloc(rust_size) as *mut qjs::c_void
+        let Some(total_size) = rust_size.checked_add(HEADER_SIZE) else {
+            return ptr::null_mut();
+        };
+
+        let account = (*state).accounting.current();
+        if account.is_some_and(|x| x.as_ref().exceeds_limit(total_size)) {
+            return ptr::null_mut();
+        }
+        let ptr = (*state).allocator.alloc(total_size);
+        Self::finish_alloc::<A>(account, ptr)
     }
 
     unsafe extern "C" fn free<A>(opaque: *mut qjs::c_void, ptr: *mut qjs::c_void)
@@ -122,8 +177,12 @@ impl AllocatorHolder {
             return;
         }
 
-        let allocator = &mut *(opaque as *mut DynAllocator);
-        allocator.dealloc(ptr as _);
+        let state = opaque as *mut AllocatorState;
+        let ptr = ptr.cast::<u8>().sub(HEADER_SIZE);
+        if let Some(account) = ptr.cast::<Header>().read().account {
+            Account::remove(account, A::usable_size(ptr));
+        }
+        (*state).allocator.dealloc(ptr);
     }
 
     unsafe extern "C" fn realloc<A>(
@@ -134,9 +193,35 @@ impl AllocatorHolder {
     where
         A: Allocator,
     {
+        if ptr.is_null() {
+            return Self::malloc::<A>(opaque, size);
+        }
+
+        let state = opaque as *mut AllocatorState;
         let rust_size: usize = size.try_into().expect(qjs::SIZE_T_ERROR);
-        let allocator = &mut *(opaque as *mut DynAllocator);
-        allocator.realloc(ptr as _, rust_size) as *mut qjs::c_void
+        let Some(total_size) = rust_size.checked_add(HEADER_SIZE) else {
+            return ptr::null_mut();
+        };
+
+        let ptr = ptr.cast::<u8>().sub(HEADER_SIZE);
+        // The allocation stays attributed to the account it was allocated from.
+        let account = ptr.cast::<Header>().read().account;
+        let old_size = A::usable_size(ptr);
+        if let Some(account) = account {
+            let growth = total_size.saturating_sub(old_size);
+            if account.as_ref().exceeds_limit(growth) {
+                return ptr::null_mut();
+            }
+        }
+
+        let ptr = (*state).allocator.realloc(ptr, total_size);
+        if ptr.is_null() {
+            return ptr::null_mut();
+        }
+        if let Some(account) = account {
+            Account::resize(account, old_size, A::usable_size(ptr));
+        }
+        ptr.add(HEADER_SIZE).cast()
     }
 
     unsafe extern "C" fn malloc_usable_size<A>(ptr: *const qjs::c_void) -> qjs::size_t
@@ -147,6 +232,7 @@ impl AllocatorHolder {
         if ptr.is_null() {
             return 0;
         }
-        A::usable_size(ptr as _).try_into().unwrap()
+        let ptr = ptr.cast::<u8>().sub(HEADER_SIZE).cast_mut();
+        (A::usable_size(ptr) - HEADER_SIZE).try_into().unwrap()
     }
 }
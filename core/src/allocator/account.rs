@@ -0,0 +1,342 @@
+use alloc::boxed::Box;
+use core::{cell::Cell, mem, ptr::NonNull};
+
+use crate::qjs;
+
+/// The header in front of every allocation made through an [`Allocator`](super::Allocator),
+/// recording the account the allocation is attributed to.
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub(crate) struct Header {
+    pub account: Option<NonNull<Account>>,
+}
+
+/// The header must keep the alignment of the allocation, which QuickJS expects to be at least
+/// the alignment of a `u64`.
+pub(crate) const HEADER_SIZE: usize = {
+    let size = mem::size_of::<Header>();
+    let align = mem::align_of::<u64>();
+    if size < align {
+        align
+    } else {
+        size
+    }
+};
+
+/// The memory attributed to a single context.
+///
+/// An account is kept alive by the allocations attributed to it. Since the context itself is
+/// allocated from its own account, the account lives at least as long as the context.
+///
+/// Jobs allocate from a temporary account, which is merged into the account of their context once
+/// the job ran and from then on forwards the size of its allocations to that account.
+pub(crate) struct Account {
+    /// The amount of bytes currently allocated.
+    size: Cell<usize>,
+    /// The maximum amount of bytes, 0 if unlimited.
+    limit: Cell<usize>,
+    /// The number of live allocations plus any temporary references.
+    refs: Cell<usize>,
+    /// The account this account was merged into, holding a reference to it.
+    merged: Cell<Option<NonNull<Account>>>,
+}
+
+impl Account {
+    /// Create a new account with a single temporary reference, which must be released with
+    /// [`Account::release`].
+    pub fn new() -> NonNull<Account> {
+        let account = Box::new(Account {
+            size: Cell::new(0),
+            limit: Cell::new(0),
+            refs: Cell::new(1),
+            merged: Cell::new(None),
+        });
+        unsafe { NonNull::new_unchecked(Box::into_raw(account)) }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size.get()
+    }
+
+    /// The account the size of allocations is attributed to.
+    fn target(&self) -> &Account {
+        match self.merged.get() {
+            Some(merged) => unsafe { &*merged.as_ptr() },
+            None => self,
+        }
+    }
+
+    pub fn set_limit(&self, limit: usize) {
+        self.limit.set(limit)
+    }
+
+    /// Returns whether allocating `size` more bytes would exceed the limit of the account.
+    pub fn exceeds_limit(&self, size: usize) -> bool {
+        let target = self.target();
+        let limit = target.limit.get();
+        limit != 0 && target.size.get().saturating_add(size) > limit
+    }
+
+    /// Attribute a new allocation of `size` bytes to the account.
+    pub unsafe fn add(this: NonNull<Account>, size: usize) {
+        let this = this.as_ref();
+        let target = this.target();
+        target.size.set(target.size.get() + size);
+        this.refs.set(this.refs.get() + 1);
+    }
+
+    /// Change the size of an allocation attributed to the account.
+    pub unsafe fn resize(this: NonNull<Account>, old_size: usize, new_size: usize) {
+        let target = this.as_ref().target();
+        target.size.set(target.size.get() - old_size + new_size);
+    }
+
+    /// Remove an allocation of `size` bytes from the account, freeing the account if it was the
+    /// last reference.
+    pub unsafe fn remove(this: NonNull<Account>, size: usize) {
+        {
+            let target = this.as_ref().target();
+            target.size.set(target.size.get() - size);
+        }
+        Self::release(this)
+    }
+
+    /// Move the size of the allocations attributed to `this` to the account `into`, which must not
+    /// have been merged itself.
+    pub unsafe fn merge(this: NonNull<Account>, into: NonNull<Account>) {
+        let (this, into_ref) = (this.as_ref(), into.as_ref());
+        debug_assert!(this.merged.get().is_none() && into_ref.merged.get().is_none());
+        into_ref
+            .size
+            .set(into_ref.size.get() + this.size.replace(0));
+        Self::retain(into);
+        this.merged.set(Some(into));
+    }
+
+    /// Take an additional reference to the account.
+    pub unsafe fn retain(this: NonNull<Account>) {
+        let this = this.as_ref();
+        this.refs.set(this.refs.get() + 1);
+    }
+
+    /// Release a reference, freeing the account if it was the last.
+    pub unsafe fn release(this: NonNull<Account>) {
+        let refs = this.as_ref().refs.get() - 1;
+        this.as_ref().refs.set(refs);
+        if refs == 0 {
+            let this = Box::from_raw(this.as_ptr());
+            if let Some(merged) = this.merged.get() {
+                Self::release(merged);
+            }
+        }
+    }
+
+    /// Returns the account of a context, if the runtime of the context accounts memory.
+    pub unsafe fn of_context<'a>(ctx: *mut qjs::JSContext) -> Option<&'a Account> {
+        NonNull::new(qjs::JS_GetContextOpaque(ctx).cast::<Account>()).map(|x| &*x.as_ptr())
+    }
+}
+
+/// Per context memory accounting for a runtime which uses an [`Allocator`](super::Allocator).
+#[derive(Default)]
+pub(crate) struct Accounting {
+    /// The account of the context which is currently entered.
+    ///
+    /// Holds a reference so the account stays valid even if the context is freed.
+    current: Cell<Option<NonNull<Account>>>,
+}
+
+impl Accounting {
+    pub fn current(&self) -> Option<NonNull<Account>> {
+        self.current.get()
+    }
+
+    fn replace(&self, account: Option<NonNull<Account>>) -> Option<NonNull<Account>> {
+        self.current.replace(account)
+    }
+
+    /// Attribute new allocations to the account of the given context, or to no account if the
+    /// context is null.
+    ///
+    /// Returns the previous account, which must be restored with [`Accounting::leave`].
+    pub unsafe fn enter(&self, ctx: *mut qjs::JSContext) -> Option<NonNull<Account>> {
+        let account = if ctx.is_null() {
+            None
+        } else {
+            NonNull::new(qjs::JS_GetContextOpaque(ctx).cast::<Account>())
+        };
+        if let Some(account) = account {
+            Account::retain(account);
+        }
+        self.replace(account)
+    }
+
+    /// Restore the account which was current before the matching [`Accounting::enter`].
+    pub unsafe fn leave(&self, prev: Option<NonNull<Account>>) {
+        if let Some(account) = self.replace(prev) {
+            Account::release(account);
+        }
+    }
+
+    /// Attribute new allocations to a temporary account until the job finishes.
+    pub unsafe fn begin_job(&self) {
+        if let Some(prev) = self.replace(Some(Account::new())) {
+            Account::release(prev);
+        }
+    }
+
+    /// Attribute the allocations made by the finished job to its context.
+    pub unsafe fn finish_job(&self, ctx: *mut qjs::JSContext) {
+        let Some(account) = self.replace(None) else {
+            return;
+        };
+        if !ctx.is_null() {
+            if let Some(ctx_account) = NonNull::new(qjs::JS_GetContextOpaque(ctx).cast::<Account>())
+            {
+                Account::merge(account, ctx_account);
+            }
+        }
+        Account::release(account);
+    }
+
+    /// Create a context with `create`, attributing all memory allocated by it to a new account.
+    pub unsafe fn new_context<F>(&self, create: F) -> *mut qjs::JSContext
+    where
+        F: FnOnce() -> *mut qjs::JSContext,
+    {
+        let account = Account::new();
+        let prev = self.replace(Some(account));
+        let ctx = create();
+        if !ctx.is_null() {
+            qjs::JS_SetContextOpaque(ctx, account.as_ptr().cast());
+        }
+        if let Some(account) = self.replace(prev) {
+            Account::release(account);
+        }
+        ctx
+    }
+}
+
+impl Drop for Accounting {
+    fn drop(&mut self) {
+        if let Some(account) = self.current.take() {
+            unsafe { Account::release(account) }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{allocator::RustAllocator, Context, Runtime};
+
+    #[test]
+    fn per_context_usage() {
+        let rt = Runtime::new_with_alloc(RustAllocator).unwrap();
+        let a = Context::full(&rt).unwrap();
+        let b = Context::full(&rt).unwrap();
+        assert!(a.memory_usage() > 0);
+
+        let before_a = a.memory_usage();
+        let before_b = b.memory_usage();
+        a.with(|ctx| {
+            ctx.eval::<(), _>("globalThis.data = new Array(100000).fill(1)")
+                .unwrap();
+        });
+        assert!(a.memory_usage() > before_a + 100_000);
+        assert!(b.memory_usage() < before_b + 1000);
+
+        a.with(|ctx| {
+            ctx.eval::<(), _>("globalThis.data = null").unwrap();
+        });
+        rt.run_gc();
+        assert!(a.memory_usage() < before_a + 10_000);
+    }
+
+    #[test]
+    fn context_limit() {
+        let rt = Runtime::new_with_alloc(RustAllocator).unwrap();
+        let limited = Context::full(&rt).unwrap();
+        let other = Context::full(&rt).unwrap();
+        limited.set_memory_limit(limited.memory_usage() + 100_000);
+
+        limited.with(|ctx| {
+            let res: bool = ctx
+                .eval(
+                    r#"
+                    try {
+                        new Array(1000000).fill(1);
+                        false
+                    } catch(e) {
+                        e instanceof InternalError
+                    }
+                "#,
+                )
+                .unwrap();
+            assert!(res);
+        });
+
+        other.with(|ctx| {
+            ctx.eval::<(), _>("new Array(1000000).fill(1)").unwrap();
+        });
+    }
+
+    #[test]
+    fn jobs_charge_their_context() {
+        let rt = Runtime::new_with_alloc(RustAllocator).unwrap();
+        let a = Context::full(&rt).unwrap();
+        let b = Context::full(&rt).unwrap();
+
+        a.with(|ctx| {
+            ctx.eval::<(), _>(
+                "Promise.resolve().then(() => { globalThis.data = new Array(100000).fill(1) })",
+            )
+            .unwrap();
+        });
+        let before_a = a.memory_usage();
+        let before_b = b.memory_usage();
+        b.with(|ctx| ctx.eval::<(), _>("1 + 1").unwrap());
+
+        while rt.execute_pending_job().unwrap() {}
+        assert!(a.memory_usage() > before_a + 100_000);
+        assert!(b.memory_usage() < before_b + 1000);
+    }
+
+    #[cfg(feature = "futures")]
+    #[tokio::test]
+    async fn spawned_futures_charge_their_context() {
+        use crate::{AsyncContext, AsyncRuntime};
+
+        let rt = AsyncRuntime::new_with_alloc(RustAllocator).unwrap();
+        let a = AsyncContext::full(&rt).await.unwrap();
+        let b = AsyncContext::full(&rt).await.unwrap();
+
+        a.with(|ctx| {
+            ctx.spawn({
+                let ctx = ctx.clone();
+                async move {
+                    ctx.eval::<(), _>("globalThis.data = new Array(100000).fill(1)")
+                        .unwrap();
+                }
+            });
+        })
+        .await;
+        let before_a = a.memory_usage().await;
+        let before_b = b.memory_usage().await;
+        b.with(|ctx| ctx.eval::<(), _>("1 + 1").unwrap()).await;
+
+        rt.idle().await;
+        assert!(a.memory_usage().await > before_a + 100_000);
+        assert!(b.memory_usage().await < before_b + 1000);
+    }
+
+    #[cfg(not(feature = "rust-alloc"))]
+    #[test]
+    fn no_accounting_without_allocator() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        assert_eq!(ctx.memory_usage(), 0);
+        ctx.set_memory_limit(1);
+        ctx.with(|ctx| ctx.eval::<(), _>("new Array(1000).fill(1)"))
+            .unwrap();
+    }
+}
@@ -4,12 +4,13 @@ use crate::{
     function::Params,
     qjs::{self},
     value::Constructor,
-    Ctx, Error, FromJs, IntoJs, JsLifetime, Object, Result, Value,
+    Atom, Ctx, Error, FromJs, IntoJs, JsLifetime, Object, Result, Value,
 };
-use alloc::boxed::Box;
+use alloc::{boxed::Box, vec::Vec};
 use core::{hash::Hash, marker::PhantomData, mem, ops::Deref, ptr::NonNull};
 
 mod cell;
+mod exotic;
 mod trace;
 
 pub(crate) mod ffi;
@@ -17,6 +18,7 @@ pub(crate) mod ffi;
 pub use cell::{
     Borrow, BorrowMut, JsCell, Mutability, OwnedBorrow, OwnedBorrowMut, Readable, Writable,
 };
+pub use exotic::{Exotic, PropertyDescriptor};
 use ffi::{ClassCell, VTable};
 pub use trace::{Trace, Tracer};
 #[doc(hidden)]
@@ -30,6 +32,19 @@ pub trait JsClass<'js>: Trace<'js> + JsLifetime<'js> + Sized {
     /// Is this class a function.
     const CALLABLE: bool = false;
 
+    /// Does this class have exotic behavior.
+    ///
+    /// The exotic hooks, [`Self::get_own_property`] through [`Self::set_property`], are only
+    /// called for instances of the class if this is true.
+    const EXOTIC: bool = false;
+
+    /// The class this class extends, if any.
+    ///
+    /// The prototype of a class which extends another class inherits from the prototype of the
+    /// parent class and its instances can be used as instances of the parent class, see
+    /// [`Class::upcast`].
+    const EXTENDS: Option<Extends> = None;
+
     /// Can the type be mutated while a JavaScript value.
     ///
     /// This should either be [`Readable`] or [`Writable`].
@@ -49,6 +64,120 @@ pub trait JsClass<'js>: Trace<'js> + JsLifetime<'js> + Sized {
         let _ = this;
         Ok(Value::new_undefined(params.ctx().clone()))
     }
+
+    /// Exotic hook which returns the descriptor of an own property of an instance which is not an
+    /// ordinary property of the object.
+    ///
+    /// Returns `None` if the instance doesn't have the property.
+    fn get_own_property(
+        this: &JsCell<'js, Self>,
+        ctx: &Ctx<'js>,
+        key: Atom<'js>,
+    ) -> Result<Option<PropertyDescriptor<'js>>> {
+        let _ = (this, ctx, key);
+        Ok(None)
+    }
+
+    /// Exotic hook which defines a new own property on an instance, returning whether the
+    /// definition succeeded.
+    ///
+    /// Returns `None` to define an ordinary property on the object.
+    fn define_own_property(
+        this: &JsCell<'js, Self>,
+        ctx: &Ctx<'js>,
+        key: Atom<'js>,
+        desc: PropertyDescriptor<'js>,
+    ) -> Result<Option<bool>> {
+        let _ = (this, ctx, key, desc);
+        Ok(None)
+    }
+
+    /// Exotic hook which deletes an own property of an instance which is not an ordinary property
+    /// of the object, returning whether the property was deleted.
+    ///
+    /// Returns `None` if the instance doesn't have the property.
+    fn delete_property(
+        this: &JsCell<'js, Self>,
+        ctx: &Ctx<'js>,
+        key: Atom<'js>,
+    ) -> Result<Option<bool>> {
+        let _ = (this, ctx, key);
+        Ok(None)
+    }
+
+    /// Exotic hook which returns the keys of the own properties of an instance in addition to the
+    /// ordinary properties of the object.
+    fn get_own_property_names(this: &JsCell<'js, Self>, ctx: &Ctx<'js>) -> Result<Vec<Atom<'js>>> {
+        let _ = (this, ctx);
+        Ok(Vec::new())
+    }
+
+    /// Exotic hook for the `in` operator.
+    ///
+    /// Returns `None` to look for the property in [`Self::get_own_property`], the ordinary
+    /// properties of the object and its prototype chain.
+    fn has_property(
+        this: &JsCell<'js, Self>,
+        ctx: &Ctx<'js>,
+        key: Atom<'js>,
+    ) -> Result<Option<bool>> {
+        let _ = (this, ctx, key);
+        Ok(None)
+    }
+
+    /// Exotic hook for getting a property value which is not an ordinary property of the object.
+    ///
+    /// Returns `None` to look for the property in [`Self::get_own_property`] and the prototype
+    /// chain.
+    fn get_property(
+        this: &JsCell<'js, Self>,
+        ctx: &Ctx<'js>,
+        key: Atom<'js>,
+        receiver: Value<'js>,
+    ) -> Result<Option<Value<'js>>> {
+        let _ = (this, ctx, key, receiver);
+        Ok(None)
+    }
+
+    /// Exotic hook for setting a property value which is not an ordinary property of the object,
+    /// returning whether the assignment succeeded.
+    ///
+    /// Returns `None` to assign the property like an ordinary object would.
+    fn set_property(
+        this: &JsCell<'js, Self>,
+        ctx: &Ctx<'js>,
+        key: Atom<'js>,
+        value: Value<'js>,
+        receiver: Value<'js>,
+    ) -> Result<Option<bool>> {
+        let _ = (this, ctx, key, value, receiver);
+        Ok(None)
+    }
+}
+
+/// The parent of a class which extends another class, see [`JsClass::EXTENDS`].
+#[derive(Clone, Copy)]
+pub struct Extends(pub(crate) &'static VTable);
+
+impl Extends {
+    /// Create the parent of class `C` which extends class `P`.
+    ///
+    /// Fails to compile if the alignment of `C` is larger than that of `P`.
+    ///
+    /// # Safety
+    /// `C` must be `#[repr(C)]` and have a value of `P` as its first field.
+    pub const unsafe fn new<'js, C, P>() -> Self
+    where
+        C: JsClass<'js, Mutable = P::Mutable>,
+        P: JsClass<'js>,
+    {
+        assert!(
+            mem::align_of::<ClassCell<JsCell<'js, C>>>()
+                == mem::align_of::<ClassCell<JsCell<'js, P>>>(),
+            "a class must have the same alignment as the class it extends"
+        );
+        Extends(VTable::get::<P>())
+    }
 }
 
 /// A object which is instance of a Rust class.
@@ -94,13 +223,7 @@ impl<'js, C: JsClass<'js>> Deref for Class<'js, C> {
 impl<'js, C: JsClass<'js>> Class<'js, C> {
     /// Create a class from a Rust object.
     pub fn instance(ctx: Ctx<'js>, value: C) -> Result<Class<'js, C>> {
-        let id = unsafe {
-            if C::CALLABLE {
-                ctx.get_opaque().get_callable_id()
-            } else {
-                ctx.get_opaque().get_class_id()
-            }
-        };
+        let id = unsafe { ctx.get_opaque().get_class_id_of::<C>() };
 
         let prototype = Self::prototype(&ctx)?;
 
@@ -119,13 +242,7 @@ impl<'js, C: JsClass<'js>> Class<'js, C> {
 
     /// Create a class from a Rust object with a given prototype.
     pub fn instance_proto(value: C, proto: Object<'js>) -> Result<Class<'js, C>> {
-        let id = unsafe {
-            if C::CALLABLE {
-                proto.ctx().get_opaque().get_callable_id()
-            } else {
-                proto.ctx().get_opaque().get_class_id()
-            }
-        };
+        let id = unsafe { proto.ctx().get_opaque().get_class_id_of::<C>() };
 
         let val = unsafe {
             proto.ctx.handle_exception(qjs::JS_NewObjectProtoClass(
@@ -223,19 +340,22 @@ impl<'js, C: JsClass<'js>> Class<'js, C> {
     }
 
     /// returns a pointer to the class object.
+    ///
+    /// For an instance of a class which extends `C` this points to the same cell, which starts
+    /// with the data of `C`.
     #[inline]
     pub(crate) fn get_class_ptr(&self) -> NonNull<ClassCell<JsCell<'js, C>>> {
-        let id = unsafe {
-            if C::CALLABLE {
-                self.ctx.get_opaque().get_callable_id()
-            } else {
-                self.ctx.get_opaque().get_class_id()
-            }
-        };
-
-        let ptr = unsafe { qjs::JS_GetOpaque2(self.0.ctx.as_ptr(), self.0 .0.as_js_value(), id) };
+        self.0
+            .get_class_cell_ptr()
+            .expect("invalid class object, object didn't have opaque value")
+            .cast()
+    }
 
-        NonNull::new(ptr.cast()).expect("invalid class object, object didn't have opaque value")
+    /// Upcast the class into the class `P` it extends.
+    ///
+    /// Returns `None` if `P` is neither this class nor one of the classes it extends.
+    pub fn upcast<P: JsClass<'js>>(self) -> Option<Class<'js, P>> {
+        Class::from_object(&self.0)
     }
 
     /// Turns the class back into a generic object.
@@ -277,24 +397,24 @@ impl<'js, C: JsClass<'js>> Class<'js, C> {
 }
 
 impl<'js> Object<'js> {
-    /// Returns if the object is of a certain Rust class.
-    pub fn instance_of<C: JsClass<'js>>(&self) -> bool {
-        let id = unsafe {
-            if C::CALLABLE {
-                self.ctx.get_opaque().get_callable_id()
-            } else {
-                self.ctx.get_opaque().get_class_id()
-            }
-        };
+    /// Returns a pointer to the class cell of the object if it is an instance of a Rust class.
+    pub(crate) fn get_class_cell_ptr(&self) -> Option<NonNull<ClassCell<()>>> {
+        let mut id = qjs::JS_INVALID_CLASS_ID;
+        let ptr = unsafe { qjs::JS_GetAnyOpaque(self.0.as_js_value(), &mut id) };
+        // Other classes can also have opaque values, so check if the class is a Rust class.
+        if !unsafe { self.ctx.get_opaque().is_class_id(id) } {
+            return None;
+        }
+        NonNull::new(ptr.cast())
+    }
 
-        // This checks if the class is of the right class id.
-        let Some(x) = NonNull::new(unsafe {
-            qjs::JS_GetOpaque2(self.0.ctx.as_ptr(), self.0.as_js_value(), id)
-        }) else {
+    /// Returns if the object is of a certain Rust class or a class which extends it.
+    pub fn instance_of<C: JsClass<'js>>(&self) -> bool {
+        let Some(x) = self.get_class_cell_ptr() else {
             return false;
         };
 
-        let v_table = unsafe { x.cast::<ClassCell<()>>().as_ref().v_table };
+        let v_table = unsafe { x.as_ref().v_table };
 
         // If the pointer is equal it must be of the right type, as the inclusion of a call to
         // generate a TypeId means that each type must have a unique v table.
@@ -306,7 +426,7 @@ impl<'js> Object<'js> {
             return true;
         }
 
-        v_table.is_of_class::<C>()
+        v_table.is_or_extends_class::<C>()
     }
 
     /// Turn the object into the class if it is an instance of that class.
@@ -350,7 +470,7 @@ mod test {
     };
 
     use crate::{
-        class::{JsClass, Readable, Trace, Tracer, Writable},
+        class::{Extends, JsClass, Readable, Trace, Tracer, Writable},
         function::This,
         test_with,
         value::Constructor,
@@ -554,6 +674,76 @@ mod test {
         })
     }
 
+    #[test]
+    fn extends() {
+        #[repr(C)]
+        pub struct Vec4 {
+            xyz: Vec3,
+            w: f32,
+        }
+
+        impl<'js> Trace<'js> for Vec4 {
+            fn trace<'a>(&self, _tracer: Tracer<'a, 'js>) {}
+        }
+
+        unsafe impl<'js> JsLifetime<'js> for Vec4 {
+            type Changed<'to> = Vec4;
+        }
+
+        impl<'js> JsClass<'js> for Vec4 {
+            const NAME: &'static str = "Vec4";
+
+            const EXTENDS: Option<Extends> = Some(unsafe { Extends::new::<Self, Vec3>() });
+
+            type Mutable = Writable;
+
+            fn prototype(ctx: &crate::Ctx<'js>) -> crate::Result<Option<Object<'js>>> {
+                let proto = Object::new(ctx.clone())?;
+                let func = Function::new(ctx.clone(), |this: This<Class<Vec4>>| this.borrow().w)?;
+                proto.set("w", func)?;
+                Ok(Some(proto))
+            }
+
+            fn constructor(_ctx: &crate::Ctx<'js>) -> crate::Result<Option<Constructor<'js>>> {
+                Ok(None)
+            }
+        }
+
+        test_with(|ctx| {
+            Class::<Vec3>::define(&ctx.globals()).unwrap();
+
+            let v = Class::instance(
+                ctx.clone(),
+                Vec4 {
+                    xyz: Vec3::new(1.0, 2.0, 3.0),
+                    w: 4.0,
+                },
+            )
+            .unwrap();
+            ctx.globals().set("v", v.clone()).unwrap();
+
+            let sum = ctx
+                .eval::<Vec3, _>("v instanceof Vec3 && v.w() === 4 ? v.add(v) : null")
+                .catch(&ctx)
+                .unwrap();
+            approx::assert_abs_diff_eq!(sum.z, 6.0);
+
+            assert!(v.instance_of::<Vec3>());
+            assert!(!Class::instance(ctx.clone(), Vec3::new(0.0, 0.0, 0.0))
+                .unwrap()
+                .instance_of::<Vec4>());
+
+            let parent = v.clone().upcast::<Vec3>().unwrap();
+            parent.borrow_mut().x = 10.0;
+            assert!(v.try_borrow_mut().is_ok());
+            let borrow = parent.borrow();
+            assert!(v.try_borrow_mut().is_err());
+            approx::assert_abs_diff_eq!(borrow.x, 10.0);
+            approx::assert_abs_diff_eq!(v.borrow().xyz.x, 10.0);
+            approx::assert_abs_diff_eq!(v.borrow().w, 4.0);
+        })
+    }
+
     #[test]
     fn get_prototype() {
         pub struct X;
@@ -0,0 +1,385 @@
+//! Exotic behavior of Rust classes.
+
+use super::JsClass;
+use crate::{function::This, qjs, Atom, Ctx, Function, Object, Result, Value};
+use alloc::vec::Vec;
+use core::mem::MaybeUninit;
+
+/// The descriptor of a property as returned and received by the exotic hooks of a class.
+#[derive(Debug, Clone)]
+pub struct PropertyDescriptor<'js> {
+    /// The value of a data property.
+    pub value: Option<Value<'js>>,
+    /// The getter of an accessor property.
+    pub getter: Option<Function<'js>>,
+    /// The setter of an accessor property.
+    pub setter: Option<Function<'js>>,
+    /// Whether the value of a data property can be changed by assignment.
+    pub writable: bool,
+    /// Whether the property shows up when enumerating the properties of the object.
+    pub enumerable: bool,
+    /// Whether the property can be deleted or redefined.
+    pub configurable: bool,
+}
+
+impl<'js> PropertyDescriptor<'js> {
+    /// Create the descriptor of a writable, enumerable and configurable data property.
+    pub fn value(value: Value<'js>) -> Self {
+        PropertyDescriptor {
+            value: Some(value),
+            getter: None,
+            setter: None,
+            writable: true,
+            enumerable: true,
+            configurable: true,
+        }
+    }
+
+    /// Create the descriptor of an enumerable and configurable accessor property.
+    pub fn accessor(getter: Option<Function<'js>>, setter: Option<Function<'js>>) -> Self {
+        PropertyDescriptor {
+            value: None,
+            getter,
+            setter,
+            writable: false,
+            enumerable: true,
+            configurable: true,
+        }
+    }
+
+    /// Returns whether the descriptor describes an accessor property.
+    pub fn is_accessor(&self) -> bool {
+        self.getter.is_some() || self.setter.is_some()
+    }
+
+    /// Create a descriptor from the arguments of a property definition.
+    ///
+    /// The values are borrowed and duplicated.
+    pub(crate) unsafe fn from_definition(
+        ctx: &Ctx<'js>,
+        value: qjs::JSValue,
+        getter: qjs::JSValue,
+        setter: qjs::JSValue,
+        flags: qjs::c_int,
+    ) -> Self {
+        let has = |flag: u32| flags & flag as qjs::c_int != 0;
+        let function =
+            |value: qjs::JSValue| Value::from_js_value_const(ctx.clone(), value).into_function();
+        PropertyDescriptor {
+            value: has(qjs::JS_PROP_HAS_VALUE)
+                .then(|| Value::from_js_value_const(ctx.clone(), value)),
+            getter: has(qjs::JS_PROP_HAS_GET)
+                .then(|| function(getter))
+                .flatten(),
+            setter: has(qjs::JS_PROP_HAS_SET)
+                .then(|| function(setter))
+                .flatten(),
+            writable: has(qjs::JS_PROP_WRITABLE),
+            enumerable: has(qjs::JS_PROP_ENUMERABLE),
+            configurable: has(qjs::JS_PROP_CONFIGURABLE),
+        }
+    }
+
+    /// Create a descriptor from a descriptor filled in by QuickJS, taking ownership of the values.
+    unsafe fn from_ffi(ctx: &Ctx<'js>, desc: qjs::JSPropertyDescriptor) -> Self {
+        let flags = desc.flags;
+        let value = Value::from_js_value(ctx.clone(), desc.value);
+        let getter = Value::from_js_value(ctx.clone(), desc.getter).into_function();
+        let setter = Value::from_js_value(ctx.clone(), desc.setter).into_function();
+        let is_accessor = flags & qjs::JS_PROP_GETSET as qjs::c_int != 0;
+        PropertyDescriptor {
+            value: (!is_accessor).then_some(value),
+            getter,
+            setter,
+            writable: flags & qjs::JS_PROP_WRITABLE as qjs::c_int != 0,
+            enumerable: flags & qjs::JS_PROP_ENUMERABLE as qjs::c_int != 0,
+            configurable: flags & qjs::JS_PROP_CONFIGURABLE as qjs::c_int != 0,
+        }
+    }
+
+    /// Write the descriptor into a descriptor provided by QuickJS, passing ownership of the
+    /// values.
+    pub(crate) unsafe fn into_ffi(self, desc: *mut qjs::JSPropertyDescriptor) {
+        if desc.is_null() {
+            return;
+        }
+        let mut flags = 0;
+        if self.enumerable {
+            flags |= qjs::JS_PROP_ENUMERABLE;
+        }
+        if self.configurable {
+            flags |= qjs::JS_PROP_CONFIGURABLE;
+        }
+        if self.is_accessor() {
+            flags |= qjs::JS_PROP_GETSET;
+        } else if self.writable {
+            flags |= qjs::JS_PROP_WRITABLE;
+        }
+        let into_js_value =
+            |value: Option<Value<'js>>| value.map_or(qjs::JS_UNDEFINED, Value::into_js_value);
+        desc.write(qjs::JSPropertyDescriptor {
+            flags: flags as qjs::c_int,
+            value: into_js_value(self.value.filter(|_| flags & qjs::JS_PROP_GETSET == 0)),
+            getter: into_js_value(self.getter.map(Function::into_value)),
+            setter: into_js_value(self.setter.map(Function::into_value)),
+        });
+    }
+}
+
+/// The exotic hooks of a class implemented with the `#[class]` macro.
+///
+/// Setting the `#[qjs(exotic)]` option on a class forwards the exotic hooks of [`JsClass`] to
+/// the methods of this trait. Every hook returns `None` to fall back to the ordinary behavior of
+/// an object, which includes looking up the property on the prototype of the class.
+///
+/// Hooks which take `&mut self` fail with an error for frozen classes.
+pub trait Exotic<'js>: JsClass<'js> {
+    /// Returns the descriptor of an own property, or `None` if there is no such property.
+    fn get_own_property(
+        &self,
+        ctx: &Ctx<'js>,
+        key: Atom<'js>,
+    ) -> Result<Option<PropertyDescriptor<'js>>> {
+        let _ = (ctx, key);
+        Ok(None)
+    }
+
+    /// Defines an own property, returning whether the definition succeeded.
+    fn define_own_property(
+        &mut self,
+        ctx: &Ctx<'js>,
+        key: Atom<'js>,
+        desc: PropertyDescriptor<'js>,
+    ) -> Result<Option<bool>> {
+        let _ = (ctx, key, desc);
+        Ok(None)
+    }
+
+    /// Deletes an own property, returning whether the property was deleted.
+    fn delete_property(&mut self, ctx: &Ctx<'js>, key: Atom<'js>) -> Result<Option<bool>> {
+        let _ = (ctx, key);
+        Ok(None)
+    }
+
+    /// Returns the keys of the own properties returned by [`Exotic::get_own_property`].
+    fn get_own_property_names(&self, ctx: &Ctx<'js>) -> Result<Vec<Atom<'js>>> {
+        let _ = ctx;
+        Ok(Vec::new())
+    }
+
+    /// Returns whether the object or its prototype chain has a property.
+    fn has_property(&self, ctx: &Ctx<'js>, key: Atom<'js>) -> Result<Option<bool>> {
+        let _ = (ctx, key);
+        Ok(None)
+    }
+
+    /// Returns the value of a property.
+    fn get_property(
+        &self,
+        ctx: &Ctx<'js>,
+        key: Atom<'js>,
+        receiver: Value<'js>,
+    ) -> Result<Option<Value<'js>>> {
+        let _ = (ctx, key, receiver);
+        Ok(None)
+    }
+
+    /// Sets the value of a property, returning whether the assignment succeeded.
+    fn set_property(
+        &mut self,
+        ctx: &Ctx<'js>,
+        key: Atom<'js>,
+        value: Value<'js>,
+        receiver: Value<'js>,
+    ) -> Result<Option<bool>> {
+        let _ = (ctx, key, value, receiver);
+        Ok(None)
+    }
+}
+
+/// Returns the descriptor of an own property of an object.
+fn own_property<'js>(
+    object: &Object<'js>,
+    key: &Atom<'js>,
+) -> Result<Option<PropertyDescriptor<'js>>> {
+    let ctx = object.ctx();
+    unsafe {
+        let mut desc = MaybeUninit::<qjs::JSPropertyDescriptor>::uninit();
+        let res = qjs::JS_GetOwnProperty(
+            ctx.as_ptr(),
+            desc.as_mut_ptr(),
+            object.as_js_value(),
+            key.atom,
+        );
+        if res < 0 {
+            return Err(ctx.raise_exception());
+        }
+        Ok((res > 0).then(|| PropertyDescriptor::from_ffi(ctx, desc.assume_init())))
+    }
+}
+
+/// Find a property on an object or its prototype chain.
+fn find_property<'js>(
+    object: &Object<'js>,
+    key: &Atom<'js>,
+) -> Result<Option<PropertyDescriptor<'js>>> {
+    let mut object = Some(object.clone());
+    while let Some(x) = object {
+        if let Some(desc) = own_property(&x, key)? {
+            return Ok(Some(desc));
+        }
+        object = x.get_prototype();
+    }
+    Ok(None)
+}
+
+/// The ordinary behavior of the `in` operator.
+pub(crate) fn ordinary_has<'js>(object: &Object<'js>, key: &Atom<'js>) -> Result<bool> {
+    find_property(object, key).map(|desc| desc.is_some())
+}
+
+/// The ordinary behavior of getting a property value.
+pub(crate) fn ordinary_get<'js>(
+    object: &Object<'js>,
+    key: &Atom<'js>,
+    receiver: Value<'js>,
+) -> Result<Value<'js>> {
+    let ctx = object.ctx();
+    match find_property(object, key)? {
+        Some(PropertyDescriptor {
+            getter: Some(getter),
+            ..
+        }) => getter.call((This(receiver),)),
+        Some(PropertyDescriptor {
+            value: Some(value), ..
+        }) => Ok(value),
+        _ => Ok(Value::new_undefined(ctx.clone())),
+    }
+}
+
+/// The ordinary behavior of setting a property value.
+pub(crate) fn ordinary_set<'js>(
+    object: &Object<'js>,
+    key: &Atom<'js>,
+    value: Value<'js>,
+    receiver: Value<'js>,
+) -> Result<bool> {
+    if let Some(desc) = find_property(object, key)? {
+        if desc.is_accessor() {
+            let Some(setter) = desc.setter else {
+                return Ok(false);
+            };
+            setter.call::<_, ()>((This(receiver), value))?;
+            return Ok(true);
+        }
+        if !desc.writable {
+            return Ok(false);
+        }
+    }
+
+    let Some(receiver) = receiver.into_object() else {
+        return Ok(false);
+    };
+    let mut flags = qjs::JS_PROP_HAS_VALUE;
+    match own_property(&receiver, key)? {
+        Some(desc) if desc.is_accessor() || !desc.writable => return Ok(false),
+        Some(_) => {}
+        None => {
+            flags |= qjs::JS_PROP_C_W_E;
+        }
+    }
+    let ctx = receiver.ctx();
+    let res = unsafe {
+        qjs::JS_DefineProperty(
+            ctx.as_ptr(),
+            receiver.as_js_value(),
+            key.atom,
+            value.as_js_value(),
+            qjs::JS_UNDEFINED,
+            qjs::JS_UNDEFINED,
+            flags as qjs::c_int,
+        )
+    };
+    if res < 0 {
+        return Err(ctx.raise_exception());
+    }
+    Ok(res > 0)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        class::{JsCell, JsClass, Readable, Trace, Tracer},
+        test_with, Atom, Class, Ctx, IntoJs, JsLifetime, Object, Result,
+    };
+
+    use super::PropertyDescriptor;
+
+    struct Numbers(u32);
+
+    impl<'js> Trace<'js> for Numbers {
+        fn trace<'a>(&self, _tracer: Tracer<'a, 'js>) {}
+    }
+
+    unsafe impl<'js> JsLifetime<'js> for Numbers {
+        type Changed<'to> = Numbers;
+    }
+
+    impl<'js> JsClass<'js> for Numbers {
+        const NAME: &'static str = "Numbers";
+        const EXOTIC: bool = true;
+
+        type Mutable = Readable;
+
+        fn prototype(ctx: &Ctx<'js>) -> Result<Option<Object<'js>>> {
+            Ok(Some(Object::new(ctx.clone())?))
+        }
+
+        fn constructor(_ctx: &Ctx<'js>) -> Result<Option<crate::value::Constructor<'js>>> {
+            Ok(None)
+        }
+
+        fn get_own_property(
+            this: &JsCell<'js, Self>,
+            ctx: &Ctx<'js>,
+            key: Atom<'js>,
+        ) -> Result<Option<PropertyDescriptor<'js>>> {
+            let Ok(idx) = key.to_string()?.parse::<u32>() else {
+                return Ok(None);
+            };
+            if idx >= this.borrow().0 {
+                return Ok(None);
+            }
+            Ok(Some(PropertyDescriptor::value((idx * 2).into_js(ctx)?)))
+        }
+
+        fn get_own_property_names(
+            this: &JsCell<'js, Self>,
+            ctx: &Ctx<'js>,
+        ) -> Result<alloc::vec::Vec<Atom<'js>>> {
+            (0..this.borrow().0)
+                .map(|idx| Atom::from_u32(ctx.clone(), idx))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn exotic_class() {
+        test_with(|ctx| {
+            let numbers = Class::instance(ctx.clone(), Numbers(3)).unwrap();
+            ctx.globals().set("n", numbers).unwrap();
+            let res: bool = ctx
+                .eval(
+                    r#"
+                    n.other = "ordinary";
+                    n[1] === 2 && n[3] === undefined
+                        && (1 in n) && !(3 in n) && ("other" in n)
+                        && Object.keys(n).join() === "other,0,1,2"
+                        && n.other === "ordinary"
+                "#,
+                )
+                .unwrap();
+            assert!(res);
+        })
+    }
+}
@@ -1,7 +1,13 @@
-use super::{JsClass, Tracer};
-use crate::{class::JsCell, function::Params, qjs, runtime::opaque::Opaque, Value};
+use super::{exotic, JsClass, PropertyDescriptor, Tracer};
+use crate::{
+    class::{Class, JsCell},
+    function::Params,
+    qjs,
+    runtime::opaque::Opaque,
+    Atom, Ctx, Error, Object, Result, Value,
+};
 use alloc::boxed::Box;
-use core::{any::TypeId, panic::AssertUnwindSafe, ptr::NonNull};
+use core::{any::TypeId, mem, panic::AssertUnwindSafe, ptr::NonNull};
 
 /// FFI finalizer, destroying the object once it is delete by the Gc.
 pub(crate) unsafe extern "C" fn class_finalizer(rt: *mut qjs::JSRuntime, val: qjs::JSValue) {
@@ -61,6 +67,155 @@ pub(crate) unsafe extern "C" fn call(
     (ptr.as_ref().v_table.call)(ptr, ctx, function, this, argc, argv, flags)
 }
 
+/// Returns the class cell of an instance of a class with exotic behavior.
+unsafe fn exotic_class_cell(val: qjs::JSValue) -> NonNull<ClassCell<()>> {
+    let mut class_id = qjs::JS_INVALID_CLASS_ID;
+    let ptr = qjs::JS_GetAnyOpaque(val, &mut class_id);
+    NonNull::new(ptr).unwrap().cast::<ClassCell<()>>()
+}
+
+/// FFI finalizer for classes with exotic behavior.
+pub(crate) unsafe extern "C" fn exotic_finalizer(_rt: *mut qjs::JSRuntime, val: qjs::JSValue) {
+    let ptr = exotic_class_cell(val);
+    (ptr.as_ref().v_table.finalizer)(ptr)
+}
+
+/// FFI tracing function for classes with exotic behavior.
+pub(crate) unsafe extern "C" fn exotic_trace(
+    rt: *mut qjs::JSRuntime,
+    val: qjs::JSValue,
+    mark_func: qjs::JS_MarkFunc,
+) {
+    let ptr = exotic_class_cell(val);
+    let tracer = Tracer::from_ffi(rt, mark_func);
+    (ptr.as_ref().v_table.trace)(ptr, tracer)
+}
+
+/// FFI calling function for callable classes with exotic behavior.
+pub(crate) unsafe extern "C" fn exotic_call(
+    ctx: *mut qjs::JSContext,
+    function: qjs::JSValue,
+    this: qjs::JSValue,
+    argc: qjs::c_int,
+    argv: *mut qjs::JSValue,
+    flags: qjs::c_int,
+) -> qjs::JSValue {
+    let ptr = exotic_class_cell(function);
+    (ptr.as_ref().v_table.call)(ptr, ctx, function, this, argc, argv, flags)
+}
+
+unsafe extern "C" fn exotic_get_own_property(
+    ctx: *mut qjs::JSContext,
+    desc: *mut qjs::JSPropertyDescriptor,
+    obj: qjs::JSValue,
+    prop: qjs::JSAtom,
+) -> qjs::c_int {
+    let ptr = exotic_class_cell(obj);
+    (ptr.as_ref().v_table.exotic.get_own_property)(ptr, ctx, desc, prop)
+}
+
+unsafe extern "C" fn exotic_get_own_property_names(
+    ctx: *mut qjs::JSContext,
+    ptab: *mut *mut qjs::JSPropertyEnum,
+    plen: *mut u32,
+    obj: qjs::JSValue,
+) -> qjs::c_int {
+    let ptr = exotic_class_cell(obj);
+    (ptr.as_ref().v_table.exotic.get_own_property_names)(ptr, ctx, ptab, plen)
+}
+
+unsafe extern "C" fn exotic_delete_property(
+    ctx: *mut qjs::JSContext,
+    obj: qjs::JSValue,
+    prop: qjs::JSAtom,
+) -> qjs::c_int {
+    let ptr = exotic_class_cell(obj);
+    (ptr.as_ref().v_table.exotic.delete_property)(ptr, ctx, prop)
+}
+
+unsafe extern "C" fn exotic_define_own_property(
+    ctx: *mut qjs::JSContext,
+    obj: qjs::JSValue,
+    prop: qjs::JSAtom,
+    val: qjs::JSValue,
+    getter: qjs::JSValue,
+    setter: qjs::JSValue,
+    flags: qjs::c_int,
+) -> qjs::c_int {
+    let ptr = exotic_class_cell(obj);
+    (ptr.as_ref().v_table.exotic.define_own_property)(
+        ptr, ctx, obj, prop, val, getter, setter, flags,
+    )
+}
+
+unsafe extern "C" fn exotic_has_property(
+    ctx: *mut qjs::JSContext,
+    obj: qjs::JSValue,
+    prop: qjs::JSAtom,
+) -> qjs::c_int {
+    let ptr = exotic_class_cell(obj);
+    (ptr.as_ref().v_table.exotic.has_property)(ptr, ctx, obj, prop)
+}
+
+unsafe extern "C" fn exotic_get_property(
+    ctx: *mut qjs::JSContext,
+    obj: qjs::JSValue,
+    prop: qjs::JSAtom,
+    receiver: qjs::JSValue,
+) -> qjs::JSValue {
+    let ptr = exotic_class_cell(obj);
+    (ptr.as_ref().v_table.exotic.get_property)(ptr, ctx, obj, prop, receiver)
+}
+
+unsafe extern "C" fn exotic_set_property(
+    ctx: *mut qjs::JSContext,
+    obj: qjs::JSValue,
+    prop: qjs::JSAtom,
+    value: qjs::JSValue,
+    receiver: qjs::JSValue,
+    flags: qjs::c_int,
+) -> qjs::c_int {
+    let ptr = exotic_class_cell(obj);
+    (ptr.as_ref().v_table.exotic.set_property)(ptr, ctx, obj, prop, value, receiver, flags)
+}
+
+/// The exotic methods shared by all classes with exotic behavior, dispatching to the hooks of
+/// the class through its v-table.
+pub(crate) static EXOTIC_METHODS: qjs::JSClassExoticMethods = qjs::JSClassExoticMethods {
+    get_own_property: Some(exotic_get_own_property),
+    get_own_property_names: Some(exotic_get_own_property_names),
+    delete_property: Some(exotic_delete_property),
+    define_own_property: Some(exotic_define_own_property),
+    has_property: Some(exotic_has_property),
+    get_property: Some(exotic_get_property),
+    set_property: Some(exotic_set_property),
+};
+
+/// Run an exotic hook, turning errors and panics into an exception signaled by returning -1.
+unsafe fn handle_hook<'js, F>(ctx: &Ctx<'js>, f: F) -> qjs::c_int
+where
+    F: FnOnce() -> Result<qjs::c_int>,
+{
+    let res = ctx.handle_panic(AssertUnwindSafe(|| match f() {
+        Ok(x) => qjs::JS_MKVAL(qjs::JS_TAG_INT, x),
+        Err(e) => e.throw(ctx),
+    }));
+    if qjs::JS_IsException(res) {
+        -1
+    } else {
+        qjs::JS_VALUE_GET_INT(res)
+    }
+}
+
+/// Returns the result of a failed assignment or definition, throwing if requested by the flags.
+fn rejected(ctx: &Ctx, flags: qjs::c_int, message: &str) -> Result<qjs::c_int> {
+    if flags & qjs::JS_PROP_THROW as qjs::c_int != 0 {
+        Err(crate::Exception::throw_type(ctx, message))
+    } else {
+        Ok(0)
+    }
+}
+
 pub(crate) type FinalizerFunc = unsafe fn(this: NonNull<ClassCell<()>>);
 pub(crate) type TraceFunc =
     for<'a> unsafe fn(this: NonNull<ClassCell<()>>, tracer: Tracer<'a, 'static>);
@@ -75,12 +230,277 @@ pub(crate) type CallFunc = for<'a> unsafe fn(
 ) -> qjs::JSValue;
 
 pub(crate) type TypeIdFn = fn() -> TypeId;
+pub(crate) type PrototypeFunc = for<'a> unsafe fn(ctx: &Ctx<'a>) -> Result<Option<Object<'a>>>;
+
+pub(crate) struct ExoticVTable {
+    get_own_property: unsafe fn(
+        this_ptr: NonNull<ClassCell<()>>,
+        ctx: *mut qjs::JSContext,
+        desc: *mut qjs::JSPropertyDescriptor,
+        prop: qjs::JSAtom,
+    ) -> qjs::c_int,
+    get_own_property_names: unsafe fn(
+        this_ptr: NonNull<ClassCell<()>>,
+        ctx: *mut qjs::JSContext,
+        ptab: *mut *mut qjs::JSPropertyEnum,
+        plen: *mut u32,
+    ) -> qjs::c_int,
+    delete_property: unsafe fn(
+        this_ptr: NonNull<ClassCell<()>>,
+        ctx: *mut qjs::JSContext,
+        prop: qjs::JSAtom,
+    ) -> qjs::c_int,
+    #[allow(clippy::type_complexity)]
+    define_own_property: unsafe fn(
+        this_ptr: NonNull<ClassCell<()>>,
+        ctx: *mut qjs::JSContext,
+        obj: qjs::JSValue,
+        prop: qjs::JSAtom,
+        val: qjs::JSValue,
+        getter: qjs::JSValue,
+        setter: qjs::JSValue,
+        flags: qjs::c_int,
+    ) -> qjs::c_int,
+    has_property: unsafe fn(
+        this_ptr: NonNull<ClassCell<()>>,
+        ctx: *mut qjs::JSContext,
+        obj: qjs::JSValue,
+        prop: qjs::JSAtom,
+    ) -> qjs::c_int,
+    get_property: unsafe fn(
+        this_ptr: NonNull<ClassCell<()>>,
+        ctx: *mut qjs::JSContext,
+        obj: qjs::JSValue,
+        prop: qjs::JSAtom,
+        receiver: qjs::JSValue,
+    ) -> qjs::JSValue,
+    #[allow(clippy::type_complexity)]
+    set_property: unsafe fn(
+        this_ptr: NonNull<ClassCell<()>>,
+        ctx: *mut qjs::JSContext,
+        obj: qjs::JSValue,
+        prop: qjs::JSAtom,
+        value: qjs::JSValue,
+        receiver: qjs::JSValue,
+        flags: qjs::c_int,
+    ) -> qjs::c_int,
+}
+
+impl ExoticVTable {
+    unsafe fn get_own_property_impl<'js, C: JsClass<'js>>(
+        this_ptr: NonNull<ClassCell<()>>,
+        ctx: *mut qjs::JSContext,
+        desc: *mut qjs::JSPropertyDescriptor,
+        prop: qjs::JSAtom,
+    ) -> qjs::c_int {
+        let this_ptr = this_ptr.cast::<ClassCell<JsCell<C>>>();
+        let ctx = Ctx::from_ptr(ctx);
+        let key = Atom::from_atom_val_dup(ctx.clone(), prop);
+        handle_hook(&ctx, || {
+            Ok(
+                match C::get_own_property(&this_ptr.as_ref().data, &ctx, key)? {
+                    Some(x) => {
+                        x.into_ffi(desc);
+                        1
+                    }
+                    None => 0,
+                },
+            )
+        })
+    }
+
+    unsafe fn get_own_property_names_impl<'js, C: JsClass<'js>>(
+        this_ptr: NonNull<ClassCell<()>>,
+        ctx: *mut qjs::JSContext,
+        ptab: *mut *mut qjs::JSPropertyEnum,
+        plen: *mut u32,
+    ) -> qjs::c_int {
+        let this_ptr = this_ptr.cast::<ClassCell<JsCell<C>>>();
+        let ctx = Ctx::from_ptr(ctx);
+        handle_hook(&ctx, || {
+            let keys = C::get_own_property_names(&this_ptr.as_ref().data, &ctx)?;
+            // QuickJS doesn't accept a null table even if there are no keys.
+            let size = mem::size_of::<qjs::JSPropertyEnum>() * keys.len().max(1);
+            let tab = qjs::js_malloc(ctx.as_ptr(), size as _).cast::<qjs::JSPropertyEnum>();
+            if tab.is_null() {
+                return Err(Error::Allocation);
+            }
+            let len = keys.len();
+            for (idx, key) in keys.iter().enumerate() {
+                // The table owns its atoms, which are freed by QuickJS.
+                tab.add(idx).write(qjs::JSPropertyEnum {
+                    is_enumerable: false,
+                    atom: qjs::JS_DupAtom(ctx.as_ptr(), key.atom),
+                });
+            }
+            ptab.write(tab);
+            plen.write(len as u32);
+            Ok(0)
+        })
+    }
+
+    unsafe fn delete_property_impl<'js, C: JsClass<'js>>(
+        this_ptr: NonNull<ClassCell<()>>,
+        ctx: *mut qjs::JSContext,
+        prop: qjs::JSAtom,
+    ) -> qjs::c_int {
+        let this_ptr = this_ptr.cast::<ClassCell<JsCell<C>>>();
+        let ctx = Ctx::from_ptr(ctx);
+        let key = Atom::from_atom_val_dup(ctx.clone(), prop);
+        handle_hook(&ctx, || {
+            let res = C::delete_property(&this_ptr.as_ref().data, &ctx, key)?;
+            Ok(res.unwrap_or(true) as qjs::c_int)
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    unsafe fn define_own_property_impl<'js, C: JsClass<'js>>(
+        this_ptr: NonNull<ClassCell<()>>,
+        ctx: *mut qjs::JSContext,
+        obj: qjs::JSValue,
+        prop: qjs::JSAtom,
+        val: qjs::JSValue,
+        getter: qjs::JSValue,
+        setter: qjs::JSValue,
+        flags: qjs::c_int,
+    ) -> qjs::c_int {
+        let this_ptr = this_ptr.cast::<ClassCell<JsCell<C>>>();
+        let ctx = Ctx::from_ptr(ctx);
+        let key = Atom::from_atom_val_dup(ctx.clone(), prop);
+        handle_hook(&ctx, || {
+            let desc = PropertyDescriptor::from_definition(&ctx, val, getter, setter, flags);
+            match C::define_own_property(&this_ptr.as_ref().data, &ctx, key, desc)? {
+                Some(true) => Ok(1),
+                Some(false) => rejected(&ctx, flags, "could not define property"),
+                None => {
+                    let res = qjs::JS_DefineProperty(
+                        ctx.as_ptr(),
+                        obj,
+                        prop,
+                        val,
+                        getter,
+                        setter,
+                        flags | qjs::JS_PROP_NO_EXOTIC as qjs::c_int,
+                    );
+                    if res < 0 {
+                        Err(ctx.raise_exception())
+                    } else {
+                        Ok(res)
+                    }
+                }
+            }
+        })
+    }
+
+    unsafe fn has_property_impl<'js, C: JsClass<'js>>(
+        this_ptr: NonNull<ClassCell<()>>,
+        ctx: *mut qjs::JSContext,
+        obj: qjs::JSValue,
+        prop: qjs::JSAtom,
+    ) -> qjs::c_int {
+        let this_ptr = this_ptr.cast::<ClassCell<JsCell<C>>>();
+        let ctx = Ctx::from_ptr(ctx);
+        let key = Atom::from_atom_val_dup(ctx.clone(), prop);
+        handle_hook(&ctx, || {
+            let res = match C::has_property(&this_ptr.as_ref().data, &ctx, key.clone())? {
+                Some(x) => x,
+                None => {
+                    let obj = Object::from_js_value_const(ctx.clone(), obj);
+                    exotic::ordinary_has(&obj, &key)?
+                }
+            };
+            Ok(res as qjs::c_int)
+        })
+    }
+
+    unsafe fn get_property_impl<'js, C: JsClass<'js>>(
+        this_ptr: NonNull<ClassCell<()>>,
+        ctx: *mut qjs::JSContext,
+        obj: qjs::JSValue,
+        prop: qjs::JSAtom,
+        receiver: qjs::JSValue,
+    ) -> qjs::JSValue {
+        let this_ptr = this_ptr.cast::<ClassCell<JsCell<C>>>();
+        let ctx = Ctx::from_ptr(ctx);
+        let key = Atom::from_atom_val_dup(ctx.clone(), prop);
+        let receiver = Value::from_js_value_const(ctx.clone(), receiver);
+
+        ctx.handle_panic(AssertUnwindSafe(|| {
+            let res =
+                match C::get_property(&this_ptr.as_ref().data, &ctx, key.clone(), receiver.clone())
+                {
+                    Ok(Some(x)) => Ok(x),
+                    Ok(None) => {
+                        let obj = Object::from_js_value_const(ctx.clone(), obj);
+                        exotic::ordinary_get(&obj, &key, receiver)
+                    }
+                    Err(e) => Err(e),
+                };
+            res.map(Value::into_js_value)
+                .unwrap_or_else(|e| e.throw(&ctx))
+        }))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    unsafe fn set_property_impl<'js, C: JsClass<'js>>(
+        this_ptr: NonNull<ClassCell<()>>,
+        ctx: *mut qjs::JSContext,
+        obj: qjs::JSValue,
+        prop: qjs::JSAtom,
+        value: qjs::JSValue,
+        receiver: qjs::JSValue,
+        flags: qjs::c_int,
+    ) -> qjs::c_int {
+        let this_ptr = this_ptr.cast::<ClassCell<JsCell<C>>>();
+        let ctx = Ctx::from_ptr(ctx);
+        let key = Atom::from_atom_val_dup(ctx.clone(), prop);
+        let value = Value::from_js_value_const(ctx.clone(), value);
+        let receiver = Value::from_js_value_const(ctx.clone(), receiver);
+        handle_hook(&ctx, || {
+            let res = match C::set_property(
+                &this_ptr.as_ref().data,
+                &ctx,
+                key.clone(),
+                value.clone(),
+                receiver.clone(),
+            )? {
+                Some(x) => x,
+                None => {
+                    let obj = Object::from_js_value_const(ctx.clone(), obj);
+                    exotic::ordinary_set(&obj, &key, value, receiver)?
+                }
+            };
+            if res {
+                Ok(1)
+            } else {
+                rejected(&ctx, flags, "could not set property")
+            }
+        })
+    }
+
+    const fn get<'js, C: JsClass<'js>>() -> ExoticVTable {
+        ExoticVTable {
+            get_own_property: Self::get_own_property_impl::<C>,
+            get_own_property_names: Self::get_own_property_names_impl::<C>,
+            delete_property: Self::delete_property_impl::<C>,
+            define_own_property: Self::define_own_property_impl::<C>,
+            has_property: Self::has_property_impl::<C>,
+            get_property: Self::get_property_impl::<C>,
+            set_property: Self::set_property_impl::<C>,
+        }
+    }
+}
 
 pub(crate) struct VTable {
     id_fn: TypeIdFn,
     finalizer: FinalizerFunc,
     trace: TraceFunc,
     call: CallFunc,
+    prototype: PrototypeFunc,
+    parent: Option<&'static VTable>,
+    exotic: ExoticVTable,
+    name: &'static str,
+    size: usize,
 }
 
 impl VTable {
@@ -119,7 +539,17 @@ impl VTable {
         }))
     }
 
-    pub fn get<'js, C: JsClass<'js>>() -> &'static VTable {
+    unsafe fn prototype_impl<'a, 'js, C: JsClass<'js>>(
+        ctx: &Ctx<'a>,
+    ) -> Result<Option<Object<'a>>> {
+        let ctx = &*(ctx as *const Ctx<'a>).cast::<Ctx<'js>>();
+        let proto = Class::<C>::prototype(ctx)?;
+        Ok(mem::transmute::<Option<Object<'js>>, Option<Object<'a>>>(
+            proto,
+        ))
+    }
+
+    pub const fn get<'js, C: JsClass<'js>>() -> &'static VTable {
         trait HasVTable {
             const VTABLE: VTable;
         }
@@ -130,6 +560,14 @@ impl VTable {
                 finalizer: VTable::finalizer_impl::<'js, C>,
                 trace: VTable::trace_impl::<C>,
                 call: VTable::call_impl::<C>,
+                prototype: VTable::prototype_impl::<C>,
+                parent: match C::EXTENDS {
+                    Some(x) => Some(x.0),
+                    None => None,
+                },
+                exotic: ExoticVTable::get::<C>(),
+                name: C::NAME,
+                size: mem::size_of::<ClassCell<JsCell<C>>>(),
             };
         }
         &<C as HasVTable>::VTABLE
@@ -139,9 +577,44 @@ impl VTable {
         (self.id_fn)()
     }
 
+    /// Returns the [`JsClass::NAME`] of the class.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the size of the Rust allocation of a class instance.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Trace the references of a class instance.
+    pub unsafe fn trace(&self, this: NonNull<ClassCell<()>>, tracer: Tracer<'_, 'static>) {
+        (self.trace)(this, tracer)
+    }
+
     pub fn is_of_class<'js, C: JsClass<'js>>(&self) -> bool {
         (self.id_fn)() == TypeId::of::<C::Changed<'static>>()
     }
+
+    /// Returns if the class of this v table is `C` or extends `C`.
+    pub fn is_or_extends_class<'js, C: JsClass<'js>>(&self) -> bool {
+        let mut v_table = Some(self);
+        while let Some(x) = v_table {
+            if core::ptr::eq(x, VTable::get::<C>()) || x.is_of_class::<C>() {
+                return true;
+            }
+            v_table = x.parent;
+        }
+        false
+    }
+
+    /// Returns the prototype of the class this class extends, if any.
+    pub fn parent_prototype<'js>(&self, ctx: &Ctx<'js>) -> Result<Option<Object<'js>>> {
+        match self.parent {
+            Some(x) => unsafe { (x.prototype)(ctx) },
+            None => Ok(None),
+        }
+    }
 }
 
 #[repr(C)]
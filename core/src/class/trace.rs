@@ -1,6 +1,7 @@
 use super::JsClass;
 use crate::{markers::Invariant, qjs, Class, Ctx, Module, Value};
-use core::marker::PhantomData;
+use alloc::vec::Vec;
+use core::{cell::RefCell, marker::PhantomData};
 
 #[cfg(feature = "either")]
 use either::{Either, Left, Right};
@@ -20,6 +21,8 @@ pub trait Trace<'js> {
 pub struct Tracer<'a, 'js> {
     rt: *mut qjs::JSRuntime,
     mark_func: qjs::JS_MarkFunc,
+    /// If set, the marked values are collected instead of being passed to the `mark_func`.
+    collector: Option<&'a RefCell<Vec<qjs::JSValue>>>,
     /// This trace should not be able to be used with different runtimes
     _inv: Invariant<'js>,
     /// Marker for acting like a reference so that the tracer can't be stored in an object.
@@ -36,6 +39,23 @@ impl<'a, 'js> Tracer<'a, 'js> {
         Self {
             rt,
             mark_func,
+            collector: None,
+            _inv: Invariant::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Create a tracer which collects the marked values instead of marking them.
+    ///
+    /// The collected values are not reference counted.
+    pub(crate) fn collect(
+        rt: *mut qjs::JSRuntime,
+        collector: &'a RefCell<Vec<qjs::JSValue>>,
+    ) -> Self {
+        Self {
+            rt,
+            mark_func: None,
+            collector: Some(collector),
             _inv: Invariant::new(),
             _marker: PhantomData,
         }
@@ -45,6 +65,7 @@ impl<'a, 'js> Tracer<'a, 'js> {
         Tracer {
             rt: self.rt,
             mark_func: self.mark_func,
+            collector: self.collector,
             _inv: Invariant::new(),
             _marker: PhantomData,
         }
@@ -52,6 +73,10 @@ impl<'a, 'js> Tracer<'a, 'js> {
 
     /// Mark a value as being reachable from the current traced object.
     pub fn mark(self, value: &Value<'js>) {
+        if let Some(collector) = self.collector {
+            collector.borrow_mut().push(value.as_js_value());
+            return;
+        }
         self.mark_ctx(value.ctx());
         let value = value.as_js_value();
         if unsafe { qjs::JS_VALUE_HAS_REF_COUNT(value) } {
@@ -61,6 +86,9 @@ impl<'a, 'js> Tracer<'a, 'js> {
 
     /// Mark the ctx object, this function should be called on any bare ctx objects.
     pub fn mark_ctx(self, ctx: &Ctx<'js>) {
+        if self.collector.is_some() {
+            return;
+        }
         let ptr = ctx.as_ptr();
         unsafe { (self.mark_func.unwrap())(self.rt, ptr.cast()) }
     }
@@ -222,6 +250,12 @@ trace_impls! {
     crate::Symbol,
     crate::Exception,
     crate::String,
+    crate::Map,
+    crate::Set,
+    crate::Date,
+    crate::RegExp,
+    crate::DataView,
+    crate::Proxy,
 }
 
 trace_impls! {
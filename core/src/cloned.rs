@@ -0,0 +1,390 @@
+//! Structured cloning of JavaScript values between contexts and runtimes.
+
+use crate::{qjs, ArrayBuffer, Atom, Coerced, Ctx, Error, Result, StdString, Value};
+use alloc::vec::Vec;
+use core::{
+    ffi::c_void,
+    fmt::Write as _,
+    mem::MaybeUninit,
+    ptr::{self, NonNull},
+    slice,
+};
+
+const WRITE_FLAGS: i32 = qjs::JS_WRITE_OBJ_REFERENCE as i32;
+const READ_FLAGS: i32 = qjs::JS_READ_OBJ_REFERENCE as i32;
+
+/// A JavaScript value serialized with a structured clone.
+///
+/// A cloned value is detached from the runtime it was created in and can be restored in any
+/// context of any runtime, possibly on another thread.
+///
+/// Besides primitive values, including `BigInt`s, cloning supports plain objects, arrays, `Map`,
+/// `Set`, `Date`, `RegExp`, `ArrayBuffer` and typed arrays. Values referenced multiple times,
+/// including cycles, are restored as a single value. Instances of JavaScript classes are cloned
+/// as plain objects and lose their prototype, as with the HTML structured clone algorithm.
+/// Functions, symbols, instances of Rust [`Class`](crate::class::Class)es and objects with
+/// accessor properties can't be cloned and result in an [`Error::DataClone`] pointing to the
+/// offending value.
+///
+/// ```
+/// # use rquickjs::{Context, ClonedValue, Runtime, Value};
+/// let rt = Runtime::new().unwrap();
+/// let ctx = Context::full(&rt).unwrap();
+/// let cloned = ctx.with(|ctx| {
+///     let value: Value = ctx.eval("new Map([[1n, new Set(['a'])]])").unwrap();
+///     ClonedValue::new(&value).unwrap()
+/// });
+///
+/// let other_rt = Runtime::new().unwrap();
+/// let other_ctx = Context::full(&other_rt).unwrap();
+/// other_ctx.with(|ctx| {
+///     ctx.globals().set("value", cloned.restore(&ctx).unwrap()).unwrap();
+///     assert!(ctx.eval::<bool, _>("value.get(1n).has('a')").unwrap());
+/// });
+/// ```
+#[derive(Debug, Clone)]
+pub struct ClonedValue {
+    bytes: Vec<u8>,
+}
+
+impl ClonedValue {
+    /// Clone a value.
+    pub fn new<'js>(value: &Value<'js>) -> Result<Self> {
+        Self::with_transfer(value, &[])
+    }
+
+    /// Clone a value, transferring the given array buffers.
+    ///
+    /// After cloning the transferred buffers are detached, leaving them empty in the source
+    /// context while their contents live on in the cloned value.
+    pub fn with_transfer<'js>(value: &Value<'js>, transfer: &[ArrayBuffer<'js>]) -> Result<Self> {
+        for (idx, buffer) in transfer.iter().enumerate() {
+            let path = || alloc::format!("transfer[{idx}]");
+            if buffer.as_bytes().is_none() {
+                return Err(Error::new_data_clone_message(
+                    "ArrayBuffer",
+                    path(),
+                    "ArrayBuffer is detached",
+                ));
+            }
+            if transfer[..idx].contains(buffer) {
+                return Err(Error::new_data_clone_message(
+                    "ArrayBuffer",
+                    path(),
+                    "ArrayBuffer is transferred more than once",
+                ));
+            }
+        }
+
+        let bytes = match write(value) {
+            Some(bytes) => bytes,
+            None => return Err(write_error(value)),
+        };
+
+        for buffer in transfer {
+            unsafe { qjs::JS_DetachArrayBuffer(buffer.ctx().as_ptr(), buffer.as_js_value()) };
+        }
+
+        Ok(ClonedValue { bytes })
+    }
+
+    /// Restore the value in the given context.
+    ///
+    /// A cloned value can be restored multiple times, each time creating a new copy.
+    pub fn restore<'js>(&self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
+        unsafe {
+            let value = qjs::JS_ReadObject2(
+                ctx.as_ptr(),
+                self.bytes.as_ptr(),
+                self.bytes.len() as _,
+                READ_FLAGS,
+                ptr::null_mut(),
+            );
+            let value = ctx.handle_exception(value)?;
+            Ok(Value::from_js_value(ctx.clone(), value))
+        }
+    }
+}
+
+/// Serialize a value, leaving the exception pending on failure.
+fn write(value: &Value) -> Option<Vec<u8>> {
+    let ctx = value.ctx();
+    let mut len = MaybeUninit::uninit();
+    let buf = unsafe {
+        qjs::JS_WriteObject2(
+            ctx.as_ptr(),
+            len.as_mut_ptr(),
+            value.as_js_value(),
+            WRITE_FLAGS,
+            ptr::null_mut(),
+        )
+    };
+    let buf = NonNull::new(buf)?;
+    let len = unsafe { len.assume_init() };
+    let bytes = Vec::from(unsafe { slice::from_raw_parts(buf.as_ptr(), len as _) });
+    unsafe { qjs::js_free(ctx.as_ptr(), buf.as_ptr().cast()) };
+    Some(bytes)
+}
+
+/// Build an error for a value which failed to serialize by locating the value which could not be
+/// cloned.
+fn write_error(value: &Value) -> Error {
+    let ctx = value.ctx();
+    let message = take_exception_message(ctx);
+
+    let mut path = StdString::from("$");
+    let mut visited = Vec::new();
+    match locate(value.clone(), &mut path, &mut visited) {
+        Ok((culprit, Some(message))) => {
+            Error::new_data_clone_message(culprit.type_name(), path, message)
+        }
+        Ok((culprit, None)) => match message {
+            Some(message) => Error::new_data_clone_message(culprit.type_name(), path, message),
+            None => Error::new_data_clone(culprit.type_name(), path),
+        },
+        Err(e) => e,
+    }
+}
+
+/// Descend into a value which failed to serialize and return the first value which also fails to
+/// serialize on its own while none of its properties do.
+fn locate<'js>(
+    value: Value<'js>,
+    path: &mut StdString,
+    visited: &mut Vec<*mut c_void>,
+) -> Result<(Value<'js>, Option<StdString>)> {
+    let Some(object) = value.as_object() else {
+        return Ok((value, None));
+    };
+    let ptr = unsafe { qjs::JS_VALUE_GET_PTR(object.as_js_value()) };
+    if visited.contains(&ptr) {
+        return Ok((value, None));
+    }
+    visited.push(ptr);
+
+    let ctx = value.ctx();
+    for key in object.keys::<Atom>() {
+        let key = key?;
+        let mut desc = MaybeUninit::<qjs::JSPropertyDescriptor>::uninit();
+        let res = unsafe {
+            qjs::JS_GetOwnProperty(
+                ctx.as_ptr(),
+                desc.as_mut_ptr(),
+                object.as_js_value(),
+                key.atom,
+            )
+        };
+        if res < 0 {
+            return Err(ctx.raise_exception());
+        }
+        if res == 0 {
+            continue;
+        }
+        let desc = unsafe { desc.assume_init() };
+        let (child, getter, setter) = unsafe {
+            (
+                Value::from_js_value(ctx.clone(), desc.value),
+                Value::from_js_value(ctx.clone(), desc.getter),
+                Value::from_js_value(ctx.clone(), desc.setter),
+            )
+        };
+
+        if desc.flags & qjs::JS_PROP_GETSET as i32 != 0 {
+            push_key(path, &key)?;
+            let accessor = if getter.is_undefined() {
+                setter
+            } else {
+                getter
+            };
+            return Ok((accessor, Some("Accessor properties can't be cloned".into())));
+        }
+        if write(&child).is_none() {
+            let message = take_exception_message(ctx);
+            push_key(path, &key)?;
+            return match locate(child, path, visited)? {
+                (culprit, None) => Ok((culprit, message)),
+                res => Ok(res),
+            };
+        }
+    }
+
+    Ok((value, None))
+}
+
+fn push_key(path: &mut StdString, key: &Atom) -> Result<()> {
+    let key = key.to_string()?;
+    if key.parse::<u32>().is_ok() {
+        let _ = write!(path, "[{key}]");
+    } else {
+        let _ = write!(path, ".{key}");
+    }
+    Ok(())
+}
+
+/// Take the pending exception and return its message, if any.
+fn take_exception_message(ctx: &Ctx) -> Option<StdString> {
+    let exception = ctx.catch();
+    let message = match exception.as_exception() {
+        Some(exception) => exception.message(),
+        None => exception.get::<Coerced<StdString>>().ok().map(|x| x.0),
+    };
+    message.filter(|x| !x.is_empty())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        class::{JsClass, Readable, Trace, Tracer},
+        *,
+    };
+
+    #[test]
+    fn clone_between_runtimes() {
+        let cloned = test_with(|ctx| {
+            let value: Value = ctx
+                .eval(
+                    r#"
+                    const shared = { n: 1 };
+                    const value = {
+                        map: new Map([["a", 1n << 70n]]),
+                        set: new Set([1, 2]),
+                        date: new Date(0),
+                        bytes: new Uint16Array([1, 2, 3]),
+                        shared,
+                        again: shared,
+                    };
+                    value.self = value;
+                    value
+                "#,
+                )
+                .unwrap();
+            ClonedValue::new(&value).unwrap()
+        });
+
+        test_with(|ctx| {
+            ctx.globals()
+                .set("value", cloned.restore(&ctx).unwrap())
+                .unwrap();
+            let ok: bool = ctx
+                .eval(
+                    r#"
+                    value.map.get("a") === 1n << 70n &&
+                    value.set.has(2) &&
+                    value.date.getTime() === 0 &&
+                    value.bytes instanceof Uint16Array && value.bytes[2] === 3 &&
+                    value.shared === value.again &&
+                    value.self === value
+                "#,
+                )
+                .unwrap();
+            assert!(ok);
+        });
+    }
+
+    #[test]
+    fn structured_clone() {
+        test_with(|ctx| {
+            let value: Value = ctx.eval("({ a: [1, 'b'] })").unwrap();
+            let cloned = ctx.structured_clone(&value, &ctx).unwrap();
+            ctx.globals().set("a", value).unwrap();
+            ctx.globals().set("b", cloned).unwrap();
+            let ok: bool = ctx
+                .eval("a !== b && a.a !== b.a && b.a[1] === 'b'")
+                .unwrap();
+            assert!(ok);
+        });
+    }
+
+    #[test]
+    fn transfer() {
+        test_with(|ctx| {
+            let buffer: ArrayBuffer = ctx.eval("new Uint8Array([1, 2, 3]).buffer").unwrap();
+            let value = buffer.clone().into_value();
+            let cloned =
+                ClonedValue::with_transfer(&value, core::slice::from_ref(&buffer)).unwrap();
+            assert!(buffer.as_bytes().is_none());
+
+            let restored = cloned.restore(&ctx).unwrap();
+            let restored = ArrayBuffer::from_value(restored).unwrap();
+            assert_eq!(restored.as_bytes(), Some(&[1u8, 2, 3][..]));
+
+            let err = ClonedValue::with_transfer(&value, &[buffer]).unwrap_err();
+            assert!(err.is_data_clone());
+            assert!(err.to_string().contains("transfer[0]"), "{err}");
+        });
+    }
+
+    #[test]
+    fn uncloneable() {
+        test_with(|ctx| {
+            let value: Value = ctx.eval("({ list: [1, { handler() {} }] })").unwrap();
+            let err = ClonedValue::new(&value).unwrap_err();
+            let Error::DataClone { from, path, .. } = &err else {
+                panic!("unexpected error: {err}");
+            };
+            assert_eq!(*from, "function");
+            assert_eq!(path, "$.list[1].handler");
+
+            let value: Value = ctx.eval("({ get a() { return 1 } })").unwrap();
+            let err = ClonedValue::new(&value).unwrap_err().to_string();
+            assert!(err.contains("`$.a`"), "{err}");
+            assert!(err.contains("Accessor"), "{err}");
+
+            let object = Object::new(ctx.clone()).unwrap();
+            object
+                .set("native", Function::new(ctx.clone(), || 1).unwrap())
+                .unwrap();
+            let err = ClonedValue::new(&object).unwrap_err().to_string();
+            assert!(err.contains("`$.native`"), "{err}");
+
+            // Only the own data properties of JavaScript class instances are cloned.
+            let value: Value = ctx.eval("class Foo { a = 1 }; new Foo()").unwrap();
+            let cloned = ClonedValue::new(&value).unwrap().restore(&ctx).unwrap();
+            ctx.globals().set("cloned", cloned).unwrap();
+            let ok: bool = ctx
+                .eval("!(cloned instanceof Foo) && cloned.a === 1")
+                .unwrap();
+            assert!(ok);
+
+            // No exception must be left behind.
+            assert!(!ctx.catch().is_exception());
+        });
+    }
+
+    #[derive(Clone, Copy)]
+    struct Point;
+
+    impl<'js> Trace<'js> for Point {
+        fn trace<'a>(&self, _tracer: Tracer<'a, 'js>) {}
+    }
+
+    unsafe impl<'js> JsLifetime<'js> for Point {
+        type Changed<'to> = Point;
+    }
+
+    impl<'js> JsClass<'js> for Point {
+        const NAME: &'static str = "Point";
+
+        type Mutable = Readable;
+
+        fn constructor(_ctx: &Ctx<'js>) -> Result<Option<crate::value::Constructor<'js>>> {
+            Ok(None)
+        }
+    }
+
+    #[test]
+    fn uncloneable_class() {
+        test_with(|ctx| {
+            let point = Class::instance(ctx.clone(), Point).unwrap();
+            let list = Array::new(ctx.clone()).unwrap();
+            list.set(0, point).unwrap();
+            let err = ClonedValue::new(&list.into_value()).unwrap_err();
+            let Error::DataClone { from, path, .. } = &err else {
+                panic!("unexpected error: {err}");
+            };
+            assert_eq!(*from, "object");
+            assert_eq!(path, "$[0]");
+            assert!(!ctx.catch().is_exception());
+        });
+    }
+}
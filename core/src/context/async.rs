@@ -3,9 +3,20 @@ use super::{
     owner::{ContextOwner, DropContext},
     ContextBuilder, Intrinsic,
 };
-use crate::{markers::ParallelSend, qjs, runtime::AsyncRuntime, Ctx, Error, Result};
+use crate::{
+    allocator::Account,
+    markers::ParallelSend,
+    qjs,
+    runtime::{opaque::ContextGuard, AsyncRuntime},
+    Ctx, Error, Result,
+};
 use alloc::boxed::Box;
-use core::{future::Future, mem, pin::Pin, ptr::NonNull};
+use core::{
+    future::Future,
+    mem,
+    pin::Pin,
+    ptr::{self, NonNull},
+};
 
 mod future;
 
@@ -156,10 +167,18 @@ impl AsyncContext {
     /// [`AsyncContext::builder`] or [`AsyncContext::full`].
     pub async fn custom<I: Intrinsic>(runtime: &AsyncRuntime) -> Result<Self> {
         let guard = runtime.inner.lock().await;
-        let ctx = NonNull::new(unsafe { qjs::JS_NewContextRaw(guard.runtime.rt.as_ptr()) })
-            .ok_or(Error::Allocation)?;
-        unsafe { qjs::JS_AddIntrinsicBaseObjects(ctx.as_ptr()) };
-        unsafe { I::add_intrinsic(ctx) };
+        let ctx = unsafe {
+            guard.runtime.get_opaque().new_context(|| {
+                let Some(ctx) = NonNull::new(qjs::JS_NewContextRaw(guard.runtime.rt.as_ptr()))
+                else {
+                    return ptr::null_mut();
+                };
+                qjs::JS_AddIntrinsicBaseObjects(ctx.as_ptr());
+                I::add_intrinsic(ctx);
+                ctx.as_ptr()
+            })
+        };
+        let ctx = NonNull::new(ctx).ok_or(Error::Allocation)?;
         let res = unsafe { ContextOwner::new(ctx, runtime.clone()) };
         guard.drop_pending();
         mem::drop(guard);
@@ -172,8 +191,13 @@ impl AsyncContext {
     /// [`AsyncContext::custom`] or [`AsyncContext::builder`].
     pub async fn full(runtime: &AsyncRuntime) -> Result<Self> {
         let guard = runtime.inner.lock().await;
-        let ctx = NonNull::new(unsafe { qjs::JS_NewContext(guard.runtime.rt.as_ptr()) })
-            .ok_or(Error::Allocation)?;
+        let ctx = unsafe {
+            guard
+                .runtime
+                .get_opaque()
+                .new_context(|| qjs::JS_NewContext(guard.runtime.rt.as_ptr()))
+        };
+        let ctx = NonNull::new(ctx).ok_or(Error::Allocation)?;
         let res = unsafe { ContextOwner::new(ctx, runtime.clone()) };
         // Explicitly drop the guard to ensure it is valid during the entire use of runtime
         guard.drop_pending();
@@ -192,6 +216,24 @@ impl AsyncContext {
         self.0.rt()
     }
 
+    /// Returns the amount of memory in bytes currently attributed to this context.
+    ///
+    /// See [`Context::memory_usage`](crate::Context::memory_usage).
+    pub async fn memory_usage(&self) -> usize {
+        let _guard = self.0.rt().inner.lock().await;
+        unsafe { Account::of_context(self.0.ctx().as_ptr()) }.map_or(0, |x| x.size())
+    }
+
+    /// Set a limit on the amount of memory attributed to this context.
+    ///
+    /// See [`Context::set_memory_limit`](crate::Context::set_memory_limit).
+    pub async fn set_memory_limit(&self, limit: usize) {
+        let _guard = self.0.rt().inner.lock().await;
+        if let Some(account) = unsafe { Account::of_context(self.0.ctx().as_ptr()) } {
+            account.set_limit(limit);
+        }
+    }
+
     /// A entry point for manipulating and using JavaScript objects and scripts.
     ///
     /// This function is rather limited in what environment it can capture. If you need to borrow
@@ -220,8 +262,11 @@ impl AsyncContext {
     {
         let guard = self.0.rt().inner.lock().await;
         guard.runtime.update_stack_top();
+        let entered =
+            unsafe { ContextGuard::new(guard.runtime.rt.as_ptr(), self.0.ctx().as_ptr()) };
         let ctx = unsafe { Ctx::new_async(self) };
         let res = f(ctx);
+        drop(entered);
         guard.drop_pending();
         res
     }
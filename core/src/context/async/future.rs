@@ -10,7 +10,7 @@ use async_lock::futures::Lock;
 
 use crate::{
     markers::ParallelSend,
-    runtime::{schedular::SchedularPoll, InnerRuntime},
+    runtime::{opaque::ContextGuard, schedular::SchedularPoll, InnerRuntime},
     AsyncContext, Ctx,
 };
 
@@ -89,6 +89,8 @@ where
         };
 
         lock.runtime.update_stack_top();
+        let _entered =
+            unsafe { ContextGuard::new(lock.runtime.rt.as_ptr(), this.context.0.ctx().as_ptr()) };
 
         // At this point we have locked the runtime so we start running the actual future
         // we can move this memory since the future is boxed and thus movable.
@@ -4,8 +4,11 @@ use super::{
     owner::{ContextOwner, DropContext},
     ContextBuilder, Intrinsic,
 };
-use crate::{qjs, Ctx, Error, Result, Runtime};
-use core::{mem, ptr::NonNull};
+use crate::{allocator::Account, qjs, runtime::opaque::ContextGuard, Ctx, Error, Result, Runtime};
+use core::{
+    mem,
+    ptr::{self, NonNull},
+};
 
 impl DropContext for Runtime {
     unsafe fn drop_context(&self, ctx: NonNull<qjs::JSContext>) {
@@ -66,11 +69,18 @@ impl Context {
     /// [`Context::builder`] or [`Context::full`].
     pub fn custom<I: Intrinsic>(runtime: &Runtime) -> Result<Self> {
         let guard = runtime.inner.lock();
-        let ctx = NonNull::new(unsafe { qjs::JS_NewContextRaw(guard.rt.as_ptr()) })
-            .ok_or(Error::Allocation)?;
-        // rquickjs assumes the base objects exist, so we allways need to add this.
-        unsafe { qjs::JS_AddIntrinsicBaseObjects(ctx.as_ptr()) };
-        unsafe { I::add_intrinsic(ctx) };
+        let ctx = unsafe {
+            guard.get_opaque().new_context(|| {
+                let Some(ctx) = NonNull::new(qjs::JS_NewContextRaw(guard.rt.as_ptr())) else {
+                    return ptr::null_mut();
+                };
+                // rquickjs assumes the base objects exist, so we allways need to add this.
+                qjs::JS_AddIntrinsicBaseObjects(ctx.as_ptr());
+                I::add_intrinsic(ctx);
+                ctx.as_ptr()
+            })
+        };
+        let ctx = NonNull::new(ctx).ok_or(Error::Allocation)?;
         let res = unsafe { ContextOwner::new(ctx, runtime.clone()) };
         mem::drop(guard);
 
@@ -82,8 +92,12 @@ impl Context {
     /// [`Context::custom`] or [`Context::builder`].
     pub fn full(runtime: &Runtime) -> Result<Self> {
         let guard = runtime.inner.lock();
-        let ctx = NonNull::new(unsafe { qjs::JS_NewContext(guard.rt.as_ptr()) })
-            .ok_or(Error::Allocation)?;
+        let ctx = unsafe {
+            guard
+                .get_opaque()
+                .new_context(|| qjs::JS_NewContext(guard.rt.as_ptr()))
+        };
+        let ctx = NonNull::new(ctx).ok_or(Error::Allocation)?;
         let res = unsafe { ContextOwner::new(ctx, runtime.clone()) };
         // Explicitly drop the guard to ensure it is valid during the entire use of runtime
         mem::drop(guard);
@@ -101,6 +115,34 @@ impl Context {
         self.0.rt()
     }
 
+    /// Returns the amount of memory in bytes currently attributed to this context.
+    ///
+    /// Memory is attributed to the context which was entered, for example with
+    /// [`Context::with`], when the memory was allocated, and stays attributed to it until it is
+    /// freed.
+    ///
+    /// Note that memory is only accounted per context when the runtime uses a custom
+    /// [`Allocator`](crate::allocator::Allocator), as is the case for the "rust-alloc" feature
+    /// or [`Runtime::new_with_alloc`]. Otherwise this always returns 0.
+    pub fn memory_usage(&self) -> usize {
+        let _guard = self.0.rt().inner.lock();
+        unsafe { Account::of_context(self.0.ctx().as_ptr()) }.map_or(0, |x| x.size())
+    }
+
+    /// Set a limit on the amount of memory attributed to this context.
+    ///
+    /// Allocations which would exceed the limit fail, which raises a catchable out of memory
+    /// error in JavaScript. Setting the limit to 0 is equivalent to unlimited memory.
+    ///
+    /// Note that, like [`Context::memory_usage`], this is a Noop unless the runtime uses a
+    /// custom [`Allocator`](crate::allocator::Allocator).
+    pub fn set_memory_limit(&self, limit: usize) {
+        let _guard = self.0.rt().inner.lock();
+        if let Some(account) = unsafe { Account::of_context(self.0.ctx().as_ptr()) } {
+            account.set_limit(limit);
+        }
+    }
+
     #[allow(dead_code)]
     pub fn get_runtime_ptr(&self) -> *mut qjs::JSRuntime {
         unsafe { qjs::JS_GetRuntime(self.0.ctx().as_ptr()) }
@@ -120,6 +162,7 @@ impl Context {
     {
         let guard = self.0.rt().inner.lock();
         guard.update_stack_top();
+        let _entered = unsafe { ContextGuard::new(guard.rt.as_ptr(), self.0.ctx().as_ptr()) };
         let ctx = unsafe { Ctx::new(self) };
         f(ctx)
     }
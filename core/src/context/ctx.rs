@@ -1,7 +1,7 @@
 #[cfg(feature = "futures")]
 use core::future::Future;
 
-use alloc::{boxed::Box, ffi::CString, vec::Vec};
+use alloc::{boxed::Box, ffi::CString, string::String as StdString, vec::Vec};
 use core::{
     any::Any,
     ffi::CStr,
@@ -11,19 +11,68 @@ use core::{
 };
 
 #[cfg(feature = "std")]
-use std::{fs, path::Path};
+use std::{
+    fs,
+    path::Path,
+    time::{Duration, Instant},
+};
 
-#[cfg(feature = "futures")]
-use crate::AsyncContext;
 use crate::{
     markers::Invariant,
     qjs,
-    runtime::{opaque::Opaque, UserDataError, UserDataGuard},
-    Atom, Error, FromJs, Function, IntoJs, JsLifetime, Object, Promise, Result, String, Value,
+    runtime::{
+        interrupt::LimitsScope, opaque::Opaque, raw::update_interrupt_handler, HeapSnapshot,
+        UserDataError, UserDataGuard,
+    },
+    Atom, ClonedValue, Error, FromJs, Function, IntoJs, JsLifetime, Object, Promise, Result,
+    String, Value,
+};
+#[cfg(feature = "futures")]
+use crate::{
+    runtime::{Abortable, InContext, TaskHandle},
+    AsyncContext,
 };
 
 use super::Context;
 
+/// Restores the execution limits of the runtime when dropped, also when unwinding.
+struct LimitsGuard<'a, 'js> {
+    ctx: &'a Ctx<'js>,
+    scope: Option<LimitsScope>,
+}
+
+impl<'a, 'js> LimitsGuard<'a, 'js> {
+    fn new(
+        ctx: &'a Ctx<'js>,
+        #[cfg(feature = "std")] deadline: Option<Instant>,
+        budget: Option<u64>,
+    ) -> Self {
+        unsafe {
+            let scope = ctx.get_opaque().limits().enter_scope(
+                #[cfg(feature = "std")]
+                deadline,
+                budget,
+            );
+            update_interrupt_handler(qjs::JS_GetRuntime(ctx.as_ptr()));
+            LimitsGuard {
+                ctx,
+                scope: Some(scope),
+            }
+        }
+    }
+}
+
+impl Drop for LimitsGuard<'_, '_> {
+    fn drop(&mut self) {
+        if let Some(scope) = self.scope.take() {
+            unsafe {
+                self.ctx.get_opaque().limits().exit_scope(scope);
+                update_interrupt_handler(qjs::JS_GetRuntime(self.ctx.as_ptr()));
+            }
+        }
+    }
+}
+
 /// Eval options.
 #[non_exhaustive]
 pub struct EvalOptions {
@@ -252,6 +301,15 @@ impl<'js> Ctx<'js> {
         Error::Exception
     }
 
+    /// Create a structured clone of a value in the target context.
+    ///
+    /// The target context can belong to any runtime, including the runtime of this context. See
+    /// [`ClonedValue`] for which values can be cloned; use it directly to move a clone to another
+    /// thread or to transfer array buffers.
+    pub fn structured_clone<'t>(&self, value: &Value<'js>, target: &Ctx<'t>) -> Result<Value<'t>> {
+        ClonedValue::new(value)?.restore(target)
+    }
+
     /// Parse json into a JavaScript value.
     pub fn json_parse<S>(&self, json: S) -> Result<Value<'js>>
     where
@@ -384,18 +442,74 @@ impl<'js> Ctx<'js> {
         res != 0
     }
 
+    /// Run `f`, interrupting any code it executes after `timeout` has elapsed from now.
+    ///
+    /// This limits a single call, like a [`Ctx::eval`] or [`Function::call`], instead of the
+    /// whole runtime. An earlier deadline set on the runtime or by an enclosing call is kept.
+    /// Code which is interrupted returns [`Error::Interrupted`].
+    ///
+    /// ```
+    /// # use rquickjs::{Runtime, Context, Error, runtime::InterruptReason};
+    /// # use std::time::Duration;
+    /// # let rt = Runtime::new().unwrap();
+    /// # let ctx = Context::full(&rt).unwrap();
+    /// ctx.with(|ctx| {
+    ///     let res = ctx.with_execution_deadline(Duration::from_millis(10), || {
+    ///         ctx.eval::<(), _>("for(;;){}")
+    ///     });
+    ///     assert!(matches!(
+    ///         res,
+    ///         Err(Error::Interrupted {
+    ///             reason: InterruptReason::Deadline
+    ///         })
+    ///     ));
+    /// });
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn with_execution_deadline<R, F>(&self, timeout: Duration, f: F) -> R
+    where
+        F: FnOnce() -> R,
+    {
+        let deadline = Instant::now() + timeout;
+        let _guard = LimitsGuard::new(self, Some(deadline), None);
+        f()
+    }
+
+    /// Run `f`, interrupting any code it executes after the interrupt handler has been polled
+    /// `budget` times.
+    ///
+    /// Like [`Ctx::with_execution_deadline`] this limits only the code executed by `f`. Polls
+    /// made while running `f` also count against the budget of the runtime or an enclosing call,
+    /// if any. See [`Runtime::set_interrupt_budget`](crate::Runtime::set_interrupt_budget).
+    pub fn with_interrupt_budget<R, F>(&self, budget: u64, f: F) -> R
+    where
+        F: FnOnce() -> R,
+    {
+        let _guard = LimitsGuard::new(
+            self,
+            #[cfg(feature = "std")]
+            None,
+            Some(budget),
+        );
+        f()
+    }
+
     pub(crate) unsafe fn get_opaque(&self) -> &Opaque<'js> {
         Opaque::from_runtime_ptr(qjs::JS_GetRuntime(self.ctx.as_ptr()))
     }
 
     /// Spawn future using configured async runtime
+    ///
+    /// The returned handle can be used to abort the future.
     #[cfg(feature = "futures")]
     #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "futures")))]
-    pub fn spawn<F>(&self, future: F)
+    pub fn spawn<F>(&self, future: F) -> TaskHandle
     where
         F: Future<Output = ()> + 'js,
     {
-        unsafe { self.get_opaque().push(future) }
+        let (future, handle) = Abortable::new(InContext::new(self.clone(), future));
+        unsafe { self.get_opaque().push(future) };
+        handle
     }
 
     /// Create a new `Ctx` from a pointer to the context and a invariant lifetime.
@@ -448,6 +562,28 @@ impl<'js> Ctx<'js> {
         unsafe { qjs::JS_RunGC(qjs::JS_GetRuntime(self.ctx.as_ptr())) }
     }
 
+    /// Create a snapshot of all objects reachable from the global object and the values held by
+    /// [`Persistent`](crate::Persistent) handles.
+    ///
+    /// Other values which are only held from Rust can be included with
+    /// [`Ctx::heap_snapshot_with_roots`].
+    pub fn heap_snapshot(&self) -> Result<HeapSnapshot> {
+        HeapSnapshot::capture(self, [("globalThis", self.globals().into_value())])
+    }
+
+    /// Create a snapshot of all objects reachable from the global object and the given named roots.
+    ///
+    /// The retaining path of every object in the snapshot starts at one of the roots.
+    pub fn heap_snapshot_with_roots<I, S>(&self, roots: I) -> Result<HeapSnapshot>
+    where
+        I: IntoIterator<Item = (S, Value<'js>)>,
+        S: Into<StdString>,
+    {
+        let globals = ("globalThis".into(), self.globals().into_value());
+        let roots = roots.into_iter().map(|(name, value)| (name.into(), value));
+        HeapSnapshot::capture::<_, StdString>(self, core::iter::once(globals).chain(roots))
+    }
+
     /// Store a type in the runtime which can be retrieved later with `Ctx::userdata`.
     ///
     /// Returns the value from the argument if the userdata is currently being accessed and
@@ -484,6 +620,30 @@ impl<'js> Ctx<'js> {
         unsafe { self.get_opaque().get_userdata() }
     }
 
+    /// Register a source map for the script or module with the given name.
+    ///
+    /// Stack traces returned by [`Exception::stack`](crate::Exception::stack) and
+    /// [`Exception::frames`](crate::Exception::frames) are remapped to the original sources.
+    /// The source maps are shared by all contexts of the runtime.
+    ///
+    /// Returns the previously registered source map for this name, if any.
+    #[cfg(feature = "loader")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "loader")))]
+    pub fn set_source_map<N: Into<StdString>>(
+        &self,
+        name: N,
+        map: crate::loader::SourceMap,
+    ) -> Option<crate::loader::SourceMap> {
+        unsafe { self.get_opaque().source_maps().insert(name.into(), map) }
+    }
+
+    /// Remove the source map of the script or module with the given name.
+    #[cfg(feature = "loader")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "loader")))]
+    pub fn remove_source_map(&self, name: &str) -> Option<crate::loader::SourceMap> {
+        unsafe { self.get_opaque().source_maps().remove(name) }
+    }
+
     /// Returns the pointer to the C library context.
     pub fn as_raw(&self) -> NonNull<qjs::JSContext> {
         self.ctx
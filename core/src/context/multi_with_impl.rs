@@ -1,5 +1,6 @@
 #![allow(clippy::many_single_char_names)]
 use super::{Context, Ctx, MultiWith};
+use crate::runtime::opaque::ContextGuard;
 use core::mem;
 
 macro_rules! list {
@@ -23,7 +24,12 @@ macro_rules! impl_multi_with{
                           "Tried to use contexts of different runtimes with each other");)*
                 let guard = self.0.0.rt.inner.lock();
                 guard.update_stack_top();
+                // Code running in multiple contexts is attributed to the first one.
+                let entered = unsafe {
+                    ContextGuard::new(guard.rt.as_ptr(), self.0.0.ctx().as_ptr())
+                };
                 let res = f(($(unsafe{ Ctx::new($t) },)*));
+                mem::drop(entered);
                 mem::drop(guard);
                 res
             }
@@ -1,6 +1,6 @@
 use crate::{
-    atom, value::Constructor, Array, Atom, BigInt, CString, Exception, Function, Module, Object,
-    Promise, String, Symbol, Value,
+    atom, value::Constructor, Array, Atom, BigInt, CString, DataView, Date, Exception, Function,
+    Map, Module, Object, Promise, RegExp, Set, String, Symbol, Value,
 };
 
 /// The trait which signifies a type using the rquickjs `'js` lifetime trick for maintaining safety around Javascript values.
@@ -106,6 +106,11 @@ outlive_impls! {
     Promise,
     Exception,
     Atom,
+    Map,
+    Set,
+    Date,
+    RegExp,
+    DataView,
 }
 
 macro_rules! impl_outlive{
@@ -20,6 +20,7 @@ extern crate std;
 pub(crate) use alloc::string::String as StdString;
 pub(crate) use core::result::Result as StdResult;
 
+mod cloned;
 mod js_lifetime;
 pub mod markers;
 mod persistent;
@@ -34,21 +35,30 @@ pub mod context;
 pub use context::{Context, Ctx};
 pub mod class;
 pub use class::Class;
+pub use cloned::ClonedValue;
 pub use js_lifetime::JsLifetime;
 pub use persistent::Persistent;
 pub use result::{CatchResultExt, CaughtError, CaughtResult, Error, Result, ThrowResultExt};
 pub use value::{
-    array, atom, convert, function, module, object, promise, Array, Atom, BigInt, CString, Coerced,
-    Exception, Filter, FromAtom, FromIteratorJs, FromJs, Function, IntoAtom, IntoJs, IteratorJs,
-    Module, Null, Object, Promise, String, Symbol, Type, Undefined, Value, WriteOptions,
-    WriteOptionsEndianness,
+    array, atom, convert, data_view, function, iterator, map, module, object, promise, proxy, set,
+    Array, Atom, BigInt, CString, Coerced, DataView, Date, Exception, ExceptionInfo, Filter,
+    FromAtom, FromIteratorJs, FromJs, Function, IntoAtom, IntoJs, IteratorJs, JsIterator, Map,
+    Module, Null, Object, Promise, Proxy, RegExp, Set, StackFrame, String, Symbol, Type, Undefined,
+    Value, WriteOptions, WriteOptionsEndianness,
 };
 
+#[cfg(feature = "futures")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "futures")))]
+pub mod abort;
 pub mod allocator;
 #[cfg(feature = "loader")]
 #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "loader")))]
 pub mod loader;
 
+#[cfg(feature = "timers")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "timers")))]
+pub mod timers;
+
 #[cfg(feature = "futures")]
 #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "futures")))]
 pub use context::AsyncContext;
@@ -6,6 +6,8 @@ use core::{ffi::CStr, ptr};
 
 use crate::{module::Declared, qjs, Ctx, Module, Result};
 
+#[cfg(feature = "futures")]
+mod async_loader;
 mod builtin_loader;
 mod builtin_resolver;
 pub mod bundle;
@@ -14,11 +16,14 @@ mod compile;
 mod file_resolver;
 mod module_loader;
 mod script_loader;
+mod source_map;
 mod util;
 
 #[cfg(feature = "dyn-load")]
 mod native_loader;
 
+#[cfg(feature = "futures")]
+pub use async_loader::{AsyncLoader, AsyncModuleLoader, AsyncResolver, ModuleFuture};
 pub use builtin_loader::BuiltinLoader;
 pub use builtin_resolver::BuiltinResolver;
 pub use compile::Compile;
@@ -26,6 +31,8 @@ pub use compile::Compile;
 pub use file_resolver::FileResolver;
 pub use module_loader::ModuleLoader;
 pub use script_loader::ScriptLoader;
+pub(crate) use source_map::SourceMaps;
+pub use source_map::{SourceLocation, SourceMap, SourceMapError};
 
 #[cfg(feature = "dyn-load")]
 #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "dyn-load")))]
@@ -33,11 +40,15 @@ pub use native_loader::NativeLoader;
 
 #[cfg(feature = "phf")]
 /// The type of bundle that the `embed!` macro returns
-pub type Bundle = bundle::Bundle<bundle::PhfBundleData<&'static [u8]>>;
+///
+/// Each module is its bytecode and its JSON source map, if any.
+pub type Bundle = bundle::Bundle<bundle::PhfBundleData<(&'static [u8], Option<&'static str>)>>;
 
 #[cfg(not(feature = "phf"))]
 /// The type of bundle that the `embed!` macro returns
-pub type Bundle = bundle::Bundle<bundle::ScaBundleData<&'static [u8]>>;
+///
+/// Each module is its bytecode and its JSON source map, if any.
+pub type Bundle = bundle::Bundle<bundle::ScaBundleData<(&'static [u8], Option<&'static str>)>>;
 
 /// Module resolver interface
 #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "loader")))]
@@ -0,0 +1,888 @@
+use alloc::{
+    boxed::Box,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::{
+    future::{poll_fn, Future},
+    hash::Hash,
+    mem,
+    pin::Pin,
+    ptr::{self, NonNull},
+    task::Poll,
+};
+use hashbrown::{HashMap, HashSet};
+
+use crate::{
+    markers::ParallelSend, module::Declared, qjs, Atom, Ctx, Error, Function, Module, Mut, Object,
+    Promise, Ref, Result,
+};
+
+use super::{Loader, Resolver};
+
+/// The future returned by [`AsyncResolver`] and [`AsyncLoader`].
+pub type ModuleFuture<T> = Pin<Box<dyn Future<Output = Result<T>> + 'static>>;
+
+/// Asynchronous module resolver interface
+///
+/// See [`Resolver`] for the meaning of the arguments.
+#[cfg_attr(
+    feature = "doc-cfg",
+    doc(cfg(all(feature = "loader", feature = "futures")))
+)]
+pub trait AsyncResolver {
+    /// Normalize module name
+    fn resolve(&self, base: &str, name: &str) -> ModuleFuture<String>;
+}
+
+/// Asynchronous module loader interface
+#[cfg_attr(
+    feature = "doc-cfg",
+    doc(cfg(all(feature = "loader", feature = "futures")))
+)]
+pub trait AsyncLoader {
+    /// Load the source of a module by name
+    fn load(&self, name: &str) -> ModuleFuture<Vec<u8>>;
+}
+
+#[cfg(not(feature = "parallel"))]
+type DynResolver = Box<dyn AsyncResolver>;
+#[cfg(feature = "parallel")]
+type DynResolver = Box<dyn AsyncResolver + Send>;
+
+#[cfg(not(feature = "parallel"))]
+type DynLoader = Box<dyn AsyncLoader>;
+#[cfg(feature = "parallel")]
+type DynLoader = Box<dyn AsyncLoader + Send>;
+
+/// The module returned for an import which was not yet prefetched.
+///
+/// The promise returned by `import()` adopts the `then` export of the namespace, which imports
+/// the real module once its graph is prefetched.
+const PENDING_MODULE: &str = r#"export function then(resolve, reject) {
+    import.meta.load
+        .then((name) => import(name))
+        .finally(import.meta.release)
+        .then(resolve, reject);
+}"#;
+
+/// The name of the empty module the static imports of a module resolve to while it is declared
+/// for a prefetch, until they are linked to the prefetched modules.
+const PLACEHOLDER: &str = "<prefetch placeholder>";
+
+/// The number of unused entries kept in each cache of a loader by default.
+const DEFAULT_CAPACITY: usize = 256;
+
+/// Cached values, the number of prefetches which depend on each entry is kept with it.
+struct Entries<K, V>(HashMap<K, (V, usize)>);
+
+impl<K: Hash + Eq, V> Entries<K, V> {
+    fn get(&self, key: &K) -> Option<&V> {
+        self.0.get(key).map(|(value, _)| value)
+    }
+
+    /// Returns the value of an entry and pins it.
+    fn pin(&mut self, key: &K) -> Option<&V> {
+        let (value, pins) = self.0.get_mut(key)?;
+        *pins += 1;
+        Some(value)
+    }
+
+    /// Insert a pinned entry.
+    fn insert(&mut self, key: K, value: V) {
+        let pins = self.0.get(&key).map_or(0, |(_, pins)| *pins);
+        self.0.insert(key, (value, pins + 1));
+    }
+
+    fn unpin(&mut self, key: &K) {
+        if let Some((_, pins)) = self.0.get_mut(key) {
+            *pins = pins.saturating_sub(1);
+        }
+    }
+
+    /// Remove all entries which are not pinned once there are more than `capacity` entries.
+    fn evict(&mut self, capacity: usize) {
+        if self.0.len() > capacity {
+            self.0.retain(|_, (_, pins)| *pins > 0);
+        }
+    }
+}
+
+impl<K, V> Default for Entries<K, V> {
+    fn default() -> Self {
+        Entries(HashMap::new())
+    }
+}
+
+/// The cache entries a prefetch depends on, which are not evicted until they are unpinned.
+#[derive(Default)]
+struct Pins {
+    resolved: Vec<(String, String)>,
+    sources: Vec<String>,
+}
+
+#[derive(Default)]
+struct Cache {
+    capacity: usize,
+    /// Resolved names by the base and name of the import.
+    resolved: Entries<(String, String), String>,
+    /// Prefetched sources by resolved name.
+    sources: Entries<String, Ref<[u8]>>,
+    /// The base and name of the imports which are waiting on their prefetch, by the name of
+    /// their pending module.
+    imports: HashMap<String, (String, String)>,
+    /// The resolved name of the imports which were prefetched but not yet linked, by the name
+    /// of their pending module.
+    prefetched: HashMap<String, (String, Pins)>,
+    /// The modules which are being declared for a prefetch with the address of their context.
+    declaring: Vec<(usize, String)>,
+    next_pending: u64,
+}
+
+struct State {
+    resolver: Mut<DynResolver>,
+    loader: Mut<DynLoader>,
+    cache: Mut<Cache>,
+}
+
+enum Fetch {
+    Module(String),
+    Resolve {
+        base: String,
+        name: String,
+        future: ModuleFuture<String>,
+    },
+    Load {
+        name: String,
+        future: ModuleFuture<Vec<u8>>,
+    },
+}
+
+/// The modules of a prefetched graph and the names their imports resolved to.
+#[derive(Default)]
+struct Graph {
+    modules: HashSet<String>,
+    links: HashMap<(String, String), String>,
+}
+
+/// Unpins the entries of a prefetch when dropped.
+struct PinGuard {
+    state: Ref<State>,
+    pins: Pins,
+}
+
+impl Drop for PinGuard {
+    fn drop(&mut self) {
+        self.state.unpin(mem::take(&mut self.pins));
+    }
+}
+
+impl State {
+    fn resolve(&self, base: String, name: String) -> Fetch {
+        let future = self.resolver.lock().resolve(&base, &name);
+        Fetch::Resolve { base, name, future }
+    }
+
+    fn load(&self, name: String) -> Fetch {
+        let future = self.loader.lock().load(&name);
+        Fetch::Load { name, future }
+    }
+
+    fn unpin(&self, pins: Pins) {
+        let mut cache = self.cache.lock();
+        pins.resolved.iter().for_each(|x| cache.resolved.unpin(x));
+        pins.sources.iter().for_each(|x| cache.sources.unpin(x));
+        let capacity = cache.capacity;
+        cache.resolved.evict(capacity);
+        cache.sources.evict(capacity);
+    }
+
+    /// Resolve an import and fetch the module graph it refers to.
+    async fn prefetch_import<'js>(
+        &self,
+        ctx: &Ctx<'js>,
+        base: String,
+        name: String,
+        pins: &mut Pins,
+    ) -> Result<String> {
+        let future = self.resolver.lock().resolve(&base, &name);
+        let root = future.await?;
+        let key = (base, name);
+        self.cache.lock().resolved.insert(key.clone(), root.clone());
+        pins.resolved.push(key);
+        self.prefetch(ctx, root.clone(), pins).await?;
+        Ok(root)
+    }
+
+    /// Fetch the graph of the static imports of a module and declare its modules.
+    ///
+    /// All modules of the graph are fetched concurrently and the first error is returned.
+    /// Modules are declared as soon as their source is loaded, their imports are linked once
+    /// the whole graph was fetched. The cache entries of the graph are added to `pins`.
+    async fn prefetch<'js>(&self, ctx: &Ctx<'js>, root: String, pins: &mut Pins) -> Result<()> {
+        let mut graph = Graph::default();
+        let mut fetches = vec![Fetch::Module(root)];
+        poll_fn(|cx| {
+            let mut i = 0;
+            while i < fetches.len() {
+                let next = match &mut fetches[i] {
+                    Fetch::Module(name) if graph.modules.insert(name.clone()) => {
+                        self.visit(ctx, mem::take(name), &mut graph, pins)?
+                    }
+                    Fetch::Module(_) => Vec::new(),
+                    Fetch::Resolve { base, name, future } => {
+                        let Poll::Ready(res) = future.as_mut().poll(cx) else {
+                            i += 1;
+                            continue;
+                        };
+                        let resolved = res?;
+                        let key = (mem::take(base), mem::take(name));
+                        self.cache
+                            .lock()
+                            .resolved
+                            .insert(key.clone(), resolved.clone());
+                        pins.resolved.push(key.clone());
+                        graph.links.insert(key, resolved.clone());
+                        vec![Fetch::Module(resolved)]
+                    }
+                    Fetch::Load { name, future } => {
+                        let Poll::Ready(res) = future.as_mut().poll(cx) else {
+                            i += 1;
+                            continue;
+                        };
+                        let source = Ref::<[u8]>::from(res?);
+                        let name = mem::take(name);
+                        self.cache
+                            .lock()
+                            .sources
+                            .insert(name.clone(), source.clone());
+                        pins.sources.push(name.clone());
+                        self.declare(ctx, name, &source, &mut graph, pins)?
+                    }
+                };
+                fetches.swap_remove(i);
+                fetches.extend(next);
+            }
+            if fetches.is_empty() {
+                Poll::Ready(Ok::<_, Error>(()))
+            } else {
+                Poll::Pending
+            }
+        })
+        .await?;
+        link(ctx, &graph)
+    }
+
+    /// Returns the fetches for a module of a graph.
+    fn visit<'js>(
+        &self,
+        ctx: &Ctx<'js>,
+        name: String,
+        graph: &mut Graph,
+        pins: &mut Pins,
+    ) -> Result<Vec<Fetch>> {
+        // Only the imports which were not linked yet are fetched for a declared module.
+        if let Some(module) = find_module(ctx, &name) {
+            let requests = unlinked(ctx, &module)?.into_iter().map(|(_, x)| x);
+            return Ok(self.imports(&name, requests.collect(), graph, pins));
+        }
+
+        let source = self.cache.lock().sources.pin(&name).cloned();
+        match source {
+            Some(source) => {
+                pins.sources.push(name.clone());
+                self.declare(ctx, name, &source, graph, pins)
+            }
+            None => Ok(vec![self.load(name)]),
+        }
+    }
+
+    /// Declare a module of a graph and returns the fetches for its static imports, which all
+    /// resolve to the placeholder until they are linked.
+    fn declare<'js>(
+        &self,
+        ctx: &Ctx<'js>,
+        name: String,
+        source: &[u8],
+        graph: &mut Graph,
+        pins: &mut Pins,
+    ) -> Result<Vec<Fetch>> {
+        let declaring = (ctx.as_ptr() as usize, name);
+        self.cache.lock().declaring.push(declaring.clone());
+        let res = Module::declare(ctx.clone(), declaring.1.as_str(), source);
+        self.cache.lock().declaring.retain(|x| *x != declaring);
+        let requests = requests(ctx, &res?)?.into_iter().map(|(x, _)| x);
+        Ok(self.imports(&declaring.1, requests.collect(), graph, pins))
+    }
+
+    /// Returns the fetches for the static imports of a module.
+    fn imports(
+        &self,
+        base: &str,
+        requests: Vec<String>,
+        graph: &mut Graph,
+        pins: &mut Pins,
+    ) -> Vec<Fetch> {
+        let mut cache = self.cache.lock();
+        requests
+            .into_iter()
+            .map(|name| {
+                let key = (base.to_string(), name);
+                match cache.resolved.pin(&key).cloned() {
+                    Some(resolved) => {
+                        pins.resolved.push(key.clone());
+                        graph.links.insert(key, resolved.clone());
+                        Fetch::Module(resolved)
+                    }
+                    None => self.resolve(key.0, key.1),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Link the imports of the modules of a prefetched graph which resolve to the placeholder to
+/// the modules they were prefetched as.
+fn link<'js>(ctx: &Ctx<'js>, graph: &Graph) -> Result<()> {
+    for base in &graph.modules {
+        let module = find_module(ctx, base).ok_or_else(|| Error::new_loading(base))?;
+        for (index, name) in unlinked(ctx, &module)? {
+            let resolved = graph
+                .links
+                .get(&(base.clone(), name.clone()))
+                .ok_or_else(|| Error::new_resolving(base, &name))?;
+            let target = find_module(ctx, resolved).ok_or_else(|| Error::new_loading(resolved))?;
+            unsafe {
+                qjs::rquickjs_set_module_request(module.as_ptr(), index as _, target.as_ptr())
+            };
+        }
+    }
+    Ok(())
+}
+
+/// Returns the module with the name which was declared in the context, if any.
+fn find_module<'js>(ctx: &Ctx<'js>, name: &str) -> Option<Module<'js, Declared>> {
+    let name = Atom::from_str(ctx.clone(), name).ok()?;
+    let ptr = unsafe { qjs::rquickjs_find_module(ctx.as_ptr(), name.atom) };
+    NonNull::new(ptr).map(|ptr| unsafe { Module::from_ptr(ctx.clone(), ptr) })
+}
+
+/// Returns the specifiers of the static imports and re-exports of a module with the modules
+/// they resolved to, which are null while the module is compiled.
+fn requests<'js>(
+    ctx: &Ctx<'js>,
+    module: &Module<'js, Declared>,
+) -> Result<Vec<(String, *mut qjs::JSModuleDef)>> {
+    let m = module.as_ptr();
+    let len = unsafe { qjs::rquickjs_get_module_requests(m, ptr::null_mut(), ptr::null_mut(), 0) };
+    let mut atoms = vec![qjs::JS_ATOM_NULL; len as usize];
+    let mut modules = vec![ptr::null_mut(); len as usize];
+    unsafe { qjs::rquickjs_get_module_requests(m, atoms.as_mut_ptr(), modules.as_mut_ptr(), len) };
+    atoms
+        .into_iter()
+        .zip(modules)
+        .map(|(atom, module)| {
+            let name = unsafe { Atom::from_atom_val_dup(ctx.clone(), atom) };
+            Ok((name.to_string()?, module))
+        })
+        .collect()
+}
+
+/// Returns the index and specifier of the imports of a module which were not linked yet.
+fn unlinked<'js>(ctx: &Ctx<'js>, module: &Module<'js, Declared>) -> Result<Vec<(usize, String)>> {
+    let Some(placeholder) = find_module(ctx, PLACEHOLDER) else {
+        return Ok(Vec::new());
+    };
+    Ok(requests(ctx, module)?
+        .into_iter()
+        .enumerate()
+        .filter(|(_, (_, module))| *module == placeholder.as_ptr())
+        .map(|(index, (name, _))| (index, name))
+        .collect())
+}
+
+/// A resolver and loader which resolves and loads modules asynchronously.
+///
+/// Use it as both the resolver and the loader of an
+/// [`AsyncRuntime`](crate::AsyncRuntime). QuickJS loads modules synchronously, so modules
+/// are fetched ahead of time: when a module is imported, the whole graph of its static imports
+/// is resolved and loaded concurrently before it is linked. The promise returned by
+/// `import()` resolves once all futures completed and the module was evaluated, or rejects
+/// with the first error of the graph.
+///
+/// Every module is declared once as soon as its source was loaded, its static imports are
+/// read from the compiled module and linked once the whole graph was fetched. A module whose
+/// graph failed to load stays declared and only its missing imports are fetched again when it
+/// is imported the next time. Modules which are not imported through `import()` must be
+/// evaluated with [`evaluate`](Self::evaluate) so their static imports are prefetched,
+/// declaring such a module otherwise fails with a resolving error for its first import.
+///
+/// Resolved names and sources are cached, so modules are only fetched again once they were
+/// evicted. Entries are never evicted while a prefetch which depends on them is not linked,
+/// other entries are dropped once a cache holds more than [`with_capacity`](Self::with_capacity)
+/// of them.
+///
+/// ```
+/// # use rquickjs::{AsyncRuntime, AsyncContext, Promise, Result, Error};
+/// # use rquickjs::loader::{AsyncLoader, AsyncModuleLoader, AsyncResolver, ModuleFuture};
+/// struct Resolver;
+///
+/// impl AsyncResolver for Resolver {
+///     fn resolve(&self, _base: &str, name: &str) -> ModuleFuture<String> {
+///         let name = name.trim_start_matches("./").to_string();
+///         Box::pin(async move { Ok(name) })
+///     }
+/// }
+///
+/// struct Loader;
+///
+/// impl AsyncLoader for Loader {
+///     fn load(&self, name: &str) -> ModuleFuture<Vec<u8>> {
+///         let name = name.to_string();
+///         Box::pin(async move {
+///             // Fetch the source from a database or over the network.
+///             match name.as_str() {
+///                 "main.js" => Ok(b"export { value } from './value.js'".to_vec()),
+///                 "value.js" => Ok(b"export const value = 42".to_vec()),
+///                 _ => Err(Error::new_loading(name)),
+///             }
+///         })
+///     }
+/// }
+///
+/// # async fn run() {
+/// let rt = AsyncRuntime::new().unwrap();
+/// let ctx = AsyncContext::full(&rt).await.unwrap();
+/// let loader = AsyncModuleLoader::new(Resolver, Loader);
+/// rt.set_loader(loader.clone(), loader).await;
+///
+/// ctx.with(|ctx| {
+///     ctx.eval::<(), _>("globalThis.result = import('main.js').then((ns) => ns.value)")
+///         .unwrap();
+/// })
+/// .await;
+/// rt.idle().await;
+/// ctx.with(|ctx| {
+///     let result: Promise = ctx.globals().get("result").unwrap();
+///     assert_eq!(result.finish::<i32>().unwrap(), 42);
+/// })
+/// .await;
+/// # }
+/// ```
+#[cfg_attr(
+    feature = "doc-cfg",
+    doc(cfg(all(feature = "loader", feature = "futures")))
+)]
+#[derive(Clone)]
+pub struct AsyncModuleLoader(Ref<State>);
+
+impl AsyncModuleLoader {
+    /// Create a loader from an asynchronous resolver and loader.
+    pub fn new<R, L>(resolver: R, loader: L) -> Self
+    where
+        R: AsyncResolver + ParallelSend + 'static,
+        L: AsyncLoader + ParallelSend + 'static,
+    {
+        AsyncModuleLoader(Ref::new(State {
+            resolver: Mut::new(Box::new(resolver)),
+            loader: Mut::new(Box::new(loader)),
+            cache: Mut::new(Cache {
+                capacity: DEFAULT_CAPACITY,
+                ..Cache::default()
+            }),
+        }))
+    }
+
+    /// Set the number of resolved names and of sources kept once no prefetch depends on them,
+    /// 256 of each by default.
+    pub fn with_capacity(self, capacity: usize) -> Self {
+        self.0.cache.lock().capacity = capacity;
+        self
+    }
+
+    /// Declare a module and evaluate it once the graph of its static imports was fetched.
+    ///
+    /// Returns a promise which resolves to the namespace of the module once it was evaluated.
+    pub fn evaluate<'js, N, S>(&self, ctx: &Ctx<'js>, name: N, source: S) -> Result<Promise<'js>>
+    where
+        N: Into<String>,
+        S: Into<Vec<u8>>,
+    {
+        let name = name.into();
+        let source = Ref::from(source.into());
+        self.0.cache.lock().sources.insert(name.clone(), source);
+
+        let (state, future_ctx) = (self.0.clone(), ctx.clone());
+        Promise::wrap_future(ctx, async move {
+            let ctx = future_ctx;
+            let mut guard = PinGuard {
+                state: state.clone(),
+                pins: Pins {
+                    sources: vec![name.clone()],
+                    ..Pins::default()
+                },
+            };
+            state.prefetch(&ctx, name.clone(), &mut guard.pins).await?;
+            let module = find_module(&ctx, &name).ok_or_else(|| Error::new_loading(&name))?;
+            let (module, promise) = module.eval()?;
+            drop(guard);
+            promise.into_future::<()>().await?;
+            module.namespace()
+        })
+    }
+}
+
+impl Resolver for AsyncModuleLoader {
+    fn resolve<'js>(&mut self, ctx: &Ctx<'js>, base: &str, name: &str) -> Result<String> {
+        let mut cache = self.0.cache.lock();
+        // The base is declared for a prefetch, its imports are linked once they were fetched.
+        if cache
+            .declaring
+            .contains(&(ctx.as_ptr() as usize, base.to_string()))
+        {
+            return Ok(PLACEHOLDER.to_string());
+        }
+        // Modules whose imports were not linked are prefetched again.
+        if let Some(resolved) = cache.resolved.get(&(base.to_string(), name.to_string())) {
+            let module = find_module(ctx, resolved);
+            if module.is_some_and(|x| unlinked(ctx, &x).is_ok_and(|x| x.is_empty())) {
+                return Ok(resolved.clone());
+            }
+        }
+        // The pending module imports the resolved name.
+        if let Some((resolved, _)) = cache.prefetched.get(base) {
+            if resolved == name {
+                return Ok(resolved.clone());
+            }
+        }
+
+        // The base is being compiled, its static imports can not wait for their modules.
+        let compiling = find_module(ctx, base).and_then(|x| requests(ctx, &x).ok());
+        if compiling.is_some_and(|x| x.iter().any(|(x, m)| m.is_null() && x == name)) {
+            return Err(Error::new_resolving_message(
+                base,
+                name,
+                "static imports must be prefetched, use `AsyncModuleLoader::evaluate`",
+            ));
+        }
+
+        // The import was not prefetched yet, load a pending module in its place.
+        let id = cache.next_pending;
+        cache.next_pending += 1;
+        let pending = format!("<pending import #{id} of {name}>");
+        cache
+            .imports
+            .insert(pending.clone(), (base.to_string(), name.to_string()));
+        Ok(pending)
+    }
+}
+
+impl Loader for AsyncModuleLoader {
+    fn load<'js>(&mut self, ctx: &Ctx<'js>, name: &str) -> Result<Module<'js, Declared>> {
+        if name == PLACEHOLDER {
+            return Module::declare(ctx.clone(), name, "");
+        }
+
+        let import = self.0.cache.lock().imports.remove(name);
+        let Some((base, import)) = import else {
+            return Err(Error::new_loading_message(
+                name,
+                "module was not prefetched",
+            ));
+        };
+        let module = Module::declare(ctx.clone(), name, PENDING_MODULE)?;
+        let state = self.0.clone();
+        let pending = name.to_string();
+        let load = Promise::wrap_future(ctx, {
+            let (state, ctx) = (state.clone(), ctx.clone());
+            async move {
+                let mut guard = PinGuard {
+                    state: state.clone(),
+                    pins: Pins::default(),
+                };
+                let resolved = state
+                    .prefetch_import(&ctx, base, import, &mut guard.pins)
+                    .await?;
+                // Keep the entries pinned until the real module is linked.
+                let pins = mem::take(&mut guard.pins);
+                state
+                    .cache
+                    .lock()
+                    .prefetched
+                    .insert(pending, (resolved.clone(), pins));
+                Ok::<_, Error>(resolved)
+            }
+        })?;
+        let pending = name.to_string();
+        let release = Function::new(ctx.clone(), move || {
+            let prefetched = state.cache.lock().prefetched.remove(&pending);
+            if let Some((_, pins)) = prefetched {
+                state.unpin(pins);
+            }
+        })?;
+        let meta: Object = module.meta()?;
+        meta.set("load", load)?;
+        meta.set("release", release)?;
+        Ok(module)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        string::{String, ToString},
+        sync::{
+            atomic::{AtomicBool, AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+        vec::Vec,
+    };
+
+    use super::{AsyncLoader, AsyncModuleLoader, AsyncResolver, ModuleFuture};
+    use crate::{AsyncContext, AsyncRuntime, CatchResultExt, Error, Module, Object, Promise};
+
+    struct TestResolver;
+
+    impl AsyncResolver for TestResolver {
+        fn resolve(&self, base: &str, name: &str) -> ModuleFuture<String> {
+            let (base, name) = (base.to_string(), name.to_string());
+            Box::pin(async move {
+                tokio::task::yield_now().await;
+                match name.strip_prefix("./") {
+                    Some(name) => Ok(name.to_string()),
+                    None => Err(Error::new_resolving(base, name)),
+                }
+            })
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct TestLoader {
+        loads: Arc<Mutex<Vec<String>>>,
+        active: Arc<AtomicUsize>,
+        max_active: Arc<AtomicUsize>,
+        available: Arc<AtomicBool>,
+    }
+
+    impl AsyncLoader for TestLoader {
+        fn load(&self, name: &str) -> ModuleFuture<Vec<u8>> {
+            let this = self.clone();
+            let name = name.to_string();
+            Box::pin(async move {
+                this.loads.lock().unwrap().push(name.clone());
+                let active = this.active.fetch_add(1, Ordering::Relaxed) + 1;
+                this.max_active.fetch_max(active, Ordering::Relaxed);
+                for _ in 0..3 {
+                    tokio::task::yield_now().await;
+                }
+                this.active.fetch_sub(1, Ordering::Relaxed);
+                let source = match name.as_str() {
+                    "main.js" => {
+                        r#"
+                        import { a } from "./a.js";
+                        import { b } from "./b.js";
+                        export { c } from "./c.js";
+                        export const value = a + b;
+                        export const lazy = () => import("./lazy.js").then((ns) => ns.d);
+                        "#
+                    }
+                    "a.js" => r#"import { c } from "./c.js"; export const a = c + 1;"#,
+                    "b.js" => r#"export const b = 2;"#,
+                    "c.js" => r#"import { a } from "./a.js"; export const c = 3;"#,
+                    "lazy.js" => r#"export const d = 4;"#,
+                    "broken.js" => r#"import { x } from "./missing.js";"#,
+                    "invalid.js" => r#"import "./b.js"; export {"#,
+                    "uses_invalid.js" => r#"import "./invalid.js";"#,
+                    "retry.js" => r#"export { f } from "./flaky.js";"#,
+                    "flaky.js" if this.available.load(Ordering::Relaxed) => {
+                        r#"export const f = 5;"#
+                    }
+                    "tricky.js" => {
+                        r#"#!/usr/bin/env qjs
+                        import * as b from "./b.js";
+                        export const h = "import x from './no.js'";
+                        // import x from "./no.js";
+                        /* import x from "./no.js"; */
+                        const re = /import "no.js"/;
+                        const t = `${b.b} import x from "./no.js" ${{ a: `${b.b}` }.a}`;
+                        export const lazy = () => import("./no.js");
+                        export function f() {
+                            return { import: "no" };
+                        }
+                        export * from "./lazy.js";
+                        "#
+                    }
+                    _ => return Err(Error::new_loading(name)),
+                };
+                Ok(source.as_bytes().to_vec())
+            })
+        }
+    }
+
+    async fn import(rt: &AsyncRuntime, ctx: &AsyncContext, source: &str) -> String {
+        let source = source.to_string();
+        ctx.with(|ctx| {
+            let promise: Promise = ctx.eval(source).unwrap();
+            ctx.globals().set("result", promise).unwrap();
+        })
+        .await;
+        rt.idle().await;
+        ctx.with(|ctx| {
+            let result: Promise = ctx.globals().get("result").unwrap();
+            result.finish::<String>().unwrap()
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn prefetch_graph() {
+        let rt = AsyncRuntime::new().unwrap();
+        let ctx = AsyncContext::full(&rt).await.unwrap();
+        let loader = TestLoader::default();
+        let modules = AsyncModuleLoader::new(TestResolver, loader.clone());
+        rt.set_loader(modules.clone(), modules).await;
+
+        let res = import(
+            &rt,
+            &ctx,
+            r#"import("./main.js").then(async (ns) => `${ns.value} ${ns.c} ${await ns.lazy()}`)"#,
+        )
+        .await;
+        assert_eq!(res, "6 3 4");
+        let mut loads = loader.loads.lock().unwrap().clone();
+        loads.sort();
+        assert_eq!(loads, ["a.js", "b.js", "c.js", "lazy.js", "main.js"]);
+        // The static imports of main.js are loaded concurrently.
+        assert_eq!(loader.max_active.load(Ordering::Relaxed), 3);
+
+        // Modules are only fetched once.
+        let res = import(&rt, &ctx, r#"import("./c.js").then((ns) => `${ns.c}`)"#).await;
+        assert_eq!(res, "3");
+        assert_eq!(loader.loads.lock().unwrap().len(), 5);
+
+        // Only the static imports compiled by the engine are prefetched.
+        let res = import(
+            &rt,
+            &ctx,
+            r#"import("./tricky.js").then((ns) => `${ns.d}`)"#,
+        )
+        .await;
+        assert_eq!(res, "4");
+        assert_eq!(loader.loads.lock().unwrap()[5..], ["tricky.js"]);
+    }
+
+    #[tokio::test]
+    async fn errors() {
+        let rt = AsyncRuntime::new().unwrap();
+        let ctx = AsyncContext::full(&rt).await.unwrap();
+        let modules = AsyncModuleLoader::new(TestResolver, TestLoader::default());
+        rt.set_loader(modules.clone(), modules).await;
+
+        let catch = |import: &str| {
+            format!("import({import:?}).then(() => 'loaded', (e) => `${{e.name}}: ${{e.message}}`)")
+        };
+        let res = import(&rt, &ctx, &catch("./missing.js")).await;
+        assert_eq!(res, "ReferenceError: Error loading module 'missing.js'");
+        let res = import(&rt, &ctx, &catch("missing.js")).await;
+        assert_eq!(
+            res,
+            "ReferenceError: Error resolving module 'missing.js' from 'eval_script'"
+        );
+        let res = import(&rt, &ctx, &catch("./broken.js")).await;
+        assert_eq!(res, "ReferenceError: Error loading module 'missing.js'");
+        let res = import(&rt, &ctx, &catch("./uses_invalid.js")).await;
+        assert!(res.starts_with("SyntaxError: "), "{res}");
+    }
+
+    #[tokio::test]
+    async fn retry() {
+        let rt = AsyncRuntime::new().unwrap();
+        let ctx = AsyncContext::full(&rt).await.unwrap();
+        let loader = TestLoader::default();
+        let modules = AsyncModuleLoader::new(TestResolver, loader.clone());
+        rt.set_loader(modules.clone(), modules).await;
+
+        let source = r#"import("./retry.js").then((ns) => `${ns.f}`, (e) => e.message)"#;
+        let res = import(&rt, &ctx, source).await;
+        assert_eq!(res, "Error loading module 'flaky.js'");
+
+        // Only the missing import of the declared module is fetched again.
+        loader.available.store(true, Ordering::Relaxed);
+        let res = import(&rt, &ctx, source).await;
+        assert_eq!(res, "5");
+        assert_eq!(
+            *loader.loads.lock().unwrap(),
+            ["retry.js", "flaky.js", "flaky.js"]
+        );
+    }
+
+    #[tokio::test]
+    async fn evaluate_entry() {
+        let rt = AsyncRuntime::new().unwrap();
+        let ctx = AsyncContext::full(&rt).await.unwrap();
+        let loader = TestLoader::default();
+        let modules = AsyncModuleLoader::new(TestResolver, loader.clone());
+        rt.set_loader(modules.clone(), modules.clone()).await;
+
+        let source = r#"import { value } from "./main.js"; export const entry = value * 2;"#;
+        ctx.with(|ctx| {
+            let promise = modules.evaluate(&ctx, "entry.js", source).unwrap();
+            ctx.globals().set("result", promise).unwrap();
+            let promise = modules.evaluate(&ctx, "invalid.js", "export {").unwrap();
+            ctx.globals().set("invalid", promise).unwrap();
+        })
+        .await;
+        rt.idle().await;
+        ctx.with(|ctx| {
+            let result: Promise = ctx.globals().get("result").unwrap();
+            let namespace: Object = result.finish().unwrap();
+            assert_eq!(namespace.get::<_, i32>("entry").unwrap(), 12);
+            let invalid: Promise = ctx.globals().get("invalid").unwrap();
+            let error = invalid.finish::<()>().catch(&ctx).unwrap_err().to_string();
+            assert!(error.contains("SyntaxError"), "{error}");
+        })
+        .await;
+        assert_eq!(loader.loads.lock().unwrap().len(), 4);
+
+        // Modules declared otherwise can not wait for their imports.
+        ctx.with(|ctx| {
+            let error = Module::declare(ctx.clone(), "declared.js", source)
+                .catch(&ctx)
+                .unwrap_err()
+                .to_string();
+            assert!(
+                error.contains("use `AsyncModuleLoader::evaluate`"),
+                "{error}"
+            );
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn bounded_cache() {
+        let rt = AsyncRuntime::new().unwrap();
+        let ctx = AsyncContext::full(&rt).await.unwrap();
+        let loader = TestLoader::default();
+        let modules = AsyncModuleLoader::new(TestResolver, loader.clone()).with_capacity(2);
+        rt.set_loader(modules.clone(), modules.clone()).await;
+
+        let res = import(
+            &rt,
+            &ctx,
+            r#"import("./main.js").then((ns) => `${ns.value}`)"#,
+        )
+        .await;
+        assert_eq!(res, "6");
+        for i in 0..3 {
+            let res = import(&rt, &ctx, r#"import("./b.js").then((ns) => `${ns.b}`)"#).await;
+            assert_eq!(res, "2", "import {i}");
+        }
+        let cache = modules.0.cache.lock();
+        assert!(cache.resolved.0.len() <= 2);
+        assert!(cache.sources.0.len() <= 2);
+        assert!(cache.resolved.0.values().all(|(_, pins)| *pins == 0));
+        assert!(cache.imports.is_empty() && cache.prefetched.is_empty());
+    }
+}
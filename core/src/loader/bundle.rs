@@ -1,8 +1,8 @@
 //! Utilities for embedding JS modules.
 
-use super::{util::resolve_simple, Loader, Resolver};
+use super::{util::resolve_simple, Loader, Resolver, SourceMap};
 use crate::{Ctx, Error, Module, Result};
-use alloc::string::String;
+use alloc::string::{String, ToString};
 use core::ops::Deref;
 
 /// The module data which contains bytecode
@@ -10,6 +10,14 @@ use core::ops::Deref;
 /// This trait needed because the modules potentially can contain any kind of data like a typing (for TypeScript) or metadata.
 pub trait HasByteCode<'bc> {
     fn get_bytecode(&self) -> &'bc [u8];
+
+    /// The source map of the module, if any, either as JSON or as a base64 encoded `data:` url
+    ///
+    /// The source map is registered when the module is loaded, so that stack traces point into
+    /// the original sources.
+    fn get_source_map(&self) -> Option<&'bc str> {
+        None
+    }
 }
 
 impl<'bc> HasByteCode<'bc> for &'bc [u8] {
@@ -18,6 +26,28 @@ impl<'bc> HasByteCode<'bc> for &'bc [u8] {
     }
 }
 
+/// The module bytecode together with its JSON source map
+impl<'bc> HasByteCode<'bc> for (&'bc [u8], &'bc str) {
+    fn get_bytecode(&self) -> &'bc [u8] {
+        self.0
+    }
+
+    fn get_source_map(&self) -> Option<&'bc str> {
+        Some(self.1)
+    }
+}
+
+/// The module bytecode together with an optional JSON source map, as embedded by `embed!`
+impl<'bc> HasByteCode<'bc> for (&'bc [u8], Option<&'bc str>) {
+    fn get_bytecode(&self) -> &'bc [u8] {
+        self.0
+    }
+
+    fn get_source_map(&self) -> Option<&'bc str> {
+        self.1
+    }
+}
+
 /// The alias for compiled modules represented as a static const arrays
 ///
 /// The element is a tuple of `(module_name, module_data)`.
@@ -70,8 +100,7 @@ where
 {
     fn load<'js>(&mut self, ctx: &Ctx<'js>, name: &str) -> Result<Module<'js>> {
         if let Some((_, x)) = self.iter().find(|(module_name, _)| *module_name == name) {
-            let module = unsafe { Module::load(ctx.clone(), x.get_bytecode())? };
-            return Ok(module);
+            return load_module(ctx, name, x);
         }
         Err(Error::new_loading(name))
     }
@@ -84,9 +113,24 @@ where
 {
     fn load<'js>(&mut self, ctx: &Ctx<'js>, name: &str) -> Result<Module<'js>> {
         if let Some(x) = self.get(name) {
-            let module = unsafe { Module::load(ctx.clone(), x.get_bytecode())? };
-            return Ok(module);
+            return load_module(ctx, name, x);
         }
         Err(Error::new_loading(name))
     }
 }
+
+fn load_module<'js, D>(ctx: &Ctx<'js>, name: &str, data: &D) -> Result<Module<'js>>
+where
+    D: HasByteCode<'static>,
+{
+    if let Some(map) = data.get_source_map() {
+        let map = if map.starts_with("data:") {
+            SourceMap::from_data_url(ctx, map)
+        } else {
+            SourceMap::parse(ctx, map)
+        };
+        let map = map.map_err(|error| Error::new_loading_message(name, error.to_string()))?;
+        ctx.set_source_map(name, map);
+    }
+    unsafe { Module::load(ctx.clone(), data.get_bytecode()) }
+}
@@ -1,4 +1,7 @@
-use crate::{loader::Resolver, Ctx, Error, Result};
+use crate::{
+    loader::{Resolver, SourceMap},
+    Ctx, Error, Result,
+};
 use alloc::{
     string::{String, ToString as _},
     vec,
@@ -13,6 +16,7 @@ use relative_path::{RelativePath, RelativePathBuf};
 pub struct FileResolver {
     paths: Vec<RelativePathBuf>,
     patterns: Vec<String>,
+    source_maps: bool,
 }
 
 impl FileResolver {
@@ -82,6 +86,24 @@ impl FileResolver {
         self
     }
 
+    /// Enable or disable loading of source maps, enabled by default
+    ///
+    /// When a script is resolved, its source map is taken from the `//# sourceMappingURL=`
+    /// comment of the script, either inline as a data url or from a file relative to the script,
+    /// or from a `.map` file next to the script. Stack traces of the module then point into the
+    /// original sources, whichever loader loads it.
+    pub fn set_source_maps(&mut self, enabled: bool) -> &mut Self {
+        self.source_maps = enabled;
+        self
+    }
+
+    /// Enable or disable loading of source maps, enabled by default
+    #[must_use]
+    pub fn with_source_maps(mut self, enabled: bool) -> Self {
+        self.set_source_maps(enabled);
+        self
+    }
+
     fn try_patterns(&self, path: &RelativePath) -> Option<RelativePathBuf> {
         if let Some(extension) = &path.extension() {
             if !is_file(path) {
@@ -119,12 +141,13 @@ impl Default for FileResolver {
         Self {
             paths: vec![],
             patterns: vec!["{}.js".into()],
+            source_maps: true,
         }
     }
 }
 
 impl Resolver for FileResolver {
-    fn resolve<'js>(&mut self, _ctx: &Ctx<'js>, base: &str, name: &str) -> Result<String> {
+    fn resolve<'js>(&mut self, ctx: &Ctx<'js>, base: &str, name: &str) -> Result<String> {
         let path = if !name.starts_with('.') {
             self.paths.iter().find_map(|path| {
                 let path = path.join_normalized(name);
@@ -141,10 +164,27 @@ impl Resolver for FileResolver {
         }
         .ok_or_else(|| Error::new_resolving(base, name))?;
 
-        Ok(path.to_string())
+        let path = path.to_string();
+        if self.source_maps && !is_native(&path) {
+            let map = std::fs::read(&path)
+                .ok()
+                .and_then(|source| SourceMap::read(ctx, &path, &source));
+            if let Some(map) = map {
+                ctx.set_source_map(path.as_str(), map);
+            }
+        }
+        Ok(path)
     }
 }
 
+/// Returns if the path is a native module, which has no source map.
+fn is_native(path: &str) -> bool {
+    matches!(
+        RelativePath::new(path).extension(),
+        Some("dll" | "dylib" | "so")
+    )
+}
+
 fn is_file<P: AsRef<RelativePath>>(path: P) -> bool {
     path.as_ref().to_path(".").is_file()
 }
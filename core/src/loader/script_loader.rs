@@ -2,7 +2,7 @@ use alloc::{string::String, vec, vec::Vec};
 
 #[cfg(feature = "std")]
 use crate::{
-    loader::{util::check_extensions, Loader},
+    loader::{util::check_extensions, Loader, SourceMap},
     Ctx, Error, Module, Result,
 };
 
@@ -12,6 +12,7 @@ use crate::{
 #[derive(Debug)]
 pub struct ScriptLoader {
     extensions: Vec<String>,
+    source_maps: bool,
 }
 
 impl ScriptLoader {
@@ -27,12 +28,30 @@ impl ScriptLoader {
         self.add_extension(extension);
         self
     }
+
+    /// Enable or disable loading of source maps, enabled by default
+    ///
+    /// Source maps are taken from the `//# sourceMappingURL=` comment of a script, either inline
+    /// as a data url or from a file relative to the script, or from a `.map` file next to the
+    /// script. Stack traces of loaded modules then point into the original sources.
+    pub fn set_source_maps(&mut self, enabled: bool) -> &mut Self {
+        self.source_maps = enabled;
+        self
+    }
+
+    /// Enable or disable loading of source maps, enabled by default
+    #[must_use]
+    pub fn with_source_maps(mut self, enabled: bool) -> Self {
+        self.set_source_maps(enabled);
+        self
+    }
 }
 
 impl Default for ScriptLoader {
     fn default() -> Self {
         Self {
             extensions: vec!["js".into()],
+            source_maps: true,
         }
     }
 }
@@ -45,6 +64,11 @@ impl Loader for ScriptLoader {
         }
 
         let source: Vec<_> = std::fs::read(path)?;
+        if self.source_maps {
+            if let Some(map) = SourceMap::read(ctx, path, &source) {
+                ctx.set_source_map(path, map);
+            }
+        }
         Module::declare(ctx.clone(), path, source)
     }
 }
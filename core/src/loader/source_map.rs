@@ -0,0 +1,563 @@
+//! Source maps for remapping stack traces of generated code.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{cell::RefCell, fmt};
+use hashbrown::HashMap;
+#[cfg(feature = "std")]
+use relative_path::RelativePath;
+
+use crate::{
+    value::exception::{data_elements, data_property},
+    Atom, Ctx, StackFrame, Value,
+};
+
+/// An error which happened while parsing a source map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SourceMapError {
+    /// The source map was not valid JSON.
+    InvalidJson,
+    /// The source map was not a version 3 source map.
+    UnsupportedVersion,
+    /// The `mappings` field was missing or malformed.
+    InvalidMappings,
+    /// The url of an inline source map was not a base64 encoded data url.
+    InvalidDataUrl,
+}
+
+impl fmt::Display for SourceMapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            SourceMapError::InvalidJson => "source map is not valid JSON".fmt(f),
+            SourceMapError::UnsupportedVersion => "only version 3 source maps are supported".fmt(f),
+            SourceMapError::InvalidMappings => "source map has invalid mappings".fmt(f),
+            SourceMapError::InvalidDataUrl => "source map url is not a base64 data url".fmt(f),
+        }
+    }
+}
+
+impl core::error::Error for SourceMapError {}
+
+/// A location in an original source file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceLocation<'a> {
+    /// The original source file.
+    pub source: &'a str,
+    /// The line number, starting at 1.
+    pub line: u32,
+    /// The column number, starting at 1.
+    pub column: u32,
+    /// The original name of the identifier at this location, if any.
+    pub name: Option<&'a str>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Segment {
+    column: u32,
+    source: u32,
+    line: u32,
+    source_column: u32,
+    name: Option<u32>,
+}
+
+/// A parsed version 3 source map.
+///
+/// Maps locations in generated code back to the original sources. Index maps with `sections`
+/// are not supported.
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    file: Option<String>,
+    sources: Vec<String>,
+    names: Vec<String>,
+    lines: Vec<Vec<Segment>>,
+}
+
+impl SourceMap {
+    /// Parse a source map from its JSON representation.
+    ///
+    /// The JSON is parsed by the engine of the given context, no JavaScript is run.
+    pub fn parse(ctx: &Ctx<'_>, json: &str) -> Result<Self, SourceMapError> {
+        let value = ctx.json_parse(json).map_err(|_| {
+            ctx.catch();
+            SourceMapError::InvalidJson
+        })?;
+        let object = value
+            .into_object()
+            .filter(|x| !x.is_array())
+            .ok_or(SourceMapError::InvalidJson)?;
+        let field = |name: &str| {
+            let atom = Atom::from_str(ctx.clone(), name).ok()?;
+            data_property(&object, atom.atom, false)
+        };
+        let string = |value: Value<'_>| value.as_string()?.to_string().ok();
+        let strings = |name: &str| -> Vec<String> {
+            field(name)
+                .and_then(Value::into_object)
+                .map(|x| {
+                    data_elements(&x)
+                        .into_iter()
+                        .map(|x| string(x).unwrap_or_default())
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        if field("version").and_then(|x| x.as_number()) != Some(3.0) {
+            return Err(SourceMapError::UnsupportedVersion);
+        }
+        let root = field("sourceRoot").and_then(string).unwrap_or_default();
+        let sources = strings("sources")
+            .into_iter()
+            .map(|source| {
+                if root.is_empty() || source.is_empty() {
+                    source
+                } else {
+                    alloc::format!("{}/{source}", root.trim_end_matches('/'))
+                }
+            })
+            .collect();
+        let mappings = field("mappings")
+            .and_then(string)
+            .ok_or(SourceMapError::InvalidMappings)?;
+
+        Ok(SourceMap {
+            file: field("file").and_then(string),
+            lines: parse_mappings(&mappings)?,
+            sources,
+            names: strings("names"),
+        })
+    }
+
+    /// Parse an inline source map from a `data:application/json;base64,` url.
+    pub fn from_data_url(ctx: &Ctx<'_>, url: &str) -> Result<Self, SourceMapError> {
+        let (header, data) = url
+            .strip_prefix("data:")
+            .and_then(|x| x.split_once(','))
+            .ok_or(SourceMapError::InvalidDataUrl)?;
+        if !header.ends_with(";base64") {
+            return Err(SourceMapError::InvalidDataUrl);
+        }
+        let data = decode_base64(data).ok_or(SourceMapError::InvalidDataUrl)?;
+        let json = core::str::from_utf8(&data).map_err(|_| SourceMapError::InvalidJson)?;
+        Self::parse(ctx, json)
+    }
+
+    /// Find the url of the `//# sourceMappingURL=` comment at the end of a script.
+    pub fn find_url(source: &[u8]) -> Option<&str> {
+        const PRAGMA: &[u8] = b"sourceMappingURL=";
+        let start = source.windows(PRAGMA.len()).rposition(|x| x == PRAGMA)?;
+        let prefix = source[..start].trim_ascii_end();
+        if !(prefix.ends_with(b"//#") || prefix.ends_with(b"//@")) {
+            return None;
+        }
+        let url = &source[start + PRAGMA.len()..];
+        let end = url
+            .iter()
+            .position(|x| x.is_ascii_whitespace())
+            .unwrap_or(url.len());
+        core::str::from_utf8(&url[..end])
+            .ok()
+            .filter(|x| !x.is_empty())
+    }
+
+    /// Read the source map of the script at `path`, ignoring maps which are missing or invalid.
+    ///
+    /// The map is taken from the `//# sourceMappingURL=` comment of the script, either inline
+    /// as a data url or from a file relative to the script, or from a `.map` file next to the
+    /// script.
+    #[cfg(feature = "std")]
+    pub(crate) fn read(ctx: &Ctx<'_>, path: &str, source: &[u8]) -> Option<Self> {
+        let relative = |base: &str, name: &str| match RelativePath::new(base).parent() {
+            Some(dir) => dir.join_normalized(name).to_string(),
+            None => name.into(),
+        };
+
+        let (map_path, mut map) = match Self::find_url(source) {
+            Some(url) if url.starts_with("data:") => {
+                (path.into(), Self::from_data_url(ctx, url).ok()?)
+            }
+            url => {
+                let map_path = match url {
+                    Some(url) => relative(path, url),
+                    None => alloc::format!("{path}.map"),
+                };
+                let json = std::fs::read_to_string(&map_path).ok()?;
+                let map = Self::parse(ctx, &json).ok()?;
+                (map_path, map)
+            }
+        };
+        map.resolve_sources(|source| relative(&map_path, source));
+        Some(map)
+    }
+
+    /// The name of the generated file, if the map contains it.
+    pub fn file(&self) -> Option<&str> {
+        self.file.as_deref()
+    }
+
+    /// The original source files.
+    pub fn sources(&self) -> &[String] {
+        &self.sources
+    }
+
+    /// Resolve all relative sources against the path of the source map.
+    #[cfg(feature = "std")]
+    fn resolve_sources(&mut self, resolve: impl Fn(&str) -> String) {
+        for source in self.sources.iter_mut() {
+            if !source.is_empty() && !source.starts_with('/') && !source.contains("://") {
+                *source = resolve(source);
+            }
+        }
+    }
+
+    /// Look up the original location of a location in the generated code.
+    ///
+    /// Lines and columns start at 1, like in stack traces.
+    pub fn lookup(&self, line: u32, column: u32) -> Option<SourceLocation<'_>> {
+        let segments = self.lines.get(line.checked_sub(1)? as usize)?;
+        let column = column.saturating_sub(1);
+        let idx = segments.partition_point(|x| x.column <= column);
+        let segment = segments.get(idx.checked_sub(1)?)?;
+        Some(SourceLocation {
+            source: self.sources.get(segment.source as usize)?,
+            line: segment.line + 1,
+            column: segment.source_column + 1,
+            name: segment
+                .name
+                .and_then(|x| self.names.get(x as usize))
+                .map(String::as_str),
+        })
+    }
+}
+
+fn parse_mappings(mappings: &str) -> Result<Vec<Vec<Segment>>, SourceMapError> {
+    let mut lines = Vec::new();
+    // All fields except the generated column are relative to the previous segment in the
+    // whole map.
+    let mut state = [0i64; 5];
+    for line in mappings.split(';') {
+        let mut segments = Vec::new();
+        state[0] = 0;
+        for segment in line.split(',').filter(|x| !x.is_empty()) {
+            let mut fields = [0i64; 5];
+            let mut len = 0;
+            let mut bytes = segment.bytes();
+            while let Some(value) = decode_vlq(&mut bytes)? {
+                if len == fields.len() {
+                    return Err(SourceMapError::InvalidMappings);
+                }
+                fields[len] = value;
+                len += 1;
+            }
+            if len != 1 && len != 4 && len != 5 {
+                return Err(SourceMapError::InvalidMappings);
+            }
+            for (state, field) in state.iter_mut().zip(&fields[..len]) {
+                *state += field;
+            }
+            // Segments with only a generated column do not map to a source.
+            if len == 1 {
+                continue;
+            }
+            let field = |x: i64| u32::try_from(x).map_err(|_| SourceMapError::InvalidMappings);
+            segments.push(Segment {
+                column: field(state[0])?,
+                source: field(state[1])?,
+                line: field(state[2])?,
+                source_column: field(state[3])?,
+                name: if len == 5 {
+                    Some(field(state[4])?)
+                } else {
+                    None
+                },
+            });
+        }
+        segments.sort_by_key(|x| x.column);
+        lines.push(segments);
+    }
+    Ok(lines)
+}
+
+fn decode_vlq(bytes: &mut impl Iterator<Item = u8>) -> Result<Option<i64>, SourceMapError> {
+    let Some(mut byte) = bytes.next() else {
+        return Ok(None);
+    };
+    let mut value = 0i64;
+    let mut shift = 0;
+    loop {
+        let digit = decode_base64_digit(byte).ok_or(SourceMapError::InvalidMappings)? as i64;
+        if shift > 60 {
+            return Err(SourceMapError::InvalidMappings);
+        }
+        value |= (digit & 0x1f) << shift;
+        if digit & 0x20 == 0 {
+            break;
+        }
+        shift += 5;
+        byte = bytes.next().ok_or(SourceMapError::InvalidMappings)?;
+    }
+    let magnitude = value >> 1;
+    Ok(Some(if value & 1 == 1 {
+        -magnitude
+    } else {
+        magnitude
+    }))
+}
+
+fn decode_base64_digit(byte: u8) -> Option<u8> {
+    Some(match byte {
+        b'A'..=b'Z' => byte - b'A',
+        b'a'..=b'z' => byte - b'a' + 26,
+        b'0'..=b'9' => byte - b'0' + 52,
+        b'+' => 62,
+        b'/' => 63,
+        _ => return None,
+    })
+}
+
+fn decode_base64(data: &str) -> Option<Vec<u8>> {
+    let mut res = Vec::with_capacity(data.len() / 4 * 3);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for byte in data.bytes().take_while(|x| *x != b'=') {
+        acc = (acc << 6) | decode_base64_digit(byte)? as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            res.push((acc >> bits) as u8);
+        }
+    }
+    Some(res)
+}
+
+/// The source maps registered in a runtime, keyed by the name of the generated script or module.
+#[derive(Default)]
+pub(crate) struct SourceMaps(RefCell<HashMap<String, SourceMap>>);
+
+impl SourceMaps {
+    pub fn insert(&self, name: String, map: SourceMap) -> Option<SourceMap> {
+        self.0.borrow_mut().insert(name, map)
+    }
+
+    pub fn remove(&self, name: &str) -> Option<SourceMap> {
+        self.0.borrow_mut().remove(name)
+    }
+
+    /// Rewrite frames to point into the original sources, returns if any frame was remapped.
+    pub fn remap_frames(&self, frames: &mut [StackFrame]) -> bool {
+        let maps = self.0.borrow();
+        let mut remapped = false;
+        for frame in frames {
+            remapped |= Self::remap_frame(&maps, frame).is_some();
+        }
+        remapped
+    }
+
+    fn remap_frame(maps: &HashMap<String, SourceMap>, frame: &mut StackFrame) -> Option<()> {
+        let map = maps.get(frame.file.as_deref()?)?;
+        let location = map.lookup(frame.line?, frame.column?)?;
+        frame.file = Some(location.source.to_string());
+        frame.line = Some(location.line);
+        frame.column = Some(location.column);
+        Some(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{SourceMap, SourceMapError, SourceMaps};
+    use crate::{
+        loader::{bundle::Bundle, Loader},
+        qjs, test_with, CatchResultExt, CaughtError, Context, Module, Runtime, StackFrame,
+        WriteOptions,
+    };
+
+    // Generated for `src/app.ts`:
+    //
+    // function greet(name: string) {
+    //     throw new Error(name);
+    // }
+    const MAP: &str = r#"{
+        "version": 3,
+        "file": "app.js",
+        "sourceRoot": "",
+        "sources": ["src/app.ts"],
+        "names": ["greet", "name"],
+        "mappings": "AAAA,SAASA,MAAMC;IACX,MAAM"
+    }"#;
+
+    #[test]
+    fn parse_and_lookup() {
+        test_with(|ctx| {
+            let map = SourceMap::parse(&ctx, MAP).unwrap();
+            assert_eq!(map.file(), Some("app.js"));
+            assert_eq!(map.sources(), ["src/app.ts"]);
+
+            let location = map.lookup(1, 10).unwrap();
+            assert_eq!(
+                (location.source, location.line, location.column),
+                ("src/app.ts", 1, 10)
+            );
+            assert_eq!(location.name, Some("greet"));
+
+            let location = map.lookup(1, 17).unwrap();
+            assert_eq!((location.line, location.column), (1, 16));
+            assert_eq!(location.name, Some("name"));
+
+            let location = map.lookup(2, 12).unwrap();
+            assert_eq!((location.line, location.column), (2, 11));
+            assert_eq!(map.lookup(3, 1), None);
+
+            assert_eq!(
+                SourceMap::parse(&ctx, r#"{"version": 2, "mappings": ""}"#).unwrap_err(),
+                SourceMapError::UnsupportedVersion
+            );
+            assert_eq!(
+                SourceMap::parse(&ctx, r#"{"version": 3, "mappings": "A$"}"#).unwrap_err(),
+                SourceMapError::InvalidMappings
+            );
+            assert_eq!(
+                SourceMap::parse(&ctx, r#"{"version": 3,"#).unwrap_err(),
+                SourceMapError::InvalidJson
+            );
+            assert_eq!(
+                SourceMap::parse(&ctx, "[3]").unwrap_err(),
+                SourceMapError::InvalidJson
+            );
+            assert!(!unsafe { qjs::JS_HasException(ctx.as_ptr()) });
+        })
+    }
+
+    #[test]
+    fn inline_map() {
+        test_with(|ctx| {
+            let source = b"let a = 1;\n//# sourceMappingURL=data:application/json;base64,eyJ2ZXJzaW9uIjozLCJzb3VyY2VzIjpbImEudHMiXSwibmFtZXMiOltdLCJtYXBwaW5ncyI6IkFBQUEifQ==\n";
+            let url = SourceMap::find_url(source).unwrap();
+            let map = SourceMap::from_data_url(&ctx, url).unwrap();
+            assert_eq!(map.sources(), ["a.ts"]);
+            assert_eq!(map.lookup(1, 1).unwrap().source, "a.ts");
+
+            assert_eq!(
+                SourceMap::find_url(b"x;\n//# sourceMappingURL=app.js.map"),
+                Some("app.js.map")
+            );
+            assert_eq!(SourceMap::find_url(b"let sourceMappingURL=1"), None);
+        })
+    }
+
+    #[test]
+    fn remap_frames() {
+        let maps = SourceMaps::default();
+        let map = test_with(|ctx| SourceMap::parse(&ctx, MAP).unwrap());
+        maps.insert("app.js".into(), map);
+        let frame = |file: Option<&str>, line, column| StackFrame {
+            function: Some("greet".into()),
+            file: file.map(Into::into),
+            line,
+            column,
+            is_native: file.is_none(),
+            is_call: true,
+        };
+        let mut frames = [
+            frame(Some("app.js"), Some(2), Some(12)),
+            frame(None, None, None),
+            frame(Some("other.js"), Some(1), Some(1)),
+        ];
+        let expected = [
+            frame(Some("src/app.ts"), Some(2), Some(11)),
+            frames[1].clone(),
+            frames[2].clone(),
+        ];
+        assert!(maps.remap_frames(&mut frames));
+        assert_eq!(frames, expected);
+        assert!(!maps.remap_frames(&mut frames[1..]));
+    }
+
+    const SCRIPT: &str = "function greet(name) {\n    throw new Error(name);\n}\n\ngreet('x');\n";
+
+    #[test]
+    fn remap_exception() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            ctx.set_source_map("app.js", SourceMap::parse(&ctx, MAP).unwrap());
+            let CaughtError::Exception(error) = Module::evaluate(ctx.clone(), "app.js", SCRIPT)
+                .and_then(|x| x.finish::<()>())
+                .catch(&ctx)
+                .unwrap_err()
+            else {
+                panic!("expected an exception")
+            };
+            let frames = error.frames();
+            assert_eq!(frames[0].function.as_deref(), Some("greet"));
+            assert_eq!(frames[0].file.as_deref(), Some("src/app.ts"));
+            assert_eq!(frames[0].line, Some(2));
+            assert_eq!(frames[1].file.as_deref(), Some("app.js"));
+            assert_eq!(frames[1].line, Some(5));
+            let stack = error.stack().unwrap();
+            assert!(stack.starts_with("    at greet (src/app.ts:2:"), "{stack}");
+
+            assert!(ctx.remove_source_map("app.js").is_some());
+            assert_eq!(error.frames()[0].file.as_deref(), Some("app.js"));
+        });
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn file_resolver_source_map() {
+        use crate::loader::{FileResolver, ScriptLoader};
+
+        let dir = "target/file_resolver_source_map";
+        std::fs::create_dir_all(dir).unwrap();
+        let source = alloc::format!("{SCRIPT}//# sourceMappingURL=app.js.map\n");
+        std::fs::write(alloc::format!("{dir}/app.js"), source).unwrap();
+        std::fs::write(alloc::format!("{dir}/app.js.map"), MAP).unwrap();
+
+        let rt = Runtime::new().unwrap();
+        rt.set_loader(
+            FileResolver::default(),
+            ScriptLoader::default().with_source_maps(false),
+        );
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            let source = alloc::format!("import './{dir}/app.js';");
+            let CaughtError::Exception(error) = Module::evaluate(ctx.clone(), "main.js", source)
+                .and_then(|x| x.finish::<()>())
+                .catch(&ctx)
+                .unwrap_err()
+            else {
+                panic!("expected an exception")
+            };
+            let file = alloc::format!("{dir}/src/app.ts");
+            assert_eq!(error.frames()[0].file.as_deref(), Some(file.as_str()));
+        });
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn bundle_source_map() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            let bytecode = Module::declare(ctx.clone(), "app.js", SCRIPT)
+                .unwrap()
+                .write(WriteOptions::default())
+                .unwrap();
+            let bytecode: &'static [u8] = alloc::vec::Vec::leak(bytecode);
+            let data = alloc::vec![("app.js", (bytecode, MAP))].leak();
+            let CaughtError::Exception(error) = Bundle(&*data)
+                .load(&ctx, "app.js")
+                .and_then(|x| x.eval())
+                .and_then(|(_, x)| x.finish::<()>())
+                .catch(&ctx)
+                .unwrap_err()
+            else {
+                panic!("expected an exception")
+            };
+            assert_eq!(error.frames()[0].file.as_deref(), Some("src/app.ts"));
+        });
+    }
+}
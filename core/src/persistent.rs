@@ -1,8 +1,18 @@
-use crate::{qjs, Ctx, Error, FromJs, IntoJs, JsLifetime, Result, Value};
+use crate::{
+    class::{Trace, Tracer},
+    qjs,
+    runtime::opaque::Opaque,
+    Ctx, Error, FromJs, IntoJs, JsLifetime, Result, Value,
+};
 
+use alloc::vec::Vec;
 use core::{
+    any::type_name,
+    cell::RefCell,
     fmt,
+    hash::{Hash, Hasher},
     mem::{self, ManuallyDrop},
+    ptr,
 };
 
 /// The wrapper for JS values to keep it from GC
@@ -30,24 +40,57 @@ use core::{
 /// It is an error (`Error::UnrelatedRuntime`) to restore the `Persistent` in a
 /// context who isn't part of the original `Runtime`.
 ///
+/// The values held by a `Persistent` are roots of the
+/// [heap snapshots](crate::Runtime::heap_snapshot) of the runtime.
+///
 /// NOTE: Be careful and ensure that no persistent links outlives the runtime,
 /// otherwise Runtime will abort the process when dropped.
 ///
-#[derive(Eq, PartialEq, Hash)]
 pub struct Persistent<T> {
     pub(crate) rt: *mut qjs::JSRuntime,
     pub(crate) value: T,
+    /// The id under which the held values are registered with the runtime, 0 if it holds none.
+    root: u64,
 }
 
 impl<T: Clone> Clone for Persistent<T> {
     fn clone(&self) -> Self {
+        let root = if self.root != 0 {
+            unsafe { Opaque::from_runtime_ptr(self.rt).clone_persistent_root(self.root) }
+        } else {
+            0
+        };
         Persistent {
             rt: self.rt,
             value: self.value.clone(),
+            root,
+        }
+    }
+}
+
+impl<T> Drop for Persistent<T> {
+    fn drop(&mut self) {
+        if self.root != 0 {
+            unsafe { Opaque::from_runtime_ptr(self.rt).remove_persistent_root(self.root) }
         }
     }
 }
 
+impl<T: PartialEq> PartialEq for Persistent<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.rt == other.rt && self.value == other.value
+    }
+}
+
+impl<T: Eq> Eq for Persistent<T> {}
+
+impl<T: Hash> Hash for Persistent<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rt.hash(state);
+        self.value.hash(state);
+    }
+}
+
 impl<T> fmt::Debug for Persistent<T>
 where
     T: fmt::Debug,
@@ -87,14 +130,25 @@ impl<T> Persistent<T> {
     /// Save the value of an arbitrary type
     pub fn save<'js>(ctx: &Ctx<'js>, val: T) -> Persistent<T::Changed<'static>>
     where
-        T: JsLifetime<'js>,
+        T: JsLifetime<'js> + Trace<'js>,
     {
+        let ptr = unsafe { qjs::JS_GetRuntime(ctx.as_ptr()) };
+        let collector = RefCell::new(Vec::new());
+        val.trace(Tracer::collect(ptr, &collector));
+        let values = collector.into_inner();
+        let root = if values.is_empty() {
+            0
+        } else {
+            let name = type_name::<T::Changed<'static>>();
+            unsafe { ctx.get_opaque().add_persistent_root(name, values) }
+        };
+
         let outlived: T::Changed<'static> =
             unsafe { Self::outlive_transmute::<'js, 'static, T>(val) };
-        let ptr = unsafe { qjs::JS_GetRuntime(ctx.as_ptr()) };
         Persistent {
             rt: ptr,
             value: outlived,
+            root,
         }
     }
 
@@ -107,14 +161,20 @@ impl<T> Persistent<T> {
         if self.rt != ctx_runtime_ptr {
             return Err(Error::UnrelatedRuntime);
         }
-        Ok(unsafe { Self::outlive_transmute::<'static, 'js, T>(self.value) })
+        let this = ManuallyDrop::new(self);
+        if this.root != 0 {
+            unsafe { ctx.get_opaque().remove_persistent_root(this.root) };
+        }
+        // Safety: `this` is never dropped, so the value is only moved out once.
+        let value = unsafe { ptr::read(&this.value) };
+        Ok(unsafe { Self::outlive_transmute::<'static, 'js, T>(value) })
     }
 }
 
 impl<'js, T, R> FromJs<'js> for Persistent<R>
 where
     R: JsLifetime<'static, Changed<'js> = T>,
-    T: JsLifetime<'js, Changed<'static> = R> + FromJs<'js>,
+    T: JsLifetime<'js, Changed<'static> = R> + FromJs<'js> + Trace<'js>,
 {
     fn from_js(ctx: &Ctx<'js>, value: Value<'js>) -> Result<Persistent<R>> {
         let value = T::from_js(ctx, value)?;
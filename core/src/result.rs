@@ -7,6 +7,7 @@ use core::{
 };
 
 use alloc::{
+    boxed::Box,
     ffi::{CString, NulError},
     string::{FromUtf8Error, ToString as _},
 };
@@ -18,8 +19,11 @@ use std::io::Error as IoError;
 use crate::context::AsyncContext;
 use crate::value::array_buffer::AsSliceError;
 use crate::{
-    atom::PredefinedAtom, qjs, runtime::UserDataError, value::exception::ERROR_FORMAT_STR, Context,
-    Ctx, Exception, Object, StdResult, StdString, Type, Value,
+    atom::PredefinedAtom,
+    qjs,
+    runtime::{InterruptReason, UserDataError},
+    value::exception::ERROR_FORMAT_STR,
+    Context, Ctx, Exception, ExceptionInfo, Object, StdResult, StdString, Type, Value,
 };
 
 /// Result type used throughout the library.
@@ -89,6 +93,20 @@ pub enum Error {
     /// When returned from a callback the JavaScript will continue to unwind with the current
     /// error.
     Exception,
+    /// A value thrown by JavaScript which was caught and detached from its context.
+    ///
+    /// Created by converting a [`CaughtError`] into an `Error`, for example with the `?`
+    /// operator. When returned from a callback a new error with the same name and message is
+    /// thrown.
+    Thrown(Box<ExceptionInfo>),
+    /// Execution was interrupted by an execution limit or the interrupt handler.
+    ///
+    /// The uncatchable exception raised by QuickJS is cleared when this error is created. When
+    /// returned from a callback the JavaScript will continue to unwind with a new uncatchable
+    /// exception.
+    Interrupted {
+        reason: InterruptReason,
+    },
     /// Error converting from JavaScript to a Rust type.
     FromJs {
         from: &'static str,
@@ -101,6 +119,13 @@ pub enum Error {
         to: &'static str,
         message: Option<StdString>,
     },
+    /// A conversion error which happened at a nested property of the converted value.
+    Nested {
+        /// The path to the property from the converted root value, like `$.servers[1].port`.
+        path: StdString,
+        /// The conversion error of the property.
+        error: Box<Error>,
+    },
     /// Error matching of function arguments
     MissingArgs {
         expected: usize,
@@ -124,6 +149,14 @@ pub enum Error {
         message: Option<StdString>,
     },
     AsSlice(AsSliceError),
+    /// Error when a value could not be structurally cloned.
+    DataClone {
+        /// The type of the value which could not be cloned.
+        from: &'static str,
+        /// The path to the value from the cloned root value, like `$.foo[1]`.
+        path: StdString,
+        message: Option<StdString>,
+    },
     /// Error when restoring a Persistent in a runtime other than the original runtime.
     UnrelatedRuntime,
     /// An error returned by a blocked on promise if block on the promise would result in a dead
@@ -204,6 +237,11 @@ impl Error {
         matches!(self, Error::Exception)
     }
 
+    /// Returns whether execution was interrupted.
+    pub fn is_interrupted(&self) -> bool {
+        matches!(self, Error::Interrupted { .. })
+    }
+
     /// Create from JS conversion error
     pub fn new_from_js(from: &'static str, to: &'static str) -> Self {
         Error::FromJs {
@@ -248,17 +286,55 @@ impl Error {
 
     /// Returns whether the error is a from JS conversion error
     pub fn is_from_js(&self) -> bool {
-        matches!(self, Self::FromJs { .. })
+        match self {
+            Self::Nested { error, .. } => error.is_from_js(),
+            error => matches!(error, Self::FromJs { .. }),
+        }
     }
 
     /// Returns whether the error is a from JS to JS type conversion error
     pub fn is_from_js_to_js(&self) -> bool {
-        matches!(self, Self::FromJs { to, .. } if Type::from_str(to).is_ok())
+        match self {
+            Self::Nested { error, .. } => error.is_from_js_to_js(),
+            error => matches!(error, Self::FromJs { to, .. } if Type::from_str(to).is_ok()),
+        }
     }
 
     /// Returns whether the error is an into JS conversion error
     pub fn is_into_js(&self) -> bool {
-        matches!(self, Self::IntoJs { .. })
+        match self {
+            Self::Nested { error, .. } => error.is_into_js(),
+            error => matches!(error, Self::IntoJs { .. }),
+        }
+    }
+
+    /// Create structured clone error
+    pub fn new_data_clone<P>(from: &'static str, path: P) -> Self
+    where
+        StdString: From<P>,
+    {
+        Error::DataClone {
+            from,
+            path: path.into(),
+            message: None,
+        }
+    }
+
+    /// Create structured clone error with message
+    pub fn new_data_clone_message<P, M>(from: &'static str, path: P, msg: M) -> Self
+    where
+        StdString: From<P> + From<M>,
+    {
+        Error::DataClone {
+            from,
+            path: path.into(),
+            message: Some(msg.into()),
+        }
+    }
+
+    /// Returns whether the error is a structured clone error
+    pub fn is_data_clone(&self) -> bool {
+        matches!(self, Self::DataClone { .. })
     }
 
     /// Return whether the error is an function args mismatch error
@@ -282,11 +358,41 @@ impl Error {
         use Error::*;
         match self {
             Exception => qjs::JS_EXCEPTION,
+            Interrupted { reason } => unsafe {
+                ctx.get_opaque().limits().set_reason(*reason);
+                qjs::JS_ThrowInternalError(
+                    ctx.as_ptr(),
+                    ERROR_FORMAT_STR.as_ptr(),
+                    c"interrupted".as_ptr(),
+                );
+                let error = qjs::JS_GetException(ctx.as_ptr());
+                qjs::JS_SetUncatchableError(ctx.as_ptr(), error);
+                qjs::JS_Throw(ctx.as_ptr(), error)
+            },
+            Thrown(info) => unsafe {
+                let value = qjs::JS_NewError(ctx.as_ptr());
+                if qjs::JS_VALUE_GET_NORM_TAG(value) == qjs::JS_TAG_EXCEPTION {
+                    return value;
+                }
+                let obj = Object::from_js_value(ctx.clone(), value);
+                let res = obj
+                    .set(PredefinedAtom::Message, info.message.as_deref())
+                    .and_then(|_| match info.name {
+                        Some(ref name) => obj.set(PredefinedAtom::Name, name.as_str()),
+                        None => Ok(()),
+                    });
+                if res.is_err() {
+                    return qjs::JS_EXCEPTION;
+                }
+                qjs::JS_Throw(ctx.as_ptr(), obj.into_js_value())
+            },
             Allocation => unsafe { qjs::JS_ThrowOutOfMemory(ctx.as_ptr()) },
             InvalidString(_)
             | Utf8(_)
             | FromJs { .. }
             | IntoJs { .. }
+            | Nested { .. }
+            | DataClone { .. }
             | TooManyArgs { .. }
             | MissingArgs { .. } => {
                 let message = self.to_cstring();
@@ -379,6 +485,11 @@ impl Display for Error {
             }
             Error::Unknown => "QuickJS library created a unknown error".fmt(f)?,
             Error::Exception => "Exception generated by QuickJS".fmt(f)?,
+            Error::Thrown(info) => info.fmt(f)?,
+            Error::Interrupted { reason } => {
+                "Execution was interrupted: ".fmt(f)?;
+                reason.fmt(f)?;
+            }
             Error::FromJs { from, to, message } => {
                 "Error converting from js '".fmt(f)?;
                 from.fmt(f)?;
@@ -405,6 +516,12 @@ impl Display for Error {
                     }
                 }
             }
+            Error::Nested { path, error } => {
+                error.fmt(f)?;
+                " at `".fmt(f)?;
+                path.fmt(f)?;
+                "`".fmt(f)?;
+            }
             Error::MissingArgs { expected, given } => {
                 "Error calling function with ".fmt(f)?;
                 given.fmt(f)?;
@@ -469,6 +586,23 @@ impl Display for Error {
                 "Could not convert array buffer to slice: ".fmt(f)?;
                 x.fmt(f)?;
             }
+            Error::DataClone {
+                from,
+                path,
+                message,
+            } => {
+                "Could not clone value of type '".fmt(f)?;
+                from.fmt(f)?;
+                "' at `".fmt(f)?;
+                path.fmt(f)?;
+                "`".fmt(f)?;
+                if let Some(message) = message {
+                    if !message.is_empty() {
+                        ": ".fmt(f)?;
+                        message.fmt(f)?;
+                    }
+                }
+            }
             Error::UnrelatedRuntime => "Restoring Persistent in an unrelated runtime".fmt(f)?,
         }
         Ok(())
@@ -531,7 +665,7 @@ impl<'js> Display for CaughtError<'js> {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         match *self {
             CaughtError::Error(ref e) => e.fmt(f),
-            CaughtError::Exception(ref e) => e.fmt(f),
+            CaughtError::Exception(ref e) => e.to_info().fmt(f),
             CaughtError::Value(ref e) => {
                 writeln!(f, "Exception generated by quickjs: {e:?}")
             }
@@ -541,6 +675,18 @@ impl<'js> Display for CaughtError<'js> {
 
 impl<'js> StdError for CaughtError<'js> {}
 
+impl<'js> From<CaughtError<'js>> for Error {
+    /// Turns the caught error into an error which no longer references the context, see
+    /// [`Error::Thrown`].
+    fn from(error: CaughtError<'js>) -> Self {
+        match error {
+            CaughtError::Error(e) => e,
+            CaughtError::Exception(e) => Error::Thrown(Box::new(e.to_info())),
+            CaughtError::Value(v) => Error::Thrown(Box::new(ExceptionInfo::from_value(&v))),
+        }
+    }
+}
+
 impl<'js> CaughtError<'js> {
     /// Create a `CaughtError` from an [`Error`], retrieving the error value from `Ctx` if there
     /// was one.
@@ -701,7 +847,25 @@ impl<'js> Ctx<'js> {
             if let Some(x) = self.get_opaque().take_panic() {
                 crate::util::resume_unwind(x);
             }
-            Err(Error::Exception)
+            Err(self.take_interrupt().unwrap_or(Error::Exception))
+        }
+    }
+
+    /// Returns [`Error::Interrupted`] if the pending exception is the uncatchable error raised
+    /// when execution was interrupted, clearing the exception.
+    unsafe fn take_interrupt(&self) -> Option<Error> {
+        let exception = qjs::JS_GetException(self.as_ptr());
+        if qjs::JS_IsUncatchableError(self.as_ptr(), exception) {
+            qjs::JS_FreeValue(self.as_ptr(), exception);
+            // The reason is only taken for the interrupt it belongs to, so a catchable exception
+            // raised in between doesn't lose it.
+            let reason = self.get_opaque().limits().take_reason();
+            Some(Error::Interrupted {
+                reason: reason.unwrap_or(InterruptReason::Handler),
+            })
+        } else {
+            qjs::JS_Throw(self.as_ptr(), exception);
+            None
         }
     }
 
@@ -713,7 +877,7 @@ impl<'js> Ctx<'js> {
             if let Some(x) = self.get_opaque().take_panic() {
                 crate::util::resume_unwind(x);
             }
-            Error::Exception
+            self.take_interrupt().unwrap_or(Error::Exception)
         }
     }
 }
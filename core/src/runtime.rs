@@ -1,7 +1,16 @@
 //! QuickJS runtime related types.
 
 mod base;
+#[cfg(feature = "std")]
+mod event_loop;
+mod heap_snapshot;
+pub(crate) mod interrupt;
+mod memory_usage;
 pub(crate) mod opaque;
+#[cfg(all(feature = "futures", feature = "std"))]
+mod pool;
+#[cfg(feature = "std")]
+mod profiler;
 pub(crate) mod raw;
 mod userdata;
 
@@ -11,18 +20,37 @@ mod r#async;
 pub(crate) mod schedular;
 #[cfg(feature = "futures")]
 mod spawner;
+#[cfg(feature = "futures")]
+mod task_handle;
 
 use alloc::boxed::Box;
 pub use base::{Runtime, WeakRuntime};
+#[cfg(feature = "std")]
+pub use event_loop::{EventLoop, EventLoopHandle, EventLoopTask, TimerId};
+pub use heap_snapshot::{HeapEdge, HeapEdgeKind, HeapNode, HeapNodeKind, HeapSnapshot};
+pub use interrupt::InterruptReason;
+pub use memory_usage::MemoryUsage;
+#[cfg(feature = "std")]
+pub use profiler::{Profile, ProfileFrame, ProfileSample};
 pub use userdata::{UserDataError, UserDataGuard};
 
+#[cfg(all(feature = "futures", feature = "std"))]
+pub use pool::{
+    ContextInit, Lease, PoolMetrics, ResetPolicy, RuntimeMetrics, RuntimePool, RuntimePoolBuilder,
+};
 #[cfg(feature = "futures")]
 pub(crate) use r#async::InnerRuntime;
 #[cfg(feature = "futures")]
 pub use r#async::{AsyncRuntime, AsyncWeakRuntime};
+#[cfg(feature = "futures")]
+pub(crate) use spawner::InContext;
+#[cfg(feature = "futures")]
+pub(crate) use task_handle::Abortable;
+#[cfg(feature = "futures")]
+pub use task_handle::TaskHandle;
 
 use crate::value::promise::PromiseHookType;
-use crate::{Ctx, Value};
+use crate::{Ctx, Exception, StackFrame, StdString, Value};
 
 /// The type of the promise hook.
 #[cfg(not(feature = "parallel"))]
@@ -48,5 +76,11 @@ pub type InterruptHandler = Box<dyn FnMut() -> bool + 'static>;
 #[cfg(feature = "parallel")]
 pub type InterruptHandler = Box<dyn FnMut() -> bool + Send + 'static>;
 
-/// A struct with information about the runtimes memory usage.
-pub type MemoryUsage = crate::qjs::JSMemoryUsage;
+/// The type of the stack formatter.
+#[cfg(not(feature = "parallel"))]
+pub type StackFormatter =
+    Box<dyn for<'a> Fn(Ctx<'a>, &Exception<'a>, &[StackFrame]) -> StdString + 'static>;
+/// The type of the stack formatter.
+#[cfg(feature = "parallel")]
+pub type StackFormatter =
+    Box<dyn for<'a> Fn(Ctx<'a>, &Exception<'a>, &[StackFrame]) -> StdString + Send + 'static>;
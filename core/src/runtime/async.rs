@@ -5,16 +5,21 @@ use alloc::{
 };
 use core::{ptr::NonNull, result::Result as StdResult, task::Poll};
 #[cfg(feature = "std")]
-use std::println;
+use std::{
+    println,
+    time::{Duration, Instant},
+};
 
 #[cfg(feature = "parallel")]
 use std::sync::mpsc::{self, Receiver, Sender};
 
 use async_lock::Mutex;
 
+#[cfg(feature = "std")]
+use super::Profile;
 use super::{
-    opaque::Opaque, raw::RawRuntime, schedular::SchedularPoll, spawner::DriveFuture,
-    InterruptHandler, MemoryUsage, PromiseHook,
+    opaque::Opaque, raw::RawRuntime, schedular::SchedularPoll, spawner::DriveFuture, HeapSnapshot,
+    InterruptHandler, MemoryUsage, PromiseHook, StackFormatter,
 };
 use crate::allocator::Allocator;
 #[cfg(feature = "loader")]
@@ -169,6 +174,26 @@ impl AsyncRuntime {
         }
     }
 
+    /// Set a closure which formats the stack of exceptions.
+    ///
+    /// The closure is called with the exception and the frames of its stack, as returned by
+    /// [`Exception::frames`](crate::Exception::frames), and returns the string which is returned by
+    /// [`Exception::stack`](crate::Exception::stack) and used when rendering caught errors. It
+    /// can for example drop frames of internal scripts. Within the closure
+    /// `Exception::stack` returns the unformatted stack.
+    ///
+    /// The `stack` property seen by JavaScript is not changed.
+    #[inline]
+    pub async fn set_stack_formatter(&self, formatter: Option<StackFormatter>) {
+        unsafe {
+            self.inner
+                .lock()
+                .await
+                .runtime
+                .set_stack_formatter(formatter);
+        }
+    }
+
     /// Set a closure which is regularly called by the engine when it is executing code.
     /// If the provided closure returns `true` the interpreter will raise and uncatchable
     /// exception and return control flow to the caller.
@@ -183,6 +208,75 @@ impl AsyncRuntime {
         }
     }
 
+    /// Interrupt all code which is still executing after `timeout` has elapsed from now.
+    ///
+    /// Code which is interrupted returns [`Error::Interrupted`](crate::Error::Interrupted). The
+    /// deadline remains in effect until it is replaced or cleared with
+    /// [`clear_execution_deadline`](Self::clear_execution_deadline).
+    #[cfg(feature = "std")]
+    #[inline]
+    pub async fn set_execution_deadline(&self, timeout: Duration) {
+        let deadline = Instant::now() + timeout;
+        unsafe {
+            self.inner
+                .lock()
+                .await
+                .runtime
+                .set_execution_deadline(Some(deadline));
+        }
+    }
+
+    /// Remove the deadline set with [`set_execution_deadline`](Self::set_execution_deadline).
+    #[cfg(feature = "std")]
+    #[inline]
+    pub async fn clear_execution_deadline(&self) {
+        unsafe {
+            self.inner.lock().await.runtime.set_execution_deadline(None);
+        }
+    }
+
+    /// Interrupt executing code after the interrupt handler has been polled `budget` more times.
+    ///
+    /// See [`Runtime::set_interrupt_budget`](crate::Runtime::set_interrupt_budget).
+    #[inline]
+    pub async fn set_interrupt_budget(&self, budget: u64) {
+        unsafe {
+            self.inner
+                .lock()
+                .await
+                .runtime
+                .set_interrupt_budget(Some(budget));
+        }
+    }
+
+    /// Remove the budget set with [`set_interrupt_budget`](Self::set_interrupt_budget).
+    #[inline]
+    pub async fn clear_interrupt_budget(&self) {
+        unsafe {
+            self.inner.lock().await.runtime.set_interrupt_budget(None);
+        }
+    }
+
+    /// Start the sampling profiler.
+    ///
+    /// See [`Runtime::start_profiling`](crate::Runtime::start_profiling).
+    #[cfg(feature = "std")]
+    #[inline]
+    pub async fn start_profiling(&self, interval: Duration) {
+        unsafe {
+            self.inner.lock().await.runtime.start_profiling(interval);
+        }
+    }
+
+    /// Stop the profiler and return the collected samples.
+    ///
+    /// See [`Runtime::stop_profiling`](crate::Runtime::stop_profiling).
+    #[cfg(feature = "std")]
+    #[inline]
+    pub async fn stop_profiling(&self) -> Profile {
+        unsafe { self.inner.lock().await.runtime.stop_profiling() }
+    }
+
     /// Set the module loader
     #[cfg(feature = "loader")]
     #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "loader")))]
@@ -247,9 +341,21 @@ impl AsyncRuntime {
         }
     }
 
+    /// Create a snapshot of all objects of the runtime.
+    ///
+    /// The roots of the snapshot are the global objects of all contexts of the runtime and the
+    /// values held by [`Persistent`](crate::Persistent) handles.
+    pub async fn heap_snapshot(&self) -> Result<HeapSnapshot> {
+        unsafe {
+            let mut lock = self.inner.lock().await;
+            lock.drop_pending();
+            lock.runtime.heap_snapshot()
+        }
+    }
+
     /// Get memory usage stats
     pub async fn memory_usage(&self) -> MemoryUsage {
-        unsafe { self.inner.lock().await.runtime.memory_usage() }
+        unsafe { self.inner.lock().await.runtime.memory_usage() }.into()
     }
 
     /// Test for pending jobs
@@ -510,7 +616,7 @@ mod test {
                 // Add a bunch of futures just to make sure possible segfaults are more likely to
                 // happen
                 for _ in 0..32{
-                    ctx_clone.spawn(async move {})
+                    ctx_clone.spawn(async move {});
                 }
 
             });
@@ -592,6 +698,39 @@ mod test {
         assert_eq!(COUNT.load(Ordering::Relaxed),2);
     });
 
+    async_test_case!(abort_spawned => (rt,ctx){
+        use std::sync::{Arc, atomic::{Ordering,AtomicBool}};
+
+        struct SetOnDrop(Arc<AtomicBool>);
+        impl Drop for SetOnDrop{
+            fn drop(&mut self){
+                self.0.store(true,Ordering::SeqCst);
+            }
+        }
+
+        let dropped = Arc::new(AtomicBool::new(false));
+        let guard = SetOnDrop(dropped.clone());
+
+        let (pending, finished) = async_with!(&ctx => |ctx|{
+            let pending = ctx.spawn(async move {
+                let _guard = guard;
+                std::future::pending::<()>().await;
+            });
+            let finished = ctx.spawn(async move {});
+            (pending, finished)
+        }).await;
+
+        assert!(!pending.is_finished());
+        assert!(!dropped.load(Ordering::SeqCst));
+
+        // idle only returns once the pending future is aborted.
+        pending.abort();
+        rt.idle().await;
+        assert!(finished.is_finished());
+        assert!(pending.is_finished());
+        assert!(dropped.load(Ordering::SeqCst));
+    });
+
     #[cfg(feature = "parallel")]
     fn assert_is_send<T: Send>(t: T) -> T {
         t
@@ -1,7 +1,8 @@
 //! QuickJS runtime related types.
 
 use super::{
-    opaque::Opaque, raw::RawRuntime, InterruptHandler, MemoryUsage, PromiseHook, RejectionTracker,
+    opaque::Opaque, raw::RawRuntime, HeapSnapshot, InterruptHandler, MemoryUsage, PromiseHook,
+    RejectionTracker, StackFormatter,
 };
 use crate::allocator::Allocator;
 #[cfg(feature = "loader")]
@@ -10,6 +11,11 @@ use crate::{result::JobException, Context, Mut, Ref, Result, Weak};
 use alloc::{ffi::CString, vec::Vec};
 use core::{ptr::NonNull, result::Result as StdResult};
 
+#[cfg(feature = "std")]
+use super::Profile;
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
 /// A weak handle to the runtime.
 ///
 /// Holding onto this struct does not prevent the runtime from being dropped.
@@ -82,6 +88,22 @@ impl Runtime {
         }
     }
 
+    /// Set a closure which formats the stack of exceptions.
+    ///
+    /// The closure is called with the exception and the frames of its stack, as returned by
+    /// [`Exception::frames`](crate::Exception::frames), and returns the string which is returned by
+    /// [`Exception::stack`](crate::Exception::stack) and used when rendering caught errors. It
+    /// can for example drop frames of internal scripts. Within the closure
+    /// `Exception::stack` returns the unformatted stack.
+    ///
+    /// The `stack` property seen by JavaScript is not changed.
+    #[inline]
+    pub fn set_stack_formatter(&self, formatter: Option<StackFormatter>) {
+        unsafe {
+            self.inner.lock().set_stack_formatter(formatter);
+        }
+    }
+
     /// Set a closure which is regularly called by the engine when it is executing code.
     /// If the provided closure returns `true` the interpreter will raise and uncatchable
     /// exception and return control flow to the caller.
@@ -92,6 +114,78 @@ impl Runtime {
         }
     }
 
+    /// Interrupt all code which is still executing after `timeout` has elapsed from now.
+    ///
+    /// Code which is interrupted returns [`Error::Interrupted`](crate::Error::Interrupted). The
+    /// deadline remains in effect until it is replaced or cleared with
+    /// [`clear_execution_deadline`](Self::clear_execution_deadline). See
+    /// [`Ctx::with_execution_deadline`](crate::Ctx::with_execution_deadline) for a deadline which
+    /// applies to a single call.
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn set_execution_deadline(&self, timeout: Duration) {
+        let deadline = Instant::now() + timeout;
+        unsafe {
+            self.inner.lock().set_execution_deadline(Some(deadline));
+        }
+    }
+
+    /// Remove the deadline set with [`set_execution_deadline`](Self::set_execution_deadline).
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn clear_execution_deadline(&self) {
+        unsafe {
+            self.inner.lock().set_execution_deadline(None);
+        }
+    }
+
+    /// Interrupt executing code after the interrupt handler has been polled `budget` more times.
+    ///
+    /// QuickJS polls the interrupt handler roughly every 10000 executed instructions, so the
+    /// budget limits the amount of work scripts can do independent of the speed of the host.
+    /// Code which is interrupted returns [`Error::Interrupted`](crate::Error::Interrupted).
+    #[inline]
+    pub fn set_interrupt_budget(&self, budget: u64) {
+        unsafe {
+            self.inner.lock().set_interrupt_budget(Some(budget));
+        }
+    }
+
+    /// Remove the budget set with [`set_interrupt_budget`](Self::set_interrupt_budget).
+    #[inline]
+    pub fn clear_interrupt_budget(&self) {
+        unsafe {
+            self.inner.lock().set_interrupt_budget(None);
+        }
+    }
+
+    /// Start the sampling profiler, taking a sample of the stack of executing code every
+    /// `interval`.
+    ///
+    /// Samples are taken when QuickJS polls the interrupt handler, roughly every 10000 executed
+    /// instructions, so the actual interval can be longer and time spent in Rust functions is not
+    /// sampled. Sampling reads the stack directly from the engine, it runs no JavaScript and is
+    /// not affected by `Error.stackTraceLimit` or `Error.prepareStackTrace`.
+    ///
+    /// Starting the profiler again discards the samples collected so far.
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn start_profiling(&self, interval: Duration) {
+        unsafe {
+            self.inner.lock().start_profiling(interval);
+        }
+    }
+
+    /// Stop the profiler started with [`start_profiling`](Self::start_profiling) and return the
+    /// collected samples.
+    ///
+    /// Returns an empty profile if the profiler was not running.
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn stop_profiling(&self) -> Profile {
+        unsafe { self.inner.lock().stop_profiling() }
+    }
+
     /// Set the module loader
     #[cfg(feature = "loader")]
     #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "loader")))]
@@ -161,9 +255,17 @@ impl Runtime {
         }
     }
 
+    /// Create a snapshot of all objects of the runtime.
+    ///
+    /// The roots of the snapshot are the global objects of all contexts of the runtime and the
+    /// values held by [`Persistent`](crate::Persistent) handles.
+    pub fn heap_snapshot(&self) -> Result<HeapSnapshot> {
+        unsafe { self.inner.lock().heap_snapshot() }
+    }
+
     /// Get memory usage stats
     pub fn memory_usage(&self) -> MemoryUsage {
-        unsafe { self.inner.lock().memory_usage() }
+        unsafe { self.inner.lock().memory_usage() }.into()
     }
 
     /// Test for pending jobs
@@ -218,4 +320,83 @@ mod test {
         rt.set_gc_threshold(0xFF);
         rt.run_gc();
     }
+
+    #[test]
+    fn stack_formatter() {
+        use crate::{CatchResultExt, Context, Error, Module};
+        use alloc::format;
+
+        let rt = Runtime::new().unwrap();
+        rt.set_stack_formatter(Some(Box::new(|_ctx, exception, frames| {
+            // The unformatted stack is available within the formatter.
+            assert!(exception.stack().unwrap().contains("prelude"));
+            frames
+                .iter()
+                .filter(|x| x.file.as_deref() != Some("prelude"))
+                .map(|x| format!("    at {x}\n"))
+                .collect()
+        })));
+        let ctx = Context::full(&rt).unwrap();
+        let error = ctx.with(|ctx| {
+            Module::evaluate(
+                ctx.clone(),
+                "prelude",
+                "globalThis.internal = function internal(f) { f() }",
+            )
+            .and_then(|x| x.finish::<()>())
+            .unwrap();
+            let error = ctx
+                .eval::<(), _>("internal(function fail() { throw new Error('oops') })")
+                .catch(&ctx)
+                .unwrap_err();
+            let rendered = error.to_string();
+            assert!(rendered.starts_with("Error: oops\n    at fail (eval_script:1:"));
+            assert!(!rendered.contains("prelude"));
+            Error::from(error)
+        });
+        let Error::Thrown(info) = error else {
+            panic!("expected a thrown error")
+        };
+        assert_eq!(info.frames.len(), 3);
+        assert_eq!(info.frames[1].file.as_deref(), Some("prelude"));
+        assert_eq!(info.stack.unwrap().lines().count(), 2);
+
+        rt.set_stack_formatter(None);
+        ctx.with(|ctx| {
+            let error = ctx
+                .eval::<(), _>("internal(function fail() { throw new Error('oops') })")
+                .catch(&ctx)
+                .unwrap_err();
+            assert!(error.to_string().contains("prelude"));
+        });
+    }
+
+    #[test]
+    fn stack_formatter_panic() {
+        use crate::{CatchResultExt, CaughtError, Context};
+        use core::sync::atomic::{AtomicBool, Ordering};
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        static PANIC: AtomicBool = AtomicBool::new(false);
+
+        let rt = Runtime::new().unwrap();
+        rt.set_stack_formatter(Some(Box::new(|_ctx, _exception, _frames| {
+            if PANIC.swap(false, Ordering::SeqCst) {
+                panic!("formatter failed");
+            }
+            "    at formatted\n".into()
+        })));
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            let Err(CaughtError::Exception(error)) =
+                ctx.eval::<(), _>("throw new Error('oops')").catch(&ctx)
+            else {
+                panic!("expected an exception")
+            };
+            PANIC.store(true, Ordering::SeqCst);
+            assert!(catch_unwind(AssertUnwindSafe(|| error.stack())).is_err());
+            // The formatter is used again after it panicked.
+            assert_eq!(error.stack().as_deref(), Some("    at formatted\n"));
+        });
+    }
 }
@@ -0,0 +1,377 @@
+//! An event loop for the synchronous runtime.
+
+use alloc::{boxed::Box, collections::VecDeque};
+use core::{cmp::Ordering, time::Duration};
+use std::{
+    collections::{BinaryHeap, HashMap},
+    time::Instant,
+};
+
+use crate::{
+    qjs, runtime::opaque::Opaque, CatchResultExt, Ctx, Error, FromJs, JsLifetime, Mut, Promise,
+    Ref, Result,
+};
+
+/// The type of the callbacks run by the event loop.
+#[cfg(not(feature = "parallel"))]
+pub type EventLoopTask = Box<dyn for<'js> FnOnce(Ctx<'js>) -> Result<()> + 'static>;
+/// The type of the callbacks run by the event loop.
+#[cfg(feature = "parallel")]
+pub type EventLoopTask = Box<dyn for<'js> FnOnce(Ctx<'js>) -> Result<()> + Send + 'static>;
+
+/// The id of a timer, used to clear it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TimerId(u64);
+
+struct Timer {
+    deadline: Instant,
+    id: u64,
+}
+
+impl PartialEq for Timer {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Timer {}
+
+impl PartialOrd for Timer {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Timer {
+    // Reversed so the binary heap pops the earliest deadline first, timers with the same
+    // deadline in the order they were created.
+    fn cmp(&self, other: &Self) -> Ordering {
+        (other.deadline, other.id).cmp(&(self.deadline, self.id))
+    }
+}
+
+#[derive(Default)]
+struct Queue {
+    tasks: VecDeque<EventLoopTask>,
+    timers: BinaryHeap<Timer>,
+    timer_tasks: HashMap<u64, EventLoopTask>,
+    next_id: u64,
+}
+
+impl Queue {
+    /// Move the tasks of all expired timers to the task queue.
+    fn expire_timers(&mut self, now: Instant) {
+        while self.timers.peek().is_some_and(|x| x.deadline <= now) {
+            let timer = self.timers.pop().unwrap();
+            // Cleared timers remain in the heap without a task.
+            if let Some(task) = self.timer_tasks.remove(&timer.id) {
+                self.tasks.push_back(task);
+            }
+        }
+    }
+
+    /// The deadline of the next timer which was not cleared.
+    fn next_deadline(&mut self) -> Option<Instant> {
+        while let Some(timer) = self.timers.peek() {
+            if self.timer_tasks.contains_key(&timer.id) {
+                return Some(timer.deadline);
+            }
+            self.timers.pop();
+        }
+        None
+    }
+}
+
+/// A handle to enqueue tasks and timers on an [`EventLoop`].
+///
+/// The handle is stored in the userdata of the runtime, so host functions can retrieve it with
+/// [`Ctx::userdata`](crate::Ctx::userdata).
+#[derive(Clone)]
+pub struct EventLoopHandle(Ref<Mut<Queue>>);
+
+unsafe impl<'js> JsLifetime<'js> for EventLoopHandle {
+    type Changed<'to> = EventLoopHandle;
+}
+
+impl EventLoopHandle {
+    /// Enqueue a task which runs after the currently running task and all pending jobs.
+    pub fn enqueue(&self, task: EventLoopTask) {
+        self.0.lock().tasks.push_back(task);
+    }
+
+    /// Enqueue a task which runs once the delay has elapsed.
+    pub fn set_timeout(&self, delay: Duration, task: EventLoopTask) -> TimerId {
+        let mut queue = self.0.lock();
+        let id = queue.next_id;
+        queue.next_id += 1;
+        queue.timers.push(Timer {
+            deadline: Instant::now() + delay,
+            id,
+        });
+        queue.timer_tasks.insert(id, task);
+        TimerId(id)
+    }
+
+    /// Clear a timer, returns `false` if the timer already ran or was cleared before.
+    pub fn clear_timeout(&self, id: TimerId) -> bool {
+        self.0.lock().timer_tasks.remove(&id.0).is_some()
+    }
+}
+
+/// An event loop for the synchronous [`Runtime`](crate::Runtime).
+///
+/// The loop runs tasks enqueued through its [`EventLoopHandle`] one at a time, in the order in
+/// which they were enqueued or their timer expired. After every task all pending jobs, like
+/// promise reactions, are run. Tasks and jobs run in the context with which the loop is run.
+///
+/// An exception thrown by a task or job stops the loop and is returned as
+/// [`Error::Thrown`]. The loop can be run again afterwards.
+///
+/// ```
+/// # use rquickjs::{Array, Runtime, Context, Ctx, Function, Promise, Result};
+/// # use rquickjs::runtime::{EventLoop, EventLoopHandle};
+/// # use std::time::Duration;
+/// fn sleep<'js>(ctx: Ctx<'js>, millis: u64) -> Result<Promise<'js>> {
+///     let (promise, resolve, _) = ctx.promise()?;
+///     // Tasks must be `Send` with the `parallel` feature, so they can not capture JavaScript
+///     // values. The resolve function is kept in a JavaScript array instead.
+///     let sleeping: Array = ctx.globals().get("sleeping")?;
+///     let idx = sleeping.len();
+///     sleeping.set(idx, resolve)?;
+///     let handle = ctx.userdata::<EventLoopHandle>().unwrap().clone();
+///     handle.set_timeout(
+///         Duration::from_millis(millis),
+///         Box::new(move |ctx| {
+///             let sleeping: Array = ctx.globals().get("sleeping")?;
+///             sleeping.get::<Function>(idx)?.call(())
+///         }),
+///     );
+///     Ok(promise)
+/// }
+///
+/// # let rt = Runtime::new().unwrap();
+/// # let ctx = Context::full(&rt).unwrap();
+/// ctx.with(|ctx| {
+///     let event_loop = EventLoop::new(&ctx).unwrap();
+///     let sleep = Function::new(ctx.clone(), sleep).unwrap();
+///     ctx.globals().set("sleep", sleep).unwrap();
+///     let sleeping = Array::new(ctx.clone()).unwrap();
+///     ctx.globals().set("sleeping", sleeping).unwrap();
+///
+///     let promise = ctx
+///         .eval("(async () => { await sleep(10); return 42 })()")
+///         .unwrap();
+///     let res: i32 = event_loop.run_until(&ctx, &promise).unwrap();
+///     assert_eq!(res, 42);
+/// });
+/// ```
+pub struct EventLoop {
+    handle: EventLoopHandle,
+}
+
+impl EventLoop {
+    /// Create a new event loop and store its handle in the userdata of the runtime.
+    ///
+    /// Returns an error if the runtime already has an event loop handle which is in use.
+    pub fn new(ctx: &Ctx<'_>) -> Result<Self> {
+        let handle = EventLoopHandle(Ref::new(Mut::new(Queue::default())));
+        ctx.store_userdata(handle.clone())?;
+        Ok(EventLoop { handle })
+    }
+
+    /// Returns a handle to enqueue tasks on this loop.
+    pub fn handle(&self) -> &EventLoopHandle {
+        &self.handle
+    }
+
+    /// Run until there are no tasks, timers or jobs left.
+    pub fn run_until_idle(&self, ctx: &Ctx<'_>) -> Result<()> {
+        self.run(ctx, None, || false)
+    }
+
+    /// Run until the duration has elapsed or until there is no work left.
+    pub fn run_for(&self, ctx: &Ctx<'_>, duration: Duration) -> Result<()> {
+        self.run(ctx, Some(Instant::now() + duration), || false)
+    }
+
+    /// Run until the promise is settled and return its result.
+    ///
+    /// Returns [`Error::WouldBlock`] if the loop runs out of work before the promise is settled.
+    pub fn run_until<'js, T: FromJs<'js>>(
+        &self,
+        ctx: &Ctx<'js>,
+        promise: &Promise<'js>,
+    ) -> Result<T> {
+        self.run(ctx, None, || promise.result::<()>().is_some())?;
+        match promise.result() {
+            Some(res) => res.catch(ctx).map_err(Error::from),
+            None => Err(Error::WouldBlock),
+        }
+    }
+
+    fn run(
+        &self,
+        ctx: &Ctx<'_>,
+        deadline: Option<Instant>,
+        mut done: impl FnMut() -> bool,
+    ) -> Result<()> {
+        loop {
+            Self::run_jobs(ctx)?;
+            let now = Instant::now();
+            if done() || deadline.is_some_and(|x| now >= x) {
+                return Ok(());
+            }
+
+            let (task, next) = {
+                let mut queue = self.handle.0.lock();
+                queue.expire_timers(now);
+                (queue.tasks.pop_front(), queue.next_deadline())
+            };
+            if let Some(task) = task {
+                task(ctx.clone()).catch(ctx).map_err(Error::from)?;
+                continue;
+            }
+
+            let wake = match (next, deadline) {
+                (None, _) => return Ok(()),
+                (Some(next), Some(deadline)) => next.min(deadline),
+                (Some(next), None) => next,
+            };
+            std::thread::sleep(wake.saturating_duration_since(now));
+        }
+    }
+
+    /// Run all pending jobs.
+    fn run_jobs(ctx: &Ctx<'_>) -> Result<()> {
+        loop {
+            let (res, job_ctx) = unsafe {
+                let rt = qjs::JS_GetRuntime(ctx.as_ptr());
+                Opaque::from_runtime_ptr(rt).execute_pending_job(rt)
+            };
+            if res == 0 {
+                return Ok(());
+            }
+            if res < 0 {
+                let job_ctx = unsafe { Ctx::from_ptr(job_ctx) };
+                return Err(Err::<(), _>(Error::Exception)
+                    .catch(&job_ctx)
+                    .unwrap_err()
+                    .into());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    };
+
+    use super::{EventLoop, EventLoopHandle};
+    use crate::{Context, Error, Function, Runtime};
+
+    #[test]
+    fn tasks_and_timers() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            let event_loop = EventLoop::new(&ctx).unwrap();
+            let log = Arc::new(Mutex::new(Vec::new()));
+            let handle = event_loop.handle();
+
+            let push = |value: i32| {
+                let log = log.clone();
+                Box::new(move |ctx: crate::Ctx| {
+                    log.lock().unwrap().push(value);
+                    // Jobs run after every task.
+                    ctx.eval::<(), _>(alloc::format!(
+                        "Promise.resolve().then(() => globalThis.job = {value})"
+                    ))
+                })
+            };
+            handle.set_timeout(Duration::from_millis(20), push(3));
+            handle.set_timeout(Duration::from_millis(10), push(2));
+            let cleared = handle.set_timeout(Duration::from_millis(5), push(-1));
+            assert!(handle.clear_timeout(cleared));
+            handle.enqueue(push(1));
+
+            let start = Instant::now();
+            event_loop.run_until_idle(&ctx).unwrap();
+            assert!(start.elapsed() >= Duration::from_millis(20));
+            assert_eq!(*log.lock().unwrap(), [1, 2, 3]);
+            assert_eq!(ctx.globals().get::<_, i32>("job").unwrap(), 3);
+            assert!(!handle.clear_timeout(cleared));
+        });
+    }
+
+    #[test]
+    fn run_for() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            let event_loop = EventLoop::new(&ctx).unwrap();
+            let ran = Arc::new(Mutex::new(false));
+            let task_ran = ran.clone();
+            event_loop.handle().set_timeout(
+                Duration::from_secs(60),
+                Box::new(move |_| {
+                    *task_ran.lock().unwrap() = true;
+                    Ok(())
+                }),
+            );
+            let start = Instant::now();
+            event_loop.run_for(&ctx, Duration::from_millis(10)).unwrap();
+            assert!(start.elapsed() < Duration::from_secs(1));
+            assert!(!*ran.lock().unwrap());
+        });
+    }
+
+    #[test]
+    fn host_functions_and_errors() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            let event_loop = EventLoop::new(&ctx).unwrap();
+            let defer = Function::new(ctx.clone(), |ctx: crate::Ctx, code: String| {
+                let handle = ctx.userdata::<EventLoopHandle>().unwrap().clone();
+                handle.enqueue(Box::new(move |ctx| ctx.eval(code)));
+            })
+            .unwrap();
+            ctx.globals().set("defer", defer).unwrap();
+
+            let promise = ctx
+                .eval(
+                    r#"new Promise((resolve) => {
+                        globalThis.resolve = resolve;
+                        defer("resolve('done')");
+                    })"#,
+                )
+                .unwrap();
+            let res: String = event_loop.run_until(&ctx, &promise).unwrap();
+            assert_eq!(res, "done");
+
+            ctx.eval::<(), _>("defer(\"throw new RangeError('task')\")")
+                .unwrap();
+            let Err(Error::Thrown(info)) = event_loop.run_until_idle(&ctx) else {
+                panic!("expected a thrown error")
+            };
+            assert_eq!(info.name.as_deref(), Some("RangeError"));
+
+            let rejected = ctx
+                .eval("Promise.resolve().then(() => { throw new TypeError('job') })")
+                .unwrap();
+            let Err(Error::Thrown(info)) = event_loop.run_until::<()>(&ctx, &rejected) else {
+                panic!("expected a thrown error")
+            };
+            assert_eq!(info.name.as_deref(), Some("TypeError"));
+
+            let pending = ctx.eval("new Promise(() => {})").unwrap();
+            assert!(matches!(
+                event_loop.run_until::<()>(&ctx, &pending),
+                Err(Error::WouldBlock)
+            ));
+        });
+    }
+}
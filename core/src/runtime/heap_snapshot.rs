@@ -0,0 +1,857 @@
+//! Snapshots of the object graph of a runtime.
+
+use alloc::{
+    collections::VecDeque,
+    string::{String as StdString, ToString},
+    vec::Vec,
+};
+use core::{
+    cell::RefCell,
+    ffi::c_void,
+    fmt::{self, Write},
+    mem,
+};
+
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+#[cfg(not(feature = "std"))]
+use hashbrown::HashMap;
+
+use crate::{class::Tracer, qjs, util, Atom, Ctx, Object, Result, Value};
+
+/// The kind of a node in a [`HeapSnapshot`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum HeapNodeKind {
+    /// A node which is not a JavaScript value, like the root of the snapshot.
+    Synthetic,
+    /// A plain JavaScript object.
+    Object,
+    /// An array.
+    Array,
+    /// A function defined in JavaScript or a builtin function.
+    Function,
+    /// An instance of a Rust class, including functions defined in Rust.
+    Native,
+}
+
+/// The kind of an edge in a [`HeapSnapshot`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum HeapEdgeKind {
+    /// A root of the snapshot, an edge from the root node.
+    Root,
+    /// A named property.
+    Property,
+    /// An indexed property.
+    Element,
+    /// The getter of an accessor property.
+    Getter,
+    /// The setter of an accessor property.
+    Setter,
+    /// The prototype of an object.
+    Prototype,
+    /// A reference which is not visible as a property, like the values traced by a Rust class or
+    /// the target of a proxy.
+    Internal,
+    /// A variable captured by a closure.
+    Closure,
+}
+
+/// An edge in a [`HeapSnapshot`], a reference from one node to another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct HeapEdge {
+    /// The kind of the reference.
+    pub kind: HeapEdgeKind,
+    /// The name of the property or root.
+    pub name: StdString,
+    /// The index of the node which is referenced.
+    pub to: usize,
+}
+
+/// A node in a [`HeapSnapshot`], a single object.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct HeapNode {
+    /// The kind of the object.
+    pub kind: HeapNodeKind,
+    /// The name of the class of the object.
+    ///
+    /// For instances of Rust classes this is [`JsClass::NAME`](crate::class::JsClass::NAME), for
+    /// functions the name of the function and for other objects the name of their constructor.
+    pub name: StdString,
+    /// The number of bytes directly allocated for the object, excluding any referenced objects.
+    pub size: usize,
+    /// The names of the own properties of the object, excluding indexed properties.
+    pub shape: Vec<StdString>,
+    /// The references from this object to other objects.
+    pub edges: Vec<HeapEdge>,
+}
+
+/// A snapshot of the objects reachable from a set of roots, created with
+/// [`Ctx::heap_snapshot`] or [`Runtime::heap_snapshot`](crate::Runtime::heap_snapshot).
+///
+/// The first node is always the synthetic root node, with an edge to every root. The values held
+/// by live [`Persistent`](crate::Persistent) handles are always roots, named after the type of
+/// the handle. Only objects are included in the snapshot, primitive values like strings are part
+/// of the size of the object referencing them.
+///
+/// The snapshot is created without running any JavaScript, getters are never invoked and proxy
+/// traps are never triggered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeapSnapshot {
+    nodes: Vec<HeapNode>,
+}
+
+impl HeapSnapshot {
+    /// Returns the nodes of the snapshot, the root node is the first node.
+    pub fn nodes(&self) -> &[HeapNode] {
+        &self.nodes
+    }
+
+    /// Returns the root node of the snapshot.
+    pub fn root(&self) -> &HeapNode {
+        &self.nodes[0]
+    }
+
+    /// Returns the node at the given index.
+    pub fn node(&self, index: usize) -> Option<&HeapNode> {
+        self.nodes.get(index)
+    }
+
+    /// Returns the indices of all nodes with the given name.
+    pub fn find<'a>(&'a self, name: &'a str) -> impl Iterator<Item = usize> + 'a {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(move |(_, node)| node.name == name)
+            .map(|(idx, _)| idx)
+    }
+
+    /// Returns the total size of all nodes.
+    pub fn total_size(&self) -> usize {
+        self.nodes.iter().map(|x| x.size).sum()
+    }
+
+    /// Returns the shortest path from the root node to the node at the given index.
+    ///
+    /// Every element of the path is the index of a retaining node together with the edge to the
+    /// next node in the path. The path of the root node is empty. Returns `None` if the index is
+    /// out of bounds.
+    pub fn retaining_path(&self, index: usize) -> Option<Vec<(usize, &HeapEdge)>> {
+        if index >= self.nodes.len() {
+            return None;
+        }
+
+        let mut parents: Vec<Option<(usize, usize)>> = alloc::vec![None; self.nodes.len()];
+        let mut visited = alloc::vec![false; self.nodes.len()];
+        let mut queue = VecDeque::new();
+        visited[0] = true;
+        queue.push_back(0);
+        while let Some(from) = queue.pop_front() {
+            if from == index {
+                break;
+            }
+            for (edge_idx, edge) in self.nodes[from].edges.iter().enumerate() {
+                if !visited[edge.to] {
+                    visited[edge.to] = true;
+                    parents[edge.to] = Some((from, edge_idx));
+                    queue.push_back(edge.to);
+                }
+            }
+        }
+
+        let mut path = Vec::new();
+        let mut cur = index;
+        while let Some((from, edge_idx)) = parents[cur] {
+            path.push((from, &self.nodes[from].edges[edge_idx]));
+            cur = from;
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    /// Write the snapshot in the `.heapsnapshot` format used by the Chrome DevTools.
+    pub fn write_chrome_json<W: Write>(&self, w: &mut W) -> fmt::Result {
+        const NODE_FIELDS: usize = 6;
+
+        let mut strings = Strings::default();
+        let edge_count: usize = self.nodes.iter().map(|x| x.edges.len()).sum();
+
+        w.write_str(concat!(
+            r#"{"snapshot":{"meta":{"#,
+            r#""node_fields":["type","name","id","self_size","edge_count","trace_node_id"],"#,
+            r#""node_types":[["hidden","array","string","object","code","closure","regexp","#,
+            r#""number","native","synthetic","concatenated string","sliced string","symbol","#,
+            r#""bigint"],"string","number","number","number","number"],"#,
+            r#""edge_fields":["type","name_or_index","to_node"],"#,
+            r#""edge_types":[["context","element","property","internal","hidden","shortcut","#,
+            r#""weak"],"string_or_number","node"],"#,
+            r#""trace_function_info_fields":[],"trace_node_fields":[],"sample_fields":[],"#,
+            r#""location_fields":[]},"#,
+        ))?;
+        write!(
+            w,
+            r#""node_count":{},"edge_count":{edge_count},"trace_function_count":0}},"nodes":["#,
+            self.nodes.len()
+        )?;
+        for (idx, node) in self.nodes.iter().enumerate() {
+            let ty = match node.kind {
+                HeapNodeKind::Object => 3,
+                HeapNodeKind::Array => 1,
+                HeapNodeKind::Function => 5,
+                HeapNodeKind::Native => 8,
+                HeapNodeKind::Synthetic => 9,
+            };
+            if idx != 0 {
+                w.write_char(',')?;
+            }
+            write!(
+                w,
+                "{ty},{},{},{},{},0",
+                strings.get(&node.name),
+                idx * 2 + 1,
+                node.size,
+                node.edges.len()
+            )?;
+        }
+
+        w.write_str(r#"],"edges":["#)?;
+        let mut first = true;
+        for edge in self.nodes.iter().flat_map(|x| x.edges.iter()) {
+            let (ty, name) = match edge.kind {
+                HeapEdgeKind::Element => match edge.name.parse::<usize>() {
+                    Ok(idx) => (1, idx),
+                    Err(_) => (2, strings.get(&edge.name)),
+                },
+                HeapEdgeKind::Property | HeapEdgeKind::Prototype => (2, strings.get(&edge.name)),
+                HeapEdgeKind::Getter => (2, strings.get(&alloc::format!("get {}", edge.name))),
+                HeapEdgeKind::Setter => (2, strings.get(&alloc::format!("set {}", edge.name))),
+                HeapEdgeKind::Root | HeapEdgeKind::Internal => (3, strings.get(&edge.name)),
+                HeapEdgeKind::Closure => (0, strings.get(&edge.name)),
+            };
+            if !mem::replace(&mut first, false) {
+                w.write_char(',')?;
+            }
+            write!(w, "{ty},{name},{}", edge.to * NODE_FIELDS)?;
+        }
+
+        w.write_str(concat!(
+            r#"],"trace_function_infos":[],"trace_tree":[],"samples":[],"locations":[],"#,
+            r#""strings":["#
+        ))?;
+        for (idx, string) in strings.list.iter().enumerate() {
+            if idx != 0 {
+                w.write_char(',')?;
+            }
+            util::write_json_string(w, string)?;
+        }
+        w.write_str("]}")
+    }
+
+    /// Create a snapshot of all objects reachable from the given roots and the persistent values of
+    /// the runtime.
+    pub(crate) fn capture<'js, I, S>(ctx: &Ctx<'js>, roots: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = (S, Value<'js>)>,
+        S: Into<StdString>,
+    {
+        let mut walker = Walker {
+            ctx: ctx.clone(),
+            nodes: alloc::vec![HeapNode {
+                kind: HeapNodeKind::Synthetic,
+                name: "(root)".to_string(),
+                size: 0,
+                shape: Vec::new(),
+                edges: Vec::new(),
+            }],
+            objects: alloc::vec![None],
+            indices: HashMap::new(),
+            queue: VecDeque::new(),
+        };
+
+        for (name, value) in roots {
+            if let Some(to) = walker.visit(value) {
+                walker.nodes[0].edges.push(HeapEdge {
+                    kind: HeapEdgeKind::Root,
+                    name: name.into(),
+                    to,
+                });
+            }
+        }
+        for (name, value) in unsafe { ctx.get_opaque().persistent_roots() } {
+            let value = unsafe { Value::from_js_value_const(ctx.clone(), value) };
+            if let Some(to) = walker.visit(value) {
+                walker.nodes[0].edges.push(HeapEdge {
+                    kind: HeapEdgeKind::Root,
+                    name: alloc::format!("Persistent<{}>", short_type_name(name)),
+                    to,
+                });
+            }
+        }
+        while let Some(idx) = walker.queue.pop_front() {
+            walker.inspect(idx)?;
+        }
+
+        Ok(HeapSnapshot {
+            nodes: walker.nodes,
+        })
+    }
+}
+
+/// The state of walking the object graph.
+struct Walker<'js> {
+    ctx: Ctx<'js>,
+    nodes: Vec<HeapNode>,
+    /// The object of every node, keeping them alive during the walk.
+    objects: Vec<Option<Object<'js>>>,
+    /// The index of the node of every visited object, by the address of the object.
+    indices: HashMap<usize, usize>,
+    queue: VecDeque<usize>,
+}
+
+impl<'js> Walker<'js> {
+    /// Returns the index of the node of the value, queueing it for inspection if it was not yet
+    /// visited. Returns `None` if the value is not an object.
+    fn visit(&mut self, value: Value<'js>) -> Option<usize> {
+        let object = value.into_object()?;
+        let addr = unsafe { qjs::JS_VALUE_GET_PTR(object.as_js_value()) } as usize;
+        if let Some(idx) = self.indices.get(&addr) {
+            return Some(*idx);
+        }
+
+        let idx = self.nodes.len();
+        self.nodes.push(HeapNode {
+            kind: HeapNodeKind::Object,
+            name: StdString::new(),
+            size: 0,
+            shape: Vec::new(),
+            edges: Vec::new(),
+        });
+        self.objects.push(Some(object));
+        self.indices.insert(addr, idx);
+        self.queue.push_back(idx);
+        Some(idx)
+    }
+
+    fn edge(&mut self, from: usize, kind: HeapEdgeKind, name: StdString, value: Value<'js>) {
+        if let Some(to) = self.visit(value) {
+            self.nodes[from].edges.push(HeapEdge { kind, name, to });
+        }
+    }
+
+    /// Take the result of a function which throws on failure, ignoring the exception.
+    unsafe fn value_or_clear(&self, value: qjs::JSValue) -> Option<Value<'js>> {
+        if qjs::JS_IsException(value) {
+            qjs::JS_FreeValue(self.ctx.as_ptr(), qjs::JS_GetException(self.ctx.as_ptr()));
+            return None;
+        }
+        Some(Value::from_js_value(self.ctx.clone(), value))
+    }
+
+    fn inspect(&mut self, idx: usize) -> Result<()> {
+        let object = self.objects[idx].clone().unwrap();
+        let ctx = self.ctx.clone();
+        let value = object.as_js_value();
+
+        let mut size = unsafe {
+            qjs::js_malloc_usable_size(ctx.as_ptr(), qjs::JS_VALUE_GET_PTR(value).cast_const())
+        } as usize;
+
+        if unsafe { qjs::JS_IsProxy(value) } {
+            let node = &mut self.nodes[idx];
+            node.name = "Proxy".to_string();
+            node.size = size;
+            let target =
+                unsafe { self.value_or_clear(qjs::JS_GetProxyTarget(ctx.as_ptr(), value)) };
+            let handler =
+                unsafe { self.value_or_clear(qjs::JS_GetProxyHandler(ctx.as_ptr(), value)) };
+            if let (Some(target), Some(handler)) = (target, handler) {
+                self.edge(idx, HeapEdgeKind::Internal, "target".to_string(), target);
+                self.edge(idx, HeapEdgeKind::Internal, "handler".to_string(), handler);
+            }
+            return Ok(());
+        }
+
+        let mut kind = if object.as_array().is_some() {
+            HeapNodeKind::Array
+        } else if object.as_function().is_some() {
+            HeapNodeKind::Function
+        } else {
+            HeapNodeKind::Object
+        };
+        let mut name = None;
+
+        let class_id = unsafe { qjs::JS_GetClassID(value) };
+        let is_exotic = unsafe { ctx.get_opaque().is_exotic_class_id(class_id) };
+        if let Some(cell) = object.get_class_cell_ptr() {
+            let v_table = unsafe { cell.as_ref().v_table };
+            kind = HeapNodeKind::Native;
+            name = Some(v_table.name().to_string());
+            size += v_table.size();
+
+            let collector = RefCell::new(Vec::new());
+            let rt = unsafe { qjs::JS_GetRuntime(ctx.as_ptr()) };
+            unsafe { v_table.trace(cell, Tracer::collect(rt, &collector)) };
+            for traced in collector.into_inner() {
+                let traced = unsafe { Value::from_js_value_const(ctx.clone(), traced) };
+                self.edge(idx, HeapEdgeKind::Internal, "traced".to_string(), traced);
+            }
+        }
+
+        if kind == HeapNodeKind::Function {
+            self.inspect_closure(idx, &object)?;
+        }
+
+        // Exotic Rust classes run Rust code to get their properties.
+        if !is_exotic {
+            self.inspect_properties(idx, &object)?;
+        }
+
+        if let Some(proto) =
+            unsafe { self.value_or_clear(qjs::JS_GetPrototype(ctx.as_ptr(), value)) }
+        {
+            self.edge(idx, HeapEdgeKind::Prototype, "__proto__".to_string(), proto);
+        }
+
+        let name = match name {
+            Some(x) => x,
+            None if kind == HeapNodeKind::Function => self
+                .data_property(&object, "name")
+                .and_then(|x| x.as_string()?.to_string().ok())
+                .unwrap_or_default(),
+            None => self
+                .constructor_name(&object)
+                .unwrap_or_else(|| "Object".to_string()),
+        };
+
+        let node = &mut self.nodes[idx];
+        node.kind = kind;
+        node.name = name;
+        node.size = size;
+        Ok(())
+    }
+
+    fn inspect_properties(&mut self, idx: usize, object: &Object<'js>) -> Result<()> {
+        let ctx = self.ctx.clone();
+        let mut enums = mem::MaybeUninit::uninit();
+        let mut count = mem::MaybeUninit::uninit();
+        let flags = (qjs::JS_GPN_STRING_MASK | qjs::JS_GPN_SYMBOL_MASK) as _;
+        let res = unsafe {
+            qjs::JS_GetOwnPropertyNames(
+                ctx.as_ptr(),
+                enums.as_mut_ptr(),
+                count.as_mut_ptr(),
+                object.as_js_value(),
+                flags,
+            )
+        };
+        if res < 0 {
+            return Err(ctx.raise_exception());
+        }
+        let enums = unsafe { enums.assume_init() };
+        let count = unsafe { count.assume_init() };
+
+        let atoms = unsafe { core::slice::from_raw_parts(enums, count as usize) }
+            .iter()
+            .map(|x| unsafe { Atom::from_atom_val_dup(ctx.clone(), x.atom) })
+            .collect::<Vec<_>>();
+        unsafe { qjs::JS_FreePropertyEnum(ctx.as_ptr(), enums, count) };
+
+        for atom in atoms {
+            let name = atom.to_string()?;
+            let is_index = name.parse::<u32>().is_ok_and(|x| x.to_string() == name);
+            if !is_index {
+                self.nodes[idx].shape.push(name.clone());
+            }
+
+            let Some(desc) = self.own_property(object, &atom) else {
+                continue;
+            };
+            if desc.flags & qjs::JS_PROP_GETSET as i32 != 0 {
+                self.edge(idx, HeapEdgeKind::Getter, name.clone(), desc.getter);
+                self.edge(idx, HeapEdgeKind::Setter, name, desc.setter);
+            } else if is_index {
+                self.edge(idx, HeapEdgeKind::Element, name, desc.value);
+            } else {
+                self.edge(idx, HeapEdgeKind::Property, name, desc.value);
+            }
+        }
+        Ok(())
+    }
+
+    fn inspect_closure(&mut self, idx: usize, function: &Object<'js>) -> Result<()> {
+        unsafe extern "C" fn collect(opaque: *mut c_void, name: qjs::JSAtom, value: qjs::JSValue) {
+            let vars = &mut *opaque.cast::<Vec<(qjs::JSAtom, qjs::JSValue)>>();
+            vars.push((name, value));
+        }
+
+        let ctx = self.ctx.clone();
+        let mut vars = Vec::new();
+        unsafe {
+            qjs::rquickjs_get_closure_vars(
+                ctx.as_ptr(),
+                function.as_js_value(),
+                Some(collect),
+                (&mut vars as *mut Vec<_>).cast(),
+            )
+        };
+        for (name, value) in vars {
+            let name = unsafe { Atom::from_atom_val_dup(ctx.clone(), name) }.to_string()?;
+            let value = unsafe { Value::from_js_value_const(ctx.clone(), value) };
+            self.edge(idx, HeapEdgeKind::Closure, name, value);
+        }
+        Ok(())
+    }
+
+    /// Returns the descriptor of an own property without invoking any getters.
+    fn own_property(&self, object: &Object<'js>, atom: &Atom<'js>) -> Option<Descriptor<'js>> {
+        let ctx = &self.ctx;
+        let mut desc = mem::MaybeUninit::<qjs::JSPropertyDescriptor>::uninit();
+        let res = unsafe {
+            qjs::JS_GetOwnProperty(
+                ctx.as_ptr(),
+                desc.as_mut_ptr(),
+                object.as_js_value(),
+                atom.atom,
+            )
+        };
+        if res < 0 {
+            unsafe { qjs::JS_FreeValue(ctx.as_ptr(), qjs::JS_GetException(ctx.as_ptr())) };
+            return None;
+        }
+        if res == 0 {
+            return None;
+        }
+        let desc = unsafe { desc.assume_init() };
+        unsafe {
+            Some(Descriptor {
+                flags: desc.flags,
+                value: Value::from_js_value(ctx.clone(), desc.value),
+                getter: Value::from_js_value(ctx.clone(), desc.getter),
+                setter: Value::from_js_value(ctx.clone(), desc.setter),
+            })
+        }
+    }
+
+    /// Returns the value of an own data property.
+    fn data_property(&self, object: &Object<'js>, name: &str) -> Option<Value<'js>> {
+        if unsafe { qjs::JS_IsProxy(object.as_js_value()) } {
+            return None;
+        }
+        let class_id = unsafe { qjs::JS_GetClassID(object.as_js_value()) };
+        if unsafe { self.ctx.get_opaque().is_exotic_class_id(class_id) } {
+            return None;
+        }
+        let atom = Atom::from_str(self.ctx.clone(), name).ok()?;
+        let desc = self.own_property(object, &atom)?;
+        (desc.flags & qjs::JS_PROP_GETSET as i32 == 0).then_some(desc.value)
+    }
+
+    /// Returns the name of the constructor found on the prototype chain of the object.
+    fn constructor_name(&self, object: &Object<'js>) -> Option<StdString> {
+        let mut cur = object.clone();
+        loop {
+            if let Some(name) = self
+                .data_property(&cur, "constructor")
+                .and_then(|x| x.into_object())
+                .and_then(|x| self.data_property(&x, "name"))
+                .and_then(|x| x.as_string()?.to_string().ok())
+            {
+                return Some(name);
+            }
+            if unsafe { qjs::JS_IsProxy(cur.as_js_value()) } {
+                return None;
+            }
+            let proto = unsafe {
+                self.value_or_clear(qjs::JS_GetPrototype(self.ctx.as_ptr(), cur.as_js_value()))
+            };
+            cur = proto?.into_object()?;
+        }
+    }
+}
+
+struct Descriptor<'js> {
+    flags: i32,
+    value: Value<'js>,
+    getter: Value<'js>,
+    setter: Value<'js>,
+}
+
+/// Returns a type name without the paths of the types and lifetimes,
+/// `alloc::vec::Vec<rquickjs_core::Value<'_>>` becomes `Vec<Value>`.
+fn short_type_name(name: &str) -> StdString {
+    let mut short = StdString::new();
+    let mut start = 0;
+    for (idx, c) in name.char_indices() {
+        if !(c.is_alphanumeric() || c == '_' || c == ':') {
+            short.push_str(name[start..idx].rsplit("::").next().unwrap_or_default());
+            short.push(c);
+            start = idx + c.len_utf8();
+        }
+    }
+    short.push_str(name[start..].rsplit("::").next().unwrap_or_default());
+    for lifetime in ["'_", "'static"] {
+        short = short
+            .replace(&alloc::format!("<{lifetime}>"), "")
+            .replace(&alloc::format!("{lifetime}, "), "");
+    }
+    short
+}
+
+/// The string table of a Chrome heap snapshot.
+#[derive(Default)]
+struct Strings {
+    list: Vec<StdString>,
+    indices: HashMap<StdString, usize>,
+}
+
+impl Strings {
+    fn get(&mut self, string: &str) -> usize {
+        if let Some(idx) = self.indices.get(string) {
+            return *idx;
+        }
+        let idx = self.list.len();
+        self.list.push(string.to_string());
+        self.indices.insert(string.to_string(), idx);
+        idx
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{HeapEdgeKind, HeapNodeKind};
+    use crate::{
+        class::{JsClass, Readable, Trace, Tracer},
+        prelude::*,
+        Class, Context, Object, Persistent, Runtime, Value,
+    };
+
+    pub struct Holder<'js> {
+        held: Value<'js>,
+    }
+
+    impl<'js> Trace<'js> for Holder<'js> {
+        fn trace<'a>(&self, tracer: Tracer<'a, 'js>) {
+            self.held.trace(tracer)
+        }
+    }
+
+    unsafe impl<'js> JsLifetime<'js> for Holder<'js> {
+        type Changed<'to> = Holder<'to>;
+    }
+
+    impl<'js> JsClass<'js> for Holder<'js> {
+        const NAME: &'static str = "Holder";
+
+        type Mutable = Readable;
+
+        fn prototype(ctx: &Ctx<'js>) -> crate::Result<Option<Object<'js>>> {
+            Ok(Some(Object::new(ctx.clone())?))
+        }
+
+        fn constructor(_ctx: &Ctx<'js>) -> crate::Result<Option<crate::value::Constructor<'js>>> {
+            Ok(None)
+        }
+    }
+
+    #[test]
+    fn object_graph() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            ctx.eval::<(), _>(
+                r#"
+                class Item {}
+                globalThis.cache = {
+                    items: [new Item(), new Item()],
+                    get lazy() { throw new Error("getters must not be invoked") },
+                };
+                globalThis.proxy = new Proxy({ hidden: {} }, {
+                    ownKeys() { throw new Error("traps must not be invoked") },
+                });
+            "#,
+            )
+            .unwrap();
+
+            let snapshot = ctx.heap_snapshot().unwrap();
+            assert_eq!(snapshot.root().kind, HeapNodeKind::Synthetic);
+            assert!(snapshot.total_size() > 0);
+
+            // The class and its prototype are named `Item` as well.
+            let items = snapshot
+                .find("Item")
+                .filter(|x| snapshot.node(*x).unwrap().shape.is_empty())
+                .collect::<Vec<_>>();
+            assert_eq!(items.len(), 2);
+            let item = snapshot.node(items[0]).unwrap();
+            assert_eq!(item.kind, HeapNodeKind::Object);
+            assert!(item.size > 0);
+
+            let path = snapshot.retaining_path(items[1]).unwrap();
+            let names = path
+                .iter()
+                .map(|(_, x)| x.name.as_str())
+                .collect::<Vec<_>>();
+            assert_eq!(names, ["globalThis", "cache", "items", "1"]);
+            assert_eq!(path[3].1.kind, HeapEdgeKind::Element);
+
+            let cache = path[2].0;
+            let cache = snapshot.node(cache).unwrap();
+            assert_eq!(cache.shape, ["items", "lazy"]);
+            assert!(cache
+                .edges
+                .iter()
+                .any(|x| x.kind == HeapEdgeKind::Getter && x.name == "lazy"));
+
+            let globals = snapshot.node(snapshot.root().edges[0].to).unwrap();
+            let proxy = globals.edges.iter().find(|x| x.name == "proxy").unwrap().to;
+            let proxy = snapshot.node(proxy).unwrap();
+            assert_eq!(proxy.name, "Proxy");
+            let target = proxy.edges.iter().find(|x| x.name == "target").unwrap().to;
+            assert_eq!(snapshot.node(target).unwrap().shape, ["hidden"]);
+        });
+    }
+
+    #[test]
+    fn native_and_persistent() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        let persistent = ctx.with(|ctx| {
+            let leaked = ctx.eval::<Value, _>("({ payload: [1, 2, 3] })").unwrap();
+            let holder = Class::instance(ctx.clone(), Holder { held: leaked }).unwrap();
+            Persistent::save(&ctx, holder)
+        });
+
+        ctx.with(|ctx| {
+            let holder = persistent.clone().restore(&ctx).unwrap();
+            let snapshot = ctx
+                .heap_snapshot_with_roots([("holder", holder.into_value())])
+                .unwrap();
+
+            let holder = snapshot.find("Holder").next().unwrap();
+            let node = snapshot.node(holder).unwrap();
+            assert_eq!(node.kind, HeapNodeKind::Native);
+            assert!(node.size > core::mem::size_of::<Holder>());
+
+            let held = node.edges.iter().find(|x| x.name == "traced").unwrap().to;
+            let held = snapshot.node(held).unwrap();
+            let payload = held.edges.iter().find(|x| x.name == "payload").unwrap().to;
+            assert_eq!(snapshot.node(payload).unwrap().kind, HeapNodeKind::Array);
+            let path = snapshot.retaining_path(payload).unwrap();
+            let names = path
+                .iter()
+                .map(|(_, x)| x.name.as_str())
+                .collect::<Vec<_>>();
+            assert_eq!(names, ["holder", "traced", "payload"]);
+            assert_eq!(path[1].1.kind, HeapEdgeKind::Internal);
+        });
+    }
+
+    #[test]
+    fn closure_variables() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            ctx.eval::<(), _>(
+                r#"
+                globalThis.counter = (() => {
+                    const state = { count: 0 };
+                    return function increment() { return ++state.count };
+                })();
+            "#,
+            )
+            .unwrap();
+
+            let snapshot = ctx.heap_snapshot().unwrap();
+            let state = snapshot
+                .nodes()
+                .iter()
+                .position(|x| x.shape == ["count"])
+                .unwrap();
+            let path = snapshot.retaining_path(state).unwrap();
+            let names = path
+                .iter()
+                .map(|(_, x)| x.name.as_str())
+                .collect::<Vec<_>>();
+            assert_eq!(names, ["globalThis", "counter", "state"]);
+            assert_eq!(path[2].1.kind, HeapEdgeKind::Closure);
+            assert_eq!(snapshot.node(path[2].0).unwrap().name, "increment");
+        });
+    }
+
+    #[test]
+    fn runtime_snapshot() {
+        let rt = Runtime::new().unwrap();
+        let persistent = {
+            let ctx = Context::full(&rt).unwrap();
+            ctx.with(|ctx| {
+                let leaked = ctx.eval::<Object, _>("({ leaked: [] })").unwrap();
+                Persistent::save(&ctx, leaked)
+            })
+        };
+
+        let snapshot = rt.heap_snapshot().unwrap();
+        let leaked = snapshot
+            .nodes()
+            .iter()
+            .position(|x| x.shape == ["leaked"])
+            .unwrap();
+        let path = snapshot.retaining_path(leaked).unwrap();
+        assert_eq!(path.len(), 1);
+        assert_eq!(path[0].1.kind, HeapEdgeKind::Root);
+        assert_eq!(path[0].1.name, "Persistent<Object>");
+
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| ctx.globals().set("a", Object::new(ctx.clone())).unwrap());
+        let other = Context::full(&rt).unwrap();
+        other.with(|ctx| ctx.globals().set("b", Object::new(ctx.clone())).unwrap());
+        let snapshot = rt.heap_snapshot().unwrap();
+        let globals = snapshot
+            .root()
+            .edges
+            .iter()
+            .filter(|x| x.name == "globalThis")
+            .map(|x| snapshot.node(x.to).unwrap())
+            .collect::<Vec<_>>();
+        // The first context is kept alive by the object it created.
+        assert_eq!(globals.len(), 3);
+        assert!(globals[1].shape.iter().any(|x| x == "a"));
+        assert!(globals[2].shape.iter().any(|x| x == "b"));
+
+        drop(persistent);
+        let snapshot = rt.heap_snapshot().unwrap();
+        assert!(snapshot.root().edges.iter().all(|x| x.name == "globalThis"));
+    }
+
+    #[test]
+    fn chrome_json() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            ctx.eval::<(), _>("globalThis['a \"quoted\"\\nname'] = {}")
+                .unwrap();
+            let snapshot = ctx.heap_snapshot().unwrap();
+            let mut json = String::new();
+            snapshot.write_chrome_json(&mut json).unwrap();
+
+            let value = ctx.json_parse(json).unwrap();
+            let obj = value.as_object().unwrap();
+            let meta: Object = obj.get::<_, Object>("snapshot").unwrap();
+            assert_eq!(
+                meta.get::<_, usize>("node_count").unwrap(),
+                snapshot.nodes().len()
+            );
+            let nodes: Vec<usize> = obj.get("nodes").unwrap();
+            assert_eq!(nodes.len(), snapshot.nodes().len() * 6);
+            let edges: Vec<usize> = obj.get("edges").unwrap();
+            let edge_count: usize = snapshot.nodes().iter().map(|x| x.edges.len()).sum();
+            assert_eq!(edges.len(), edge_count * 3);
+            assert!(edges.chunks(3).all(|x| x[2] % 6 == 0 && x[2] < nodes.len()));
+            let strings: Vec<String> = obj.get("strings").unwrap();
+            assert!(strings.iter().any(|x| x == "a \"quoted\"\nname"));
+        });
+    }
+}
@@ -0,0 +1,245 @@
+//! Limits on how long scripts are allowed to run.
+
+use core::{
+    cell::Cell,
+    fmt::{self, Display, Formatter},
+};
+
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+/// The reason why the execution of a script was interrupted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum InterruptReason {
+    /// The execution deadline has passed.
+    Deadline,
+    /// The interrupt budget was exhausted.
+    Budget,
+    /// The interrupt handler requested the interruption.
+    Handler,
+}
+
+impl Display for InterruptReason {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            InterruptReason::Deadline => "execution deadline exceeded".fmt(f),
+            InterruptReason::Budget => "interrupt budget exhausted".fmt(f),
+            InterruptReason::Handler => "interrupted by the interrupt handler".fmt(f),
+        }
+    }
+}
+
+/// The execution limits of a runtime.
+///
+/// The limits are checked every time QuickJS polls the interrupt handler, which happens roughly
+/// every 10000 executed instructions.
+#[derive(Default)]
+pub(crate) struct ExecutionLimits {
+    #[cfg(feature = "std")]
+    deadline: Cell<Option<Instant>>,
+    budget: Cell<Option<u64>>,
+    /// The reason of the last interrupt which has not yet been turned into an error.
+    reason: Cell<Option<InterruptReason>>,
+}
+
+impl ExecutionLimits {
+    #[cfg(feature = "std")]
+    pub fn set_deadline(&self, deadline: Option<Instant>) {
+        self.deadline.set(deadline)
+    }
+
+    pub fn set_budget(&self, budget: Option<u64>) {
+        self.budget.set(budget)
+    }
+
+    /// Returns if any limit is set and the interrupt handler needs to be polled.
+    pub fn is_active(&self) -> bool {
+        #[cfg(feature = "std")]
+        if self.deadline.get().is_some() {
+            return true;
+        }
+        self.budget.get().is_some()
+    }
+
+    /// Check the limits, returns the reason for interrupting execution if a limit was exceeded.
+    pub fn poll(&self) -> Option<InterruptReason> {
+        if let Some(budget) = self.budget.get() {
+            if budget == 0 {
+                return Some(InterruptReason::Budget);
+            }
+            self.budget.set(Some(budget - 1));
+        }
+        #[cfg(feature = "std")]
+        if let Some(deadline) = self.deadline.get() {
+            if Instant::now() >= deadline {
+                return Some(InterruptReason::Deadline);
+            }
+        }
+        None
+    }
+
+    pub fn set_reason(&self, reason: InterruptReason) {
+        self.reason.set(Some(reason))
+    }
+
+    pub fn take_reason(&self) -> Option<InterruptReason> {
+        self.reason.take()
+    }
+
+    /// Tighten the limits until the returned scope is restored.
+    ///
+    /// Scoped limits can only make the current limits stricter, never looser.
+    pub fn enter_scope(
+        &self,
+        #[cfg(feature = "std")] deadline: Option<Instant>,
+        budget: Option<u64>,
+    ) -> LimitsScope {
+        let scope = LimitsScope {
+            #[cfg(feature = "std")]
+            deadline: self.deadline.get(),
+            budget: self.budget.get(),
+            scoped_budget: None,
+        };
+
+        #[cfg(feature = "std")]
+        if let Some(deadline) = deadline {
+            let deadline = scope.deadline.map_or(deadline, |x| x.min(deadline));
+            self.deadline.set(Some(deadline));
+        }
+
+        let scoped_budget = budget.map(|budget| scope.budget.map_or(budget, |x| x.min(budget)));
+        if scoped_budget.is_some() {
+            self.budget.set(scoped_budget);
+        }
+
+        LimitsScope {
+            scoped_budget,
+            ..scope
+        }
+    }
+
+    /// Restore the limits from before the scope was entered.
+    pub fn exit_scope(&self, scope: LimitsScope) {
+        #[cfg(feature = "std")]
+        self.deadline.set(scope.deadline);
+
+        let budget = match (scope.budget, scope.scoped_budget) {
+            // Polls made inside the scope also count against the outer budget.
+            (Some(outer), Some(scoped)) => {
+                let used = scoped.saturating_sub(self.budget.get().unwrap_or(0));
+                Some(outer.saturating_sub(used))
+            }
+            (None, Some(_)) => None,
+            (_, None) => self.budget.get(),
+        };
+        self.budget.set(budget);
+    }
+}
+
+/// The limits which were active before a scope was entered.
+pub(crate) struct LimitsScope {
+    #[cfg(feature = "std")]
+    deadline: Option<Instant>,
+    budget: Option<u64>,
+    scoped_budget: Option<u64>,
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::InterruptReason;
+    use crate::{prelude::*, Context, Error, Runtime};
+
+    fn is_interrupted<T>(res: crate::Result<T>, reason: InterruptReason) -> bool {
+        matches!(res, Err(Error::Interrupted { reason: x }) if x == reason)
+    }
+
+    #[test]
+    fn runtime_deadline() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        rt.set_execution_deadline(Duration::from_millis(10));
+        ctx.with(|ctx| {
+            let res = ctx.eval::<(), _>("try { for(;;){} } catch(e) {}");
+            assert!(is_interrupted(res, InterruptReason::Deadline));
+        });
+        rt.clear_execution_deadline();
+        ctx.with(|ctx| {
+            assert_eq!(ctx.eval::<i32, _>("1 + 1").unwrap(), 2);
+        });
+    }
+
+    #[test]
+    fn runtime_budget() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        rt.set_interrupt_budget(5);
+        ctx.with(|ctx| {
+            let res = ctx.eval::<(), _>("for(;;){}");
+            assert!(is_interrupted(res, InterruptReason::Budget));
+        });
+    }
+
+    #[test]
+    fn handler_reason() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        rt.set_interrupt_handler(Some(Box::new(|| true)));
+        ctx.with(|ctx| {
+            let res = ctx.eval::<(), _>("for(;;){}");
+            assert!(is_interrupted(res, InterruptReason::Handler));
+        });
+    }
+
+    #[test]
+    fn scoped_limits() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            let res = ctx.with_execution_deadline(Duration::from_millis(10), || {
+                ctx.eval::<(), _>("for(;;){}")
+            });
+            assert!(is_interrupted(res, InterruptReason::Deadline));
+
+            let res = ctx.with_interrupt_budget(2, || ctx.eval::<(), _>("for(;;){}"));
+            assert!(is_interrupted(res, InterruptReason::Budget));
+
+            // The limits no longer apply after the scope.
+            let res: i32 = ctx
+                .eval("let x = 0; for(let i = 0; i < 100000; i++){ x += 1 }; x")
+                .unwrap();
+            assert_eq!(res, 100000);
+        });
+    }
+
+    #[test]
+    fn interrupt_through_callback() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            let f = Func::from(|ctx: Ctx| ctx.eval::<(), _>("for(;;){}"));
+            ctx.globals().set("f", f).unwrap();
+            let res = ctx.with_interrupt_budget(2, || {
+                ctx.eval::<(), _>("try { f() } catch(e) {} globalThis.reached = true")
+            });
+            assert!(is_interrupted(res, InterruptReason::Budget));
+            assert!(!ctx.globals().contains_key("reached").unwrap());
+        });
+    }
+
+    #[test]
+    fn catchable_exception_keeps_reason() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            let limits = unsafe { ctx.get_opaque().limits() };
+            limits.set_reason(InterruptReason::Budget);
+            let res = ctx.eval::<(), _>("throw new Error('catchable')");
+            assert!(matches!(res, Err(Error::Exception)));
+            let _ = ctx.catch();
+            assert_eq!(limits.take_reason(), Some(InterruptReason::Budget));
+        });
+    }
+}
@@ -0,0 +1,283 @@
+//! Memory usage statistics of a runtime.
+
+use core::{
+    fmt::{self, Display, Formatter, Write},
+    ops::Sub,
+};
+
+use crate::qjs;
+
+macro_rules! memory_usage {
+    ($(
+        $(#[doc = $doc:literal])*
+        $field:ident => $metric:literal,
+    )*) => {
+        /// Statistics about the memory used by a runtime, as returned by
+        /// [`Runtime::memory_usage`](crate::Runtime::memory_usage).
+        ///
+        /// Sizes are in bytes. The fields are signed so that the result of
+        /// [`MemoryUsage::delta`] can represent a decrease.
+        #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+        #[non_exhaustive]
+        pub struct MemoryUsage {
+            $(
+                $(#[doc = $doc])*
+                pub $field: i64,
+            )*
+        }
+
+        impl From<qjs::JSMemoryUsage> for MemoryUsage {
+            fn from(usage: qjs::JSMemoryUsage) -> Self {
+                MemoryUsage {
+                    $($field: usage.$field,)*
+                }
+            }
+        }
+
+        impl MemoryUsage {
+            /// The name, Prometheus metric name and description of every field.
+            const FIELDS: &'static [(&'static str, &'static str, &'static str)] = &[
+                $((stringify!($field), $metric, concat!($($doc),*)),)*
+            ];
+
+            fn values(&self) -> [i64; Self::FIELDS.len()] {
+                [$(self.$field,)*]
+            }
+
+            fn zip_with(&self, other: &Self, f: impl Fn(i64, i64) -> i64) -> Self {
+                MemoryUsage {
+                    $($field: f(self.$field, other.$field),)*
+                }
+            }
+        }
+    };
+}
+
+memory_usage! {
+    /// The amount of memory allocated by the runtime.
+    malloc_size => "malloc_size_bytes",
+    /// The memory limit of the runtime, 0 or -1 if unlimited.
+    malloc_limit => "malloc_limit_bytes",
+    /// The amount of memory in use by the runtime, including internal bookkeeping.
+    memory_used_size => "memory_used_size_bytes",
+    /// The number of allocations.
+    malloc_count => "malloc_count",
+    /// The number of memory blocks in use.
+    memory_used_count => "memory_used_count",
+    /// The number of atoms.
+    atom_count => "atom_count",
+    /// The memory used by atoms.
+    atom_size => "atom_size_bytes",
+    /// The number of strings.
+    str_count => "string_count",
+    /// The memory used by strings.
+    str_size => "string_size_bytes",
+    /// The number of objects.
+    obj_count => "object_count",
+    /// The memory used by objects.
+    obj_size => "object_size_bytes",
+    /// The number of object properties.
+    prop_count => "property_count",
+    /// The memory used by object properties.
+    prop_size => "property_size_bytes",
+    /// The number of object shapes.
+    shape_count => "shape_count",
+    /// The memory used by object shapes.
+    shape_size => "shape_size_bytes",
+    /// The number of JavaScript functions.
+    js_func_count => "js_function_count",
+    /// The memory used by JavaScript functions.
+    js_func_size => "js_function_size_bytes",
+    /// The memory used by the bytecode of JavaScript functions.
+    js_func_code_size => "js_function_code_size_bytes",
+    /// The number of JavaScript functions with line number information.
+    js_func_pc2line_count => "js_function_pc2line_count",
+    /// The memory used by line number information.
+    js_func_pc2line_size => "js_function_pc2line_size_bytes",
+    /// The number of C functions, which includes Rust functions.
+    c_func_count => "c_function_count",
+    /// The number of arrays.
+    array_count => "array_count",
+    /// The number of fast arrays, arrays which store their elements contiguously.
+    fast_array_count => "fast_array_count",
+    /// The number of elements in fast arrays.
+    fast_array_elements => "fast_array_elements",
+    /// The number of array buffers and typed arrays.
+    binary_object_count => "binary_object_count",
+    /// The memory used by array buffers and typed arrays.
+    binary_object_size => "binary_object_size_bytes",
+}
+
+impl MemoryUsage {
+    /// Returns the change in memory usage since `previous`.
+    ///
+    /// Every field is the difference between the fields of `self` and `previous`, except for
+    /// [`malloc_limit`](Self::malloc_limit) which is taken from `self`.
+    pub fn delta(&self, previous: &MemoryUsage) -> MemoryUsage {
+        MemoryUsage {
+            malloc_limit: self.malloc_limit,
+            ..self.zip_with(previous, i64::wrapping_sub)
+        }
+    }
+
+    /// Write the memory usage as a flat JSON object.
+    pub fn write_json<W: Write>(&self, w: &mut W) -> fmt::Result {
+        w.write_char('{')?;
+        for (idx, ((name, _, _), value)) in Self::FIELDS.iter().zip(self.values()).enumerate() {
+            if idx != 0 {
+                w.write_char(',')?;
+            }
+            write!(w, "\"{name}\":{value}")?;
+        }
+        w.write_char('}')
+    }
+
+    /// Write the memory usage in the Prometheus text exposition format.
+    ///
+    /// Every field is written as a gauge named `quickjs_<field>` with the given labels, for
+    /// example `quickjs_object_count{runtime="main"} 12`. Use
+    /// [`MemoryUsage::write_prometheus_all`] to export the usage of multiple runtimes.
+    pub fn write_prometheus<W: Write>(&self, w: &mut W, labels: &[(&str, &str)]) -> fmt::Result {
+        Self::write_prometheus_all(w, [(labels, self)])
+    }
+
+    /// Write the memory usage of multiple runtimes in the Prometheus text exposition format.
+    ///
+    /// Every runtime should have a unique set of labels.
+    pub fn write_prometheus_all<'a, W, I>(w: &mut W, usages: I) -> fmt::Result
+    where
+        W: Write,
+        I: IntoIterator<Item = (&'a [(&'a str, &'a str)], &'a MemoryUsage)>,
+        I::IntoIter: Clone,
+    {
+        let usages = usages.into_iter();
+        for (idx, (_, metric, help)) in Self::FIELDS.iter().enumerate() {
+            writeln!(w, "# HELP quickjs_{metric} {}", help.trim())?;
+            writeln!(w, "# TYPE quickjs_{metric} gauge")?;
+            for (labels, usage) in usages.clone() {
+                write!(w, "quickjs_{metric}")?;
+                if !labels.is_empty() {
+                    w.write_char('{')?;
+                    for (label_idx, (name, value)) in labels.iter().enumerate() {
+                        if label_idx != 0 {
+                            w.write_char(',')?;
+                        }
+                        write!(w, "{name}=\"")?;
+                        for c in value.chars() {
+                            match c {
+                                '\\' => w.write_str("\\\\")?,
+                                '"' => w.write_str("\\\"")?,
+                                '\n' => w.write_str("\\n")?,
+                                c => w.write_char(c)?,
+                            }
+                        }
+                        w.write_char('"')?;
+                    }
+                    w.write_char('}')?;
+                }
+                writeln!(w, " {}", usage.values()[idx])?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Sub for MemoryUsage {
+    type Output = MemoryUsage;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.delta(&rhs)
+    }
+}
+
+impl Display for MemoryUsage {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for ((name, _, _), value) in Self::FIELDS.iter().zip(self.values()) {
+            writeln!(f, "{name}: {value}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "serde")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "serde")))]
+impl serde::Serialize for MemoryUsage {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("MemoryUsage", Self::FIELDS.len())?;
+        for ((name, _, _), value) in Self::FIELDS.iter().zip(self.values()) {
+            state.serialize_field(name, &value)?;
+        }
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Context, Runtime};
+
+    #[test]
+    fn memory_usage_delta() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        let before = rt.memory_usage();
+        assert!(before.malloc_size > 0);
+        assert!(before.atom_count > 0);
+
+        ctx.with(|ctx| {
+            ctx.eval::<(), _>("globalThis.objects = Array.from({ length: 100 }, () => ({}))")
+                .unwrap();
+        });
+        let delta = rt.memory_usage().delta(&before);
+        assert!(delta.obj_count >= 100);
+        assert_eq!(delta.malloc_limit, before.malloc_limit);
+        assert_eq!(rt.memory_usage() - before, delta);
+        assert!(delta.to_string().contains("obj_count: "));
+    }
+
+    #[test]
+    fn memory_usage_export() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        let usage = rt.memory_usage();
+
+        let mut prometheus = String::new();
+        usage
+            .write_prometheus(&mut prometheus, &[("runtime", "main \"1\"")])
+            .unwrap();
+        assert!(prometheus.contains("# TYPE quickjs_shape_count gauge\n"));
+        assert!(prometheus.contains(&format!(
+            "quickjs_object_count{{runtime=\"main \\\"1\\\"\"}} {}\n",
+            usage.obj_count
+        )));
+
+        let mut json = String::new();
+        usage.write_json(&mut json).unwrap();
+        ctx.with(|ctx| {
+            let value = ctx.json_parse(json).unwrap();
+            let obj = value.as_object().unwrap();
+            assert_eq!(obj.get::<_, i64>("atom_count").unwrap(), usage.atom_count);
+            assert_eq!(obj.keys::<String>().count(), 26);
+        });
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn memory_usage_serde() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        let usage = rt.memory_usage();
+        ctx.with(|ctx| {
+            let value = crate::value::convert::serde::to_value(ctx, &usage).unwrap();
+            let obj = value.as_object().unwrap();
+            assert_eq!(
+                obj.get::<_, i64>("js_func_count").unwrap(),
+                usage.js_func_count
+            );
+        });
+    }
+}
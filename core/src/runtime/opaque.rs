@@ -1,22 +1,34 @@
 use crate::{
+    allocator::{Account, Accounting},
     class::{self, ffi::VTable, JsClass},
-    qjs, Ctx, Error, JsLifetime, Object, Value,
+    qjs,
+    value::exception::capture,
+    Ctx, Error, Exception, JsLifetime, Mut, Object, StackFrame, StdString, Value,
 };
 
 use super::{
+    interrupt::{ExecutionLimits, InterruptReason},
     userdata::{UserDataGuard, UserDataMap},
-    InterruptHandler, PromiseHook, PromiseHookType, RejectionTracker, UserDataError,
+    InterruptHandler, PromiseHook, PromiseHookType, RejectionTracker, StackFormatter,
+    UserDataError,
 };
-use alloc::boxed::Box;
+use alloc::{boxed::Box, collections::BTreeMap, vec::Vec};
 use core::{
     any::{Any, TypeId},
     cell::{Cell, UnsafeCell},
     marker::PhantomData,
-    ptr,
+    ptr::{self, NonNull},
 };
 
 #[cfg(feature = "std")]
-use std::collections::{hash_map::Entry, HashMap};
+use super::profiler::{Profile, Profiler};
+#[cfg(feature = "loader")]
+use crate::loader::SourceMaps;
+#[cfg(feature = "std")]
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    time::Duration,
+};
 
 #[cfg(not(feature = "std"))]
 use hashbrown::{hash_map::Entry, HashMap};
@@ -30,6 +42,48 @@ use core::{
     task::{Context, Waker},
 };
 
+/// The running context saved by [`Opaque::enter_context`].
+pub(crate) struct EnteredContext {
+    ctx: *mut qjs::JSContext,
+    account: Option<NonNull<Account>>,
+}
+
+/// Makes a context the running context until dropped.
+pub(crate) struct ContextGuard {
+    opaque: NonNull<Opaque<'static>>,
+    prev: Option<EnteredContext>,
+}
+
+impl ContextGuard {
+    /// Enter the context, or leave any running context if `ctx` is null.
+    ///
+    /// # Safety
+    /// The runtime must be locked, and stay locked and alive until the guard is dropped.
+    pub unsafe fn new(rt: *mut qjs::JSRuntime, ctx: *mut qjs::JSContext) -> Self {
+        let opaque = NonNull::from(Opaque::<'static>::from_runtime_ptr(rt));
+        let prev = opaque.as_ref().enter_context(ctx);
+        ContextGuard {
+            opaque,
+            prev: Some(prev),
+        }
+    }
+}
+
+impl Drop for ContextGuard {
+    fn drop(&mut self) {
+        if let Some(prev) = self.prev.take() {
+            unsafe { self.opaque.as_ref().leave_context(prev) }
+        }
+    }
+}
+
+/// The values held by live persistent values, by the id of the persistent value.
+#[derive(Default)]
+pub(crate) struct PersistentRoots {
+    next_id: u64,
+    roots: BTreeMap<u64, (&'static str, Vec<qjs::JSValue>)>,
+}
+
 /// Opaque book keeping data for Rust.
 pub(crate) struct Opaque<'js> {
     /// Used to carry a panic if a callback triggered one.
@@ -44,15 +98,47 @@ pub(crate) struct Opaque<'js> {
     /// The user provided interrupt handler, if any.
     interrupt_handler: UnsafeCell<Option<InterruptHandler>>,
 
+    /// The deadline and budget after which execution is interrupted.
+    limits: ExecutionLimits,
+
+    /// The user provided stack formatter, if any.
+    stack_formatter: UnsafeCell<Option<StackFormatter>>,
+    /// Whether the stack formatter is currently running.
+    formatting_stack: Cell<bool>,
+
+    /// The sampling profiler, if profiling.
+    #[cfg(feature = "std")]
+    profiler: UnsafeCell<Option<Profiler>>,
+
+    /// The per context memory accounting, if the runtime uses a custom allocator.
+    accounting: Option<NonNull<Accounting>>,
+    /// The context which is currently running, null if none.
+    running: Cell<*mut qjs::JSContext>,
+
     /// The class id for rust classes.
     class_id: qjs::JSClassID,
     /// The class id for rust classes which can be called.
     callable_class_id: qjs::JSClassID,
+    /// The class id for rust classes with exotic behavior.
+    exotic_class_id: qjs::JSClassID,
+    /// The class id for rust classes with exotic behavior which can be called.
+    exotic_callable_class_id: qjs::JSClassID,
+
+    /// The private symbol under which the call sites of new errors are stored, see
+    /// [`exception::capture`](crate::value::exception::capture).
+    stack_atom: qjs::JSAtom,
 
     prototypes: UnsafeCell<HashMap<TypeId, Option<Object<'js>>>>,
 
+    /// The values held by every live [`Persistent`](crate::Persistent).
+    persistent_roots: Mut<PersistentRoots>,
+
     userdata: UserDataMap,
 
+    /// The source maps used to remap stack traces.
+    #[cfg(feature = "loader")]
+    source_maps: SourceMaps,
+
     #[cfg(feature = "futures")]
     spawner: Option<UnsafeCell<Spawner>>,
 
@@ -70,13 +156,33 @@ impl<'js> Opaque<'js> {
 
             interrupt_handler: UnsafeCell::new(None),
 
+            limits: ExecutionLimits::default(),
+
+            stack_formatter: UnsafeCell::new(None),
+            formatting_stack: Cell::new(false),
+
+            #[cfg(feature = "std")]
+            profiler: UnsafeCell::new(None),
+
+            accounting: None,
+            running: Cell::new(ptr::null_mut()),
+
             class_id: qjs::JS_INVALID_CLASS_ID,
             callable_class_id: qjs::JS_INVALID_CLASS_ID,
+            exotic_class_id: qjs::JS_INVALID_CLASS_ID,
+            exotic_callable_class_id: qjs::JS_INVALID_CLASS_ID,
+
+            stack_atom: qjs::JS_ATOM_NULL,
 
             prototypes: UnsafeCell::new(HashMap::new()),
 
+            persistent_roots: Mut::new(PersistentRoots::default()),
+
             userdata: UserDataMap::default(),
 
+            #[cfg(feature = "loader")]
+            source_maps: SourceMaps::default(),
+
             _marker: PhantomData,
 
             #[cfg(feature = "futures")]
@@ -94,6 +200,11 @@ impl<'js> Opaque<'js> {
     pub unsafe fn initialize(&mut self, rt: *mut qjs::JSRuntime) -> Result<(), Error> {
         qjs::JS_NewClassID(rt, (&mut self.class_id) as *mut qjs::JSClassID);
         qjs::JS_NewClassID(rt, (&mut self.callable_class_id) as *mut qjs::JSClassID);
+        qjs::JS_NewClassID(rt, (&mut self.exotic_class_id) as *mut qjs::JSClassID);
+        qjs::JS_NewClassID(
+            rt,
+            (&mut self.exotic_callable_class_id) as *mut qjs::JSClassID,
+        );
 
         let class_def = qjs::JSClassDef {
             class_name: c"RustClass".as_ptr().cast(),
@@ -119,9 +230,44 @@ impl<'js> Opaque<'js> {
             return Err(Error::Unknown);
         }
 
+        let exotic = ptr::addr_of!(class::ffi::EXOTIC_METHODS).cast_mut();
+
+        let class_def = qjs::JSClassDef {
+            class_name: c"RustExoticClass".as_ptr().cast(),
+            finalizer: Some(class::ffi::exotic_finalizer),
+            gc_mark: Some(class::ffi::exotic_trace),
+            call: None,
+            exotic,
+        };
+
+        if 0 != qjs::JS_NewClass(rt, self.exotic_class_id, &class_def) {
+            return Err(Error::Unknown);
+        }
+
+        let class_def = qjs::JSClassDef {
+            class_name: c"RustExoticFunction".as_ptr().cast(),
+            finalizer: Some(class::ffi::exotic_finalizer),
+            gc_mark: Some(class::ffi::exotic_trace),
+            call: Some(class::ffi::exotic_call),
+            exotic,
+        };
+
+        if 0 != qjs::JS_NewClass(rt, self.exotic_callable_class_id, &class_def) {
+            return Err(Error::Unknown);
+        }
+
+        self.stack_atom = qjs::rquickjs_new_private_atom(rt, c"stack frames".as_ptr());
+        if self.stack_atom == qjs::JS_ATOM_NULL {
+            return Err(Error::Allocation);
+        }
+
         Ok(())
     }
 
+    pub fn stack_atom(&self) -> qjs::JSAtom {
+        self.stack_atom
+    }
+
     pub unsafe fn from_runtime_ptr<'a>(rt: *mut qjs::JSRuntime) -> &'a Self {
         &*(qjs::JS_GetRuntimeOpaque(rt).cast::<Self>())
     }
@@ -210,12 +356,196 @@ impl<'js> Opaque<'js> {
         }
     }
 
+    pub fn set_stack_formatter(&self, formatter: Option<StackFormatter>) {
+        unsafe { (*self.stack_formatter.get()) = formatter }
+    }
+
+    /// Formats the stack of an exception with the stack formatter.
+    ///
+    /// Returns `None` if there is no formatter or if the formatter itself asks for a stack.
+    pub fn format_stack<'a>(
+        &self,
+        ctx: Ctx<'a>,
+        exception: &Exception<'a>,
+        frames: &[StackFrame],
+    ) -> Option<StdString> {
+        if self.formatting_stack.get() {
+            return None;
+        }
+        /// Clears the flag when the formatter returns or panics.
+        struct Reset<'a>(&'a Cell<bool>);
+
+        impl Drop for Reset<'_> {
+            fn drop(&mut self) {
+                self.0.set(false);
+            }
+        }
+
+        let formatter = unsafe { (*self.stack_formatter.get()).as_ref()? };
+        self.formatting_stack.set(true);
+        let _reset = Reset(&self.formatting_stack);
+        Some(formatter(ctx, exception, frames))
+    }
+
     pub fn set_interrupt_handler(&self, interupt: Option<InterruptHandler>) {
         unsafe { (*self.interrupt_handler.get()) = interupt }
     }
 
-    pub fn run_interrupt_handler(&self) -> bool {
-        unsafe { (*self.interrupt_handler.get()).as_mut().unwrap()() }
+    #[cfg_attr(not(feature = "std"), allow(unused_variables))]
+    pub unsafe fn run_interrupt_handler(&self, rt: *mut qjs::JSRuntime) -> bool {
+        #[cfg(feature = "std")]
+        if let Some(profiler) = (*self.profiler.get()).as_ref() {
+            profiler.tick(rt);
+        }
+        if let Some(reason) = self.limits.poll() {
+            self.limits.set_reason(reason);
+            return true;
+        }
+        let Some(handler) = (unsafe { (*self.interrupt_handler.get()).as_mut() }) else {
+            return false;
+        };
+        let interrupt = handler();
+        if interrupt {
+            self.limits.set_reason(InterruptReason::Handler);
+        }
+        interrupt
+    }
+
+    /// Returns if the interrupt handler needs to be installed.
+    pub fn needs_interrupt_handler(&self) -> bool {
+        #[cfg(feature = "std")]
+        if unsafe { (*self.profiler.get()).is_some() } {
+            return true;
+        }
+        self.limits.is_active() || unsafe { (*self.interrupt_handler.get()).is_some() }
+    }
+
+    /// Start the sampling profiler, discarding any running profile.
+    #[cfg(feature = "std")]
+    pub fn start_profiling(&self, interval: Duration) {
+        unsafe { *self.profiler.get() = Some(Profiler::new(interval)) }
+    }
+
+    /// Stop the sampling profiler, returning an empty profile if it was not running.
+    #[cfg(feature = "std")]
+    pub fn stop_profiling(&self) -> Profile {
+        unsafe { (*self.profiler.get()).take() }
+            .map(Profiler::finish)
+            .unwrap_or_default()
+    }
+
+    pub fn limits(&self) -> &ExecutionLimits {
+        &self.limits
+    }
+
+    #[cfg(feature = "loader")]
+    pub fn source_maps(&self) -> &SourceMaps {
+        &self.source_maps
+    }
+
+    pub fn set_accounting(&mut self, accounting: NonNull<Accounting>) {
+        self.accounting = Some(accounting);
+    }
+
+    /// Make the given context the running context, attributing new allocations to it.
+    ///
+    /// A null context runs code which is not attributed to any context. Returns the previously
+    /// running context, which must be restored with [`Opaque::leave_context`].
+    pub unsafe fn enter_context(&self, ctx: *mut qjs::JSContext) -> EnteredContext {
+        EnteredContext {
+            ctx: self.running.replace(ctx),
+            account: self
+                .accounting
+                .and_then(|accounting| accounting.as_ref().enter(ctx)),
+        }
+    }
+
+    /// Restore the context which was running before the matching [`Opaque::enter_context`].
+    pub unsafe fn leave_context(&self, prev: EnteredContext) {
+        self.running.set(prev.ctx);
+        if let Some(accounting) = self.accounting {
+            accounting.as_ref().leave(prev.account)
+        }
+    }
+
+    /// Execute a pending job, returning the result of `JS_ExecutePendingJob` and the context of
+    /// the job.
+    ///
+    /// The context of a job is only known once the job ran, so the job runs without a running
+    /// context and the memory it allocated is attributed to its context afterwards.
+    pub unsafe fn execute_pending_job(
+        &self,
+        rt: *mut qjs::JSRuntime,
+    ) -> (qjs::c_int, *mut qjs::JSContext) {
+        let prev = self.enter_context(ptr::null_mut());
+        if let Some(accounting) = self.accounting {
+            accounting.as_ref().begin_job();
+        }
+        let mut ctx = ptr::null_mut();
+        let res = qjs::JS_ExecutePendingJob(rt, &mut ctx);
+        if let Some(accounting) = self.accounting {
+            accounting.as_ref().finish_job(ctx);
+        }
+        self.leave_context(prev);
+        (res, ctx)
+    }
+
+    /// Create a new context, giving it its own memory account if the runtime accounts memory.
+    ///
+    /// Installs the hook which captures the frames of errors in the new context.
+    pub unsafe fn new_context<F>(&self, create: F) -> *mut qjs::JSContext
+    where
+        F: FnOnce() -> *mut qjs::JSContext,
+    {
+        let create = || {
+            let ctx = create();
+            if !ctx.is_null() && capture::install(ctx).is_err() {
+                qjs::JS_FreeContext(ctx);
+                return ptr::null_mut();
+            }
+            ctx
+        };
+        match self.accounting {
+            Some(accounting) => accounting.as_ref().new_context(create),
+            None => create(),
+        }
+    }
+
+    /// Register the values held by a persistent value, returning its id.
+    ///
+    /// The values are not reference counted, they must be unregistered before they are freed.
+    pub fn add_persistent_root(&self, name: &'static str, values: Vec<qjs::JSValue>) -> u64 {
+        let mut persistent = self.persistent_roots.lock();
+        persistent.next_id += 1;
+        let id = persistent.next_id;
+        persistent.roots.insert(id, (name, values));
+        id
+    }
+
+    /// Register a copy of the values of a persistent value, returning the id of the copy.
+    pub fn clone_persistent_root(&self, id: u64) -> u64 {
+        let mut persistent = self.persistent_roots.lock();
+        let Some(root) = persistent.roots.get(&id).cloned() else {
+            return 0;
+        };
+        persistent.next_id += 1;
+        let id = persistent.next_id;
+        persistent.roots.insert(id, root);
+        id
+    }
+
+    pub fn remove_persistent_root(&self, id: u64) {
+        self.persistent_roots.lock().roots.remove(&id);
+    }
+
+    /// Returns the values held by all live persistent values, with the type name of the values.
+    pub fn persistent_roots(&self) -> Vec<(&'static str, qjs::JSValue)> {
+        let persistent = self.persistent_roots.lock();
+        persistent
+            .roots
+            .values()
+            .flat_map(|(name, values)| values.iter().map(|x| (*name, *x)))
+            .collect()
     }
 
     #[allow(dead_code)] // not used in no_std
@@ -235,6 +565,31 @@ impl<'js> Opaque<'js> {
         self.callable_class_id
     }
 
+    /// Returns the class id used for instances of the given class.
+    pub fn get_class_id_of<C: JsClass<'js>>(&self) -> qjs::JSClassID {
+        match (C::CALLABLE, C::EXOTIC) {
+            (false, false) => self.class_id,
+            (true, false) => self.callable_class_id,
+            (false, true) => self.exotic_class_id,
+            (true, true) => self.exotic_callable_class_id,
+        }
+    }
+
+    /// Returns if the class id is one of the ids used for rust classes.
+    pub fn is_class_id(&self, id: qjs::JSClassID) -> bool {
+        id != qjs::JS_INVALID_CLASS_ID
+            && (id == self.class_id
+                || id == self.callable_class_id
+                || id == self.exotic_class_id
+                || id == self.exotic_callable_class_id)
+    }
+
+    /// Returns if the class id is one of the ids used for rust classes with exotic behavior.
+    pub fn is_exotic_class_id(&self, id: qjs::JSClassID) -> bool {
+        id != qjs::JS_INVALID_CLASS_ID
+            && (id == self.exotic_class_id || id == self.exotic_callable_class_id)
+    }
+
     pub fn get_or_insert_prototype<C: JsClass<'js>>(
         &self,
         ctx: &Ctx<'js>,
@@ -242,12 +597,22 @@ impl<'js> Opaque<'js> {
         unsafe {
             let vtable = VTable::get::<C>();
             let id = vtable.id();
+            if let Some(x) = (*self.prototypes.get()).get(&id) {
+                return Ok(x.clone());
+            }
+
+            // Creating the prototypes can recursively create the prototypes of other classes, so
+            // the map must not be borrowed while calling into the class.
+            let proto = C::prototype(ctx)?;
+            if let Some(proto) = proto.as_ref() {
+                if let Some(parent) = vtable.parent_prototype(ctx)? {
+                    proto.set_prototype(Some(&parent))?;
+                }
+            }
+
             match (*self.prototypes.get()).entry(id) {
                 Entry::Occupied(x) => Ok(x.get().clone()),
-                Entry::Vacant(x) => {
-                    let proto = C::prototype(ctx)?;
-                    Ok(x.insert(proto).clone())
-                }
+                Entry::Vacant(x) => Ok(x.insert(proto).clone()),
             }
         }
     }
@@ -259,6 +624,9 @@ impl<'js> Opaque<'js> {
     pub fn clear(&mut self) {
         self.rejection_tracker.get_mut().take();
         self.interrupt_handler.get_mut().take();
+        self.stack_formatter.get_mut().take();
+        #[cfg(feature = "std")]
+        self.profiler.get_mut().take();
         self.panic.take();
         self.prototypes.get_mut().clear();
         #[cfg(feature = "futures")]
@@ -0,0 +1,678 @@
+//! A pool of async runtimes which schedules leases with affinity and work-stealing.
+//!
+//! Every runtime of a [`RuntimePool`] has its own queue of tasks waiting for a lease. A task
+//! acquired with an affinity key waits in the queue of the runtime selected by the key, other
+//! tasks wait in a queue shared by all runtimes. A released runtime serves its own queue first,
+//! then the shared queue, and otherwise steals the oldest task from the longest queue of another
+//! runtime. A task which finds its preferred runtime busy while another runtime is idle takes the
+//! idle runtime right away.
+
+use alloc::{boxed::Box, collections::VecDeque, vec::Vec};
+use core::{
+    future::Future,
+    ops::Deref,
+    pin::Pin,
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+use std::{sync::Mutex, thread, time::Instant};
+
+use crate::{markers::ParallelSend, AsyncContext, AsyncRuntime, Ctx, Ref, Result};
+
+/// The type of the function which initializes new contexts of a [`RuntimePool`].
+#[cfg(not(feature = "parallel"))]
+pub type ContextInit = Box<dyn for<'js> Fn(Ctx<'js>) -> Result<()> + 'static>;
+/// The type of the function which initializes new contexts of a [`RuntimePool`].
+#[cfg(feature = "parallel")]
+pub type ContextInit = Box<dyn for<'js> Fn(Ctx<'js>) -> Result<()> + Send + Sync + 'static>;
+
+/// When the context of a runtime in a [`RuntimePool`] is replaced with a fresh one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResetPolicy {
+    /// Keep using the same context, globals set by one lease are visible to the next.
+    Never,
+    /// Create a new context before every lease.
+    Always,
+    /// Create a new context after the given number of leases.
+    After(u32),
+}
+
+/// Usage statistics of a single runtime in a [`RuntimePool`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuntimeMetrics {
+    /// Whether the runtime is currently leased.
+    pub in_use: bool,
+    /// The number of leases of the runtime.
+    pub leases: u64,
+    /// The number of leases with an affinity for another runtime, which this runtime took over.
+    pub steals: u64,
+    /// The number of times the context of the runtime was reset.
+    pub resets: u64,
+    /// The number of times the runtime was replaced after [`Lease::recycle`].
+    pub recycles: u64,
+    /// The number of leases which were released after their deadline.
+    pub deadlines_exceeded: u64,
+    /// The total time the runtime was leased.
+    pub busy: Duration,
+}
+
+/// Usage statistics of a [`RuntimePool`], see [`RuntimePool::metrics`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolMetrics {
+    /// The number of runtimes which are not leased.
+    pub idle: usize,
+    /// The number of tasks waiting for a runtime, including those waiting for a specific one.
+    pub waiting: usize,
+    /// The statistics of every runtime in the pool.
+    pub runtimes: Vec<RuntimeMetrics>,
+}
+
+#[derive(Default)]
+struct SlotMetrics {
+    in_use: AtomicBool,
+    leases: AtomicU64,
+    steals: AtomicU64,
+    resets: AtomicU64,
+    recycles: AtomicU64,
+    deadlines_exceeded: AtomicU64,
+    busy: AtomicU64,
+}
+
+impl SlotMetrics {
+    fn get(&self) -> RuntimeMetrics {
+        RuntimeMetrics {
+            in_use: self.in_use.load(Ordering::Relaxed),
+            leases: self.leases.load(Ordering::Relaxed),
+            steals: self.steals.load(Ordering::Relaxed),
+            resets: self.resets.load(Ordering::Relaxed),
+            recycles: self.recycles.load(Ordering::Relaxed),
+            deadlines_exceeded: self.deadlines_exceeded.load(Ordering::Relaxed),
+            busy: Duration::from_nanos(self.busy.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+struct Slot {
+    index: usize,
+    runtime: AsyncRuntime,
+    context: AsyncContext,
+    uses: u32,
+    recycle: bool,
+}
+
+/// A task waiting for a runtime, a released runtime is handed to it directly.
+#[derive(Default)]
+struct Waiter {
+    slot: Option<Slot>,
+    waker: Option<Waker>,
+}
+
+type WaiterRef = Ref<Mutex<Waiter>>;
+
+#[derive(Default)]
+struct State {
+    idle: Vec<Slot>,
+    /// The tasks waiting for a specific runtime, by the index of the runtime.
+    queues: Vec<VecDeque<WaiterRef>>,
+    /// The tasks waiting for any runtime.
+    shared: VecDeque<WaiterRef>,
+}
+
+impl State {
+    /// Take an idle runtime, the preferred one if it is idle.
+    fn take_idle(&mut self, preferred: Option<usize>) -> Option<Slot> {
+        let position = preferred
+            .and_then(|x| self.idle.iter().position(|slot| slot.index == x))
+            .or_else(|| self.idle.len().checked_sub(1))?;
+        Some(self.idle.swap_remove(position))
+    }
+
+    /// Hand a released runtime to the next waiting task, or make it idle.
+    fn release(&mut self, slot: Slot) {
+        let index = slot.index;
+        let waiter = self.queues[index]
+            .pop_front()
+            .or_else(|| self.shared.pop_front())
+            .or_else(|| {
+                self.queues
+                    .iter_mut()
+                    .max_by_key(|x| x.len())
+                    .and_then(VecDeque::pop_front)
+            });
+        let Some(waiter) = waiter else {
+            self.idle.push(slot);
+            return;
+        };
+        let mut waiter = waiter.lock().unwrap();
+        waiter.slot = Some(slot);
+        if let Some(waker) = waiter.waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn waiting(&self) -> usize {
+        self.shared.len() + self.queues.iter().map(VecDeque::len).sum::<usize>()
+    }
+}
+
+struct Inner {
+    state: Mutex<State>,
+    metrics: Vec<SlotMetrics>,
+    reset: ResetPolicy,
+    deadline: Option<Duration>,
+    init: Option<ContextInit>,
+}
+
+impl Inner {
+    async fn new_context(&self, runtime: &AsyncRuntime) -> Result<AsyncContext> {
+        let context = AsyncContext::full(runtime).await?;
+        if let Some(init) = self.init.as_ref() {
+            context.with(init).await?;
+        }
+        Ok(context)
+    }
+}
+
+/// A builder for a [`RuntimePool`].
+pub struct RuntimePoolBuilder {
+    size: usize,
+    reset: ResetPolicy,
+    deadline: Option<Duration>,
+    init: Option<ContextInit>,
+}
+
+impl RuntimePoolBuilder {
+    /// Set the number of runtimes in the pool, at least one.
+    ///
+    /// Defaults to the available parallelism of the system.
+    pub fn with_size(mut self, size: usize) -> Self {
+        self.size = size.max(1);
+        self
+    }
+
+    /// Set when contexts are reset between leases, defaults to [`ResetPolicy::Never`].
+    pub fn with_reset(mut self, reset: ResetPolicy) -> Self {
+        self.reset = reset;
+        self
+    }
+
+    /// Interrupt code running on a lease once `deadline` has elapsed since the lease was
+    /// acquired.
+    ///
+    /// Interrupted code returns [`Error::Interrupted`](crate::Error::Interrupted), see
+    /// [`AsyncRuntime::set_execution_deadline`].
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Set a function which is called for every new context, for example to install globals.
+    pub fn with_init(mut self, init: ContextInit) -> Self {
+        self.init = Some(init);
+        self
+    }
+
+    /// Create the runtimes and contexts of the pool.
+    pub async fn build(self) -> Result<RuntimePool> {
+        let mut inner = Inner {
+            state: Mutex::new(State::default()),
+            metrics: (0..self.size).map(|_| SlotMetrics::default()).collect(),
+            reset: self.reset,
+            deadline: self.deadline,
+            init: self.init,
+        };
+        let mut idle = Vec::with_capacity(self.size);
+        // Reversed so that runtimes are handed out in order of their index.
+        for index in (0..self.size).rev() {
+            let runtime = AsyncRuntime::new()?;
+            let context = inner.new_context(&runtime).await?;
+            idle.push(Slot {
+                index,
+                runtime,
+                context,
+                uses: 0,
+                recycle: false,
+            });
+        }
+        inner.state = Mutex::new(State {
+            idle,
+            queues: (0..self.size).map(|_| VecDeque::new()).collect(),
+            shared: VecDeque::new(),
+        });
+        Ok(RuntimePool(Ref::new(inner)))
+    }
+}
+
+/// A pool of [`AsyncRuntime`]s, each with a context ready for use.
+///
+/// A runtime executes one script at a time, a pool allows running scripts in parallel by
+/// leasing each of them a runtime of its own. A [`Lease`] dereferences to its context so it can
+/// be used like an [`AsyncContext`], including with the [`async_with!`](crate::async_with)
+/// macro. The runtime returns to the pool once the lease is dropped.
+///
+/// Leases acquired with [`acquire_with_affinity`](Self::acquire_with_affinity) prefer one
+/// runtime, which keeps its caches warm, but are taken over by any other runtime which would
+/// otherwise be idle. See the [module documentation](self) for the details of the scheduling.
+///
+/// ```
+/// # use rquickjs::{async_with, runtime::{ResetPolicy, RuntimePool}};
+/// # use std::time::Duration;
+/// # async fn run() {
+/// let pool = RuntimePool::builder()
+///     .with_size(4)
+///     .with_reset(ResetPolicy::Always)
+///     .with_deadline(Duration::from_secs(1))
+///     .with_init(Box::new(|ctx| ctx.globals().set("answer", 42)))
+///     .build()
+///     .await
+///     .unwrap();
+///
+/// let lease = pool.acquire().await.unwrap();
+/// let res = async_with!(lease => |ctx| {
+///     ctx.eval::<i32, _>("answer").unwrap()
+/// })
+/// .await;
+/// assert_eq!(res, 42);
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct RuntimePool(Ref<Inner>);
+
+impl RuntimePool {
+    /// Create a pool with `size` runtimes.
+    pub async fn new(size: usize) -> Result<Self> {
+        Self::builder().with_size(size).build().await
+    }
+
+    /// Create a builder for a pool with custom options.
+    pub fn builder() -> RuntimePoolBuilder {
+        RuntimePoolBuilder {
+            size: thread::available_parallelism().map_or(1, |x| x.get()),
+            reset: ResetPolicy::Never,
+            deadline: None,
+            init: None,
+        }
+    }
+
+    /// Returns the number of runtimes in the pool.
+    pub fn size(&self) -> usize {
+        self.0.metrics.len()
+    }
+
+    /// Lease a runtime, waiting until one is available.
+    pub async fn acquire(&self) -> Result<Lease> {
+        self.acquire_inner(None).await
+    }
+
+    /// Lease a runtime, preferring the runtime selected by `key`.
+    ///
+    /// Leases with the same key end up on the same runtime, which keeps its caches warm. If the
+    /// runtime is busy any other idle runtime is used instead. If all runtimes are busy the task
+    /// waits in the queue of the preferred runtime, where it is served before tasks without an
+    /// affinity, unless another runtime is released first and steals it.
+    pub async fn acquire_with_affinity(&self, key: u64) -> Result<Lease> {
+        let preferred = (key % self.size() as u64) as usize;
+        self.acquire_inner(Some(preferred)).await
+    }
+
+    /// Lease a runtime and run a closure with its context.
+    ///
+    /// See [`AsyncContext::with`].
+    pub async fn with<F, R>(&self, f: F) -> Result<R>
+    where
+        F: for<'js> FnOnce(Ctx<'js>) -> R + ParallelSend,
+        R: ParallelSend,
+    {
+        let lease = self.acquire().await?;
+        Ok(lease.with(f).await)
+    }
+
+    /// Returns the current usage statistics of the pool.
+    pub fn metrics(&self) -> PoolMetrics {
+        let state = self.0.state.lock().unwrap();
+        PoolMetrics {
+            idle: state.idle.len(),
+            waiting: state.waiting(),
+            runtimes: self.0.metrics.iter().map(SlotMetrics::get).collect(),
+        }
+    }
+
+    async fn acquire_inner(&self, preferred: Option<usize>) -> Result<Lease> {
+        let slot = Acquire {
+            pool: &self.0,
+            preferred,
+            waiter: None,
+        }
+        .await;
+        let metrics = &self.0.metrics[slot.index];
+        metrics.in_use.store(true, Ordering::Relaxed);
+        metrics.leases.fetch_add(1, Ordering::Relaxed);
+        if preferred.is_some_and(|x| x != slot.index) {
+            metrics.steals.fetch_add(1, Ordering::Relaxed);
+        }
+
+        // Return the slot to the pool if preparing it fails or the future is dropped.
+        let mut lease = Lease {
+            pool: self.0.clone(),
+            slot: Some(slot),
+            acquired: Instant::now(),
+            deadline: None,
+        };
+        lease.prepare().await?;
+        Ok(lease)
+    }
+}
+
+/// A runtime leased from a [`RuntimePool`].
+///
+/// Dereferences to the context of the runtime. The runtime is returned to the pool on drop.
+pub struct Lease {
+    pool: Ref<Inner>,
+    slot: Option<Slot>,
+    acquired: Instant,
+    deadline: Option<Instant>,
+}
+
+impl Lease {
+    /// Returns the leased runtime.
+    pub fn runtime(&self) -> &AsyncRuntime {
+        &self.slot().runtime
+    }
+
+    /// Returns the context of the leased runtime.
+    pub fn context(&self) -> &AsyncContext {
+        &self.slot().context
+    }
+
+    /// Returns the index of the leased runtime in the pool.
+    pub fn index(&self) -> usize {
+        self.slot().index
+    }
+
+    /// Interrupt code running on this lease once `timeout` has elapsed from now, replacing the
+    /// deadline of the pool.
+    pub async fn set_deadline(&mut self, timeout: Duration) {
+        self.deadline = Some(Instant::now() + timeout);
+        self.runtime().set_execution_deadline(timeout).await;
+    }
+
+    /// Remove the deadline of this lease.
+    pub async fn clear_deadline(&mut self) {
+        self.deadline = None;
+        self.runtime().clear_execution_deadline().await;
+    }
+
+    /// Replace the runtime with a new one before it is leased again.
+    ///
+    /// Use this if the runtime was left in a bad state, for example if it ran out of memory.
+    pub fn recycle(&mut self) {
+        self.slot.as_mut().unwrap().recycle = true;
+    }
+
+    fn slot(&self) -> &Slot {
+        self.slot.as_ref().unwrap()
+    }
+
+    async fn prepare(&mut self) -> Result<()> {
+        let pool = self.pool.clone();
+        let slot = self.slot.as_mut().unwrap();
+        let metrics = &pool.metrics[slot.index];
+        if slot.recycle {
+            let runtime = AsyncRuntime::new()?;
+            slot.context = pool.new_context(&runtime).await?;
+            slot.runtime = runtime;
+            slot.recycle = false;
+            slot.uses = 0;
+            metrics.recycles.fetch_add(1, Ordering::Relaxed);
+        } else if slot.uses > 0
+            && match pool.reset {
+                ResetPolicy::Never => false,
+                ResetPolicy::Always => true,
+                ResetPolicy::After(uses) => slot.uses >= uses,
+            }
+        {
+            slot.context = pool.new_context(&slot.runtime).await?;
+            slot.uses = 0;
+            metrics.resets.fetch_add(1, Ordering::Relaxed);
+        }
+        slot.uses += 1;
+
+        match pool.deadline {
+            Some(deadline) => self.set_deadline(deadline).await,
+            None => self.clear_deadline().await,
+        }
+        Ok(())
+    }
+}
+
+impl Deref for Lease {
+    type Target = AsyncContext;
+
+    fn deref(&self) -> &AsyncContext {
+        self.context()
+    }
+}
+
+impl Drop for Lease {
+    fn drop(&mut self) {
+        let Some(slot) = self.slot.take() else {
+            return;
+        };
+        let metrics = &self.pool.metrics[slot.index];
+        let now = Instant::now();
+        if self.deadline.is_some_and(|x| now >= x) {
+            metrics.deadlines_exceeded.fetch_add(1, Ordering::Relaxed);
+        }
+        let busy = now.duration_since(self.acquired).as_nanos() as u64;
+        metrics.busy.fetch_add(busy, Ordering::Relaxed);
+        metrics.in_use.store(false, Ordering::Relaxed);
+        self.pool.state.lock().unwrap().release(slot);
+    }
+}
+
+/// Waits until a runtime is handed to the task.
+struct Acquire<'a> {
+    pool: &'a Inner,
+    preferred: Option<usize>,
+    waiter: Option<WaiterRef>,
+}
+
+impl Future for Acquire<'_> {
+    type Output = Slot;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Slot> {
+        let this = self.get_mut();
+        let mut state = this.pool.state.lock().unwrap();
+        if let Some(waiter) = this.waiter.as_ref() {
+            let mut waiter = waiter.lock().unwrap();
+            if let Some(slot) = waiter.slot.take() {
+                drop(waiter);
+                this.waiter = None;
+                return Poll::Ready(slot);
+            }
+            waiter.waker = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+
+        if let Some(slot) = state.take_idle(this.preferred) {
+            return Poll::Ready(slot);
+        }
+        let waiter = Ref::new(Mutex::new(Waiter {
+            slot: None,
+            waker: Some(cx.waker().clone()),
+        }));
+        match this.preferred {
+            Some(index) => state.queues[index].push_back(waiter.clone()),
+            None => state.shared.push_back(waiter.clone()),
+        }
+        this.waiter = Some(waiter);
+        Poll::Pending
+    }
+}
+
+impl Drop for Acquire<'_> {
+    fn drop(&mut self) {
+        let Some(waiter) = self.waiter.take() else {
+            return;
+        };
+        let Ok(mut state) = self.pool.state.lock() else {
+            return;
+        };
+        let queued = |x: &WaiterRef| !Ref::ptr_eq(x, &waiter);
+        state.shared.retain(queued);
+        state.queues.iter_mut().for_each(|x| x.retain(queued));
+        // The task was cancelled after a runtime was handed to it.
+        let slot = waiter.lock().unwrap().slot.take();
+        if let Some(slot) = slot {
+            state.release(slot);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{pin::pin, time::Duration};
+
+    use futures_rs::poll;
+
+    use super::{Lease, ResetPolicy, RuntimePool};
+
+    async fn interrupted(lease: &Lease) -> bool {
+        lease
+            .with(|ctx| {
+                ctx.eval::<(), _>("for(;;){}")
+                    .is_err_and(|e| e.is_interrupted())
+            })
+            .await
+    }
+
+    #[tokio::test]
+    async fn reset_and_affinity() {
+        let pool = RuntimePool::builder()
+            .with_size(2)
+            .with_reset(ResetPolicy::After(2))
+            .with_init(Box::new(|ctx| ctx.globals().set("uses", 0)))
+            .build()
+            .await
+            .unwrap();
+
+        let mut uses = Vec::new();
+        for _ in 0..5 {
+            let lease = pool.acquire_with_affinity(3).await.unwrap();
+            assert_eq!(lease.index(), 1);
+            uses.push(
+                lease
+                    .with(|ctx| ctx.eval::<i32, _>("++uses").unwrap())
+                    .await,
+            );
+        }
+        assert_eq!(uses, [1, 2, 1, 2, 1]);
+
+        // Without affinity the other runtime is used while the preferred one is busy.
+        let first = pool.acquire_with_affinity(1).await.unwrap();
+        let second = pool.acquire_with_affinity(1).await.unwrap();
+        assert_eq!((first.index(), second.index()), (1, 0));
+        drop(second);
+
+        let mut lease = pool.acquire_with_affinity(0).await.unwrap();
+        lease.recycle();
+        drop(lease);
+        let lease = pool.acquire_with_affinity(0).await.unwrap();
+        assert_eq!(
+            lease
+                .with(|ctx| ctx.eval::<i32, _>("++uses").unwrap())
+                .await,
+            1
+        );
+        drop((first, lease));
+
+        let metrics = pool.metrics();
+        assert_eq!(metrics.idle, 2);
+        assert_eq!(metrics.waiting, 0);
+        assert_eq!(metrics.runtimes[0].leases, 3);
+        assert_eq!(metrics.runtimes[0].recycles, 1);
+        assert_eq!(metrics.runtimes[1].leases, 6);
+        assert_eq!(metrics.runtimes[1].resets, 2);
+        assert!(metrics.runtimes.iter().all(|x| !x.in_use));
+    }
+
+    #[tokio::test]
+    async fn waits_for_lease() {
+        let pool = RuntimePool::new(1).await.unwrap();
+        let lease = pool.acquire().await.unwrap();
+        let waiting = pool.clone();
+        let (res, _) = tokio::join!(
+            waiting.with(|ctx| ctx.eval::<i32, _>("1 + 1").unwrap()),
+            async {
+                tokio::task::yield_now().await;
+                let metrics = pool.metrics();
+                assert_eq!((metrics.idle, metrics.waiting), (0, 1));
+                assert!(metrics.runtimes[0].in_use);
+                drop(lease);
+            }
+        );
+        assert_eq!(res.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn queues_and_steals() {
+        let pool = RuntimePool::new(2).await.unwrap();
+        let first = pool.acquire_with_affinity(0).await.unwrap();
+        let second = pool.acquire_with_affinity(1).await.unwrap();
+
+        let mut own = pin!(pool.acquire_with_affinity(0));
+        let mut stolen = pin!(pool.acquire_with_affinity(2));
+        let mut shared = pin!(pool.acquire());
+        let mut cancelled = pin!(pool.acquire());
+        assert!(poll!(own.as_mut()).is_pending());
+        assert!(poll!(stolen.as_mut()).is_pending());
+        assert!(poll!(shared.as_mut()).is_pending());
+        assert!(poll!(cancelled.as_mut()).is_pending());
+        assert_eq!(pool.metrics().waiting, 4);
+        cancelled.set(pool.acquire());
+        assert_eq!(pool.metrics().waiting, 3);
+
+        // A runtime serves its own queue first, then the shared queue.
+        drop(first);
+        let own = own.await.unwrap();
+        assert_eq!(own.index(), 0);
+        drop(second);
+        let shared = shared.await.unwrap();
+        assert_eq!(shared.index(), 1);
+
+        // With nothing else to do the runtime steals from the queue of the busy runtime.
+        drop(shared);
+        let stolen = stolen.await.unwrap();
+        assert_eq!(stolen.index(), 1);
+        drop((own, stolen));
+
+        let metrics = pool.metrics();
+        assert_eq!((metrics.idle, metrics.waiting), (2, 0));
+        assert_eq!(metrics.runtimes[0].steals, 0);
+        assert_eq!(metrics.runtimes[1].steals, 1);
+    }
+
+    #[tokio::test]
+    async fn deadlines() {
+        let pool = RuntimePool::builder()
+            .with_size(1)
+            .with_deadline(Duration::from_millis(10))
+            .build()
+            .await
+            .unwrap();
+        let lease = pool.acquire().await.unwrap();
+        assert!(interrupted(&lease).await);
+        drop(lease);
+        assert_eq!(pool.metrics().runtimes[0].deadlines_exceeded, 1);
+
+        let mut lease = pool.acquire().await.unwrap();
+        lease.set_deadline(Duration::from_millis(20)).await;
+        assert!(interrupted(&lease).await);
+        lease.clear_deadline().await;
+        let res = lease.with(|ctx| ctx.eval::<i32, _>("1 + 1").unwrap()).await;
+        assert_eq!(res, 2);
+        drop(lease);
+        assert_eq!(pool.metrics().runtimes[0].deadlines_exceeded, 1);
+    }
+}
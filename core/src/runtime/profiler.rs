@@ -0,0 +1,361 @@
+//! A sampling profiler for JavaScript code.
+
+use alloc::{
+    string::{String as StdString, ToString},
+    vec::Vec,
+};
+use core::{
+    cell::{Cell, RefCell},
+    ffi::{c_char, c_int, c_void, CStr},
+    fmt::{self, Write},
+    mem,
+};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use crate::{qjs, util};
+
+/// A single function in a sampled stack.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct ProfileFrame {
+    /// The name of the function, `<anonymous>` for functions without a name.
+    pub function: StdString,
+    /// The name of the script or module the function was defined in, `None` for native functions.
+    pub script: Option<StdString>,
+    /// The line which was executing when the sample was taken.
+    pub line: Option<u32>,
+    /// The column which was executing when the sample was taken.
+    pub column: Option<u32>,
+}
+
+impl ProfileFrame {
+    /// The name of the frame used in folded stacks.
+    fn label(&self) -> StdString {
+        match self.script {
+            Some(ref script) => alloc::format!("{} ({script})", self.function),
+            None => self.function.clone(),
+        }
+    }
+}
+
+/// A sampled stack.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ProfileSample {
+    /// The time the sample was taken relative to the start of profiling.
+    pub time: Duration,
+    /// The stack, the outermost function first and the executing function last.
+    pub stack: Vec<ProfileFrame>,
+}
+
+/// The result of profiling a runtime, returned by
+/// [`Runtime::stop_profiling`](crate::Runtime::stop_profiling).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub struct Profile {
+    /// The requested interval between samples.
+    pub interval: Duration,
+    /// The time between starting and stopping the profiler.
+    pub duration: Duration,
+    /// The samples taken while profiling.
+    pub samples: Vec<ProfileSample>,
+}
+
+impl Profile {
+    /// Write the profile as folded stacks, the input format of most flamegraph tools.
+    ///
+    /// Every line contains a unique stack with the functions separated by `;` followed by the
+    /// number of samples of that stack.
+    pub fn write_folded<W: Write>(&self, w: &mut W) -> fmt::Result {
+        let mut stacks = Vec::<(StdString, usize)>::new();
+        let mut indices = HashMap::<StdString, usize>::new();
+        for sample in &self.samples {
+            let stack = sample
+                .stack
+                .iter()
+                .map(ProfileFrame::label)
+                .collect::<Vec<_>>()
+                .join(";");
+            match indices.get(&stack) {
+                Some(idx) => stacks[*idx].1 += 1,
+                None => {
+                    indices.insert(stack.clone(), stacks.len());
+                    stacks.push((stack, 1));
+                }
+            }
+        }
+        for (stack, count) in stacks {
+            writeln!(w, "{stack} {count}")?;
+        }
+        Ok(())
+    }
+
+    /// Write the profile in the `.cpuprofile` format used by the Chrome DevTools.
+    pub fn write_chrome_cpuprofile<W: Write>(&self, w: &mut W) -> fmt::Result {
+        struct Node<'a> {
+            function: &'a str,
+            script: Option<&'a str>,
+            hits: usize,
+            lines: Vec<(u32, usize)>,
+            children: Vec<usize>,
+        }
+
+        let mut nodes = alloc::vec![Node {
+            function: "(root)",
+            script: None,
+            hits: 0,
+            lines: Vec::new(),
+            children: Vec::new(),
+        }];
+        let mut scripts = Vec::<&str>::new();
+        let mut sample_nodes = Vec::with_capacity(self.samples.len());
+        for sample in &self.samples {
+            let mut cur = 0;
+            for frame in &sample.stack {
+                let script = frame.script.as_deref();
+                if let Some(script) = script {
+                    if !scripts.contains(&script) {
+                        scripts.push(script);
+                    }
+                }
+                let child =
+                    nodes[cur].children.iter().copied().find(|x| {
+                        nodes[*x].function == frame.function && nodes[*x].script == script
+                    });
+                cur = match child {
+                    Some(x) => x,
+                    None => {
+                        nodes.push(Node {
+                            function: &frame.function,
+                            script,
+                            hits: 0,
+                            lines: Vec::new(),
+                            children: Vec::new(),
+                        });
+                        let idx = nodes.len() - 1;
+                        nodes[cur].children.push(idx);
+                        idx
+                    }
+                };
+            }
+            let node = &mut nodes[cur];
+            node.hits += 1;
+            if let Some(line) = sample.stack.last().and_then(|x| x.line) {
+                match node.lines.iter_mut().find(|x| x.0 == line) {
+                    Some(x) => x.1 += 1,
+                    None => node.lines.push((line, 1)),
+                }
+            }
+            sample_nodes.push(cur);
+        }
+
+        w.write_str(r#"{"nodes":["#)?;
+        for (idx, node) in nodes.iter().enumerate() {
+            if idx != 0 {
+                w.write_char(',')?;
+            }
+            let script_id = node
+                .script
+                .and_then(|x| scripts.iter().position(|s| *s == x))
+                .map_or(0, |x| x + 1);
+            write!(w, r#"{{"id":{},"callFrame":{{"functionName":"#, idx + 1)?;
+            util::write_json_string(w, node.function)?;
+            write!(w, r#","scriptId":"{script_id}","url":"#)?;
+            util::write_json_string(w, node.script.unwrap_or(""))?;
+            write!(
+                w,
+                r#","lineNumber":-1,"columnNumber":-1}},"hitCount":{},"children":["#,
+                node.hits
+            )?;
+            for (idx, child) in node.children.iter().enumerate() {
+                if idx != 0 {
+                    w.write_char(',')?;
+                }
+                write!(w, "{}", child + 1)?;
+            }
+            w.write_str(r#"],"positionTicks":["#)?;
+            for (idx, (line, ticks)) in node.lines.iter().enumerate() {
+                if idx != 0 {
+                    w.write_char(',')?;
+                }
+                write!(w, r#"{{"line":{line},"ticks":{ticks}}}"#)?;
+            }
+            w.write_str("]}")?;
+        }
+
+        write!(
+            w,
+            r#"],"startTime":0,"endTime":{},"samples":["#,
+            self.duration.as_micros()
+        )?;
+        for (idx, node) in sample_nodes.iter().enumerate() {
+            if idx != 0 {
+                w.write_char(',')?;
+            }
+            write!(w, "{}", node + 1)?;
+        }
+        w.write_str(r#"],"timeDeltas":["#)?;
+        let mut last = 0;
+        for (idx, sample) in self.samples.iter().enumerate() {
+            if idx != 0 {
+                w.write_char(',')?;
+            }
+            let time = sample.time.as_micros();
+            write!(w, "{}", time - last)?;
+            last = time;
+        }
+        w.write_str("]}")
+    }
+}
+
+/// The state of a running profiler.
+pub(crate) struct Profiler {
+    interval: Duration,
+    start: Instant,
+    next: Cell<Instant>,
+    samples: RefCell<Vec<ProfileSample>>,
+}
+
+impl Profiler {
+    pub fn new(interval: Duration) -> Self {
+        let start = Instant::now();
+        Profiler {
+            interval,
+            start,
+            next: Cell::new(start),
+            samples: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Called every time the interrupt handler is polled, takes a sample if the interval has
+    /// passed since the last sample.
+    pub unsafe fn tick(&self, rt: *mut qjs::JSRuntime) {
+        let now = Instant::now();
+        if now < self.next.get() {
+            return;
+        }
+        self.next.set(now + self.interval);
+
+        let stack = capture_stack(rt);
+        if !stack.is_empty() {
+            self.samples.borrow_mut().push(ProfileSample {
+                time: now - self.start,
+                stack,
+            });
+        }
+    }
+
+    pub fn finish(self) -> Profile {
+        Profile {
+            interval: self.interval,
+            duration: self.start.elapsed(),
+            samples: mem::take(&mut *self.samples.borrow_mut()),
+        }
+    }
+}
+
+/// Capture the stack of the currently executing code, outermost frame first.
+///
+/// The stack is read directly from the engine, no JavaScript is run and the depth of the stack is
+/// not limited by `Error.stackTraceLimit`.
+unsafe fn capture_stack(rt: *mut qjs::JSRuntime) -> Vec<ProfileFrame> {
+    unsafe extern "C" fn push(
+        opaque: *mut c_void,
+        function: *const c_char,
+        file: *const c_char,
+        line: c_int,
+        column: c_int,
+    ) {
+        let frames = &mut *opaque.cast::<Vec<ProfileFrame>>();
+        let string = |ptr: *const c_char| {
+            (!ptr.is_null()).then(|| CStr::from_ptr(ptr).to_string_lossy().into_owned())
+        };
+        frames.push(ProfileFrame {
+            function: string(function)
+                .filter(|x| !x.is_empty())
+                .unwrap_or_else(|| "<anonymous>".to_string()),
+            script: string(file),
+            line: u32::try_from(line).ok(),
+            column: u32::try_from(column).ok(),
+        });
+    }
+
+    let mut frames = Vec::new();
+    qjs::rquickjs_get_stack(rt, Some(push), (&mut frames as *mut Vec<_>).cast());
+    frames.reverse();
+    frames
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::{Context, Runtime};
+
+    #[test]
+    fn profile() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        rt.start_profiling(Duration::from_micros(10));
+        ctx.with(|ctx| {
+            ctx.eval::<(), _>(
+                r#"
+                // Sampling does not depend on either of them.
+                Error.stackTraceLimit = 1;
+                Error.prepareStackTrace = () => { throw new Error("must not be called") };
+                function fib(n) { return n < 2 ? n : fib(n - 1) + fib(n - 2) }
+                function work() { return fib(22) }
+                work();
+            "#,
+            )
+            .unwrap();
+        });
+        let profile = rt.stop_profiling();
+        assert!(!profile.samples.is_empty());
+        assert!(profile.duration > Duration::ZERO);
+
+        let sample = &profile.samples[0];
+        let functions = sample
+            .stack
+            .iter()
+            .map(|x| x.function.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(&functions[..2], ["<eval>", "work"]);
+        assert!(functions[2..].iter().all(|x| *x == "fib"));
+        assert_eq!(sample.stack[1].script.as_deref(), Some("eval_script"));
+        assert_eq!(sample.stack[1].line, Some(6));
+        assert!(profile.samples.iter().any(|x| x.stack.len() > 10));
+
+        let mut folded = String::new();
+        profile.write_folded(&mut folded).unwrap();
+        assert!(folded.starts_with("<eval> (eval_script);work (eval_script)"));
+        let count: usize = folded
+            .lines()
+            .map(|x| x.rsplit(' ').next().unwrap().parse::<usize>().unwrap())
+            .sum();
+        assert_eq!(count, profile.samples.len());
+
+        let mut json = String::new();
+        profile.write_chrome_cpuprofile(&mut json).unwrap();
+        ctx.with(|ctx| {
+            let value = ctx.json_parse(json).unwrap();
+            let obj = value.as_object().unwrap();
+            let samples: Vec<usize> = obj.get("samples").unwrap();
+            let deltas: Vec<u64> = obj.get("timeDeltas").unwrap();
+            assert_eq!(samples.len(), profile.samples.len());
+            assert_eq!(deltas.len(), profile.samples.len());
+            let nodes: Vec<crate::Object> = obj.get("nodes").unwrap();
+            let hits: usize = nodes
+                .iter()
+                .map(|x| x.get::<_, usize>("hitCount").unwrap())
+                .sum();
+            assert_eq!(hits, profile.samples.len());
+        });
+
+        // Profiling is stopped, a new profile is empty.
+        assert!(rt.stop_profiling().samples.is_empty());
+    }
+}
@@ -1,6 +1,11 @@
 #![allow(dead_code, unused_imports)]
-use alloc::{boxed::Box, ffi::CString};
-use core::{mem, panic::AssertUnwindSafe, ptr::NonNull, result::Result as StdResult};
+use alloc::{boxed::Box, ffi::CString, vec, vec::Vec};
+use core::{
+    mem,
+    panic::AssertUnwindSafe,
+    ptr::{self, NonNull},
+    result::Result as StdResult,
+};
 
 use rquickjs_sys::JSPromiseHookType;
 
@@ -12,7 +17,12 @@ use crate::{
     Ctx, Error, Result, Value,
 };
 
-use super::{opaque::Opaque, InterruptHandler, PromiseHook, PromiseHookType, RejectionTracker};
+#[cfg(feature = "std")]
+use super::Profile;
+use super::{
+    opaque::Opaque, HeapSnapshot, InterruptHandler, PromiseHook, PromiseHookType, RejectionTracker,
+    StackFormatter,
+};
 
 const DUMP_BYTECODE_FINAL: u64 = 0x01;
 const DUMP_BYTECODE_PASS2: u64 = 0x02;
@@ -126,6 +136,7 @@ impl Drop for RawRuntime {
             let ptr = qjs::JS_GetRuntimeOpaque(self.rt.as_ptr());
             let mut opaque: Box<Opaque> = Box::from_raw(ptr as *mut _);
             opaque.clear();
+            qjs::JS_FreeAtomRT(self.rt.as_ptr(), opaque.stack_atom());
             qjs::JS_FreeRuntime(self.rt.as_ptr());
             mem::drop(opaque);
         }
@@ -171,13 +182,14 @@ impl RawRuntime {
         let functions = AllocatorHolder::functions::<A>();
         let opaque_ptr = allocator.opaque_ptr();
 
-        let rt = qjs::JS_NewRuntime2(&functions, opaque_ptr as _);
+        let rt = qjs::JS_NewRuntime2(&functions, opaque_ptr);
 
         Self::add_dump_flags(rt);
 
         let rt = NonNull::new(rt).ok_or(Error::Allocation)?;
 
         opaque.initialize(rt.as_ptr())?;
+        opaque.set_accounting(allocator.accounting());
 
         let opaque = Box::into_raw(Box::new(opaque));
         unsafe { qjs::JS_SetRuntimeOpaque(rt.as_ptr(), opaque as *mut _) };
@@ -207,8 +219,7 @@ impl RawRuntime {
     }
 
     pub fn execute_pending_job(&mut self) -> StdResult<bool, *mut qjs::JSContext> {
-        let mut ctx_ptr = mem::MaybeUninit::<*mut qjs::JSContext>::uninit();
-        let result = unsafe { qjs::JS_ExecutePendingJob(self.rt.as_ptr(), ctx_ptr.as_mut_ptr()) };
+        let (result, ctx) = unsafe { self.get_opaque().execute_pending_job(self.rt.as_ptr()) };
         if result == 0 {
             // no jobs executed
             return Ok(false);
@@ -217,7 +228,7 @@ impl RawRuntime {
             // single job executed
             return Ok(true);
         }
-        Err(unsafe { ctx_ptr.assume_init() })
+        Err(ctx)
     }
 
     #[cfg(feature = "loader")]
@@ -276,6 +287,41 @@ impl RawRuntime {
         qjs::JS_RunGC(self.rt.as_ptr());
     }
 
+    /// Create a snapshot of all objects reachable from the globals of the contexts of the runtime.
+    pub unsafe fn heap_snapshot(&mut self) -> Result<HeapSnapshot> {
+        let rt = self.rt.as_ptr();
+        let count = qjs::rquickjs_get_contexts(rt, ptr::null_mut(), 0);
+        let mut contexts = vec![ptr::null_mut(); count as usize];
+        qjs::rquickjs_get_contexts(rt, contexts.as_mut_ptr(), count);
+
+        if let Some(first) = contexts.first() {
+            let ctx = Ctx::from_ptr(*first);
+            let roots = contexts
+                .iter()
+                .map(|x| {
+                    let globals = Value::from_js_value(ctx.clone(), qjs::JS_GetGlobalObject(*x));
+                    ("globalThis", globals)
+                })
+                .collect::<Vec<_>>();
+            return HeapSnapshot::capture(&ctx, roots);
+        }
+
+        // Persistent values can outlive all contexts, walk them with a temporary context.
+        let ctx = self.get_opaque().new_context(|| {
+            let Some(ctx) = NonNull::new(qjs::JS_NewContextRaw(rt)) else {
+                return ptr::null_mut();
+            };
+            qjs::JS_AddIntrinsicBaseObjects(ctx.as_ptr());
+            ctx.as_ptr()
+        });
+        if ctx.is_null() {
+            return Err(Error::Allocation);
+        }
+        let res = HeapSnapshot::capture(&Ctx::from_ptr(ctx), Vec::<(&str, Value)>::new());
+        qjs::JS_FreeContext(ctx);
+        res
+    }
+
     /// Get memory usage stats
     pub unsafe fn memory_usage(&mut self) -> qjs::JSMemoryUsage {
         let mut stats = mem::MaybeUninit::uninit();
@@ -377,42 +423,46 @@ impl RawRuntime {
         self.get_opaque().set_rejection_tracker(tracker);
     }
 
+    /// Set a closure which formats the stack returned by [`Exception::stack`](crate::Exception::stack).
+    pub unsafe fn set_stack_formatter(&mut self, formatter: Option<StackFormatter>) {
+        self.get_opaque().set_stack_formatter(formatter);
+    }
+
     /// Set a closure which is regularly called by the engine when it is executing code.
     /// If the provided closure returns `true` the interpreter will raise and uncatchable
     /// exception and return control flow to the caller.
     pub unsafe fn set_interrupt_handler(&mut self, handler: Option<InterruptHandler>) {
-        unsafe extern "C" fn interrupt_handler_trampoline(
-            _rt: *mut qjs::JSRuntime,
-            opaque: *mut ::core::ffi::c_void,
-        ) -> ::core::ffi::c_int {
-            // This should be safe as the value is set below to a non-null pointer.
-            let opaque = NonNull::new_unchecked(opaque).cast::<Opaque>();
+        self.get_opaque().set_interrupt_handler(handler);
+        update_interrupt_handler(self.rt.as_ptr());
+    }
 
-            let should_interrupt = {
-                let catch_unwind = crate::util::catch_unwind(AssertUnwindSafe(move || {
-                    opaque.as_ref().run_interrupt_handler()
-                }));
-                match catch_unwind {
-                    Ok(should_interrupt) => should_interrupt,
-                    Err(panic) => {
-                        opaque.as_ref().set_panic(panic);
-                        // Returning true here will cause the interpreter to raise an un-catchable exception.
-                        // The Rust code that is running the interpreter will see that exception and continue
-                        // the panic handling. See crate::result::{handle_exception, handle_panic} for details.
-                        true
-                    }
-                }
-            };
+    /// Set the point in time after which executing code is interrupted.
+    #[cfg(feature = "std")]
+    pub unsafe fn set_execution_deadline(&mut self, deadline: Option<std::time::Instant>) {
+        self.get_opaque().limits().set_deadline(deadline);
+        update_interrupt_handler(self.rt.as_ptr());
+    }
 
-            should_interrupt as _
-        }
+    /// Set the number of times the interrupt handler can be polled before executing code is
+    /// interrupted.
+    pub unsafe fn set_interrupt_budget(&mut self, budget: Option<u64>) {
+        self.get_opaque().limits().set_budget(budget);
+        update_interrupt_handler(self.rt.as_ptr());
+    }
 
-        qjs::JS_SetInterruptHandler(
-            self.rt.as_ptr(),
-            handler.as_ref().map(|_| interrupt_handler_trampoline as _),
-            qjs::JS_GetRuntimeOpaque(self.rt.as_ptr()),
-        );
-        self.get_opaque().set_interrupt_handler(handler);
+    /// Start sampling the stack of executing code every `interval`.
+    #[cfg(feature = "std")]
+    pub unsafe fn start_profiling(&mut self, interval: std::time::Duration) {
+        self.get_opaque().start_profiling(interval);
+        update_interrupt_handler(self.rt.as_ptr());
+    }
+
+    /// Stop the profiler and return the collected samples.
+    #[cfg(feature = "std")]
+    pub unsafe fn stop_profiling(&mut self) -> Profile {
+        let profile = self.get_opaque().stop_profiling();
+        update_interrupt_handler(self.rt.as_ptr());
+        profile
     }
 
     fn add_dump_flags(rt: *mut rquickjs_sys::JSRuntime) {
@@ -422,6 +472,44 @@ impl RawRuntime {
     }
 }
 
+unsafe extern "C" fn interrupt_handler_trampoline(
+    rt: *mut qjs::JSRuntime,
+    opaque: *mut ::core::ffi::c_void,
+) -> ::core::ffi::c_int {
+    // This should be safe as the value is set below to a non-null pointer.
+    let opaque = NonNull::new_unchecked(opaque).cast::<Opaque>();
+
+    let should_interrupt = {
+        let catch_unwind = crate::util::catch_unwind(AssertUnwindSafe(move || {
+            opaque.as_ref().run_interrupt_handler(rt)
+        }));
+        match catch_unwind {
+            Ok(should_interrupt) => should_interrupt,
+            Err(panic) => {
+                opaque.as_ref().set_panic(panic);
+                // Returning true here will cause the interpreter to raise an un-catchable exception.
+                // The Rust code that is running the interpreter will see that exception and continue
+                // the panic handling. See crate::result::{handle_exception, handle_panic} for details.
+                true
+            }
+        }
+    };
+
+    should_interrupt as _
+}
+
+/// Install the interrupt handler if either a handler or an execution limit is set, remove it
+/// otherwise.
+pub(crate) unsafe fn update_interrupt_handler(rt: *mut qjs::JSRuntime) {
+    let opaque = qjs::JS_GetRuntimeOpaque(rt);
+    let needed = Opaque::from_runtime_ptr(rt).needs_interrupt_handler();
+    qjs::JS_SetInterruptHandler(
+        rt,
+        needed.then_some(interrupt_handler_trampoline as _),
+        opaque,
+    );
+}
+
 #[cfg(test)]
 mod test {
     use std::sync::{Arc, Mutex};
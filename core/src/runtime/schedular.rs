@@ -8,7 +8,7 @@ use core::{
     task::{Context, Poll},
 };
 
-mod atomic_waker;
+pub(crate) mod atomic_waker;
 mod queue;
 mod task;
 mod vtable;
@@ -1,8 +1,9 @@
 use super::{
+    opaque::ContextGuard,
     schedular::{Schedular, SchedularPoll},
     AsyncWeakRuntime, InnerRuntime,
 };
-use crate::AsyncRuntime;
+use crate::{qjs, AsyncRuntime, Ctx};
 use alloc::vec::Vec;
 use core::{
     future::Future,
@@ -47,6 +48,31 @@ impl Spawner {
     }
 }
 
+/// A spawned future which runs in the context which spawned it.
+pub(crate) struct InContext<'js, F> {
+    ctx: Ctx<'js>,
+    future: F,
+}
+
+impl<'js, F> InContext<'js, F> {
+    pub fn new(ctx: Ctx<'js>, future: F) -> Self {
+        InContext { ctx, future }
+    }
+}
+
+impl<F: Future<Output = ()>> Future for InContext<'_, F> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        // Safety: The future is never moved out of the struct.
+        let this = unsafe { self.get_unchecked_mut() };
+        // Safety: Spawned futures are only polled while the runtime is locked.
+        let _entered =
+            unsafe { ContextGuard::new(qjs::JS_GetRuntime(this.ctx.as_ptr()), this.ctx.as_ptr()) };
+        unsafe { Pin::new_unchecked(&mut this.future) }.poll(cx)
+    }
+}
+
 enum DriveFutureState {
     Initial,
     Lock {
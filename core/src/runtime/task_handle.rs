@@ -0,0 +1,77 @@
+use alloc::sync::Arc;
+use core::{
+    future::Future,
+    pin::Pin,
+    sync::atomic::{AtomicBool, Ordering},
+    task::{Context, Poll},
+};
+
+use super::schedular::atomic_waker::AtomicWaker;
+
+struct TaskState {
+    aborted: AtomicBool,
+    finished: AtomicBool,
+    waker: AtomicWaker,
+}
+
+/// A handle to a future spawned with [`Ctx::spawn`](crate::Ctx::spawn).
+///
+/// Dropping the handle does not cancel the future, it will continue to run to completion.
+#[derive(Clone)]
+pub struct TaskHandle(Arc<TaskState>);
+
+impl TaskHandle {
+    /// Abort the future.
+    ///
+    /// The future is dropped the next time the runtime polls spawned futures, without being
+    /// polled again. Aborting a future which already finished does nothing.
+    pub fn abort(&self) {
+        if !self.0.aborted.swap(true, Ordering::AcqRel) {
+            self.0.waker.wake();
+        }
+    }
+
+    /// Returns whether the future completed, was aborted and dropped or was dropped with the
+    /// runtime.
+    pub fn is_finished(&self) -> bool {
+        self.0.finished.load(Ordering::Acquire)
+    }
+}
+
+/// A spawned future which can be aborted through a [`TaskHandle`].
+pub(crate) struct Abortable<F> {
+    future: F,
+    state: Arc<TaskState>,
+}
+
+impl<F> Abortable<F> {
+    pub fn new(future: F) -> (Self, TaskHandle) {
+        let state = Arc::new(TaskState {
+            aborted: AtomicBool::new(false),
+            finished: AtomicBool::new(false),
+            waker: AtomicWaker::new(),
+        });
+        let handle = TaskHandle(state.clone());
+        (Abortable { future, state }, handle)
+    }
+}
+
+impl<F: Future<Output = ()>> Future for Abortable<F> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        // Safety: The future is never moved out of the struct.
+        let this = unsafe { self.get_unchecked_mut() };
+        this.state.waker.register(cx.waker());
+        if this.state.aborted.load(Ordering::Acquire) {
+            return Poll::Ready(());
+        }
+        unsafe { Pin::new_unchecked(&mut this.future) }.poll(cx)
+    }
+}
+
+impl<F> Drop for Abortable<F> {
+    fn drop(&mut self) {
+        self.state.finished.store(true, Ordering::Release);
+    }
+}
@@ -0,0 +1,417 @@
+//! Timer functions for contexts of an [`AsyncRuntime`](crate::AsyncRuntime).
+//!
+//! [`install`] adds `setTimeout`, `setInterval`, `clearTimeout`, `clearInterval` and
+//! `queueMicrotask` to the global object of a context. Timers are futures spawned on the
+//! runtime, so [`AsyncRuntime::idle`](crate::AsyncRuntime::idle) only returns once all timers
+//! have fired or were cleared.
+//!
+//! Time is measured by a [`Clock`], which makes the timers independent of any particular async
+//! executor. [`SystemClock`] uses the system time and [`VirtualClock`] only advances when told
+//! to, which makes tests deterministic. [`SystemClock`] needs a background thread, so it is not
+//! available on WASI.
+//!
+//! ```
+//! # use rquickjs::{AsyncRuntime, AsyncContext, timers::{self, VirtualClock}};
+//! # use std::time::Duration;
+//! # async fn run() {
+//! let rt = AsyncRuntime::new().unwrap();
+//! let ctx = AsyncContext::full(&rt).await.unwrap();
+//! let clock = VirtualClock::new();
+//!
+//! let timers_clock = clock.clone();
+//! ctx.with(|ctx| {
+//!     timers::install(&ctx, timers_clock).unwrap();
+//!     ctx.eval::<(), _>("globalThis.fired = false; setTimeout(() => fired = true, 100)")
+//!         .unwrap();
+//! })
+//! .await;
+//!
+//! clock.advance(Duration::from_millis(100));
+//! rt.idle().await;
+//! assert!(ctx.with(|ctx| ctx.globals().get::<_, bool>("fired").unwrap()).await);
+//! # }
+//! ```
+
+use alloc::{boxed::Box, rc::Rc, sync::Arc, vec::Vec};
+use core::{
+    cell::{Cell, RefCell},
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+use std::{collections::HashMap, sync::Mutex};
+
+use crate::{
+    convert::Coerced,
+    function::{Opt, Rest},
+    Ctx, Exception, Function, Result, Value,
+};
+
+#[cfg(not(target_os = "wasi"))]
+mod system;
+
+#[cfg(not(target_os = "wasi"))]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(not(target_os = "wasi"))))]
+pub use system::SystemClock;
+
+/// A future which completes at a deadline of a [`Clock`].
+pub type Sleep = Pin<Box<dyn Future<Output = ()> + 'static>>;
+
+/// A source of time for timers.
+pub trait Clock {
+    /// Returns the time elapsed since an arbitrary fixed point, which must never decrease.
+    fn now(&self) -> Duration;
+
+    /// Returns a future which completes once [`now`](Self::now) has reached the deadline.
+    fn sleep_until(&self, deadline: Duration) -> Sleep;
+}
+
+/// Install the timer functions into the global object of the context.
+///
+/// Callbacks are called as jobs, like promise reactions, so an exception thrown by a callback is
+/// reported like an exception thrown by a job. The context must belong to an
+/// [`AsyncRuntime`](crate::AsyncRuntime).
+pub fn install<'js, C: Clock + 'static>(ctx: &Ctx<'js>, clock: C) -> Result<()> {
+    let timers = Rc::new(Timers {
+        clock: Box::new(clock),
+        next_id: Cell::new(1),
+        active: RefCell::new(HashMap::new()),
+    });
+    let globals = ctx.globals();
+
+    let set = |repeat: bool| {
+        let timers = timers.clone();
+        move |ctx: Ctx<'js>,
+              callback: Value<'js>,
+              delay: Opt<Value<'js>>,
+              args: Rest<Value<'js>>| {
+            let Some(callback) = callback.into_function() else {
+                return Err(Exception::throw_type(&ctx, "callback is not a function"));
+            };
+            Ok(timers.spawn(&ctx, callback, to_delay(delay.0), args, repeat))
+        }
+    };
+    globals.set("setTimeout", Function::new(ctx.clone(), set(false))?)?;
+    globals.set("setInterval", Function::new(ctx.clone(), set(true))?)?;
+
+    let clear = || {
+        let timers = timers.clone();
+        move |id: Opt<Coerced<f64>>| {
+            if let Some(Coerced(id)) = id.0 {
+                timers.clear(id);
+            }
+        }
+    };
+    globals.set("clearTimeout", Function::new(ctx.clone(), clear())?)?;
+    globals.set("clearInterval", Function::new(ctx.clone(), clear())?)?;
+
+    globals.set(
+        "queueMicrotask",
+        Function::new(ctx.clone(), |ctx: Ctx<'js>, callback: Value<'js>| {
+            let Some(callback) = callback.into_function() else {
+                return Err(Exception::throw_type(&ctx, "callback is not a function"));
+            };
+            callback.defer(())
+        })?,
+    )?;
+    Ok(())
+}
+
+/// Convert the delay argument like browsers do: missing, negative, non-numeric or out of range
+/// delays become zero and fractions are truncated.
+fn to_delay(delay: Option<Value<'_>>) -> Duration {
+    let millis = delay
+        .and_then(|x| x.get::<Coerced<f64>>().ok())
+        .map(|x| x.0)
+        .filter(|x| *x >= 0.0 && *x <= i32::MAX as f64)
+        .unwrap_or(0.0);
+    Duration::from_millis(millis as u64)
+}
+
+struct Timers {
+    clock: Box<dyn Clock>,
+    next_id: Cell<u32>,
+    active: RefCell<HashMap<u32, Rc<TimerState>>>,
+}
+
+#[derive(Default)]
+struct TimerState {
+    cancelled: Cell<bool>,
+    waker: Cell<Option<Waker>>,
+}
+
+impl Timers {
+    fn spawn<'js>(
+        self: &Rc<Self>,
+        ctx: &Ctx<'js>,
+        callback: Function<'js>,
+        delay: Duration,
+        args: Rest<Value<'js>>,
+        repeat: bool,
+    ) -> u32 {
+        let id = self.next_id.get();
+        self.next_id.set(id.checked_add(1).unwrap_or(1));
+        let state = Rc::new(TimerState::default());
+        self.active.borrow_mut().insert(id, state.clone());
+
+        let timers = self.clone();
+        let mut deadline = self.clock.now() + delay;
+        ctx.spawn(async move {
+            loop {
+                let fired = Cancellable {
+                    sleep: timers.clock.sleep_until(deadline),
+                    state: &state,
+                }
+                .await;
+                if !fired {
+                    break;
+                }
+                if !repeat {
+                    timers.active.borrow_mut().remove(&id);
+                }
+                if callback.defer((Rest(args.0.clone()),)).is_err() || !repeat {
+                    break;
+                }
+                // Let the callback run before sleeping again, a zero delay would otherwise never
+                // yield.
+                YieldNow(false).await;
+                deadline = timers.clock.now() + delay;
+            }
+            let mut active = timers.active.borrow_mut();
+            if active.get(&id).is_some_and(|x| Rc::ptr_eq(x, &state)) {
+                active.remove(&id);
+            }
+        });
+        id
+    }
+
+    fn clear(&self, id: f64) {
+        if id.fract() != 0.0 || !(1.0..=u32::MAX as f64).contains(&id) {
+            return;
+        }
+        if let Some(state) = self.active.borrow_mut().remove(&(id as u32)) {
+            state.cancelled.set(true);
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// Waits for a sleep unless the timer is cancelled, returns whether the sleep completed.
+struct Cancellable<'a> {
+    sleep: Sleep,
+    state: &'a TimerState,
+}
+
+impl Future for Cancellable<'_> {
+    type Output = bool;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<bool> {
+        if self.state.cancelled.get() {
+            return Poll::Ready(false);
+        }
+        self.state.waker.set(Some(cx.waker().clone()));
+        self.sleep.as_mut().poll(cx).map(|_| true)
+    }
+}
+
+struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0 {
+            return Poll::Ready(());
+        }
+        self.0 = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+/// A clock which only advances when [`advance`](VirtualClock::advance) is called.
+///
+/// Clones share the same time.
+#[derive(Debug, Clone, Default)]
+pub struct VirtualClock(Arc<Mutex<VirtualState>>);
+
+#[derive(Debug, Default)]
+struct VirtualState {
+    now: Duration,
+    sleepers: Vec<(Duration, Arc<Mutex<Option<Waker>>>)>,
+}
+
+impl VirtualClock {
+    /// Create a clock which starts at zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Advance the time, waking all sleeps whose deadline has been reached in deadline order.
+    pub fn advance(&self, duration: Duration) {
+        let due = {
+            let mut state = self.0.lock().unwrap();
+            state.now += duration;
+            let now = state.now;
+            let (mut due, pending): (Vec<_>, Vec<_>) =
+                state.sleepers.drain(..).partition(|x| x.0 <= now);
+            state.sleepers = pending;
+            due.sort_by_key(|x| x.0);
+            due
+        };
+        for (_, waker) in due {
+            if let Some(waker) = waker.lock().unwrap().take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+impl Clock for VirtualClock {
+    fn now(&self) -> Duration {
+        self.0.lock().unwrap().now
+    }
+
+    fn sleep_until(&self, deadline: Duration) -> Sleep {
+        let waker = Arc::new(Mutex::new(None));
+        self.0
+            .lock()
+            .unwrap()
+            .sleepers
+            .push((deadline, waker.clone()));
+        Box::pin(VirtualSleep {
+            clock: self.clone(),
+            deadline,
+            waker,
+        })
+    }
+}
+
+struct VirtualSleep {
+    clock: VirtualClock,
+    deadline: Duration,
+    waker: Arc<Mutex<Option<Waker>>>,
+}
+
+impl Future for VirtualSleep {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.clock.now() >= self.deadline {
+            return Poll::Ready(());
+        }
+        *self.waker.lock().unwrap() = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl Drop for VirtualSleep {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.clock.0.lock() {
+            state.sleepers.retain(|x| !Arc::ptr_eq(&x.1, &self.waker));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use super::{install, SystemClock, VirtualClock};
+    use crate::{Array, AsyncContext, AsyncRuntime};
+
+    async fn log(ctx: &AsyncContext) -> Vec<i32> {
+        ctx.with(|ctx| {
+            ctx.globals()
+                .get::<_, Array>("log")
+                .unwrap()
+                .iter()
+                .collect::<crate::Result<_>>()
+                .unwrap()
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn virtual_clock() {
+        let rt = AsyncRuntime::new().unwrap();
+        let ctx = AsyncContext::full(&rt).await.unwrap();
+        let clock = VirtualClock::new();
+        let install_clock = clock.clone();
+        ctx.with(|ctx| {
+            install(&ctx, install_clock).unwrap();
+            ctx.eval::<(), _>(
+                r#"
+                globalThis.log = [];
+                setTimeout((x) => log.push(x), 20, 2);
+                setTimeout((x) => log.push(x), 10, 1);
+                const cancelled = setTimeout(() => log.push(-1), 5);
+                clearTimeout(cancelled);
+                clearTimeout(undefined);
+                let count = 0;
+                const interval = setInterval(() => {
+                    log.push(100 + count);
+                    if (++count == 3) clearInterval(interval);
+                }, 7);
+                queueMicrotask(() => log.push(0));
+                "#,
+            )
+            .unwrap();
+        })
+        .await;
+
+        // `idle` would wait for the timers, so only run what is ready.
+        while rt.execute_pending_job().await.unwrap() {}
+        assert_eq!(log(&ctx).await, [0]);
+        // The sleep of the cleared timer is no longer registered.
+        assert_eq!(clock.0.lock().unwrap().sleepers.len(), 3);
+        for _ in 0..25 {
+            clock.advance(Duration::from_millis(1));
+            while rt.execute_pending_job().await.unwrap() {}
+        }
+        assert_eq!(log(&ctx).await, [0, 100, 1, 101, 2, 102]);
+        rt.idle().await;
+        assert!(clock.0.lock().unwrap().sleepers.is_empty());
+    }
+
+    #[tokio::test]
+    async fn errors() {
+        let rt = AsyncRuntime::new().unwrap();
+        let ctx = AsyncContext::full(&rt).await.unwrap();
+        ctx.with(|ctx| {
+            install(&ctx, VirtualClock::new()).unwrap();
+            let res: String = ctx
+                .eval("try { setTimeout('code', 0) } catch (e) { e.name }")
+                .unwrap();
+            assert_eq!(res, "TypeError");
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn idle_waits_for_timers() {
+        let rt = AsyncRuntime::new().unwrap();
+        let ctx = AsyncContext::full(&rt).await.unwrap();
+        ctx.with(|ctx| {
+            install(&ctx, SystemClock::new()).unwrap();
+            ctx.eval::<(), _>(
+                r#"
+                globalThis.log = [];
+                setTimeout(() => log.push(2), 30);
+                setTimeout(() => setTimeout(() => log.push(3), 10), 30);
+                setTimeout(() => log.push(1), 10);
+                "#,
+            )
+            .unwrap();
+        })
+        .await;
+
+        let start = Instant::now();
+        rt.idle().await;
+        assert!(start.elapsed() >= Duration::from_millis(40));
+        assert_eq!(log(&ctx).await, [1, 2, 3]);
+    }
+}
@@ -0,0 +1,204 @@
+//! A clock backed by the system time.
+
+use alloc::{boxed::Box, collections::BTreeMap};
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+use std::{
+    sync::{Condvar, Mutex, OnceLock},
+    time::Instant,
+};
+
+use super::{Clock, Sleep};
+
+/// A clock which measures time with [`Instant`].
+///
+/// Sleeps are woken by a single background thread shared by all system clocks.
+#[derive(Debug, Clone, Copy)]
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl SystemClock {
+    /// Create a clock which starts at zero now.
+    pub fn new() -> Self {
+        SystemClock {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    fn sleep_until(&self, deadline: Duration) -> Sleep {
+        Box::pin(SystemSleep {
+            deadline: self.start + deadline,
+            id: None,
+        })
+    }
+}
+
+struct SystemSleep {
+    deadline: Instant,
+    /// The id of the registration with the timer thread, once polled.
+    id: Option<u64>,
+}
+
+impl Future for SystemSleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if Instant::now() >= self.deadline {
+            return Poll::Ready(());
+        }
+        let deadline = self.deadline;
+        let thread = TimerThread::get();
+        match self.id {
+            Some(id) => thread.update(deadline, id, cx.waker()),
+            None => self.id = Some(thread.register(deadline, cx.waker().clone())),
+        }
+        Poll::Pending
+    }
+}
+
+impl Drop for SystemSleep {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            TimerThread::get().remove(self.deadline, id);
+        }
+    }
+}
+
+#[derive(Default)]
+struct TimerState {
+    next_id: u64,
+    /// The wakers of pending sleeps, ordered by deadline.
+    wakers: BTreeMap<(Instant, u64), Waker>,
+}
+
+struct TimerThread {
+    state: Mutex<TimerState>,
+    changed: Condvar,
+}
+
+impl TimerThread {
+    fn get() -> &'static TimerThread {
+        static THREAD: OnceLock<TimerThread> = OnceLock::new();
+        let mut spawn = false;
+        let thread = THREAD.get_or_init(|| {
+            spawn = true;
+            TimerThread {
+                state: Mutex::new(TimerState::default()),
+                changed: Condvar::new(),
+            }
+        });
+        if spawn {
+            std::thread::Builder::new()
+                .name("rquickjs-timers".into())
+                .spawn(move || thread.run())
+                .expect("failed to spawn the timer thread");
+        }
+        thread
+    }
+
+    fn register(&self, deadline: Instant, waker: Waker) -> u64 {
+        let mut state = self.state.lock().unwrap();
+        let id = state.next_id;
+        state.next_id += 1;
+        state.wakers.insert((deadline, id), waker);
+        self.changed.notify_one();
+        id
+    }
+
+    fn update(&self, deadline: Instant, id: u64, waker: &Waker) {
+        let mut state = self.state.lock().unwrap();
+        match state.wakers.get_mut(&(deadline, id)) {
+            Some(x) if x.will_wake(waker) => {}
+            Some(x) => *x = waker.clone(),
+            // Already woken, but polled again before the deadline.
+            None => {
+                state.wakers.insert((deadline, id), waker.clone());
+                self.changed.notify_one();
+            }
+        }
+    }
+
+    fn remove(&self, deadline: Instant, id: u64) {
+        if let Ok(mut state) = self.state.lock() {
+            state.wakers.remove(&(deadline, id));
+        }
+    }
+
+    fn run(&self) {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            let now = Instant::now();
+            while let Some(entry) = state.wakers.first_entry() {
+                if entry.key().0 > now {
+                    break;
+                }
+                entry.remove().wake();
+            }
+            state = match state.wakers.keys().next() {
+                Some(&(deadline, _)) => {
+                    let timeout = deadline - now;
+                    self.changed.wait_timeout(state, timeout).unwrap().0
+                }
+                None => self.changed.wait(state).unwrap(),
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::{sync::Arc, task::Wake};
+    use core::{
+        task::{Context, Poll, Waker},
+        time::Duration,
+    };
+
+    use super::{SystemClock, TimerThread};
+    use crate::timers::Clock;
+
+    struct Noop;
+
+    impl Wake for Noop {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    #[test]
+    fn register_once() {
+        let waker = Waker::from(Arc::new(Noop));
+        let mut cx = Context::from_waker(&waker);
+        let clock = SystemClock::new();
+        let mut sleep = clock.sleep_until(Duration::from_secs(3600));
+        let registered = || {
+            let state = TimerThread::get().state.lock().unwrap();
+            state
+                .wakers
+                .keys()
+                .filter(|x| x.0 == clock.start + Duration::from_secs(3600))
+                .count()
+        };
+
+        for _ in 0..3 {
+            assert_eq!(sleep.as_mut().poll(&mut cx), Poll::Pending);
+        }
+        assert_eq!(registered(), 1);
+        drop(sleep);
+        assert_eq!(registered(), 0);
+    }
+}
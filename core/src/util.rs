@@ -150,6 +150,23 @@ mod futures {
     }
 }
 
+/// Write a string as a quoted and escaped JSON string.
+pub fn write_json_string<W: core::fmt::Write>(w: &mut W, string: &str) -> core::fmt::Result {
+    w.write_char('"')?;
+    for c in string.chars() {
+        match c {
+            '"' => w.write_str("\\\"")?,
+            '\\' => w.write_str("\\\\")?,
+            '\n' => w.write_str("\\n")?,
+            '\r' => w.write_str("\\r")?,
+            '\t' => w.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(w, "\\u{:04x}", c as u32)?,
+            c => w.write_char(c)?,
+        }
+    }
+    w.write_char('"')
+}
+
 #[cfg(feature = "std")]
 pub fn catch_unwind<R>(
     f: impl FnOnce() -> R + UnwindSafe,
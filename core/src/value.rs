@@ -5,11 +5,18 @@ pub mod array;
 pub mod atom;
 mod bigint;
 pub mod convert;
+pub mod data_view;
+mod date;
 pub(crate) mod exception;
 pub mod function;
+pub mod iterator;
+pub mod map;
 pub mod module;
 pub mod object;
 pub mod promise;
+pub mod proxy;
+mod reg_exp;
+pub mod set;
 mod string;
 mod symbol;
 
@@ -17,11 +24,18 @@ pub use array::Array;
 pub use atom::Atom;
 pub use bigint::BigInt;
 pub use convert::{Coerced, FromAtom, FromIteratorJs, FromJs, IntoAtom, IntoJs, IteratorJs};
-pub use exception::Exception;
+pub use data_view::DataView;
+pub use date::Date;
+pub use exception::{Exception, ExceptionInfo, StackFrame};
 pub use function::{Constructor, Function};
+pub use iterator::JsIterator;
+pub use map::Map;
 pub use module::{Module, WriteOptions, WriteOptionsEndianness};
 pub use object::{Filter, Object};
 pub use promise::Promise;
+pub use proxy::Proxy;
+pub use reg_exp::RegExp;
+pub use set::Set;
 pub use string::{CString, String};
 pub use symbol::Symbol;
 
@@ -97,7 +111,8 @@ impl<'js> fmt::Debug for Value<'js> {
                 unsafe { self.ref_string() }.to_string().fmt(f)?;
                 write!(f, ")")?;
             }
-            Symbol | Object | Array | Function | Constructor | Promise => {
+            Symbol | Object | Array | Function | Constructor | Promise | Map | Set | Date
+            | RegExp | DataView => {
                 write!(f, "(")?;
                 unsafe { self.get_ptr() }.fmt(f)?;
                 write!(f, ")")?;
@@ -370,6 +385,36 @@ impl<'js> Value<'js> {
         (unsafe { qjs::JS_PromiseState(self.ctx.as_ptr(), self.value) } as core::ffi::c_int) >= 0
     }
 
+    /// Check if the value is a map
+    #[inline]
+    pub fn is_map(&self) -> bool {
+        unsafe { qjs::JS_IsMap(self.value) }
+    }
+
+    /// Check if the value is a set
+    #[inline]
+    pub fn is_set(&self) -> bool {
+        unsafe { qjs::JS_IsSet(self.value) }
+    }
+
+    /// Check if the value is a date
+    #[inline]
+    pub fn is_date(&self) -> bool {
+        unsafe { qjs::JS_IsDate(self.value) }
+    }
+
+    /// Check if the value is a regular expression
+    #[inline]
+    pub fn is_reg_exp(&self) -> bool {
+        unsafe { qjs::JS_IsRegExp(self.value) }
+    }
+
+    /// Check if the value is a data view
+    #[inline]
+    pub fn is_data_view(&self) -> bool {
+        unsafe { qjs::JS_IsDataView(self.value) }
+    }
+
     /// Check if the value is an exception
     #[inline]
     pub fn is_exception(&self) -> bool {
@@ -421,6 +466,18 @@ impl<'js> Value<'js> {
     }
 }
 
+/// Call an operation of the engine and convert the value it returns.
+pub(crate) fn call<'js, R: FromJs<'js>>(
+    ctx: &Ctx<'js>,
+    f: impl FnOnce(*mut qjs::JSContext) -> qjs::JSValue,
+) -> Result<R> {
+    let value = unsafe {
+        let value = ctx.handle_exception(f(ctx.as_ptr()))?;
+        Value::from_js_value(ctx.clone(), value)
+    };
+    R::from_js(ctx, value)
+}
+
 impl<'js> AsRef<Value<'js>> for Value<'js> {
     fn as_ref(&self) -> &Value<'js> {
         self
@@ -431,8 +488,12 @@ macro_rules! type_impls {
     // type: name => tag
     ($($type:ident: $name:ident => $($tag:ident)|+,)*) => {
         /// The type of JavaScript value
+        ///
+        /// New variants may be added for more specific classes of objects, so matches should
+        /// handle unknown types, for example by falling back to [`Value::is_object`].
         #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
         #[repr(u8)]
+        #[non_exhaustive]
         pub enum Type {
             $($type,)*
             Unknown
@@ -454,7 +515,7 @@ macro_rules! type_impls {
                 }
                 match other{
                     Float => matches!(self, Int),
-                    Object => matches!(self, Array | Function | Constructor | Exception | Promise),
+                    Object => matches!(self, Array | Function | Constructor | Exception | Promise | Map | Set | Date | RegExp | DataView),
                     Function => matches!(self, Constructor),
                     _ => false
                 }
@@ -514,6 +575,11 @@ macro_rules! type_impls {
     (@cond Function $self:expr) => { $self.is_function() };
     (@cond Promise $self:expr) => { $self.is_promise() };
     (@cond Exception $self:expr) => { $self.is_error() };
+    (@cond Map $self:expr) => { $self.is_map() };
+    (@cond Set $self:expr) => { $self.is_set() };
+    (@cond Date $self:expr) => { $self.is_date() };
+    (@cond RegExp $self:expr) => { $self.is_reg_exp() };
+    (@cond DataView $self:expr) => { $self.is_data_view() };
     (@cond $type:ident $self:expr) => { true };
 }
 
@@ -531,6 +597,11 @@ type_impls! {
     Function: function => JS_TAG_OBJECT,
     Promise: promise => JS_TAG_OBJECT,
     Exception: exception => JS_TAG_OBJECT,
+    Map: map => JS_TAG_OBJECT,
+    Set: set => JS_TAG_OBJECT,
+    Date: date => JS_TAG_OBJECT,
+    RegExp: reg_exp => JS_TAG_OBJECT,
+    DataView: data_view => JS_TAG_OBJECT,
     Object: object => JS_TAG_OBJECT,
     Module: module => JS_TAG_MODULE,
     BigInt: big_int => JS_TAG_BIG_INT | JS_TAG_SHORT_BIG_INT,
@@ -569,10 +640,10 @@ macro_rules! sub_types {
                 /// Convert from value
                 pub fn from_value(value: Value<'js>) -> Result<Self> {
                     let type_ = value.type_of();
-                    if type_.interpretable_as(Type::$head) {
+                    if sub_types!(@is $head value) {
                         Ok(sub_types!(@wrap $head$(->$sub_type)*  value))
                     } else {
-                        Err(Error::new_from_js(type_.as_str(), Type::$head.as_str()))
+                        Err(Error::new_from_js(type_.as_str(), sub_types!(@name $head)))
                     }
                 }
 
@@ -623,7 +694,7 @@ macro_rules! sub_types {
 
                 #[doc = concat!("Try reinterpret as [`",stringify!($head),"`]")]
                 pub fn $as(&self) -> Option<&$head<'js>> {
-                    if self.type_of().interpretable_as(Type::$head) {
+                    if sub_types!(@is $head self) {
                         Some(unsafe { self.$ref() })
                     } else {
                         None
@@ -632,7 +703,7 @@ macro_rules! sub_types {
 
                 #[doc = concat!("Try convert into [`",stringify!($head),"`]")]
                 pub fn $into(self) -> Option<$head<'js>> {
-                    if self.type_of().interpretable_as(Type::$head) {
+                    if sub_types!(@is $head self) {
                         Some(sub_types!(@wrap $head$(->$sub_type)* self))
                     } else {
                         None
@@ -641,7 +712,7 @@ macro_rules! sub_types {
 
                 #[doc = concat!("Try convert into [`",stringify!($head),"`] returning self if the conversion fails.")]
                 pub fn $try_into(self) -> core::result::Result<$head<'js>, Value<'js>> {
-                    if self.type_of().interpretable_as(Type::$head) {
+                    if sub_types!(@is $head self) {
                         Ok(sub_types!(@wrap $head$(->$sub_type)* self))
                     } else {
                         Err(self)
@@ -682,6 +753,13 @@ macro_rules! sub_types {
 
     (@type $type:ident) => { $type<'js> };
 
+    // Proxies wrap any object, including functions, so they are not a distinct [`Type`].
+    (@is Proxy $val:expr) => { $val.is_proxy() };
+    (@is $type:ident $val:expr) => { $val.type_of().interpretable_as(Type::$type) };
+
+    (@name Proxy) => { "proxy" };
+    (@name $type:ident) => { Type::$type.as_str() };
+
     (@head $head:ident $(rem:ident)*)  => { $head };
     (@head_ty $head:ident$(,$rem:ident)*)  => { $head<'js> };
 
@@ -725,7 +803,13 @@ sub_types! {
     Constructor->Function->Object->Value as_constructor ref_constructor into_constructor try_into_constructor from_constructor,
     Promise->Object->Value as_promise ref_promise into_promise try_into_promise from_promise,
     Array->Object->Value as_array ref_array into_array try_into_array from_array,
+    Map->Object->Value as_map ref_map into_map try_into_map from_map,
+    Set->Object->Value as_set ref_set into_set try_into_set from_set,
+    Date->Object->Value as_date ref_date into_date try_into_date from_date,
+    RegExp->Object->Value as_reg_exp ref_reg_exp into_reg_exp try_into_reg_exp from_reg_exp,
+    DataView->Object->Value as_data_view ref_data_view into_data_view try_into_data_view from_data_view,
     Exception->Object->Value as_exception ref_exception into_exception try_into_exception from_exception,
+    Proxy->Object->Value as_proxy ref_proxy into_proxy try_into_proxy from_proxy,
     BigInt->Value as_big_int ref_big_int into_big_int try_into_big_int from_big_int,
 }
 
@@ -142,6 +142,16 @@ impl<'js> Atom<'js> {
         self.to_js_string().map(|String(value)| value)
     }
 
+    /// Convert the atom to a property key, a symbol for symbol atoms and a string or number
+    /// otherwise.
+    pub(crate) fn to_property_key(&self) -> Result<Value<'js>> {
+        unsafe {
+            let val = qjs::JS_AtomToValue(self.ctx.as_ptr(), self.atom);
+            let val = self.ctx.handle_exception(val)?;
+            Ok(Value::from_js_value(self.ctx.clone(), val))
+        }
+    }
+
     pub(crate) unsafe fn from_atom_val(ctx: Ctx<'js>, val: qjs::JSAtom) -> Self {
         Atom { atom: val, ctx }
     }
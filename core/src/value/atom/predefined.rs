@@ -391,6 +391,8 @@ pub enum PredefinedAtom {
     InternalError = qjs::JS_ATOM_InternalError as u32,
     /// "Symbol.asyncIterator"
     SymbolAsyncIterator = qjs::JS_ATOM_Symbol_asyncIterator as u32,
+    /// "Symbol.toPrimitive"
+    SymbolToPrimitive = qjs::JS_ATOM_Symbol_toPrimitive as u32,
     /// "Symbol.iterator"
     SymbolIterator = qjs::JS_ATOM_Symbol_iterator as u32,
     /// "Symbol.match"
@@ -420,6 +422,7 @@ impl PredefinedAtom {
         matches!(
             self,
             PredefinedAtom::SymbolAsyncIterator
+                | PredefinedAtom::SymbolToPrimitive
                 | PredefinedAtom::SymbolIterator
                 | PredefinedAtom::SymbolMatch
                 | PredefinedAtom::SymbolMatchAll
@@ -627,6 +630,7 @@ impl PredefinedAtom {
             PredefinedAtom::URIError => "URIError",
             PredefinedAtom::InternalError => "InternalError",
             PredefinedAtom::SymbolAsyncIterator => "Symbol.asyncIterator",
+            PredefinedAtom::SymbolToPrimitive => "Symbol.toPrimitive",
             PredefinedAtom::SymbolIterator => "Symbol.iterator",
             PredefinedAtom::SymbolMatch => "Symbol.match",
             PredefinedAtom::SymbolMatchAll => "Symbol.matchAll",
@@ -840,6 +844,7 @@ mod test {
             PredefinedAtom::URIError,
             PredefinedAtom::InternalError,
             PredefinedAtom::SymbolAsyncIterator,
+            PredefinedAtom::SymbolToPrimitive,
             PredefinedAtom::SymbolIterator,
             PredefinedAtom::SymbolMatch,
             PredefinedAtom::SymbolMatchAll,
@@ -6,6 +6,9 @@ mod atom;
 mod coerce;
 mod from;
 mod into;
+#[cfg(feature = "serde")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "serde")))]
+pub mod serde;
 
 /// The wrapper for values to force coercion
 ///
@@ -94,3 +97,37 @@ where
 /// A helper type for turning a tuple into a JavaScript array.
 /// Implements [`IntoJs`] and [`FromJs`] for tuples of various lengths
 pub struct List<T>(pub T);
+
+/// A helper type for converting Rust maps to and from JavaScript [`Map`](crate::Map) objects.
+///
+/// Rust maps are converted to plain objects by default which restricts keys to strings. With this
+/// wrapper maps are converted to a JavaScript `Map` instead, keeping keys of any type.
+///
+/// ```
+/// # use rquickjs::{Runtime, Context, Result, convert::AsMap};
+/// # use std::collections::BTreeMap;
+/// # let rt = Runtime::new().unwrap();
+/// # let ctx = Context::full(&rt).unwrap();
+/// # ctx.with(|ctx| -> Result<()> {
+/// #
+/// let map = BTreeMap::from([(1, "a"), (2, "b")]);
+/// ctx.globals().set("map", AsMap(map))?;
+/// assert_eq!(ctx.eval::<String, _>("map.get(2)")?, "b");
+///
+/// let AsMap(map) = ctx.eval::<AsMap<BTreeMap<i32, String>>, _>("new Map([[3, 'c']])")?;
+/// assert_eq!(map[&3], "c");
+/// #
+/// # Ok(())
+/// # }).unwrap();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[repr(transparent)]
+pub struct AsMap<T>(pub T);
+
+/// A helper type for converting Rust sets to and from JavaScript [`Set`](crate::Set) objects.
+///
+/// Rust sets are converted to arrays by default. With this wrapper sets are converted to a
+/// JavaScript `Set` instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[repr(transparent)]
+pub struct AsSet<T>(pub T);
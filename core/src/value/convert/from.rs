@@ -1,6 +1,7 @@
 use crate::{
-    convert::List, Array, CString, Ctx, Error, FromAtom, FromJs, Object, Result, StdString, String,
-    Type, Value,
+    convert::{AsMap, AsSet, List},
+    Array, CString, Ctx, Date, Error, FromAtom, FromJs, Map, Object, Result, Set, StdString,
+    String, Type, Value,
 };
 use alloc::{
     boxed::Box,
@@ -229,6 +230,41 @@ macro_rules! from_js_impls {
         )*
     };
 
+    // for set-like Rust types from JS sets
+    (js_set: $($(#[$meta:meta])* $type:ident $({$param:ident: $($pguard:tt)*})* $(($($guard:tt)*))*,)*) => {
+        $(
+            $(#[$meta])*
+            impl<'js, T $(,$param)*> FromJs<'js> for AsSet<$type<T $(,$param)*>>
+            where
+                T: FromJs<'js> $(+ $($guard)*)*,
+                $($param: $($pguard)*,)*
+            {
+                fn from_js(_ctx: &Ctx<'js>, value: Value<'js>) -> Result<Self> {
+                    let set = Set::from_value(value)?;
+                    set.iter().collect::<Result<_>>().map(AsSet)
+                }
+            }
+        )*
+    };
+
+    // for map-like Rust types from JS maps
+    (js_map: $($(#[$meta:meta])* $type:ident $({$param:ident: $($pguard:tt)*})* $(($($guard:tt)*))*,)*) => {
+        $(
+            $(#[$meta])*
+            impl<'js, K, V $(,$param)*> FromJs<'js> for AsMap<$type<K, V $(,$param)*>>
+            where
+                K: FromJs<'js> $(+ $($guard)*)*,
+                V: FromJs<'js>,
+                $($param: $($pguard)*,)*
+            {
+                fn from_js(_ctx: &Ctx<'js>, value: Value<'js>) -> Result<Self> {
+                    let map = Map::from_value(value)?;
+                    map.iter().collect::<Result<_>>().map(AsMap)
+                }
+            }
+        )*
+    };
+
     // for basic primitive types (int and float)
     // (ex. f64 => Float as_float Int as_int)
     (val: $($type:ty => $($jstype:ident $getfn:ident)*,)*) => {
@@ -362,6 +398,38 @@ from_js_impls! {
     IndexMap {S: Default + BuildHasher} (Eq + Hash),
 }
 
+from_js_impls! {
+    js_set:
+    /// Convert from JS set to Rust vector
+    Vec,
+    /// Convert from JS set to Rust hash set
+    #[cfg(feature = "std")]
+    HashSet {S: Default + BuildHasher} (Eq + Hash),
+    /// Convert from JS set to hashbrown hash set
+    HashbrownSet {S: Default + BuildHasher} (Eq + Hash),
+    /// Convert from JS set to Rust btree set
+    BTreeSet (Eq + Ord),
+    /// Convert from JS set to Rust index set
+    #[cfg(feature = "indexmap")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "indexmap")))]
+    IndexSet {S: Default + BuildHasher} (Eq + Hash),
+}
+
+from_js_impls! {
+    js_map:
+    /// Convert from JS map to Rust hash map
+    #[cfg(feature = "std")]
+    HashMap {S: Default + BuildHasher} (Eq + Hash),
+    /// Convert from JS map to hashbrown hash map
+    HashbrownMap {S: Default + BuildHasher} (Eq + Hash),
+    /// Convert from JS map to Rust btree map
+    BTreeMap (Eq + Ord),
+    /// Convert from JS map to Rust index map
+    #[cfg(feature = "indexmap")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "indexmap")))]
+    IndexMap {S: Default + BuildHasher} (Eq + Hash),
+}
+
 impl<'js> FromJs<'js> for f32 {
     fn from_js(ctx: &Ctx<'js>, value: Value<'js>) -> Result<Self> {
         f64::from_js(ctx, value).map(|value| value as _)
@@ -370,18 +438,8 @@ impl<'js> FromJs<'js> for f32 {
 
 #[allow(dead_code)]
 fn date_to_millis<'js>(ctx: &Ctx<'js>, value: Value<'js>) -> Result<i64> {
-    let global = ctx.globals();
-    let date_ctor: Object = global.get("Date")?;
-
-    let value = Object::from_value(value)?;
-
-    if !value.is_instance_of(&date_ctor) {
-        return Err(Error::new_from_js("Object", "Date"));
-    }
-
-    let get_time_fn: crate::Function = value.get("getTime")?;
-
-    get_time_fn.call((crate::function::This(value),))
+    let millis = Date::from_js(ctx, value)?.epoch_ms()?;
+    i64::from_js(ctx, Value::new_float(ctx.clone(), millis))
 }
 
 #[cfg(feature = "std")]
@@ -1,8 +1,7 @@
 use crate::{
-    convert::{IteratorJs, List},
-    value::Constructor,
-    Array, CString, Ctx, Error, IntoAtom, IntoJs, Object, Result, StdResult, StdString, String,
-    Value,
+    convert::{AsMap, AsSet, IteratorJs, List},
+    Array, CString, Ctx, Date, Error, IntoAtom, IntoJs, Map, Object, Result, Set, StdResult,
+    StdString, String, Value,
 };
 use alloc::{
     boxed::Box,
@@ -349,6 +348,70 @@ macro_rules! into_js_impls {
         )*
     };
 
+    // for set-like Rust types to JS sets
+    (js_set: $($(#[$meta:meta])* $type:ident $({$param:ident})*,)*) => {
+        $(
+            $(#[$meta])*
+            impl<'js, T $(,$param)*> IntoJs<'js> for AsSet<$type<T $(,$param)*>>
+            where
+                T: IntoJs<'js>,
+            {
+                fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
+                    self.0
+                        .into_iter()
+                        .collect_js(ctx)
+                        .map(|set: Set| set.into_value())
+                }
+            }
+
+            $(#[$meta])*
+            impl<'js, T $(,$param)*> IntoJs<'js> for AsSet<&$type<T $(,$param)*>>
+            where
+                for<'a> &'a T: IntoJs<'js>,
+            {
+                fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
+                    self.0
+                        .iter()
+                        .collect_js(ctx)
+                        .map(|set: Set| set.into_value())
+                }
+            }
+        )*
+    };
+
+    // for map-like Rust types to JS maps
+    (js_map: $($(#[$meta:meta])* $type:ident $({$param:ident})*,)*) => {
+        $(
+            $(#[$meta])*
+            impl<'js, K, V $(,$param)*> IntoJs<'js> for AsMap<$type<K, V $(,$param)*>>
+            where
+                K: IntoJs<'js>,
+                V: IntoJs<'js>,
+            {
+                fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
+                    self.0
+                        .into_iter()
+                        .collect_js(ctx)
+                        .map(|map: Map| map.into_value())
+                }
+            }
+
+            $(#[$meta])*
+            impl<'js, K, V $(,$param)*> IntoJs<'js> for AsMap<&$type<K, V $(,$param)*>>
+            where
+                for<'a> &'a K: IntoJs<'js>,
+                for<'a> &'a V: IntoJs<'js>,
+            {
+                fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
+                    self.0
+                        .iter()
+                        .collect_js(ctx)
+                        .map(|map: Map| map.into_value())
+                }
+            }
+        )*
+    };
+
     // for primitive types using `new` function
     (val: $($new:ident: $($type:ident)*,)*) => {
         $(
@@ -473,6 +536,38 @@ into_js_impls! {
     IndexMap {S},
 }
 
+into_js_impls! {
+    js_set:
+    /// Convert from Rust vector to JS set
+    Vec,
+    /// Convert from Rust hash set to JS set
+    #[cfg(feature = "std")]
+    HashSet {S},
+    /// Convert from hashbrown hash set to JS set
+    HashbrownSet {S},
+    /// Convert from Rust btree set to JS set
+    BTreeSet,
+    /// Convert from Rust index set to JS set
+    #[cfg(feature = "indexmap")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "indexmap")))]
+    IndexSet {S},
+}
+
+into_js_impls! {
+    js_map:
+    /// Convert from Rust hash map to JS map
+    #[cfg(feature = "std")]
+    HashMap {S},
+    /// Convert from hashbrown hash map to JS map
+    HashbrownMap {S},
+    /// Convert from Rust btree map to JS map
+    BTreeMap,
+    /// Convert from Rust index map to JS map
+    #[cfg(feature = "indexmap")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "indexmap")))]
+    IndexMap {S},
+}
+
 into_js_impls! {
     val:
     new_bool: bool,
@@ -487,9 +582,7 @@ into_js_impls! {
 
 #[allow(dead_code)]
 fn millis_to_date<'js>(ctx: &Ctx<'js>, millis: i64) -> Result<Value<'js>> {
-    let date_ctor: Constructor = ctx.globals().get("Date")?;
-
-    date_ctor.construct((millis,))
+    Date::new(ctx.clone(), millis as _).map(Date::into_value)
 }
 
 #[cfg(feature = "std")]
@@ -0,0 +1,553 @@
+//! Conversion between JavaScript values and Rust types implementing [`serde`] traits.
+//!
+//! The [`Serializer`] turns any [`Serialize`] type into a [`Value`] and the [`Deserializer`] reads
+//! any [`Deserialize`] type back out of a [`Value`].
+//!
+//! | Rust / serde data model               | JavaScript                                      |
+//! | ------------------------------------- | ----------------------------------------------- |
+//! | `bool`                                | `boolean`                                       |
+//! | integers and floats                   | `number`                                        |
+//! | `i64`, `u64`, `i128`, `u128`          | `number` when it is a safe integer, else `BigInt` |
+//! | `char`, `&str`, `String`              | `string`                                        |
+//! | bytes                                 | `Uint8Array`                                    |
+//! | `None`, `()` and unit structs         | `null` (both `null` and `undefined` are read)   |
+//! | sequences, tuples and tuple structs   | `Array`                                         |
+//! | maps and structs                      | `Object`                                        |
+//! | enums                                 | externally tagged by default, see [serde enum representations](https://serde.rs/enum-representations.html) |
+//!
+//! When reading, typed arrays are accepted for both sequences and bytes and `ArrayBuffer`s for
+//! bytes.
+//!
+//! Conversion errors are reported as [`Error::FromJs`] and [`Error::IntoJs`]. Errors in a nested
+//! property are wrapped in an [`Error::Nested`] holding the path of the offending property, like
+//! `$.servers[2].port`.
+//!
+//! ```
+//! # use rquickjs::{Runtime, Context, Result, convert::serde::{from_value, to_value}};
+//! # use serde::{Deserialize, Serialize};
+//! # let rt = Runtime::new().unwrap();
+//! # let ctx = Context::full(&rt).unwrap();
+//! # ctx.with(|ctx| -> Result<()> {
+//! #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! struct Config {
+//!     name: String,
+//!     retries: u32,
+//!     tags: Vec<String>,
+//! }
+//!
+//! let config: Config = from_value(ctx.eval(r#"({ name: "api", retries: 3, tags: ["a"] })"#)?)?;
+//! assert_eq!(config.retries, 3);
+//!
+//! let value = to_value(ctx.clone(), &config)?;
+//! ctx.globals().set("config", value)?;
+//! assert_eq!(ctx.eval::<String, _>("config.tags[0]")?, "a");
+//! # Ok(())
+//! # }).unwrap();
+//! ```
+
+use crate::{Ctx, Error, FromJs, IntoJs, Result, Value};
+use ::serde::{de::DeserializeOwned, Serialize};
+use alloc::{boxed::Box, format, string::ToString as _};
+use core::fmt;
+
+mod de;
+mod ser;
+
+pub use de::Deserializer;
+pub use ser::{SerializeArray, SerializeObject, SerializeVariant, Serializer};
+
+/// Serialize a Rust value into a JavaScript value.
+pub fn to_value<'js, T>(ctx: Ctx<'js>, value: &T) -> Result<Value<'js>>
+where
+    T: Serialize + ?Sized,
+{
+    value.serialize(Serializer::new(ctx))
+}
+
+/// Deserialize a Rust value from a JavaScript value.
+pub fn from_value<'js, T>(value: Value<'js>) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    T::deserialize(Deserializer::new(value))
+}
+
+/// The wrapper for values to convert them through [`serde`].
+///
+/// Implements [`FromJs`] and [`IntoJs`] for any type implementing [`Deserialize`] and
+/// [`Serialize`] respectively, so it can be used directly as a function argument or return value.
+///
+/// ```
+/// # use rquickjs::{Runtime, Context, Function, Result, convert::serde::Serde};
+/// # use serde::{Deserialize, Serialize};
+/// # let rt = Runtime::new().unwrap();
+/// # let ctx = Context::full(&rt).unwrap();
+/// # ctx.with(|ctx| -> Result<()> {
+/// #[derive(Serialize, Deserialize)]
+/// struct Point {
+///     x: f64,
+///     y: f64,
+/// }
+///
+/// let flip = Function::new(ctx.clone(), |Serde(p): Serde<Point>| Serde(Point { x: p.y, y: p.x }))?;
+/// ctx.globals().set("flip", flip)?;
+/// assert_eq!(ctx.eval::<f64, _>("flip({ x: 1, y: 2 }).x")?, 2.0);
+/// # Ok(())
+/// # }).unwrap();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[repr(transparent)]
+pub struct Serde<T>(pub T);
+
+impl<'js, T> FromJs<'js> for Serde<T>
+where
+    T: DeserializeOwned,
+{
+    fn from_js(_: &Ctx<'js>, value: Value<'js>) -> Result<Self> {
+        from_value(value).map(Serde)
+    }
+}
+
+impl<'js, T> IntoJs<'js> for Serde<T>
+where
+    T: Serialize,
+{
+    fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
+        to_value(ctx.clone(), &self.0)
+    }
+}
+
+impl<'js, T> IntoJs<'js> for &Serde<T>
+where
+    T: Serialize,
+{
+    fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
+        to_value(ctx.clone(), &self.0)
+    }
+}
+
+/// A single step in the path to a property.
+pub(crate) enum PathSegment<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+impl fmt::Display for PathSegment<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PathSegment::Index(idx) => write!(f, "[{idx}]"),
+            PathSegment::Key(key) if is_identifier(key) => write!(f, ".{key}"),
+            PathSegment::Key(key) => write!(f, "[{key:?}]"),
+        }
+    }
+}
+
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    matches!(chars.next(), Some(c) if c.is_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+}
+
+/// Add the segment of the property a conversion error happened in to the path of the error.
+///
+/// Errors are unwound from the innermost value outwards so each call adds the segment in front of
+/// the path which is already there.
+pub(crate) fn with_path(error: Error, segment: PathSegment) -> Error {
+    match error {
+        Error::Nested { mut path, error } => {
+            path.insert_str(1, &segment.to_string());
+            Error::Nested { path, error }
+        }
+        error @ (Error::FromJs { .. } | Error::IntoJs { .. }) => Error::Nested {
+            path: format!("${segment}"),
+            error: Box::new(error),
+        },
+        error => error,
+    }
+}
+
+impl ::serde::ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::new_into_js_message("value", "value", msg.to_string())
+    }
+}
+
+impl ::serde::de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::new_from_js_message("value", "value", msg.to_string())
+    }
+
+    fn invalid_type(unexp: ::serde::de::Unexpected, exp: &dyn ::serde::de::Expected) -> Self {
+        use ::serde::de::Unexpected;
+
+        let from = match unexp {
+            Unexpected::Bool(_) => "bool",
+            Unexpected::Unsigned(_) | Unexpected::Signed(_) => "int",
+            Unexpected::Float(_) => "float",
+            Unexpected::Char(_) | Unexpected::Str(_) => "string",
+            Unexpected::Bytes(_) => "bytes",
+            Unexpected::Unit | Unexpected::Option => "null",
+            Unexpected::Seq => "array",
+            Unexpected::Map | Unexpected::StructVariant => "object",
+            _ => "value",
+        };
+        Error::new_from_js_message(
+            from,
+            "value",
+            format!("invalid type: {unexp}, expected {exp}"),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{test_with, Array, BigInt, Object, TypedArray};
+    use alloc::{collections::BTreeMap, string::String, vec, vec::Vec};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Server {
+        host: String,
+        port: u16,
+        #[serde(default)]
+        tls: bool,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Config {
+        name: String,
+        servers: Vec<Server>,
+        limits: BTreeMap<String, u32>,
+        timeout: Option<f64>,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum External {
+        Unit,
+        Newtype(i32),
+        Tuple(i32, String),
+        Struct { a: bool },
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(tag = "type", rename_all = "camelCase")]
+    enum Internal {
+        Circle { radius: f64 },
+        Rect { width: u32, height: u32 },
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(untagged)]
+    enum Untagged {
+        Number(u32),
+        Text(String),
+        List(Vec<Untagged>),
+    }
+
+    #[test]
+    fn struct_roundtrip() {
+        test_with(|ctx| {
+            let config: Config = from_value(
+                ctx.eval(
+                    r#"({
+                        name: "api",
+                        servers: [{ host: "a", port: 80 }, { host: "b", port: 443, tls: true }],
+                        limits: { rps: 100 },
+                        timeout: null,
+                    })"#,
+                )
+                .unwrap(),
+            )
+            .unwrap();
+            assert_eq!(
+                config,
+                Config {
+                    name: "api".into(),
+                    servers: vec![
+                        Server {
+                            host: "a".into(),
+                            port: 80,
+                            tls: false
+                        },
+                        Server {
+                            host: "b".into(),
+                            port: 443,
+                            tls: true
+                        },
+                    ],
+                    limits: [("rps".into(), 100)].into_iter().collect(),
+                    timeout: None,
+                }
+            );
+
+            let value = to_value(ctx.clone(), &config).unwrap();
+            ctx.globals().set("config", value.clone()).unwrap();
+            let ok: bool = ctx
+                .eval(
+                    r#"
+                    config.servers[1].port === 443 &&
+                    config.servers[1].tls === true &&
+                    config.limits.rps === 100 &&
+                    config.timeout === null
+                    "#,
+                )
+                .unwrap();
+            assert!(ok);
+            assert_eq!(from_value::<Config>(value).unwrap(), config);
+        })
+    }
+
+    #[test]
+    fn option_accepts_undefined() {
+        test_with(|ctx| {
+            let v: Option<i32> = from_value(Value::new_undefined(ctx.clone())).unwrap();
+            assert_eq!(v, None);
+            let v: Option<i32> = from_value(Value::new_null(ctx.clone())).unwrap();
+            assert_eq!(v, None);
+            let v: Option<i32> = from_value(Value::new_int(ctx.clone(), 3)).unwrap();
+            assert_eq!(v, Some(3));
+            let v: Server = from_value(
+                ctx.eval("({ host: 'x', port: 1, tls: undefined })")
+                    .unwrap(),
+            )
+            .unwrap();
+            assert!(!v.tls);
+        })
+    }
+
+    #[test]
+    fn enums() {
+        test_with(|ctx| {
+            for (value, js) in [
+                (External::Unit, r#""Unit""#),
+                (External::Newtype(3), "({ Newtype: 3 })"),
+                (External::Tuple(1, "a".into()), r#"({ Tuple: [1, "a"] })"#),
+                (External::Struct { a: true }, "({ Struct: { a: true } })"),
+            ] {
+                assert_eq!(
+                    from_value::<External>(ctx.eval(js).unwrap()).unwrap(),
+                    value
+                );
+                let roundtrip = to_value(ctx.clone(), &value).unwrap();
+                assert_eq!(from_value::<External>(roundtrip).unwrap(), value);
+            }
+
+            let shape: Internal =
+                from_value(ctx.eval("({ type: 'rect', width: 2, height: 3 })").unwrap()).unwrap();
+            assert_eq!(
+                shape,
+                Internal::Rect {
+                    width: 2,
+                    height: 3
+                }
+            );
+            let obj: Object = to_value(ctx.clone(), &Internal::Circle { radius: 1.5 })
+                .unwrap()
+                .into_object()
+                .unwrap();
+            assert_eq!(obj.get::<_, String>("type").unwrap(), "circle");
+
+            let untagged: Untagged = from_value(ctx.eval("[1, 'two', [3.0]]").unwrap()).unwrap();
+            assert_eq!(
+                untagged,
+                Untagged::List(vec![
+                    Untagged::Number(1),
+                    Untagged::Text("two".into()),
+                    Untagged::List(vec![Untagged::Number(3)]),
+                ])
+            );
+        })
+    }
+
+    #[test]
+    fn big_int() {
+        test_with(|ctx| {
+            let v = to_value(ctx.clone(), &i64::MAX).unwrap();
+            assert!(v.is_big_int());
+            let v = to_value(ctx.clone(), &42u64).unwrap();
+            assert_eq!(v.as_int(), Some(42));
+            let v = to_value(ctx.clone(), &(u64::MAX as i128 + 1)).unwrap();
+            assert!(v.is_big_int());
+            assert_eq!(from_value::<i128>(v).unwrap(), u64::MAX as i128 + 1);
+
+            let v = BigInt::from_i64(ctx.clone(), -5).unwrap().into_value();
+            assert_eq!(from_value::<i64>(v.clone()).unwrap(), -5);
+            assert!(from_value::<u64>(v).is_err());
+            assert_eq!(
+                from_value::<u64>(ctx.eval("18446744073709551615n").unwrap()).unwrap(),
+                u64::MAX
+            );
+        })
+    }
+
+    #[test]
+    fn bytes() {
+        test_with(|ctx| {
+            #[derive(Serialize, Deserialize)]
+            struct Blob {
+                #[serde(with = "bytes")]
+                data: Vec<u8>,
+            }
+
+            mod bytes {
+                use super::*;
+
+                pub fn serialize<S: serde::Serializer>(
+                    v: &[u8],
+                    s: S,
+                ) -> core::result::Result<S::Ok, S::Error> {
+                    s.serialize_bytes(v)
+                }
+
+                pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+                    d: D,
+                ) -> core::result::Result<Vec<u8>, D::Error> {
+                    struct Visitor;
+                    impl<'de> serde::de::Visitor<'de> for Visitor {
+                        type Value = Vec<u8>;
+                        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                            f.write_str("bytes")
+                        }
+                        fn visit_bytes<E>(self, v: &[u8]) -> core::result::Result<Vec<u8>, E> {
+                            Ok(v.to_vec())
+                        }
+                    }
+                    d.deserialize_byte_buf(Visitor)
+                }
+            }
+
+            let obj: Object = to_value(
+                ctx.clone(),
+                &Blob {
+                    data: vec![1, 2, 3],
+                },
+            )
+            .unwrap()
+            .into_object()
+            .unwrap();
+            let data: TypedArray<u8> = obj.get("data").unwrap();
+            assert_eq!(data.as_bytes().unwrap(), &[1, 2, 3]);
+
+            let blob: Blob =
+                from_value(ctx.eval("({ data: new Uint16Array([1]).buffer })").unwrap()).unwrap();
+            assert_eq!(blob.data.len(), 2);
+
+            let v: Vec<u8> = from_value(ctx.eval("new Uint8Array([4, 5])").unwrap()).unwrap();
+            assert_eq!(v, [4, 5]);
+        })
+    }
+
+    #[test]
+    fn errors_contain_path() {
+        test_with(|ctx| {
+            let err = from_value::<Config>(
+                ctx.eval(
+                    r#"({
+                        name: "api",
+                        servers: [{ host: "a", port: 80 }, { host: "b", port: "443" }],
+                        limits: {},
+                    })"#,
+                )
+                .unwrap(),
+            )
+            .unwrap_err();
+            assert!(err.is_from_js());
+            let Error::Nested { path, .. } = &err else {
+                panic!("{err}");
+            };
+            assert_eq!(path, "$.servers[1].port");
+            let msg = err.to_string();
+            assert!(msg.contains("$.servers[1].port"), "{msg}");
+            assert!(msg.contains("'string'"), "{msg}");
+
+            let err = from_value::<Config>(ctx.eval("({ name: 'api', limits: {} })").unwrap())
+                .unwrap_err();
+            assert!(err.to_string().contains("missing field `servers`"), "{err}");
+
+            let err = from_value::<BTreeMap<String, u8>>(ctx.eval("({ 'a b': 300 })").unwrap())
+                .unwrap_err();
+            assert!(err.to_string().contains(r#"$["a b"]"#), "{err}");
+
+            let err = from_value::<Vec<i32>>(ctx.eval("[1, () => {}]").unwrap()).unwrap_err();
+            assert!(err.to_string().contains("$[1]"), "{err}");
+        })
+    }
+
+    #[test]
+    fn unknown_fields() {
+        #[derive(Debug, PartialEq, Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct Strict {
+            a: u8,
+            #[serde(default)]
+            b: u8,
+        }
+
+        test_with(|ctx| {
+            let strict: Strict = from_value(ctx.eval("({ a: 1, c: undefined })").unwrap()).unwrap();
+            assert_eq!(strict, Strict { a: 1, b: 0 });
+
+            let err =
+                from_value::<Strict>(ctx.eval("({ a: 1, b: 2, c: 3 })").unwrap()).unwrap_err();
+            assert!(err.to_string().contains("unknown field `c`"), "{err}");
+
+            let err =
+                from_value::<Vec<Strict>>(ctx.eval("[{ a: 1 }, { a: 1, 'd e': 3 }]").unwrap())
+                    .unwrap_err();
+            let Error::Nested { path, .. } = &err else {
+                panic!("{err}");
+            };
+            assert_eq!(path, r#"$[1]["d e"]"#);
+
+            // Unknown fields are still ignored without `deny_unknown_fields`.
+            let server: Server =
+                from_value(ctx.eval("({ host: 'a', port: 1, extra: true })").unwrap()).unwrap();
+            assert_eq!(server.port, 1);
+        })
+    }
+
+    #[test]
+    fn value_serialize() {
+        test_with(|ctx| {
+            let value: Value = ctx.eval("({ a: [1, 'b', null], c: 2.5 })").unwrap();
+            let copy = to_value(ctx.clone(), &value).unwrap();
+            let copy = Object::from_value(copy).unwrap();
+            let a: Array = copy.get("a").unwrap();
+            assert_eq!(a.len(), 3);
+            assert_eq!(copy.get::<_, f64>("c").unwrap(), 2.5);
+        })
+    }
+
+    #[test]
+    fn maps_and_sets() {
+        test_with(|ctx| {
+            let value: BTreeMap<u32, Vec<String>> = from_value(
+                ctx.eval("new Map([[2, new Set(['b'])], [1, new Set(['a', 'c'])]])")
+                    .unwrap(),
+            )
+            .unwrap();
+            assert_eq!(value[&1], ["a", "c"]);
+            assert_eq!(value[&2], ["b"]);
+
+            let err = from_value::<BTreeMap<u32, u32>>(ctx.eval("new Map([[1, 'a']])").unwrap())
+                .unwrap_err();
+            assert!(err.to_string().contains("$[0]"), "{err}");
+
+            let value: Value = ctx.eval("new Map([['a', new Set([1])]])").unwrap();
+            let copy = Object::from_value(to_value(ctx.clone(), &value).unwrap()).unwrap();
+            let a: Array = copy.get("a").unwrap();
+            assert_eq!(a.get::<u32>(0).unwrap(), 1);
+        })
+    }
+
+    #[test]
+    fn serde_wrapper() {
+        test_with(|ctx| {
+            let Serde(server): Serde<Server> = ctx.eval("({ host: 'h', port: 1 })").unwrap();
+            assert_eq!(server.host, "h");
+            ctx.globals().set("server", Serde(server)).unwrap();
+            assert_eq!(ctx.eval::<u16, _>("server.port").unwrap(), 1);
+        })
+    }
+}
@@ -0,0 +1,660 @@
+use super::{with_path, PathSegment};
+use crate::{
+    atom::PredefinedAtom,
+    convert::Coerced,
+    map::MapIter,
+    object::{ObjectIter, ObjectKeysIter},
+    qjs,
+    set::SetIter,
+    Error, Object, Result, StdString, Type, TypedArray, Value,
+};
+use ::serde::de::{self, DeserializeSeed, Visitor};
+use alloc::borrow::Cow;
+use core::slice;
+
+/// The largest integer which can be represented exactly by a JavaScript number.
+const MAX_SAFE_INTEGER: f64 = ((1u64 << 53) - 1) as f64;
+
+/// A [`serde::Deserializer`](de::Deserializer) which reads Rust values from a JavaScript
+/// [`Value`].
+pub struct Deserializer<'js> {
+    value: Value<'js>,
+}
+
+impl<'js> Deserializer<'js> {
+    /// Create a deserializer reading from the given value.
+    pub fn new(value: Value<'js>) -> Self {
+        Self { value }
+    }
+
+    fn mismatch(&self, to: &'static str) -> Error {
+        Error::new_from_js(self.value.type_name(), to)
+    }
+
+    fn visit_big_int<'de, V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let Coerced(digits) = self.value.get::<Coerced<StdString>>()?;
+        if let Ok(v) = digits.parse::<i64>() {
+            visitor.visit_i64(v)
+        } else if let Ok(v) = digits.parse::<u64>() {
+            visitor.visit_u64(v)
+        } else if let Ok(v) = digits.parse::<i128>() {
+            visitor.visit_i128(v)
+        } else if let Ok(v) = digits.parse::<u128>() {
+            visitor.visit_u128(v)
+        } else {
+            Err(Error::new_from_js_message("BigInt", "int", "Overflow"))
+        }
+    }
+
+    fn visit_number<'de, V: Visitor<'de>>(self, to: &'static str, visitor: V) -> Result<V::Value> {
+        match self.value.type_of() {
+            Type::Int | Type::Float | Type::BigInt => {
+                de::Deserializer::deserialize_any(self, visitor)
+            }
+            _ => Err(self.mismatch(to)),
+        }
+    }
+
+    fn visit_object<'de, V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let object = Object(self.value);
+        if let Some(bytes) = array_buffer_bytes(&object) {
+            return visitor.visit_bytes(bytes);
+        }
+        if is_typed_array(&object) {
+            return visitor.visit_seq(SeqAccess::new(object)?);
+        }
+        visitor.visit_map(MapAccess {
+            iter: object.props(),
+            value: None,
+        })
+    }
+}
+
+macro_rules! deserialize_numbers {
+    ($($method:ident => $to:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                self.visit_number(stringify!($to), visitor)
+            }
+        )*
+    };
+}
+
+impl<'de, 'js> de::Deserializer<'de> for Deserializer<'js> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value.type_of() {
+            Type::Uninitialized | Type::Undefined | Type::Null => visitor.visit_unit(),
+            Type::Bool => visitor.visit_bool(unsafe { self.value.get_bool() }),
+            Type::Int => visitor.visit_i32(unsafe { self.value.get_int() }),
+            Type::Float => {
+                let v = unsafe { self.value.get_float() };
+                // JavaScript does not distinguish integers and floats so integral floats are
+                // reported as integers for the benefit of integer fields.
+                if (v as i64) as f64 == v && (-MAX_SAFE_INTEGER..=MAX_SAFE_INTEGER).contains(&v) {
+                    visitor.visit_i64(v as _)
+                } else {
+                    visitor.visit_f64(v)
+                }
+            }
+            Type::String => visitor.visit_string(unsafe { self.value.ref_string() }.to_string()?),
+            Type::BigInt => self.visit_big_int(visitor),
+            Type::Array => visitor.visit_seq(SeqAccess::new(Object(self.value))?),
+            Type::Map => visitor.visit_map(EntriesAccess {
+                iter: unsafe { self.value.ref_map() }.iter(),
+                index: 0,
+                value: None,
+            }),
+            Type::Set => visitor.visit_seq(ValuesAccess {
+                iter: unsafe { self.value.ref_set() }.iter(),
+                index: 0,
+            }),
+            Type::DataView => match unsafe { self.value.ref_data_view() }.as_bytes() {
+                Some(bytes) => visitor.visit_bytes(bytes),
+                None => Err(self.mismatch("bytes")),
+            },
+            Type::Object | Type::Exception | Type::Promise | Type::Date | Type::RegExp => {
+                self.visit_object(visitor)
+            }
+            _ => Err(self.mismatch("value")),
+        }
+    }
+
+    deserialize_numbers! {
+        deserialize_i8 => i8,
+        deserialize_i16 => i16,
+        deserialize_i32 => i32,
+        deserialize_i64 => i64,
+        deserialize_i128 => i128,
+        deserialize_u8 => u8,
+        deserialize_u16 => u16,
+        deserialize_u32 => u32,
+        deserialize_u64 => u64,
+        deserialize_u128 => u128,
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_f64(visitor)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value.as_number() {
+            Some(v) => visitor.visit_f64(v),
+            None => self.visit_number("f64", visitor),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value.as_bool() {
+            Some(v) => visitor.visit_bool(v),
+            None => Err(self.mismatch("bool")),
+        }
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value.as_string() {
+            Some(v) => visitor.visit_string(v.to_string()?),
+            None => Err(self.mismatch("char")),
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value.as_string() {
+            Some(v) => visitor.visit_string(v.to_string()?),
+            None => Err(self.mismatch("string")),
+        }
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let Some(object) = self.value.as_object() else {
+            return Err(self.mismatch("bytes"));
+        };
+        if let Some(bytes) = array_buffer_bytes(object)
+            .or_else(|| typed_array_bytes(object))
+            .or_else(|| object.as_data_view().and_then(|view| view.as_bytes()))
+        {
+            return visitor.visit_bytes(bytes);
+        }
+        if self.value.is_array() {
+            return visitor.visit_seq(SeqAccess::new(Object(self.value))?);
+        }
+        Err(self.mismatch("bytes"))
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        if self.value.type_of().is_void() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        if self.value.type_of().is_void() {
+            visitor.visit_unit()
+        } else {
+            Err(self.mismatch("()"))
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value.as_object() {
+            Some(object) if self.value.is_array() || is_typed_array(object) => {
+                visitor.visit_seq(SeqAccess::new(Object(self.value))?)
+            }
+            Some(_) if self.value.is_set() => de::Deserializer::deserialize_any(self, visitor),
+            _ => Err(self.mismatch("array")),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        if self.value.is_map() {
+            de::Deserializer::deserialize_any(self, visitor)
+        } else if self.value.is_object() && !self.value.is_function() {
+            visitor.visit_map(MapAccess {
+                iter: Object(self.value).props(),
+                value: None,
+            })
+        } else {
+            Err(self.mismatch("object"))
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        if self.value.is_array() {
+            return self.deserialize_seq(visitor);
+        }
+        if !self.value.is_object() || self.value.is_function() {
+            return Err(self.mismatch("object"));
+        }
+        // Fields are looked up one by one instead of enumerating the own properties so inherited
+        // properties and getters, like those of class instances, are visible as well. The other
+        // own properties are passed on afterwards so `deny_unknown_fields` can reject them.
+        let object = Object(self.value);
+        visitor.visit_map(StructAccess {
+            unknown: object.keys(),
+            object,
+            fields,
+            known: fields.iter(),
+            value: None,
+        })
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        if let Some(variant) = self.value.as_string() {
+            return visitor.visit_enum(EnumAccess {
+                variant: variant.to_string()?,
+                value: None,
+            });
+        }
+        let from = self.value.type_name();
+        if let Some(object) = self.value.as_object() {
+            let mut props = object.props::<StdString, Value>();
+            if let (Some(prop), None) = (props.next(), props.next()) {
+                let (variant, value) = prop?;
+                return visitor.visit_enum(EnumAccess {
+                    variant,
+                    value: Some(value),
+                });
+            }
+        }
+        Err(Error::new_from_js_message(
+            from,
+            "enum",
+            "Expected a string or an object with a single property",
+        ))
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+}
+
+/// Returns the bytes of an `ArrayBuffer`.
+pub(super) fn array_buffer_bytes<'a>(object: &'a Object) -> Option<&'a [u8]> {
+    object
+        .as_array_buffer()
+        .and_then(|buffer| buffer.as_bytes())
+}
+
+/// Returns whether the object is any kind of typed array.
+pub(super) fn is_typed_array(object: &Object) -> bool {
+    unsafe { qjs::JS_GetTypedArrayType(object.as_js_value()) >= 0 }
+}
+
+/// Returns the bytes viewed by a typed array of any element type.
+fn typed_array_bytes<'a>(object: &'a Object) -> Option<&'a [u8]> {
+    if !is_typed_array(object) {
+        return None;
+    }
+    let (_, len, ptr) = TypedArray::<u8>::get_raw_bytes(object.as_value())?;
+    Some(unsafe { slice::from_raw_parts(ptr.as_ptr(), len) })
+}
+
+/// Returns the `length` of an array or typed array.
+pub(super) fn array_like_len(object: &Object) -> Result<usize> {
+    object.get(PredefinedAtom::Length)
+}
+
+struct SeqAccess<'js> {
+    object: Object<'js>,
+    index: usize,
+    len: usize,
+}
+
+impl<'js> SeqAccess<'js> {
+    fn new(object: Object<'js>) -> Result<Self> {
+        let len = array_like_len(&object)?;
+        Ok(Self {
+            object,
+            index: 0,
+            len,
+        })
+    }
+}
+
+impl<'de, 'js> de::SeqAccess<'de> for SeqAccess<'js> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+        if self.index >= self.len {
+            return Ok(None);
+        }
+        let index = self.index;
+        self.index += 1;
+        let value: Value = self.object.get(index as u32)?;
+        seed.deserialize(Deserializer::new(value))
+            .map(Some)
+            .map_err(|e| with_path(e, PathSegment::Index(index)))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len - self.index)
+    }
+}
+
+struct MapAccess<'js> {
+    iter: ObjectIter<'js, StdString, Value<'js>>,
+    value: Option<(StdString, Value<'js>)>,
+}
+
+impl<'de, 'js> de::MapAccess<'de> for MapAccess<'js> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        let Some((key, value)) = self.iter.next().transpose()? else {
+            return Ok(None);
+        };
+        let res = seed
+            .deserialize(KeyDeserializer(&key))
+            .map_err(|e| with_path(e, PathSegment::Key(&key)))?;
+        self.value = Some((key, value));
+        Ok(Some(res))
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let (key, value) = self
+            .value
+            .take()
+            .expect("next_value_seed called before next_key_seed");
+        seed.deserialize(Deserializer::new(value))
+            .map_err(|e| with_path(e, PathSegment::Key(&key)))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct EntriesAccess<'js> {
+    iter: MapIter<'js, Value<'js>, Value<'js>>,
+    index: usize,
+    value: Option<Value<'js>>,
+}
+
+impl<'de, 'js> de::MapAccess<'de> for EntriesAccess<'js> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        let Some((key, value)) = self.iter.next().transpose()? else {
+            return Ok(None);
+        };
+        self.index += 1;
+        let res = seed
+            .deserialize(Deserializer::new(key))
+            .map_err(|e| with_path(e, PathSegment::Index(self.index - 1)))?;
+        self.value = Some(value);
+        Ok(Some(res))
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let value = self
+            .value
+            .take()
+            .expect("next_value_seed called before next_key_seed");
+        seed.deserialize(Deserializer::new(value))
+            .map_err(|e| with_path(e, PathSegment::Index(self.index - 1)))
+    }
+}
+
+struct ValuesAccess<'js> {
+    iter: SetIter<'js, Value<'js>>,
+    index: usize,
+}
+
+impl<'de, 'js> de::SeqAccess<'de> for ValuesAccess<'js> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+        let Some(value) = self.iter.next().transpose()? else {
+            return Ok(None);
+        };
+        let index = self.index;
+        self.index += 1;
+        seed.deserialize(Deserializer::new(value))
+            .map(Some)
+            .map_err(|e| with_path(e, PathSegment::Index(index)))
+    }
+}
+
+struct StructAccess<'js> {
+    object: Object<'js>,
+    fields: &'static [&'static str],
+    known: slice::Iter<'static, &'static str>,
+    unknown: ObjectKeysIter<'js, StdString>,
+    value: Option<(Cow<'static, str>, Value<'js>)>,
+}
+
+impl<'de, 'js> de::MapAccess<'de> for StructAccess<'js> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        for &field in self.known.by_ref() {
+            let value: Value = self.object.get(field)?;
+            // Missing and undefined properties are left to serde which either uses a default or
+            // reports the missing field.
+            if value.is_undefined() {
+                continue;
+            }
+            self.value = Some((Cow::Borrowed(field), value));
+            return seed
+                .deserialize(de::value::StrDeserializer::<Error>::new(field))
+                .map(Some);
+        }
+        for key in self.unknown.by_ref() {
+            let key = key?;
+            if self.fields.contains(&key.as_str()) {
+                continue;
+            }
+            let value: Value = self.object.get(key.as_str())?;
+            if value.is_undefined() {
+                continue;
+            }
+            let res = seed
+                .deserialize(KeyDeserializer(&key))
+                .map_err(|e| with_path(e, PathSegment::Key(&key)))?;
+            self.value = Some((Cow::Owned(key), value));
+            return Ok(Some(res));
+        }
+        Ok(None)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let (key, value) = self
+            .value
+            .take()
+            .expect("next_value_seed called before next_key_seed");
+        seed.deserialize(Deserializer::new(value))
+            .map_err(|e| with_path(e, PathSegment::Key(&key)))
+    }
+}
+
+struct EnumAccess<'js> {
+    variant: StdString,
+    value: Option<Value<'js>>,
+}
+
+impl<'de, 'js> de::EnumAccess<'de> for EnumAccess<'js> {
+    type Error = Error;
+    type Variant = VariantAccess<'js>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self::Variant)> {
+        let variant = seed.deserialize(de::value::StrDeserializer::<Error>::new(&self.variant))?;
+        Ok((
+            variant,
+            VariantAccess {
+                variant: self.variant,
+                value: self.value,
+            },
+        ))
+    }
+}
+
+struct VariantAccess<'js> {
+    variant: StdString,
+    value: Option<Value<'js>>,
+}
+
+impl<'js> VariantAccess<'js> {
+    fn content<T>(self, f: impl FnOnce(Deserializer<'js>) -> Result<T>) -> Result<T> {
+        match self.value {
+            Some(value) => f(Deserializer::new(value))
+                .map_err(|e| with_path(e, PathSegment::Key(&self.variant))),
+            None => Err(Error::new_from_js_message(
+                "string",
+                "enum",
+                "Expected an object for a variant with content",
+            )),
+        }
+    }
+}
+
+impl<'de, 'js> de::VariantAccess<'de> for VariantAccess<'js> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        match self.value {
+            Some(value) if !value.type_of().is_void() => Err(Error::new_from_js_message(
+                value.type_name(),
+                "enum",
+                "Expected a unit variant",
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        self.content(|de| seed.deserialize(de))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value> {
+        self.content(|de| de::Deserializer::deserialize_tuple(de, len, visitor))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.content(|de| de::Deserializer::deserialize_struct(de, "", fields, visitor))
+    }
+}
+
+/// Deserializes the keys of an object which are always strings but may be parsed as numbers
+/// and booleans for maps with such keys.
+struct KeyDeserializer<'a>(&'a str);
+
+macro_rules! deserialize_parsed_keys {
+    ($($method:ident => $visit:ident $type:ty,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                match self.0.parse::<$type>() {
+                    Ok(v) => visitor.$visit(v),
+                    Err(_) => visitor.visit_str(self.0),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for KeyDeserializer<'_> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_str(self.0)
+    }
+
+    deserialize_parsed_keys! {
+        deserialize_bool => visit_bool bool,
+        deserialize_i8 => visit_i8 i8,
+        deserialize_i16 => visit_i16 i16,
+        deserialize_i32 => visit_i32 i32,
+        deserialize_i64 => visit_i64 i64,
+        deserialize_i128 => visit_i128 i128,
+        deserialize_u8 => visit_u8 u8,
+        deserialize_u16 => visit_u16 u16,
+        deserialize_u32 => visit_u32 u32,
+        deserialize_u64 => visit_u64 u64,
+        deserialize_u128 => visit_u128 u128,
+        deserialize_f32 => visit_f32 f32,
+        deserialize_f64 => visit_f64 f64,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_enum(de::value::StrDeserializer::<Error>::new(self.0))
+    }
+
+    ::serde::forward_to_deserialize_any! {
+        char str string bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
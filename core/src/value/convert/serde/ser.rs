@@ -0,0 +1,506 @@
+use super::{
+    de::{array_buffer_bytes, array_like_len, is_typed_array},
+    with_path, PathSegment,
+};
+use crate::{
+    atom::PredefinedAtom, Array, Atom, BigInt, Ctx, Error, Function, IntoJs, Object, Result,
+    StdString, String, Type, TypedArray, Value,
+};
+use ::serde::ser::{self, Serialize, SerializeMap as _, SerializeSeq as _};
+use alloc::string::ToString as _;
+
+/// The largest integer which can be represented exactly by a JavaScript number.
+const MAX_SAFE_INTEGER: i64 = (1 << 53) - 1;
+
+/// A [`serde::Serializer`](ser::Serializer) which turns Rust values into JavaScript [`Value`]s.
+#[derive(Clone)]
+pub struct Serializer<'js> {
+    ctx: Ctx<'js>,
+}
+
+impl<'js> Serializer<'js> {
+    /// Create a serializer which creates values in the given context.
+    pub fn new(ctx: Ctx<'js>) -> Self {
+        Self { ctx }
+    }
+
+    fn big_int_from_str(&self, digits: &str) -> Result<Value<'js>> {
+        let ctor: Function = self.ctx.globals().get(PredefinedAtom::BigInt)?;
+        ctor.call((digits,))
+    }
+}
+
+impl<'js> ser::Serializer for Serializer<'js> {
+    type Ok = Value<'js>;
+    type Error = Error;
+
+    type SerializeSeq = SerializeArray<'js>;
+    type SerializeTuple = SerializeArray<'js>;
+    type SerializeTupleStruct = SerializeArray<'js>;
+    type SerializeTupleVariant = SerializeVariant<'js, SerializeArray<'js>>;
+    type SerializeMap = SerializeObject<'js>;
+    type SerializeStruct = SerializeObject<'js>;
+    type SerializeStructVariant = SerializeVariant<'js, SerializeObject<'js>>;
+
+    fn serialize_bool(self, v: bool) -> Result<Value<'js>> {
+        Ok(Value::new_bool(self.ctx, v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value<'js>> {
+        Ok(Value::new_int(self.ctx, v as _))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value<'js>> {
+        Ok(Value::new_int(self.ctx, v as _))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value<'js>> {
+        Ok(Value::new_int(self.ctx, v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value<'js>> {
+        if (-MAX_SAFE_INTEGER..=MAX_SAFE_INTEGER).contains(&v) {
+            Ok(Value::new_number(self.ctx, v as _))
+        } else {
+            BigInt::from_i64(self.ctx, v).map(BigInt::into_value)
+        }
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Value<'js>> {
+        if let Ok(v) = i64::try_from(v) {
+            self.serialize_i64(v)
+        } else if let Ok(v) = u64::try_from(v) {
+            self.serialize_u64(v)
+        } else {
+            self.big_int_from_str(&v.to_string())
+        }
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value<'js>> {
+        Ok(Value::new_int(self.ctx, v as _))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value<'js>> {
+        Ok(Value::new_int(self.ctx, v as _))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value<'js>> {
+        v.into_js(&self.ctx)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value<'js>> {
+        if v <= MAX_SAFE_INTEGER as u64 {
+            Ok(Value::new_number(self.ctx, v as _))
+        } else {
+            BigInt::from_u64(self.ctx, v).map(BigInt::into_value)
+        }
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Value<'js>> {
+        if let Ok(v) = u64::try_from(v) {
+            self.serialize_u64(v)
+        } else {
+            self.big_int_from_str(&v.to_string())
+        }
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value<'js>> {
+        Ok(Value::new_float(self.ctx, v as _))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value<'js>> {
+        Ok(Value::new_float(self.ctx, v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value<'js>> {
+        v.into_js(&self.ctx)
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value<'js>> {
+        String::from_str(self.ctx, v).map(String::into_value)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value<'js>> {
+        TypedArray::<u8>::new_copy(self.ctx, v).map(TypedArray::into_value)
+    }
+
+    fn serialize_none(self) -> Result<Value<'js>> {
+        Ok(Value::new_null(self.ctx))
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value<'js>> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value<'js>> {
+        Ok(Value::new_null(self.ctx))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value<'js>> {
+        Ok(Value::new_null(self.ctx))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Value<'js>> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Value<'js>> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value<'js>> {
+        let object = Object::new(self.ctx.clone())?;
+        let value = value
+            .serialize(self)
+            .map_err(|e| with_path(e, PathSegment::Key(variant)))?;
+        object.set(variant, value)?;
+        Ok(object.into_value())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<SerializeArray<'js>> {
+        Ok(SerializeArray {
+            array: Array::new(self.ctx)?,
+            index: 0,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeArray<'js>> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeArray<'js>> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Ok(SerializeVariant {
+            object: Object::new(self.ctx.clone())?,
+            variant,
+            inner: self.serialize_seq(Some(len))?,
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<SerializeObject<'js>> {
+        Ok(SerializeObject {
+            object: Object::new(self.ctx)?,
+            key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<SerializeObject<'js>> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Ok(SerializeVariant {
+            object: Object::new(self.ctx.clone())?,
+            variant,
+            inner: self.serialize_map(Some(len))?,
+        })
+    }
+}
+
+/// Serializes sequences and tuples into an [`Array`].
+pub struct SerializeArray<'js> {
+    array: Array<'js>,
+    index: usize,
+}
+
+impl<'js> ser::SerializeSeq for SerializeArray<'js> {
+    type Ok = Value<'js>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let value = value
+            .serialize(Serializer::new(self.array.ctx().clone()))
+            .map_err(|e| with_path(e, PathSegment::Index(self.index)))?;
+        self.array.set(self.index, value)?;
+        self.index += 1;
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value<'js>> {
+        Ok(self.array.into_value())
+    }
+}
+
+impl<'js> ser::SerializeTuple for SerializeArray<'js> {
+    type Ok = Value<'js>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value<'js>> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl<'js> ser::SerializeTupleStruct for SerializeArray<'js> {
+    type Ok = Value<'js>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value<'js>> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+/// Serializes maps and structs into an [`Object`].
+pub struct SerializeObject<'js> {
+    object: Object<'js>,
+    key: Option<Atom<'js>>,
+}
+
+impl<'js> SerializeObject<'js> {
+    fn serialize_property<T: Serialize + ?Sized>(&self, key: &str, value: &T) -> Result<()> {
+        let value = value
+            .serialize(Serializer::new(self.object.ctx().clone()))
+            .map_err(|e| with_path(e, PathSegment::Key(key)))?;
+        self.object.set(key, value)
+    }
+}
+
+impl<'js> ser::SerializeMap for SerializeObject<'js> {
+    type Ok = Value<'js>;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        let ctx = self.object.ctx().clone();
+        let key = key.serialize(Serializer::new(ctx.clone()))?;
+        let type_ = key.type_of();
+        if !matches!(
+            type_,
+            Type::String | Type::Symbol | Type::Int | Type::Float | Type::Bool | Type::BigInt
+        ) {
+            return Err(Error::new_into_js_message(
+                type_.as_str(),
+                "atom",
+                "Map keys must be strings, numbers or symbols",
+            ));
+        }
+        self.key = Some(Atom::from_value(ctx, &key)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let key = self
+            .key
+            .take()
+            .expect("serialize_value called before serialize_key");
+        let value = value
+            .serialize(Serializer::new(self.object.ctx().clone()))
+            .map_err(|e| match key.to_string() {
+                Ok(key) => with_path(e, PathSegment::Key(&key)),
+                Err(_) => e,
+            })?;
+        self.object.set(key, value)
+    }
+
+    fn end(self) -> Result<Value<'js>> {
+        Ok(self.object.into_value())
+    }
+}
+
+impl<'js> ser::SerializeStruct for SerializeObject<'js> {
+    type Ok = Value<'js>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.serialize_property(key, value)
+    }
+
+    fn end(self) -> Result<Value<'js>> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+/// Serializes tuple and struct enum variants into an object with the variant name as its only
+/// key.
+pub struct SerializeVariant<'js, S> {
+    object: Object<'js>,
+    variant: &'static str,
+    inner: S,
+}
+
+impl<'js> ser::SerializeTupleVariant for SerializeVariant<'js, SerializeArray<'js>> {
+    type Ok = Value<'js>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.inner
+            .serialize_element(value)
+            .map_err(|e| with_path(e, PathSegment::Key(self.variant)))
+    }
+
+    fn end(self) -> Result<Value<'js>> {
+        self.object.set(self.variant, self.inner.array)?;
+        Ok(self.object.into_value())
+    }
+}
+
+impl<'js> ser::SerializeStructVariant for SerializeVariant<'js, SerializeObject<'js>> {
+    type Ok = Value<'js>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.inner
+            .serialize_property(key, value)
+            .map_err(|e| with_path(e, PathSegment::Key(self.variant)))
+    }
+
+    fn end(self) -> Result<Value<'js>> {
+        self.object.set(self.variant, self.inner.object)?;
+        Ok(self.object.into_value())
+    }
+}
+
+/// Serialize a JavaScript value as the equivalent serde data model value.
+///
+/// Objects are serialized as maps of their own enumerable string properties, `Map`s as maps of
+/// their entries and `Set`s as sequences of their values. Functions, symbols and other values
+/// without a serde equivalent result in an error.
+impl<'js> Serialize for Value<'js> {
+    fn serialize<S>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        use ser::Error as _;
+
+        let type_ = self.type_of();
+        match type_ {
+            Type::Uninitialized | Type::Undefined | Type::Null => serializer.serialize_unit(),
+            Type::Bool => serializer.serialize_bool(unsafe { self.get_bool() }),
+            Type::Int => serializer.serialize_i32(unsafe { self.get_int() }),
+            Type::Float => serializer.serialize_f64(unsafe { self.get_float() }),
+            Type::String => {
+                let string = unsafe { self.ref_string() }
+                    .to_string()
+                    .map_err(S::Error::custom)?;
+                serializer.serialize_str(&string)
+            }
+            Type::BigInt => {
+                let digits = self
+                    .get::<crate::convert::Coerced<StdString>>()
+                    .map_err(S::Error::custom)?;
+                if let Ok(v) = digits.parse::<i64>() {
+                    serializer.serialize_i64(v)
+                } else if let Ok(v) = digits.parse::<u64>() {
+                    serializer.serialize_u64(v)
+                } else if let Ok(v) = digits.parse::<i128>() {
+                    serializer.serialize_i128(v)
+                } else if let Ok(v) = digits.parse::<u128>() {
+                    serializer.serialize_u128(v)
+                } else {
+                    Err(S::Error::custom("BigInt too large to be serialized"))
+                }
+            }
+            Type::Array => serialize_elements(unsafe { self.ref_object() }, serializer),
+            Type::Map => {
+                let mut map = serializer.serialize_map(None)?;
+                for entry in unsafe { self.ref_map() }.iter::<Value, Value>() {
+                    let (key, value) = entry.map_err(S::Error::custom)?;
+                    map.serialize_entry(&key, &value)?;
+                }
+                map.end()
+            }
+            Type::Set => {
+                let mut seq = serializer.serialize_seq(None)?;
+                for value in unsafe { self.ref_set() }.iter::<Value>() {
+                    seq.serialize_element(&value.map_err(S::Error::custom)?)?;
+                }
+                seq.end()
+            }
+            Type::DataView => {
+                let bytes = unsafe { self.ref_data_view() }
+                    .as_bytes()
+                    .ok_or_else(|| S::Error::custom("DataView is detached"))?;
+                serializer.serialize_bytes(bytes)
+            }
+            Type::Object | Type::Exception | Type::Promise | Type::Date | Type::RegExp => {
+                let object = unsafe { self.ref_object() };
+                if let Some(bytes) = array_buffer_bytes(object).or_else(|| {
+                    object
+                        .as_typed_array::<u8>()
+                        .and_then(|array| array.as_bytes())
+                }) {
+                    return serializer.serialize_bytes(bytes);
+                }
+                if is_typed_array(object) {
+                    return serialize_elements(object, serializer);
+                }
+                let mut map = serializer.serialize_map(None)?;
+                for prop in object.props::<StdString, Value>() {
+                    let (key, value) = prop.map_err(S::Error::custom)?;
+                    map.serialize_entry(&key, &value)?;
+                }
+                map.end()
+            }
+            _ => Err(S::Error::custom(Error::new_from_js(
+                type_.as_str(),
+                "serde value",
+            ))),
+        }
+    }
+}
+
+fn serialize_elements<'js, S>(
+    object: &Object<'js>,
+    serializer: S,
+) -> core::result::Result<S::Ok, S::Error>
+where
+    S: ser::Serializer,
+{
+    use ser::Error as _;
+
+    let len = array_like_len(object).map_err(S::Error::custom)?;
+    let mut seq = serializer.serialize_seq(Some(len))?;
+    for idx in 0..len {
+        let value: Value = object.get(idx as u32).map_err(S::Error::custom)?;
+        seq.serialize_element(&value)?;
+    }
+    seq.end()
+}
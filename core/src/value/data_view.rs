@@ -0,0 +1,208 @@
+//! JavaScript data view types.
+
+use crate::{qjs, ArrayBuffer, Exception, IntoJs, Object, Result};
+
+use super::call;
+use core::{mem::size_of, ptr, slice};
+
+/// Types which can be read from and written to a [`DataView`].
+pub trait DataViewItem: Copy {
+    #[doc(hidden)]
+    fn from_bytes(bytes: [u8; 8], little_endian: bool) -> Self;
+
+    #[doc(hidden)]
+    fn to_bytes(self, little_endian: bool) -> [u8; 8];
+}
+
+macro_rules! data_view_items {
+    ($($type:ty,)*) => {
+        $(impl DataViewItem for $type {
+            fn from_bytes(bytes: [u8; 8], little_endian: bool) -> Self {
+                let mut value = [0; size_of::<$type>()];
+                value.copy_from_slice(&bytes[..size_of::<$type>()]);
+                if little_endian {
+                    <$type>::from_le_bytes(value)
+                } else {
+                    <$type>::from_be_bytes(value)
+                }
+            }
+
+            fn to_bytes(self, little_endian: bool) -> [u8; 8] {
+                let value = if little_endian {
+                    self.to_le_bytes()
+                } else {
+                    self.to_be_bytes()
+                };
+                let mut bytes = [0; 8];
+                bytes[..size_of::<$type>()].copy_from_slice(&value);
+                bytes
+            }
+        })*
+    };
+}
+
+data_view_items! {
+    i8, u8, i16, u16, i32, u32, i64, u64, f32, f64,
+}
+
+/// Rust representation of a JavaScript object of class `DataView`.
+///
+/// A data view reads and writes values of different types at arbitrary byte offsets of an
+/// [`ArrayBuffer`] with a chosen endianness.
+#[derive(Debug, PartialEq, Clone, Eq, Hash)]
+#[repr(transparent)]
+pub struct DataView<'js>(pub(crate) Object<'js>);
+
+impl<'js> DataView<'js> {
+    /// Create a new data view over a part of an array buffer.
+    ///
+    /// The view covers the rest of the buffer after `byte_offset` if `byte_length` is `None`.
+    pub fn new(
+        buffer: ArrayBuffer<'js>,
+        byte_offset: usize,
+        byte_length: Option<usize>,
+    ) -> Result<Self> {
+        let ctx = buffer.ctx().clone();
+        let (offset, length) = (byte_offset.into_js(&ctx)?, byte_length.into_js(&ctx)?);
+        call(&ctx, |ctx| unsafe {
+            qjs::rquickjs_dataview_new(
+                ctx,
+                buffer.as_js_value(),
+                offset.as_js_value(),
+                length.as_js_value(),
+            )
+        })
+    }
+
+    /// Returns the array buffer referenced by the view.
+    pub fn buffer(&self) -> Result<ArrayBuffer<'js>> {
+        self.0.get("buffer")
+    }
+
+    /// Returns the offset of the view from the start of its array buffer in bytes.
+    pub fn byte_offset(&self) -> Result<usize> {
+        self.0.get("byteOffset")
+    }
+
+    /// Returns the length of the view in bytes.
+    pub fn byte_length(&self) -> Result<usize> {
+        self.0.get("byteLength")
+    }
+
+    /// Read a value at the given byte offset of the view.
+    ///
+    /// Returns a `RangeError` exception if the value does not fit in the view.
+    pub fn get<T: DataViewItem>(&self, offset: usize, little_endian: bool) -> Result<T> {
+        let mut bytes = [0; 8];
+        let ptr = self.item_ptr(offset, size_of::<T>())?;
+        unsafe { ptr::copy_nonoverlapping(ptr, bytes.as_mut_ptr(), size_of::<T>()) };
+        Ok(T::from_bytes(bytes, little_endian))
+    }
+
+    /// Write a value at the given byte offset of the view.
+    ///
+    /// Returns a `RangeError` exception if the value does not fit in the view.
+    pub fn set<T: DataViewItem>(&self, offset: usize, value: T, little_endian: bool) -> Result<()> {
+        let bytes = value.to_bytes(little_endian);
+        let ptr = self.item_ptr(offset, size_of::<T>())?;
+        unsafe { ptr::copy_nonoverlapping(bytes.as_ptr(), ptr, size_of::<T>()) };
+        Ok(())
+    }
+
+    /// Returns the bytes covered by the view.
+    ///
+    /// Returns `None` if the underlying array buffer is detached.
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        let raw = ArrayBuffer::get_raw(self.buffer().ok()?.as_value())?;
+        let offset = self.byte_offset().ok()?;
+        let len = self.byte_length().ok()?;
+        if offset + len > raw.len {
+            return None;
+        }
+        Some(unsafe { slice::from_raw_parts(raw.ptr.as_ptr().add(offset), len) })
+    }
+
+    pub fn into_object(self) -> Object<'js> {
+        self.0
+    }
+
+    pub fn as_object(&self) -> &Object<'js> {
+        &self.0
+    }
+
+    fn item_ptr(&self, offset: usize, size: usize) -> Result<*mut u8> {
+        let ctx = self.ctx();
+        let raw = ArrayBuffer::get_raw(self.buffer()?.as_value())
+            .ok_or_else(|| Exception::throw_type(ctx, "ArrayBuffer is detached"))?;
+        let view_offset = self.byte_offset()?;
+        let view_len = self.byte_length()?;
+        match offset.checked_add(size) {
+            Some(end) if end <= view_len && view_offset + end <= raw.len => {}
+            _ => {
+                return Err(Exception::throw_range(
+                    ctx,
+                    "Offset is outside the bounds of the DataView",
+                ))
+            }
+        }
+        Ok(unsafe { raw.ptr.as_ptr().add(view_offset + offset) })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn from_javascript() {
+        test_with(|ctx| {
+            let view: DataView = ctx
+                .eval(
+                    r#"
+                    const view = new DataView(new ArrayBuffer(16), 4, 8);
+                    view.setUint16(0, 0x1234);
+                    view.setFloat32(2, 1.5, true);
+                    view
+                "#,
+                )
+                .unwrap();
+            assert_eq!(view.byte_offset().unwrap(), 4);
+            assert_eq!(view.byte_length().unwrap(), 8);
+            assert_eq!(view.buffer().unwrap().len(), 16);
+            assert_eq!(view.get::<u16>(0, false).unwrap(), 0x1234);
+            assert_eq!(view.get::<u16>(0, true).unwrap(), 0x3412);
+            assert_eq!(view.get::<f32>(2, true).unwrap(), 1.5);
+            assert_eq!(view.as_bytes().unwrap().len(), 8);
+
+            let err = view.get::<u64>(4, true).unwrap_err();
+            assert!(err.is_exception());
+            ctx.globals().set("err", ctx.catch()).unwrap();
+            assert!(ctx.eval::<bool, _>("err instanceof RangeError").unwrap());
+        });
+    }
+
+    #[test]
+    fn into_javascript() {
+        test_with(|ctx| {
+            let buffer = ArrayBuffer::new(ctx.clone(), vec![0u8; 12]).unwrap();
+            ctx.eval::<(), _>("globalThis.DataView = null").unwrap();
+            let view = DataView::new(buffer, 2, None).unwrap();
+            assert_eq!(view.type_of(), Type::DataView);
+            assert_eq!(view.byte_length().unwrap(), 10);
+            view.set(0, -2i64, false).unwrap();
+            view.set(8, 7u8, true).unwrap();
+            ctx.globals().set("view", view).unwrap();
+            let ok: bool = ctx
+                .eval(
+                    r#"
+                    Object.getPrototypeOf(view)[Symbol.toStringTag] === "DataView" &&
+                    view.getBigInt64(0) === -2n &&
+                    view.getUint8(8) === 7 &&
+                    new Uint8Array(view.buffer)[10] === 7
+                "#,
+                )
+                .unwrap();
+            assert!(ok);
+        });
+    }
+}
@@ -0,0 +1,84 @@
+use crate::{qjs, Ctx, Error, Object, Result, Value};
+
+/// Rust representation of a JavaScript object of class `Date`.
+#[derive(Debug, PartialEq, Clone, Eq, Hash)]
+#[repr(transparent)]
+pub struct Date<'js>(pub(crate) Object<'js>);
+
+impl<'js> Date<'js> {
+    /// Create a new date from the number of milliseconds since the unix epoch.
+    pub fn new(ctx: Ctx<'js>, epoch_ms: f64) -> Result<Self> {
+        Ok(Date(unsafe {
+            let val = qjs::JS_NewDate(ctx.as_ptr(), epoch_ms);
+            let val = ctx.handle_exception(val)?;
+            Value::from_js_value(ctx, val)
+                .into_object()
+                .expect("dates should always be objects")
+        }))
+    }
+
+    /// Returns the number of milliseconds since the unix epoch.
+    ///
+    /// Returns `NaN` if the date is invalid.
+    pub fn epoch_ms(&self) -> Result<f64> {
+        let mut time = 0.0;
+        let res = unsafe {
+            qjs::rquickjs_date_get_time(self.0.ctx().as_ptr(), self.0.as_js_value(), &mut time)
+        };
+        if res < 0 {
+            return Err(Error::Exception);
+        }
+        Ok(time)
+    }
+
+    /// Returns whether the date represents an actual point in time.
+    pub fn is_valid(&self) -> Result<bool> {
+        self.epoch_ms().map(|ms| !ms.is_nan())
+    }
+
+    pub fn into_object(self) -> Object<'js> {
+        self.0
+    }
+
+    pub fn as_object(&self) -> &Object<'js> {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn from_javascript() {
+        test_with(|ctx| {
+            let date: Date = ctx.eval("new Date(Date.UTC(2020, 0, 1))").unwrap();
+            assert_eq!(date.epoch_ms().unwrap(), 1577836800000.0);
+            assert!(date.is_valid().unwrap());
+
+            let invalid: Date = ctx.eval("new Date('not a date')").unwrap();
+            assert!(!invalid.is_valid().unwrap());
+
+            ctx.eval::<(), _>("Date.prototype.getTime = () => 1")
+                .unwrap();
+            assert_eq!(date.epoch_ms().unwrap(), 1577836800000.0);
+
+            let value: Value = ctx.eval("({ getTime() { return 1 } })").unwrap();
+            assert!(!value.is_date());
+            assert!(Date::from_js(&ctx, value).is_err());
+        });
+    }
+
+    #[test]
+    fn into_javascript() {
+        test_with(|ctx| {
+            let date = Date::new(ctx.clone(), 1500.0).unwrap();
+            assert_eq!(date.type_of(), Type::Date);
+            ctx.globals().set("d", date).unwrap();
+            let ok: bool = ctx
+                .eval("d instanceof Date && d.getTime() === 1500")
+                .unwrap();
+            assert!(ok);
+        });
+    }
+}
@@ -1,7 +1,11 @@
-use alloc::string::String;
-use core::{error::Error as ErrorTrait, ffi::CStr, fmt};
+use alloc::{string::String, vec::Vec};
+use core::{error::Error as ErrorTrait, ffi::CStr, fmt, mem::MaybeUninit};
 
-use crate::{atom::PredefinedAtom, convert::Coerced, qjs, Ctx, Error, Object, Result, Value};
+use crate::{atom::PredefinedAtom, convert::Coerced, qjs, Atom, Ctx, Error, Object, Result, Value};
+
+pub(crate) mod capture;
+mod info;
+pub use info::{ExceptionInfo, StackFrame};
 
 /// A JavaScript instance of Error
 ///
@@ -79,14 +83,96 @@ impl<'js> Exception<'js> {
 
     /// Returns the error stack.
     ///
-    /// Same as retrieving `error.stack` in JavaScript.
+    /// Same as retrieving `error.stack` in JavaScript, except that frames in scripts or modules
+    /// with a registered [`SourceMap`](crate::loader::SourceMap) point into the original sources
+    /// and that the stack is formatted by the
+    /// [stack formatter](crate::Runtime::set_stack_formatter) of the runtime, if any. A stack
+    /// returned by a custom `Error.prepareStackTrace` is not remapped.
     pub fn stack(&self) -> Option<String> {
-        self.get::<_, Option<Coerced<String>>>(PredefinedAtom::Stack)
+        let stack = self
+            .get::<_, Option<Coerced<String>>>(PredefinedAtom::Stack)
+            .ok()
+            .and_then(|x| x)
+            .map(|x| x.0)?;
+        self.finish_stack(stack)
+    }
+
+    /// Remap and format the value of the `stack` property.
+    pub(crate) fn finish_stack(&self, stack: String) -> Option<String> {
+        let stack = self.remap_stack(stack);
+        unsafe { self.ctx().get_opaque() }
+            .format_stack(self.ctx().clone(), self, &self.frames())
+            .or(Some(stack))
+    }
+
+    /// Returns the stack remapped by source maps, if it is the stack formatted by QuickJS.
+    #[cfg(feature = "loader")]
+    fn remap_stack(&self, stack: String) -> String {
+        let Some(mut frames) = capture::captured_frames(self.as_object()) else {
+            return stack;
+        };
+        let source_maps = unsafe { self.ctx().get_opaque() }.source_maps();
+        if stack != capture::render(&frames) || !source_maps.remap_frames(&mut frames) {
+            return stack;
+        }
+        capture::render(&frames)
+    }
+
+    #[cfg(not(feature = "loader"))]
+    fn remap_stack(&self, stack: String) -> String {
+        stack
+    }
+
+    /// Returns the name of the error, like `TypeError`.
+    ///
+    /// Same as retrieving `error.name` in JavaScript.
+    pub fn name(&self) -> Option<String> {
+        self.get::<_, Option<Coerced<String>>>(PredefinedAtom::Name)
             .ok()
             .and_then(|x| x)
             .map(|x| x.0)
     }
 
+    /// Returns the frames of the error stack.
+    ///
+    /// The frames are captured by the engine when the error is created, and are not affected by
+    /// `Error.prepareStackTrace`, by changes to the `stack` property or by the stack formatter.
+    /// Frames in scripts or modules with a registered [`SourceMap`](crate::loader::SourceMap)
+    /// point into the original sources.
+    ///
+    /// Returns an empty list if no frames were captured, like for errors created in contexts which
+    /// were not created by rquickjs.
+    pub fn frames(&self) -> Vec<StackFrame> {
+        #[allow(unused_mut)]
+        let mut frames = capture::captured_frames(self.as_object()).unwrap_or_default();
+        #[cfg(feature = "loader")]
+        unsafe { self.ctx().get_opaque() }
+            .source_maps()
+            .remap_frames(&mut frames);
+        frames
+    }
+
+    /// Returns the cause of the error, if any.
+    ///
+    /// Same as retrieving `error.cause` in JavaScript.
+    pub fn cause(&self) -> Option<Value<'js>> {
+        self.get::<_, Option<Value>>("cause").ok().and_then(|x| x)
+    }
+
+    /// Returns the errors of an `AggregateError`.
+    ///
+    /// Returns `None` if the error has no `errors` array.
+    pub fn errors(&self) -> Option<Vec<Value<'js>>> {
+        self.get::<_, Option<Vec<Value>>>(PredefinedAtom::Errors)
+            .ok()
+            .and_then(|x| x)
+    }
+
+    /// Returns the information of the error which remains available after the context is gone.
+    pub fn to_info(&self) -> ExceptionInfo {
+        ExceptionInfo::from_exception(self, 0)
+    }
+
     /// Throws a new generic error.
     ///
     /// Equivalent to:
@@ -214,6 +300,68 @@ impl<'js> Exception<'js> {
     }
 }
 
+/// Returns the value of a data property without running any JavaScript, `None` if the property
+/// does not exist or is an accessor.
+///
+/// Looks up the prototype chain if `inherited` is set, stopping at proxies and Rust classes with
+/// exotic behavior.
+pub(crate) fn data_property<'js>(
+    object: &Object<'js>,
+    atom: qjs::JSAtom,
+    inherited: bool,
+) -> Option<Value<'js>> {
+    let ctx = object.ctx();
+    let mut cur = object.clone();
+    loop {
+        let value = cur.as_js_value();
+        let class_id = unsafe { qjs::JS_GetClassID(value) };
+        if unsafe { qjs::JS_IsProxy(value) || ctx.get_opaque().is_exotic_class_id(class_id) } {
+            return None;
+        }
+
+        let mut desc = MaybeUninit::<qjs::JSPropertyDescriptor>::uninit();
+        let res = unsafe { qjs::JS_GetOwnProperty(ctx.as_ptr(), desc.as_mut_ptr(), value, atom) };
+        if res < 0 {
+            unsafe { qjs::JS_FreeValue(ctx.as_ptr(), qjs::JS_GetException(ctx.as_ptr())) };
+            return None;
+        }
+        if res > 0 {
+            let desc = unsafe { desc.assume_init() };
+            let (value, getter, setter) = unsafe {
+                (
+                    Value::from_js_value(ctx.clone(), desc.value),
+                    Value::from_js_value(ctx.clone(), desc.getter),
+                    Value::from_js_value(ctx.clone(), desc.setter),
+                )
+            };
+            let is_data = getter.is_undefined() && setter.is_undefined();
+            return (desc.flags & qjs::JS_PROP_GETSET as i32 == 0 && is_data).then_some(value);
+        }
+        if !inherited {
+            return None;
+        }
+        let proto = unsafe { qjs::JS_GetPrototype(ctx.as_ptr(), value) };
+        if unsafe { qjs::JS_IsException(proto) } {
+            unsafe { qjs::JS_FreeValue(ctx.as_ptr(), qjs::JS_GetException(ctx.as_ptr())) };
+            return None;
+        }
+        cur = unsafe { Value::from_js_value(ctx.clone(), proto) }.into_object()?;
+    }
+}
+
+/// Returns the elements of an array-like object which are data properties.
+pub(crate) fn data_elements<'js>(array: &Object<'js>) -> Vec<Value<'js>> {
+    let len = data_property(array, PredefinedAtom::Length as qjs::JSAtom, false)
+        .and_then(|x| x.as_int())
+        .unwrap_or_default();
+    (0..len.max(0) as u32)
+        .filter_map(|idx| {
+            let atom = Atom::from_u32(array.ctx().clone(), idx).ok()?;
+            data_property(array, atom.atom, false)
+        })
+        .collect()
+}
+
 impl fmt::Display for Exception<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         "Error:".fmt(f)?;
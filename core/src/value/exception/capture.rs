@@ -0,0 +1,145 @@
+//! Capturing the frames of errors when they are created.
+//!
+//! Every context created by a runtime has a native function installed as the function the engine
+//! calls to prepare the stack of a new error. It stores the call sites of the error on the error
+//! under a private symbol, which is invisible to JavaScript, and formats the stack like QuickJS
+//! does. The accessor of `Error.prepareStackTrace` is replaced so a function set from JavaScript
+//! is still called to format the stack.
+
+use alloc::{string::String as StdString, vec::Vec};
+use core::{fmt::Write, mem::MaybeUninit};
+
+use crate::{
+    atom::PredefinedAtom, function::This, object::Accessor, qjs, Ctx, Function, Object, Result,
+    StackFrame, String, Value,
+};
+
+use super::{data_elements, data_property};
+
+/// Install the hook in a new context.
+pub(crate) unsafe fn install(ctx: *mut qjs::JSContext) -> Result<()> {
+    let ctx = Ctx::from_ptr(ctx);
+    let hook = Function::new(ctx.clone(), prepare_stack_trace)?;
+    let constructor: Object = ctx.globals().get(PredefinedAtom::Error)?;
+    constructor.prop(
+        "prepareStackTrace",
+        Accessor::new(get_prepare_stack_trace, set_prepare_stack_trace).configurable(),
+    )?;
+    qjs::rquickjs_set_prepare_stack_trace(ctx.as_ptr(), hook.into_value().into_js_value());
+    Ok(())
+}
+
+/// Returns the hook installed in the context.
+fn hook<'js>(ctx: &Ctx<'js>) -> Option<Object<'js>> {
+    let hook = unsafe { qjs::rquickjs_get_prepare_stack_trace(ctx.as_ptr()) };
+    unsafe { Value::from_js_value(ctx.clone(), hook) }.into_object()
+}
+
+/// The getter of `Error.prepareStackTrace`, returns the function set from JavaScript.
+fn get_prepare_stack_trace(ctx: Ctx<'_>) -> Value<'_> {
+    let atom = unsafe { ctx.get_opaque().stack_atom() };
+    hook(&ctx)
+        .and_then(|x| data_property(&x, atom, false))
+        .unwrap_or_else(|| Value::new_undefined(ctx))
+}
+
+/// The setter of `Error.prepareStackTrace`, stores the function on the hook.
+fn set_prepare_stack_trace<'js>(ctx: Ctx<'js>, value: Value<'js>) -> Result<()> {
+    let atom = unsafe { ctx.get_opaque().stack_atom() };
+    if let Some(hook) = hook(&ctx) {
+        unsafe { define_hidden(&ctx, &hook, atom, value)? };
+    }
+    Ok(())
+}
+
+/// Called by the engine with every new error and its call sites.
+fn prepare_stack_trace<'js>(
+    ctx: Ctx<'js>,
+    this: This<Value<'js>>,
+    error: Value<'js>,
+    sites: Value<'js>,
+) -> Result<Value<'js>> {
+    let atom = unsafe { ctx.get_opaque().stack_atom() };
+    if let Some(error) = error.as_object().filter(|x| x.is_error()) {
+        unsafe { define_hidden(&ctx, error, atom, sites.clone())? };
+    }
+
+    if let Some(prepare) = get_prepare_stack_trace(ctx.clone()).into_function() {
+        return prepare.call((this, error, sites));
+    }
+    let frames = sites
+        .as_object()
+        .map(|x| call_sites(&ctx, x))
+        .unwrap_or_default();
+    String::from_str(ctx, &render(&frames)).map(|x| x.into_value())
+}
+
+unsafe fn define_hidden<'js>(
+    ctx: &Ctx<'js>,
+    object: &Object<'js>,
+    atom: qjs::JSAtom,
+    value: Value<'js>,
+) -> Result<()> {
+    let flags = (qjs::JS_PROP_CONFIGURABLE | qjs::JS_PROP_WRITABLE) as _;
+    let res = qjs::JS_DefinePropertyValue(
+        ctx.as_ptr(),
+        object.as_js_value(),
+        atom,
+        value.into_js_value(),
+        flags,
+    );
+    if res < 0 {
+        return Err(ctx.raise_exception());
+    }
+    Ok(())
+}
+
+/// Returns the frames captured when the error was created, `None` if the error was not created
+/// in a context with the hook installed.
+pub(crate) fn captured_frames(error: &Object<'_>) -> Option<Vec<StackFrame>> {
+    let atom = unsafe { error.ctx().get_opaque().stack_atom() };
+    let sites = data_property(error, atom, false)?.into_object()?;
+    Some(call_sites(error.ctx(), &sites))
+}
+
+/// Read the frames of an array of call sites, skipping any value which is not a call site.
+///
+/// The array is shared with `Error.prepareStackTrace`, so only data properties are read.
+fn call_sites<'js>(ctx: &Ctx<'js>, sites: &Object<'js>) -> Vec<StackFrame> {
+    data_elements(sites)
+        .iter()
+        .filter_map(|x| unsafe { StackFrame::from_call_site(ctx, x) })
+        .collect()
+}
+
+/// Format frames like QuickJS does.
+pub(crate) fn render(frames: &[StackFrame]) -> StdString {
+    let mut stack = StdString::new();
+    for frame in frames {
+        let _ = writeln!(stack, "    at {frame}");
+    }
+    stack
+}
+
+impl StackFrame {
+    unsafe fn from_call_site<'js>(ctx: &Ctx<'js>, value: &Value<'js>) -> Option<StackFrame> {
+        let mut site = MaybeUninit::uninit();
+        if qjs::rquickjs_get_call_site(value.as_js_value(), site.as_mut_ptr()) < 0 {
+            return None;
+        }
+        let site = site.assume_init();
+        let string = |value| {
+            Value::from_js_value_const(ctx.clone(), value)
+                .as_string()
+                .and_then(|x| x.to_string().ok())
+        };
+        Some(StackFrame {
+            function: string(site.function_name),
+            file: string(site.file_name),
+            line: u32::try_from(site.line).ok(),
+            column: u32::try_from(site.column).ok(),
+            is_native: site.is_native,
+            is_call: site.has_function,
+        })
+    }
+}
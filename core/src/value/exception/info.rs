@@ -0,0 +1,369 @@
+use alloc::{boxed::Box, string::String as StdString, vec::Vec};
+use core::{error::Error as ErrorTrait, fmt};
+
+use crate::{atom::PredefinedAtom, convert::Coerced, qjs, Atom, Value};
+
+use super::{data_elements, data_property, Exception};
+
+/// The maximum depth of causes and aggregated errors which are included in an
+/// [`ExceptionInfo`], guarding against cycles like `error.cause = error`.
+const MAX_DEPTH: usize = 8;
+
+/// A single frame of the stack trace of an exception.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub struct StackFrame {
+    /// The name of the function, `None` for anonymous functions and top level code which was not
+    /// part of a function.
+    pub function: Option<StdString>,
+    /// The name of the script or module, `None` for native functions.
+    pub file: Option<StdString>,
+    /// The line number, starting at 1.
+    pub line: Option<u32>,
+    /// The column number, starting at 1.
+    pub column: Option<u32>,
+    /// Whether the function is implemented natively, in C or Rust.
+    pub is_native: bool,
+    /// Whether the frame is a call of a function, `false` for the location in the source of a
+    /// syntax error.
+    pub is_call: bool,
+}
+
+impl fmt::Display for StackFrame {
+    /// Formats the frame like QuickJS does, without the leading `at`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let location = |f: &mut fmt::Formatter<'_>| -> fmt::Result {
+            if self.is_native {
+                return "native".fmt(f);
+            }
+            self.file.as_deref().unwrap_or("<unknown>").fmt(f)?;
+            if let Some(line) = self.line {
+                write!(f, ":{line}")?;
+                if let Some(column) = self.column {
+                    write!(f, ":{column}")?;
+                }
+            }
+            Ok(())
+        };
+        if !self.is_call {
+            return location(f);
+        }
+        write!(f, "{} (", self.function.as_deref().unwrap_or("<anonymous>"))?;
+        location(f)?;
+        ')'.fmt(f)
+    }
+}
+
+/// The information of a thrown JavaScript value which remains available after the context is
+/// gone.
+///
+/// Created from a [`CaughtError`](crate::CaughtError) by converting it into an
+/// [`Error::Thrown`](crate::Error::Thrown), or directly with [`Exception::to_info`].
+///
+/// Creating the info never runs JavaScript, only data properties of the error are read and values
+/// which are not errors are only converted to a string if they are primitives.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub struct ExceptionInfo {
+    /// The name of the error, like `TypeError`, `None` if the thrown value was not an error.
+    pub name: Option<StdString>,
+    /// The message of the error, or the thrown value converted to a string.
+    pub message: Option<StdString>,
+    /// The stack string of the error, as returned by [`Exception::stack`].
+    pub stack: Option<StdString>,
+    /// The frames of the stack, see [`Exception::frames`].
+    pub frames: Vec<StackFrame>,
+    /// The cause of the error, if any.
+    pub cause: Option<Box<ExceptionInfo>>,
+    /// The errors of an `AggregateError`.
+    pub errors: Vec<ExceptionInfo>,
+}
+
+impl ExceptionInfo {
+    /// Create the info of an arbitrary thrown value.
+    pub fn from_value(value: &Value<'_>) -> Self {
+        Self::from_value_depth(value, 0)
+    }
+
+    fn from_value_depth(value: &Value<'_>, depth: usize) -> Self {
+        if let Some(exception) = value.as_exception() {
+            return Self::from_exception(exception, depth);
+        }
+        ExceptionInfo {
+            message: primitive_string(value),
+            ..ExceptionInfo::default()
+        }
+    }
+
+    pub(crate) fn from_exception(exception: &Exception<'_>, depth: usize) -> Self {
+        let object = exception.as_object();
+        let ctx = object.ctx();
+        let string = |atom: PredefinedAtom| {
+            data_property(object, atom as qjs::JSAtom, true).and_then(|x| primitive_string(&x))
+        };
+        let mut info = ExceptionInfo {
+            name: string(PredefinedAtom::Name),
+            message: string(PredefinedAtom::Message),
+            stack: string(PredefinedAtom::Stack).and_then(|x| exception.finish_stack(x)),
+            frames: exception.frames(),
+            cause: None,
+            errors: Vec::new(),
+        };
+        if depth < MAX_DEPTH {
+            info.cause = Atom::from_str(ctx.clone(), "cause")
+                .ok()
+                .and_then(|x| data_property(object, x.atom, false))
+                .map(|x| Box::new(Self::from_value_depth(&x, depth + 1)));
+            info.errors = data_property(object, PredefinedAtom::Errors as qjs::JSAtom, false)
+                .and_then(|x| x.into_object())
+                .map(|x| data_elements(&x))
+                .unwrap_or_default()
+                .iter()
+                .map(|x| Self::from_value_depth(x, depth + 1))
+                .collect();
+        }
+        info
+    }
+}
+
+/// Convert a primitive value to a string, returns `None` for objects and symbols.
+fn primitive_string(value: &Value<'_>) -> Option<StdString> {
+    if value.is_object() || value.is_symbol() {
+        return None;
+    }
+    value.get::<Coerced<StdString>>().ok().map(|x| x.0)
+}
+
+impl fmt::Display for ExceptionInfo {
+    /// Formats the error followed by its stack, or by one `at file:line:column` line per frame if
+    /// there is no stack.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.name, &self.message) {
+            (Some(name), Some(message)) if !message.is_empty() => write!(f, "{name}: {message}")?,
+            (Some(name), _) => name.fmt(f)?,
+            (None, Some(message)) => write!(f, "Uncaught {message}")?,
+            (None, None) => "Uncaught exception".fmt(f)?,
+        }
+        writeln!(f)?;
+        match self.stack {
+            Some(ref stack) => {
+                stack.fmt(f)?;
+                if !stack.is_empty() && !stack.ends_with('\n') {
+                    writeln!(f)?;
+                }
+            }
+            None => {
+                for frame in &self.frames {
+                    writeln!(f, "    at {frame}")?;
+                }
+            }
+        }
+        for (idx, error) in self.errors.iter().enumerate() {
+            write!(f, "[{idx}] {error}")?;
+        }
+        if let Some(cause) = &self.cause {
+            write!(f, "Caused by: {cause}")?;
+        }
+        Ok(())
+    }
+}
+
+impl ErrorTrait for ExceptionInfo {}
+
+#[cfg(test)]
+mod test {
+    use crate::{CatchResultExt, CaughtError, Context, Error, Exception, Function, Runtime};
+
+    #[test]
+    fn captured_frames() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            let error: Exception = ctx
+                .eval(
+                    r#"
+function fail() { return [1].map(() => new Error("fail"))[0]; }
+const error = fail();
+error.stack = "replaced";
+error
+"#,
+                )
+                .unwrap();
+            let frames = error.frames();
+            assert_eq!(frames[0].function, None);
+            assert!(frames[0].is_call);
+            assert_eq!(frames[0].file.as_deref(), Some("eval_script"));
+            assert_eq!((frames[0].line, frames[0].column), (Some(2), Some(44)));
+            assert_eq!(frames[1].function.as_deref(), Some("map"));
+            assert!(frames[1].is_native);
+            assert_eq!(frames[2].function.as_deref(), Some("fail"));
+            assert_eq!(error.stack().as_deref(), Some("replaced"));
+
+            let rendered = frames.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+            assert_eq!(
+                rendered[..3],
+                [
+                    "<anonymous> (eval_script:2:44)",
+                    "map (native)",
+                    "fail (eval_script:2:30)"
+                ]
+            );
+
+            let error: Exception = ctx.eval("new Error('plain')").unwrap();
+            let stack = ctx
+                .eval::<crate::String, _>("new Error('plain').stack")
+                .unwrap()
+                .to_string()
+                .unwrap();
+            assert_eq!(error.stack().unwrap(), stack);
+            assert_eq!(stack, "    at <eval> (eval_script:1:4)\n");
+
+            let err = ctx.eval::<(), _>("let x = ;").catch(&ctx).unwrap_err();
+            let CaughtError::Exception(error) = err else {
+                panic!("expected a syntax error")
+            };
+            let frames = error.frames();
+            assert!(!frames[0].is_call);
+            assert_eq!(frames[0].to_string(), "eval_script:1:1");
+        });
+    }
+
+    #[test]
+    fn prepare_stack_trace() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            let error: Exception = ctx
+                .eval(
+                    r#"
+const prepare = (error, sites) => `${error.message}: ${sites[0].getFunctionName()}`;
+Error.prepareStackTrace = prepare;
+if (Error.prepareStackTrace !== prepare) throw new Error("not stored");
+function named() { return new Error("custom"); }
+const error = named();
+Error.prepareStackTrace = undefined;
+if (Reflect.ownKeys(error).some((x) => typeof x === "symbol")) throw new Error("visible");
+error
+"#,
+                )
+                .unwrap();
+            assert_eq!(error.stack().as_deref(), Some("custom: named"));
+            assert_eq!(error.frames()[0].function.as_deref(), Some("named"));
+
+            let stack: crate::String = ctx.eval("new Error().stack").unwrap();
+            assert!(stack.to_string().unwrap().starts_with("    at <eval> ("));
+        });
+    }
+
+    #[test]
+    fn info_reads_data_properties() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            let err = ctx
+                .eval::<(), _>(
+                    r#"
+const error = new TypeError("data");
+Object.defineProperty(error, "message", { get() { globalThis.called = true; return "getter"; } });
+error.cause = { toString() { globalThis.called = true; return "cause"; } };
+throw error;
+"#,
+                )
+                .catch(&ctx)
+                .unwrap_err();
+            let rendered = err.to_string();
+            assert!(rendered.starts_with("TypeError\n"), "{rendered}");
+            let CaughtError::Exception(error) = err else {
+                panic!("expected an exception")
+            };
+            let info = error.to_info();
+            assert_eq!(info.name.as_deref(), Some("TypeError"));
+            assert_eq!(info.message, None);
+            assert_eq!(info.cause.unwrap().message, None);
+            assert!(!ctx.globals().contains_key("called").unwrap());
+        });
+    }
+
+    #[test]
+    fn frames_and_cause() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            let error: Exception = ctx
+                .eval(
+                    r#"
+function inner() { return new TypeError("inner"); }
+function outer() { return new Error("outer", { cause: inner() }); }
+outer()
+"#,
+                )
+                .unwrap();
+            assert_eq!(error.name().as_deref(), Some("Error"));
+            let frames = error.frames();
+            assert_eq!(frames[0].function.as_deref(), Some("outer"));
+            assert_eq!(frames[0].file.as_deref(), Some("eval_script"));
+            assert_eq!(frames[0].line, Some(3));
+
+            let cause = error.cause().unwrap().into_exception().unwrap();
+            assert_eq!(cause.name().as_deref(), Some("TypeError"));
+            assert_eq!(cause.frames()[0].function.as_deref(), Some("inner"));
+
+            let info = error.to_info();
+            assert_eq!(info.cause.unwrap().message.as_deref(), Some("inner"));
+        });
+    }
+
+    #[test]
+    fn aggregate_errors() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            let error: Exception = ctx
+                .eval(r#"new AggregateError([new Error("a"), "b"], "both")"#)
+                .unwrap();
+            let errors = error.errors().unwrap();
+            assert_eq!(errors.len(), 2);
+            let info = error.to_info();
+            assert_eq!(info.errors[0].name.as_deref(), Some("Error"));
+            assert_eq!(info.errors[1].name, None);
+            assert_eq!(info.errors[1].message.as_deref(), Some("b"));
+            assert!(info.to_string().starts_with("AggregateError: both\n"));
+        });
+    }
+
+    #[test]
+    fn detached_error() {
+        let rt = Runtime::new().unwrap();
+        let error = {
+            let ctx = Context::full(&rt).unwrap();
+            ctx.with(|ctx| {
+                let err = ctx
+                    .eval::<(), _>("function fail() { throw new RangeError('bad') }\nfail()")
+                    .catch(&ctx)
+                    .unwrap_err();
+                assert!(err
+                    .to_string()
+                    .starts_with("RangeError: bad\n    at fail (eval_script:1:"));
+                Error::from(err)
+            })
+        };
+        let Error::Thrown(info) = error else {
+            panic!("expected a thrown error")
+        };
+        assert_eq!(info.name.as_deref(), Some("RangeError"));
+        assert_eq!(info.frames[1].line, Some(2));
+
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            let func = Function::new(ctx.clone(), move || -> crate::Result<()> {
+                Err(Error::Thrown(info.clone()))
+            })
+            .unwrap();
+            ctx.globals().set("f", func).unwrap();
+            let msg: crate::String = ctx
+                .eval("try { f() } catch (e) { e.name + ': ' + e.message }")
+                .unwrap();
+            assert_eq!(msg.to_string().unwrap(), "RangeError: bad");
+        });
+    }
+}
@@ -0,0 +1,415 @@
+//! Iterators passed between Rust and JavaScript.
+
+use crate::{
+    atom::PredefinedAtom,
+    class::{Trace, Tracer},
+    function::{MutFn, Opt, This},
+    Ctx, Error, Exception, FromJs, Function, IntoJs, JsLifetime, Object, Result, Value,
+};
+use alloc::rc::Rc;
+use core::{cell::RefCell, iter::FusedIterator, marker::PhantomData};
+
+#[cfg(feature = "futures")]
+use crate::Promise;
+#[cfg(feature = "futures")]
+use alloc::boxed::Box;
+#[cfg(feature = "futures")]
+use async_lock::Mutex;
+#[cfg(feature = "futures")]
+use core::{future::poll_fn, pin::Pin};
+#[cfg(feature = "futures")]
+use futures_core::Stream;
+
+/// A Rust iterator which is converted into a lazy JavaScript iterator.
+///
+/// The items of the iterator are only converted when requested by JavaScript with `next()`. The
+/// iterator object is also iterable itself so it can be used directly in a `for of` loop. Calling
+/// `return()` drops the Rust iterator.
+///
+/// ```
+/// # use rquickjs::{Runtime, Context, iterator::Iterator};
+/// # let rt = Runtime::new().unwrap();
+/// # let ctx = Context::full(&rt).unwrap();
+/// # ctx.with(|ctx| {
+/// ctx.globals().set("squares", Iterator((1..).map(|x: u32| x * x))).unwrap();
+/// let sum: u32 = ctx.eval(r#"
+///     let sum = 0;
+///     for (const x of squares) {
+///         if (x > 10) break;
+///         sum += x;
+///     }
+///     sum
+/// "#).unwrap();
+/// assert_eq!(sum, 14);
+/// # });
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Iterator<I>(pub I);
+
+impl<'js, I> IntoJs<'js> for Iterator<I>
+where
+    I: core::iter::Iterator + 'js,
+    I::Item: IntoJs<'js>,
+{
+    fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
+        let state = Rc::new(RefCell::new(Some(self.0)));
+
+        let object = Object::new(ctx.clone())?;
+        // Inherit the iterator helpers like `map` and `take` if they are available.
+        if let Some(ctor) = ctx.globals().get::<_, Option<Object>>("Iterator")? {
+            object.set_prototype(
+                ctor.get::<_, Option<Object>>(PredefinedAtom::Prototype)?
+                    .as_ref(),
+            )?;
+        }
+
+        let next_state = state.clone();
+        let next = Function::new(
+            ctx.clone(),
+            MutFn::new(move |ctx: Ctx<'js>| -> Result<Object<'js>> {
+                let item = {
+                    let mut state = next_state
+                        .try_borrow_mut()
+                        .map_err(|_| Exception::throw_type(&ctx, "iterator is already running"))?;
+                    let item = state.as_mut().and_then(|iter| iter.next());
+                    if item.is_none() {
+                        // Drop the iterator as soon as it is exhausted.
+                        *state = None;
+                    }
+                    item
+                };
+                match item {
+                    Some(x) => iter_result(&ctx, x.into_js(&ctx)?, false),
+                    None => iter_result(&ctx, Value::new_undefined(ctx.clone()), true),
+                }
+            }),
+        )?
+        .with_name("next")?;
+        object.set(PredefinedAtom::Next, next)?;
+
+        let return_ = Function::new(
+            ctx.clone(),
+            move |ctx: Ctx<'js>, value: Opt<Value<'js>>| -> Result<Object<'js>> {
+                let iter = state
+                    .try_borrow_mut()
+                    .map_err(|_| Exception::throw_type(&ctx, "iterator is already running"))?
+                    .take();
+                drop(iter);
+                let value = value.0.unwrap_or_else(|| Value::new_undefined(ctx.clone()));
+                iter_result(&ctx, value, true)
+            },
+        )?
+        .with_name("return")?;
+        object.set(PredefinedAtom::Return, return_)?;
+
+        let iterator = Function::new(ctx.clone(), |this: This<Value<'js>>| this.0)?
+            .with_name("[Symbol.iterator]")?;
+        object.set(PredefinedAtom::SymbolIterator, iterator)?;
+
+        Ok(object.into_value())
+    }
+}
+
+/// A Rust stream which is converted into a JavaScript async iterator.
+///
+/// The items of the stream are only polled when requested by JavaScript with `next()`, which
+/// returns a promise for the next item. The iterator object is also async iterable itself so it
+/// can be used directly in a `for await` loop. Calling `return()` drops the stream.
+#[cfg(feature = "futures")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "futures")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AsyncIterator<S>(pub S);
+
+#[cfg(feature = "futures")]
+impl<'js, S> IntoJs<'js> for AsyncIterator<S>
+where
+    S: Stream + 'js,
+    S::Item: IntoJs<'js>,
+{
+    fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
+        // The lock makes sure that calls to `next()` which are made before the previous one
+        // resolved are handled in order.
+        let state = Rc::new(Mutex::new(Some(Box::pin(self.0))));
+
+        let object = Object::new(ctx.clone())?;
+
+        let next_state = state.clone();
+        let next = Function::new(ctx.clone(), move |ctx: Ctx<'js>| -> Result<Promise<'js>> {
+            let state = next_state.clone();
+            let ctx_clone = ctx.clone();
+            Promise::wrap_future(&ctx, async move {
+                let mut state = state.lock().await;
+                let item = match state.as_mut() {
+                    Some(stream) => poll_fn(|cx| Pin::as_mut(stream).poll_next(cx)).await,
+                    None => None,
+                };
+                match item {
+                    Some(x) => iter_result(&ctx_clone, x.into_js(&ctx_clone)?, false),
+                    None => {
+                        *state = None;
+                        iter_result(&ctx_clone, Value::new_undefined(ctx_clone.clone()), true)
+                    }
+                }
+            })
+        })?
+        .with_name("next")?;
+        object.set(PredefinedAtom::Next, next)?;
+
+        let return_ = Function::new(
+            ctx.clone(),
+            move |ctx: Ctx<'js>, value: Opt<Value<'js>>| -> Result<Promise<'js>> {
+                let state = state.clone();
+                let ctx_clone = ctx.clone();
+                let value = value.0.unwrap_or_else(|| Value::new_undefined(ctx.clone()));
+                Promise::wrap_future(&ctx, async move {
+                    let stream = state.lock().await.take();
+                    drop(stream);
+                    iter_result(&ctx_clone, value, true)
+                })
+            },
+        )?
+        .with_name("return")?;
+        object.set(PredefinedAtom::Return, return_)?;
+
+        let iterator = Function::new(ctx.clone(), |this: This<Value<'js>>| this.0)?
+            .with_name("[Symbol.asyncIterator]")?;
+        object.set(PredefinedAtom::SymbolAsyncIterator, iterator)?;
+
+        Ok(object.into_value())
+    }
+}
+
+/// Create the result object of a call to `next()` or `return()` of an iterator.
+fn iter_result<'js>(ctx: &Ctx<'js>, value: Value<'js>, done: bool) -> Result<Object<'js>> {
+    let res = Object::new(ctx.clone())?;
+    res.set(PredefinedAtom::Value, value)?;
+    res.set(PredefinedAtom::Done, done)?;
+    Ok(res)
+}
+
+/// A Rust iterator over the values of a JavaScript iterable, like an array, a set or a generator.
+///
+/// The iterator is closed by calling its `return()` method if it is dropped before it is
+/// exhausted, allowing for example a generator to run its `finally` blocks.
+pub struct JsIterator<'js, T> {
+    iterator: Object<'js>,
+    next: Function<'js>,
+    done: bool,
+    marker: PhantomData<T>,
+}
+
+impl<'js, T> JsIterator<'js, T> {
+    /// Create an iterator over the values of an iterable by calling its `[Symbol.iterator]()`
+    /// method.
+    pub fn new(iterable: &Value<'js>) -> Result<Self> {
+        let ctx = iterable.ctx();
+        let Some(method) = iterable
+            .as_object()
+            .map(|x| x.get::<_, Option<Function>>(PredefinedAtom::SymbolIterator))
+            .transpose()?
+            .flatten()
+        else {
+            return Err(Error::new_from_js(iterable.type_name(), "iterable"));
+        };
+        let iterator: Value = method.call((This(iterable.clone()),))?;
+        let Some(iterator) = iterator.into_object() else {
+            return Err(Exception::throw_type(ctx, "iterator is not an object"));
+        };
+        Self::from_iterator(iterator)
+    }
+
+    /// Create an iterator from an object which already is an iterator, i.e. which has a `next()`
+    /// method.
+    pub fn from_iterator(iterator: Object<'js>) -> Result<Self> {
+        let next = iterator.get(PredefinedAtom::Next)?;
+        Ok(JsIterator {
+            iterator,
+            next,
+            done: false,
+            marker: PhantomData,
+        })
+    }
+
+    /// Returns the JavaScript iterator object.
+    pub fn as_object(&self) -> &Object<'js> {
+        &self.iterator
+    }
+
+    /// Close the iterator by calling its `return()` method if it is not yet exhausted.
+    ///
+    /// Unlike dropping the iterator this returns errors thrown while closing the iterator.
+    pub fn close(mut self) -> Result<()> {
+        self.close_inner()
+    }
+
+    fn close_inner(&mut self) -> Result<()> {
+        if self.done {
+            return Ok(());
+        }
+        self.done = true;
+        let Some(return_) = self
+            .iterator
+            .get::<_, Option<Function>>(PredefinedAtom::Return)?
+        else {
+            return Ok(());
+        };
+        return_.call::<_, Value>((This(self.iterator.clone()),))?;
+        Ok(())
+    }
+
+    fn next_value(&mut self) -> Option<Result<Value<'js>>> {
+        if self.done {
+            return None;
+        }
+        let res = self
+            .next
+            .call::<_, Object>((This(self.iterator.clone()),))
+            .and_then(|res| {
+                if res.get(PredefinedAtom::Done)? {
+                    Ok(None)
+                } else {
+                    res.get(PredefinedAtom::Value).map(Some)
+                }
+            });
+        // An iterator which threw an error or finished must not be closed.
+        if !matches!(res, Ok(Some(_))) {
+            self.done = true;
+        }
+        res.transpose()
+    }
+}
+
+impl<'js, T: FromJs<'js>> core::iter::Iterator for JsIterator<'js, T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = match self.next_value()? {
+            Ok(x) => x,
+            Err(e) => return Some(Err(e)),
+        };
+        Some(T::from_js(self.iterator.ctx(), value))
+    }
+}
+
+impl<'js, T: FromJs<'js>> FusedIterator for JsIterator<'js, T> {}
+
+impl<'js, T> Drop for JsIterator<'js, T> {
+    fn drop(&mut self) {
+        // Errors thrown while closing the iterator can't be reported from drop.
+        if self.close_inner().is_err() {
+            let _ = self.iterator.ctx().catch();
+        }
+    }
+}
+
+impl<'js, T> FromJs<'js> for JsIterator<'js, T> {
+    fn from_js(_ctx: &Ctx<'js>, value: Value<'js>) -> Result<Self> {
+        Self::new(&value)
+    }
+}
+
+impl<'js, T> Trace<'js> for JsIterator<'js, T> {
+    fn trace<'a>(&self, tracer: Tracer<'a, 'js>) {
+        self.iterator.trace(tracer);
+        self.next.trace(tracer);
+    }
+}
+
+unsafe impl<'js, T: JsLifetime<'js>> JsLifetime<'js> for JsIterator<'js, T> {
+    type Changed<'to> = JsIterator<'to, T::Changed<'to>>;
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{iterator::Iterator, *};
+
+    #[test]
+    fn rust_iterator() {
+        test_with(|ctx| {
+            ctx.globals()
+                .set("numbers", Iterator((1..=3).map(|x: i32| x * 2)))
+                .unwrap();
+            let res: Vec<i32> = ctx.eval("[...numbers, ...numbers]").catch(&ctx).unwrap();
+            assert_eq!(res, [2, 4, 6]);
+
+            ctx.globals()
+                .set("numbers", Iterator(["a", "b", "c"].into_iter()))
+                .unwrap();
+            let res: bool = ctx
+                .eval(
+                    r#"
+                    numbers.next().value === "a"
+                        && numbers.return(1).value === 1
+                        && numbers.next().done
+                "#,
+                )
+                .catch(&ctx)
+                .unwrap();
+            assert!(res);
+        });
+    }
+
+    #[test]
+    fn js_iterator() {
+        test_with(|ctx| {
+            let value: Value = ctx.eval("new Set([1, 2, 3])").unwrap();
+            let res = JsIterator::<i32>::new(&value)
+                .unwrap()
+                .collect::<Result<Vec<_>>>()
+                .unwrap();
+            assert_eq!(res, [1, 2, 3]);
+
+            let iter: JsIterator<StdString> = ctx
+                .eval(
+                    r#"
+                    globalThis.closed = false;
+                    (function*(){
+                        try {
+                            yield "a";
+                            yield "b";
+                        } finally {
+                            closed = true;
+                        }
+                    })()
+                "#,
+                )
+                .unwrap();
+            let first = iter.take(1).collect::<Result<Vec<_>>>().unwrap();
+            assert_eq!(first, ["a"]);
+            assert!(ctx.globals().get::<_, bool>("closed").unwrap());
+
+            let value: Value = ctx.eval("42").unwrap();
+            assert!(JsIterator::<Value>::new(&value).is_err());
+        });
+    }
+
+    #[cfg(feature = "futures")]
+    #[tokio::test]
+    async fn rust_stream() {
+        use crate::iterator::AsyncIterator;
+
+        let rt = AsyncRuntime::new().unwrap();
+        let ctx = AsyncContext::full(&rt).await.unwrap();
+
+        async_with!(ctx => |ctx| {
+            let stream = futures_rs::stream::iter([1, 2, 3]);
+            ctx.globals().set("numbers", AsyncIterator(stream)).unwrap();
+            let promise: Promise = ctx
+                .eval(
+                    r#"
+                    (async () => {
+                        let sum = 0;
+                        for await (const x of numbers) {
+                            sum += x;
+                        }
+                        return sum;
+                    })()
+                "#,
+                )
+                .catch(&ctx)
+                .unwrap();
+            assert_eq!(promise.into_future::<i32>().await.catch(&ctx).unwrap(), 6);
+        })
+        .await;
+    }
+}
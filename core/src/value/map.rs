@@ -0,0 +1,308 @@
+//! JavaScript map types.
+
+use crate::{qjs, Ctx, FromJs, IntoJs, Object, Result, Value};
+use core::{iter::FusedIterator, marker::PhantomData};
+
+use super::{call, convert::FromIteratorJs};
+
+/// Rust representation of a JavaScript object of class `Map`.
+///
+/// Unlike plain objects a map can have keys of any type and remembers the insertion order of its
+/// entries.
+///
+/// The methods call the engine directly, so they are not affected by changes to the global `Map`
+/// or its prototype.
+#[derive(Debug, PartialEq, Clone, Eq, Hash)]
+#[repr(transparent)]
+pub struct Map<'js>(pub(crate) Object<'js>);
+
+impl<'js> Map<'js> {
+    /// Create a new JavaScript map.
+    pub fn new(ctx: Ctx<'js>) -> Result<Self> {
+        call(&ctx, |ctx| unsafe { qjs::rquickjs_map_new(ctx, false) }).map(Map)
+    }
+
+    /// Get the number of entries in the map.
+    pub fn size(&self) -> Result<usize> {
+        let map = self.0.as_js_value();
+        call(self.0.ctx(), |ctx| unsafe {
+            qjs::rquickjs_map_size(ctx, map, false)
+        })
+    }
+
+    /// Returns whether the map is empty.
+    pub fn is_empty(&self) -> Result<bool> {
+        self.size().map(|size| size == 0)
+    }
+
+    /// Get the value stored for a key.
+    ///
+    /// Returns `undefined` converted to `V` if the key is not present.
+    pub fn get<K: IntoJs<'js>, V: FromJs<'js>>(&self, key: K) -> Result<V> {
+        let ctx = self.0.ctx();
+        let (map, key) = (self.0.as_js_value(), key.into_js(ctx)?);
+        call(ctx, |ctx| unsafe {
+            qjs::rquickjs_map_get(ctx, map, key.as_js_value())
+        })
+    }
+
+    /// Set the value stored for a key.
+    pub fn set<K: IntoJs<'js>, V: IntoJs<'js>>(&self, key: K, value: V) -> Result<()> {
+        let ctx = self.0.ctx();
+        let (map, key, value) = (self.0.as_js_value(), key.into_js(ctx)?, value.into_js(ctx)?);
+        call::<Value>(ctx, |ctx| unsafe {
+            qjs::rquickjs_map_set(ctx, map, key.as_js_value(), value.as_js_value(), false)
+        })?;
+        Ok(())
+    }
+
+    /// Returns whether the map contains a key.
+    pub fn has<K: IntoJs<'js>>(&self, key: K) -> Result<bool> {
+        let ctx = self.0.ctx();
+        let (map, key) = (self.0.as_js_value(), key.into_js(ctx)?);
+        call(ctx, |ctx| unsafe {
+            qjs::rquickjs_map_has(ctx, map, key.as_js_value(), false)
+        })
+    }
+
+    /// Remove a key from the map, returning whether the key was present.
+    pub fn delete<K: IntoJs<'js>>(&self, key: K) -> Result<bool> {
+        let ctx = self.0.ctx();
+        let (map, key) = (self.0.as_js_value(), key.into_js(ctx)?);
+        call(ctx, |ctx| unsafe {
+            qjs::rquickjs_map_delete(ctx, map, key.as_js_value(), false)
+        })
+    }
+
+    /// Remove all entries from the map.
+    pub fn clear(&self) -> Result<()> {
+        let map = self.0.as_js_value();
+        call(self.0.ctx(), |ctx| unsafe {
+            qjs::rquickjs_map_clear(ctx, map, false)
+        })
+    }
+
+    /// Get an iterator over the entries of the map in insertion order.
+    ///
+    /// The iterator is live, entries added to the map during iteration are visited as well.
+    pub fn iter<K: FromJs<'js>, V: FromJs<'js>>(&self) -> MapIter<'js, K, V> {
+        MapIter {
+            iter: RawIter::new(&self.0, false),
+            marker: PhantomData,
+        }
+    }
+
+    pub fn into_object(self) -> Object<'js> {
+        self.0
+    }
+
+    pub fn as_object(&self) -> &Object<'js> {
+        &self.0
+    }
+}
+
+/// An iterator over the entries of a map or the values of a set.
+pub(super) struct RawIter<'js> {
+    state: Option<Result<Object<'js>>>,
+    set: bool,
+}
+
+impl<'js> RawIter<'js> {
+    pub(super) fn new(object: &Object<'js>, set: bool) -> Self {
+        let map = object.as_js_value();
+        let state = call(object.ctx(), |ctx| unsafe {
+            qjs::rquickjs_map_iterator(ctx, map, set)
+        });
+        RawIter {
+            state: Some(state),
+            set,
+        }
+    }
+
+    /// Get the next value of the iterator, the iterator is finished after returning an error.
+    pub(super) fn next_value(&mut self) -> Option<Result<Value<'js>>> {
+        let iter = match self.state.take()? {
+            Ok(iter) => iter,
+            Err(error) => return Some(Err(error)),
+        };
+        let ctx = iter.ctx();
+        let mut done = false;
+        let value = unsafe {
+            let value = qjs::rquickjs_map_iterator_next(
+                ctx.as_ptr(),
+                iter.as_js_value(),
+                self.set,
+                &mut done,
+            );
+            match ctx.handle_exception(value) {
+                Ok(value) => Value::from_js_value(ctx.clone(), value),
+                Err(error) => return Some(Err(error)),
+            }
+        };
+        if done {
+            return None;
+        }
+        self.state = Some(Ok(iter));
+        Some(Ok(value))
+    }
+}
+
+/// The iterator for a map
+pub struct MapIter<'js, K, V> {
+    iter: RawIter<'js>,
+    marker: PhantomData<(K, V)>,
+}
+
+impl<'js, K, V> Iterator for MapIter<'js, K, V>
+where
+    K: FromJs<'js>,
+    V: FromJs<'js>,
+{
+    type Item = Result<(K, V)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.iter.next_value()?;
+        Some(entry.and_then(|entry| {
+            let entry = Object::from_value(entry)?;
+            Ok((entry.get(0)?, entry.get(1)?))
+        }))
+    }
+}
+
+impl<'js, K, V> FusedIterator for MapIter<'js, K, V>
+where
+    K: FromJs<'js>,
+    V: FromJs<'js>,
+{
+}
+
+impl<'js> IntoIterator for Map<'js> {
+    type Item = Result<(Value<'js>, Value<'js>)>;
+    type IntoIter = MapIter<'js, Value<'js>, Value<'js>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'js, K, V> FromIteratorJs<'js, (K, V)> for Map<'js>
+where
+    K: IntoJs<'js>,
+    V: IntoJs<'js>,
+{
+    type Item = (Value<'js>, Value<'js>);
+
+    fn from_iter_js<T>(ctx: &Ctx<'js>, iter: T) -> Result<Self>
+    where
+        T: IntoIterator<Item = (K, V)>,
+    {
+        let map = Map::new(ctx.clone())?;
+        for (key, value) in iter {
+            map.set(key, value)?;
+        }
+        Ok(map)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn from_javascript() {
+        test_with(|ctx| {
+            let map: Map = ctx
+                .eval(r#"new Map([[1, "a"], ["b", 2], [{}, true]])"#)
+                .unwrap();
+            assert_eq!(map.size().unwrap(), 3);
+            assert_eq!(map.get::<_, StdString>(1).unwrap(), "a");
+            assert_eq!(map.get::<_, i32>("b").unwrap(), 2);
+            assert!(map.has("b").unwrap());
+            assert!(!map.has(2).unwrap());
+            assert_eq!(map.get::<_, Option<i32>>("c").unwrap(), None);
+        });
+    }
+
+    #[test]
+    fn into_javascript() {
+        test_with(|ctx| {
+            let map = Map::new(ctx.clone()).unwrap();
+            map.set("a", 1).unwrap();
+            map.set(2, "b").unwrap();
+            map.set("c", 3).unwrap();
+            assert!(map.delete("c").unwrap());
+            assert!(!map.delete("c").unwrap());
+            ctx.globals().set("m", map.clone()).unwrap();
+            let ok: bool = ctx
+                .eval(r#"m instanceof Map && m.size === 2 && m.get("a") === 1 && m.get(2) === "b""#)
+                .unwrap();
+            assert!(ok);
+            map.clear().unwrap();
+            assert!(map.is_empty().unwrap());
+        });
+    }
+
+    #[test]
+    fn iter() {
+        test_with(|ctx| {
+            let map: Map = ctx.eval(r#"new Map([["a", 1], ["b", 2]])"#).unwrap();
+            let entries = map
+                .iter::<StdString, i32>()
+                .collect::<Result<Vec<_>>>()
+                .unwrap();
+            assert_eq!(entries, [("a".into(), 1), ("b".into(), 2)]);
+            assert!(map.iter::<i32, i32>().next().unwrap().is_err());
+
+            let map: Map = [(1, "x"), (2, "y")].into_iter().collect_js(&ctx).unwrap();
+            let keys = map
+                .into_iter()
+                .map(|entry| entry.and_then(|(key, _)| i32::from_js(&ctx, key)))
+                .collect::<Result<Vec<_>>>()
+                .unwrap();
+            assert_eq!(keys, [1, 2]);
+        });
+    }
+
+    #[test]
+    fn intrinsic() {
+        test_with(|ctx| {
+            let map: Map = ctx.eval("new Map([[1, 2]])").unwrap();
+            ctx.eval::<(), _>(
+                r#"
+                Map.prototype.get = () => 42;
+                Map.prototype[Symbol.iterator] = null;
+                Object.getPrototypeOf(new Map().entries()).next = null;
+                globalThis.Map = null;
+                "#,
+            )
+            .unwrap();
+            assert_eq!(map.get::<_, i32>(1).unwrap(), 2);
+            let map = Map::new(ctx.clone()).unwrap();
+            map.set("a", 1).unwrap();
+            assert_eq!(map.get::<_, i32>("a").unwrap(), 1);
+            let entries = map.iter::<StdString, i32>().collect::<Result<Vec<_>>>();
+            assert_eq!(entries.unwrap(), [("a".into(), 1)]);
+
+            let object = Object::new(ctx.clone()).unwrap();
+            let not_map = Map(object);
+            assert!(not_map.size().is_err());
+            assert!(not_map.iter::<Value, Value>().next().unwrap().is_err());
+        });
+    }
+
+    #[test]
+    fn type_of() {
+        test_with(|ctx| {
+            let value: Value = ctx.eval("new Map()").unwrap();
+            assert!(value.is_map());
+            assert!(!value.is_set());
+            assert_eq!(value.type_of(), Type::Map);
+            assert!(value.as_map().is_some());
+            assert!(value.as_object().is_some());
+
+            let value: Value = ctx.eval("new WeakMap()").unwrap();
+            assert!(!value.is_map());
+            assert!(Map::from_js(&ctx, value).is_err());
+        });
+    }
+}
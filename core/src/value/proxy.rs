@@ -0,0 +1,661 @@
+//! JavaScript proxy objects backed by Rust handlers.
+
+use crate::{
+    atom::PredefinedAtom,
+    class::{Class, JsClass, Readable, Trace, Tracer},
+    function::{Constructor, This},
+    qjs, Array, Atom, Ctx, Error, FromJs, Function, IntoJs, JsLifetime, Object, Result, Value,
+};
+
+use super::call;
+use alloc::vec::Vec;
+
+/// The hooks of a [`Proxy`] implemented in Rust.
+///
+/// Every hook corresponds to the proxy trap of the same name. Only the traps of the hooks whose
+/// `IMPLEMENTS_*` constant is set to `true` are installed on the handler object, the engine
+/// forwards the other operations to the target object itself. An implemented hook must set its
+/// constant, otherwise it is never called. The default implementations forward the operation to
+/// the target object like a proxy without the trap would.
+///
+/// The handler is kept alive by the proxy and is traced by the garbage collector like a Rust
+/// class, so it can hold JavaScript values.
+///
+/// ```
+/// # use rquickjs::{Runtime, Context, Object, Value, Atom, Result, JsLifetime, proxy::{Proxy, ProxyHandler}, class::{Trace, Tracer}};
+/// # let rt = Runtime::new().unwrap();
+/// # let ctx = Context::full(&rt).unwrap();
+/// # ctx.with(|ctx| -> Result<()> {
+/// struct Upper;
+/// #
+/// # impl<'js> Trace<'js> for Upper {
+/// #     fn trace<'a>(&self, _tracer: Tracer<'a, 'js>) {}
+/// # }
+/// #
+/// # unsafe impl<'js> JsLifetime<'js> for Upper {
+/// #     type Changed<'to> = Upper;
+/// # }
+///
+/// impl<'js> ProxyHandler<'js> for Upper {
+///     const IMPLEMENTS_GET: bool = true;
+///
+///     fn get(&self, target: Object<'js>, key: Atom<'js>, _receiver: Value<'js>) -> Result<Value<'js>> {
+///         let name = key.to_string()?.to_uppercase();
+///         target.get(name)
+///     }
+/// }
+///
+/// let target = Object::new(ctx.clone())?;
+/// target.set("FOO", 42)?;
+/// let proxy = Proxy::new(ctx.clone(), target, Upper)?;
+/// ctx.globals().set("proxy", proxy)?;
+/// assert_eq!(ctx.eval::<i32, _>("proxy.foo")?, 42);
+/// # Ok(())
+/// # }).unwrap();
+/// ```
+pub trait ProxyHandler<'js>: Trace<'js> + JsLifetime<'js> + Sized {
+    /// Whether the handler implements [`get`](ProxyHandler::get).
+    const IMPLEMENTS_GET: bool = false;
+    /// Whether the handler implements [`set`](ProxyHandler::set).
+    const IMPLEMENTS_SET: bool = false;
+    /// Whether the handler implements [`has`](ProxyHandler::has).
+    const IMPLEMENTS_HAS: bool = false;
+    /// Whether the handler implements [`delete_property`](ProxyHandler::delete_property).
+    const IMPLEMENTS_DELETE_PROPERTY: bool = false;
+    /// Whether the handler implements [`own_keys`](ProxyHandler::own_keys).
+    const IMPLEMENTS_OWN_KEYS: bool = false;
+    /// Whether the handler implements
+    /// [`get_own_property_descriptor`](ProxyHandler::get_own_property_descriptor).
+    const IMPLEMENTS_GET_OWN_PROPERTY_DESCRIPTOR: bool = false;
+    /// Whether the handler implements [`apply`](ProxyHandler::apply).
+    const IMPLEMENTS_APPLY: bool = false;
+    /// Whether the handler implements [`construct`](ProxyHandler::construct).
+    const IMPLEMENTS_CONSTRUCT: bool = false;
+
+    /// Trap for getting a property value.
+    fn get(&self, target: Object<'js>, key: Atom<'js>, receiver: Value<'js>) -> Result<Value<'js>> {
+        let ctx = target.ctx().clone();
+        reflect(
+            &ctx,
+            qjs::RQUICKJS_REFLECT_GET,
+            &[target.into_value(), key.to_property_key()?, receiver],
+        )
+    }
+
+    /// Trap for setting a property value, returns whether the assignment succeeded.
+    fn set(
+        &self,
+        target: Object<'js>,
+        key: Atom<'js>,
+        value: Value<'js>,
+        receiver: Value<'js>,
+    ) -> Result<bool> {
+        let ctx = target.ctx().clone();
+        reflect(
+            &ctx,
+            qjs::RQUICKJS_REFLECT_SET,
+            &[target.into_value(), key.to_property_key()?, value, receiver],
+        )
+    }
+
+    /// Trap for the `in` operator.
+    fn has(&self, target: Object<'js>, key: Atom<'js>) -> Result<bool> {
+        let ctx = target.ctx().clone();
+        reflect(
+            &ctx,
+            qjs::RQUICKJS_REFLECT_HAS,
+            &[target.into_value(), key.to_property_key()?],
+        )
+    }
+
+    /// Trap for the `delete` operator, returns whether the property was deleted.
+    fn delete_property(&self, target: Object<'js>, key: Atom<'js>) -> Result<bool> {
+        let ctx = target.ctx().clone();
+        reflect(
+            &ctx,
+            qjs::RQUICKJS_REFLECT_DELETE_PROPERTY,
+            &[target.into_value(), key.to_property_key()?],
+        )
+    }
+
+    /// Trap for listing the own property keys of the object.
+    fn own_keys(&self, target: Object<'js>) -> Result<Vec<Atom<'js>>> {
+        let ctx = target.ctx().clone();
+        let keys: Array = reflect(&ctx, qjs::RQUICKJS_REFLECT_OWN_KEYS, &[target.into_value()])?;
+        keys.iter::<Value>()
+            .map(|key| Atom::from_value(ctx.clone(), &key?))
+            .collect()
+    }
+
+    /// Trap for getting the descriptor of an own property.
+    ///
+    /// Returns the descriptor object or `None` if the property doesn't exist.
+    fn get_own_property_descriptor(
+        &self,
+        target: Object<'js>,
+        key: Atom<'js>,
+    ) -> Result<Option<Object<'js>>> {
+        let ctx = target.ctx().clone();
+        reflect(
+            &ctx,
+            qjs::RQUICKJS_REFLECT_GET_OWN_PROPERTY_DESCRIPTOR,
+            &[target.into_value(), key.to_property_key()?],
+        )
+    }
+
+    /// Trap for calling the proxy as a function.
+    ///
+    /// Only called if the target is a function.
+    fn apply(
+        &self,
+        target: Object<'js>,
+        this: Value<'js>,
+        args: Vec<Value<'js>>,
+    ) -> Result<Value<'js>> {
+        let ctx = target.ctx().clone();
+        let args = args.into_js(&ctx)?;
+        reflect(
+            &ctx,
+            qjs::RQUICKJS_REFLECT_APPLY,
+            &[target.into_value(), this, args],
+        )
+    }
+
+    /// Trap for calling the proxy as a constructor with `new`.
+    ///
+    /// Only called if the target is a constructor.
+    fn construct(
+        &self,
+        target: Object<'js>,
+        args: Vec<Value<'js>>,
+        new_target: Value<'js>,
+    ) -> Result<Value<'js>> {
+        let ctx = target.ctx().clone();
+        let args = args.into_js(&ctx)?;
+        reflect(
+            &ctx,
+            qjs::RQUICKJS_REFLECT_CONSTRUCT,
+            &[target.into_value(), args, new_target],
+        )
+    }
+}
+
+/// Call the intrinsic `Reflect` function selected by `op`.
+fn reflect<'js, R: FromJs<'js>>(
+    ctx: &Ctx<'js>,
+    op: core::ffi::c_int,
+    args: &[Value<'js>],
+) -> Result<R> {
+    let mut raw = [qjs::JS_UNDEFINED; 4];
+    for (raw, arg) in raw.iter_mut().zip(args) {
+        *raw = arg.as_js_value();
+    }
+    call(ctx, |ctx| unsafe {
+        qjs::rquickjs_reflect(ctx, op, args.len() as _, raw.as_mut_ptr())
+    })
+}
+
+/// The class of the handler objects of proxies with a Rust handler.
+struct HandlerClass<H>(H);
+
+unsafe impl<'js, H: ProxyHandler<'js>> JsLifetime<'js> for HandlerClass<H> {
+    type Changed<'to> = HandlerClass<H::Changed<'to>>;
+}
+
+impl<'js, H: ProxyHandler<'js>> Trace<'js> for HandlerClass<H> {
+    fn trace<'a>(&self, tracer: Tracer<'a, 'js>) {
+        self.0.trace(tracer)
+    }
+}
+
+type HandlerThis<'js, H> = This<Class<'js, HandlerClass<H>>>;
+
+impl<'js, H: ProxyHandler<'js> + 'js> JsClass<'js> for HandlerClass<H> {
+    const NAME: &'static str = "ProxyHandler";
+
+    type Mutable = Readable;
+
+    fn prototype(ctx: &Ctx<'js>) -> Result<Option<Object<'js>>> {
+        let proto = Object::new(ctx.clone())?;
+        if H::IMPLEMENTS_GET {
+            proto.set(
+                PredefinedAtom::Getter,
+                Function::new(
+                    ctx.clone(),
+                    |ctx: Ctx<'js>,
+                     this: HandlerThis<'js, H>,
+                     target: Object<'js>,
+                     key: Value<'js>,
+                     receiver: Value<'js>| {
+                        let key = Atom::from_value(ctx, &key)?;
+                        this.borrow().0.get(target, key, receiver)
+                    },
+                )?,
+            )?;
+        }
+        if H::IMPLEMENTS_SET {
+            proto.set(
+                PredefinedAtom::Setter,
+                Function::new(
+                    ctx.clone(),
+                    |ctx: Ctx<'js>,
+                     this: HandlerThis<'js, H>,
+                     target: Object<'js>,
+                     key: Value<'js>,
+                     value: Value<'js>,
+                     receiver: Value<'js>| {
+                        let key = Atom::from_value(ctx, &key)?;
+                        this.borrow().0.set(target, key, value, receiver)
+                    },
+                )?,
+            )?;
+        }
+        if H::IMPLEMENTS_HAS {
+            proto.set(
+                PredefinedAtom::Has,
+                Function::new(
+                    ctx.clone(),
+                    |ctx: Ctx<'js>,
+                     this: HandlerThis<'js, H>,
+                     target: Object<'js>,
+                     key: Value<'js>| {
+                        let key = Atom::from_value(ctx, &key)?;
+                        this.borrow().0.has(target, key)
+                    },
+                )?,
+            )?;
+        }
+        if H::IMPLEMENTS_DELETE_PROPERTY {
+            proto.set(
+                PredefinedAtom::DeleteProperty,
+                Function::new(
+                    ctx.clone(),
+                    |ctx: Ctx<'js>,
+                     this: HandlerThis<'js, H>,
+                     target: Object<'js>,
+                     key: Value<'js>| {
+                        let key = Atom::from_value(ctx, &key)?;
+                        this.borrow().0.delete_property(target, key)
+                    },
+                )?,
+            )?;
+        }
+        if H::IMPLEMENTS_OWN_KEYS {
+            proto.set(
+                PredefinedAtom::OwnKeys,
+                Function::new(
+                    ctx.clone(),
+                    |ctx: Ctx<'js>, this: HandlerThis<'js, H>, target: Object<'js>| {
+                        let keys = this.borrow().0.own_keys(target)?;
+                        let array = Array::new(ctx)?;
+                        for (idx, key) in keys.into_iter().enumerate() {
+                            array.set(idx, key.to_property_key()?)?;
+                        }
+                        Ok::<_, Error>(array)
+                    },
+                )?,
+            )?;
+        }
+        if H::IMPLEMENTS_GET_OWN_PROPERTY_DESCRIPTOR {
+            proto.set(
+                PredefinedAtom::GetOwnPropertyDescriptor,
+                Function::new(
+                    ctx.clone(),
+                    |ctx: Ctx<'js>,
+                     this: HandlerThis<'js, H>,
+                     target: Object<'js>,
+                     key: Value<'js>| {
+                        let key = Atom::from_value(ctx, &key)?;
+                        this.borrow().0.get_own_property_descriptor(target, key)
+                    },
+                )?,
+            )?;
+        }
+        if H::IMPLEMENTS_APPLY {
+            proto.set(
+                PredefinedAtom::Apply,
+                Function::new(
+                    ctx.clone(),
+                    |this: HandlerThis<'js, H>,
+                     target: Object<'js>,
+                     this_arg: Value<'js>,
+                     args: Vec<Value<'js>>| {
+                        this.borrow().0.apply(target, this_arg, args)
+                    },
+                )?,
+            )?;
+        }
+        if H::IMPLEMENTS_CONSTRUCT {
+            proto.set(
+                PredefinedAtom::Construct,
+                Function::new(
+                    ctx.clone(),
+                    |this: HandlerThis<'js, H>,
+                     target: Object<'js>,
+                     args: Vec<Value<'js>>,
+                     new_target: Value<'js>| {
+                        this.borrow().0.construct(target, args, new_target)
+                    },
+                )?,
+            )?;
+        }
+        Ok(Some(proto))
+    }
+
+    fn constructor(_ctx: &Ctx<'js>) -> Result<Option<Constructor<'js>>> {
+        Ok(None)
+    }
+}
+
+/// Rust representation of a JavaScript proxy object.
+#[derive(Debug, PartialEq, Clone, Eq, Hash)]
+#[repr(transparent)]
+pub struct Proxy<'js>(pub(crate) Object<'js>);
+
+unsafe impl<'js> JsLifetime<'js> for Proxy<'js> {
+    type Changed<'to> = Proxy<'to>;
+}
+
+impl<'js> Proxy<'js> {
+    /// Create a new proxy for the target object with the hooks of a Rust handler.
+    pub fn new<H>(ctx: Ctx<'js>, target: Object<'js>, handler: H) -> Result<Self>
+    where
+        H: ProxyHandler<'js> + 'js,
+    {
+        let handler = Class::instance(ctx.clone(), HandlerClass(handler))?;
+        Self::with_handler_object(ctx, target, handler.into_inner())
+    }
+
+    /// Create a new proxy for the target object with a JavaScript handler object.
+    pub fn with_handler_object(
+        ctx: Ctx<'js>,
+        target: Object<'js>,
+        handler: Object<'js>,
+    ) -> Result<Self> {
+        let ctor: Constructor = ctx.globals().get(PredefinedAtom::Proxy)?;
+        ctor.construct((target, handler)).map(Proxy)
+    }
+
+    /// Returns the target object of the proxy.
+    ///
+    /// Returns a `TypeError` exception if the proxy was revoked.
+    pub fn target(&self) -> Result<Object<'js>> {
+        let ctx = self.0.ctx();
+        let target = unsafe {
+            let val = qjs::JS_GetProxyTarget(ctx.as_ptr(), self.0.as_js_value());
+            let val = ctx.handle_exception(val)?;
+            Value::from_js_value(ctx.clone(), val)
+        };
+        Object::from_value(target)
+    }
+
+    /// Returns the handler object of the proxy.
+    ///
+    /// Returns a `TypeError` exception if the proxy was revoked.
+    pub fn handler(&self) -> Result<Object<'js>> {
+        let ctx = self.0.ctx();
+        let handler = unsafe {
+            let val = qjs::JS_GetProxyHandler(ctx.as_ptr(), self.0.as_js_value());
+            let val = ctx.handle_exception(val)?;
+            Value::from_js_value(ctx.clone(), val)
+        };
+        Object::from_value(handler)
+    }
+
+    /// Reference as an object
+    #[inline]
+    pub fn as_object(&self) -> &Object<'js> {
+        &self.0
+    }
+
+    /// Convert into an object
+    #[inline]
+    pub fn into_object(self) -> Object<'js> {
+        self.0
+    }
+
+    /// Convert from an object
+    pub fn from_object(object: Object<'js>) -> Option<Self> {
+        object.is_proxy().then_some(Self(object))
+    }
+}
+
+impl<'js> Value<'js> {
+    /// Check if the value is a proxy
+    #[inline]
+    pub fn is_proxy(&self) -> bool {
+        unsafe { qjs::JS_IsProxy(self.value) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        class::{Trace, Tracer},
+        proxy::ProxyHandler,
+        *,
+    };
+    use std::cell::RefCell;
+
+    struct Lazy<'js> {
+        loaded: RefCell<Vec<StdString>>,
+        fallback: Value<'js>,
+    }
+
+    impl<'js> Trace<'js> for Lazy<'js> {
+        fn trace<'a>(&self, tracer: Tracer<'a, 'js>) {
+            self.fallback.trace(tracer)
+        }
+    }
+
+    unsafe impl<'js> JsLifetime<'js> for Lazy<'js> {
+        type Changed<'to> = Lazy<'to>;
+    }
+
+    impl<'js> ProxyHandler<'js> for Lazy<'js> {
+        const IMPLEMENTS_GET: bool = true;
+        const IMPLEMENTS_HAS: bool = true;
+        const IMPLEMENTS_OWN_KEYS: bool = true;
+
+        fn get(&self, target: Object<'js>, key: Atom<'js>, _: Value<'js>) -> Result<Value<'js>> {
+            let name = key.to_string()?;
+            if !target.contains_key(name.as_str())? {
+                if name.starts_with("row") {
+                    self.loaded.borrow_mut().push(name.clone());
+                    target.set(name.as_str(), name.len())?;
+                } else {
+                    return Ok(self.fallback.clone());
+                }
+            }
+            target.get(name)
+        }
+
+        fn has(&self, _: Object<'js>, key: Atom<'js>) -> Result<bool> {
+            Ok(key.to_string()?.starts_with("row"))
+        }
+
+        fn own_keys(&self, _: Object<'js>) -> Result<Vec<Atom<'js>>> {
+            let ctx = self.fallback.ctx();
+            Ok(vec![Atom::from_str(ctx.clone(), "row1")?])
+        }
+    }
+
+    #[test]
+    fn rust_handler() {
+        test_with(|ctx| {
+            let fallback = StdString::from("missing").into_js(&ctx).unwrap();
+            let handler = Lazy {
+                loaded: RefCell::new(Vec::new()),
+                fallback,
+            };
+            let target = Object::new(ctx.clone()).unwrap();
+            let proxy = Proxy::new(ctx.clone(), target.clone(), handler).unwrap();
+            let handler = proxy.handler().unwrap();
+            ctx.globals().set("proxy", proxy.clone()).unwrap();
+            let ok: bool = ctx
+                .eval(
+                    r#"
+                    proxy.row12 === 5 &&
+                    proxy.other === "missing" &&
+                    "row3" in proxy && !("other" in proxy) &&
+                    Reflect.ownKeys(proxy).join() === "row1" &&
+                    Object.keys(proxy).length === 0 &&
+                    (proxy.foo = 1, proxy.foo === 1) &&
+                    delete proxy.foo
+                "#,
+                )
+                .catch(&ctx)
+                .unwrap();
+            assert!(ok);
+            assert!(target.contains_key("row12").unwrap());
+
+            assert!(proxy.is_proxy());
+            assert_eq!(proxy.target().unwrap(), target);
+            assert_eq!(proxy.handler().unwrap(), handler);
+            assert!(handler.get::<_, Function>("get").is_ok());
+            assert!(!handler.contains_key("set").unwrap());
+            assert!(!handler.contains_key("deleteProperty").unwrap());
+        });
+    }
+
+    #[test]
+    fn apply_and_construct() {
+        struct Double;
+
+        impl<'js> Trace<'js> for Double {
+            fn trace<'a>(&self, _tracer: Tracer<'a, 'js>) {}
+        }
+
+        unsafe impl<'js> JsLifetime<'js> for Double {
+            type Changed<'to> = Double;
+        }
+
+        impl<'js> ProxyHandler<'js> for Double {
+            const IMPLEMENTS_APPLY: bool = true;
+
+            fn apply(
+                &self,
+                target: Object<'js>,
+                _: Value<'js>,
+                args: Vec<Value<'js>>,
+            ) -> Result<Value<'js>> {
+                let x: i32 = target.as_function().unwrap().call((args[0].clone(),))?;
+                (x * 2).into_js(target.ctx())
+            }
+        }
+
+        test_with(|ctx| {
+            let target: Object = ctx
+                .eval("(function (x) { if (new.target) this.x = x; return x + 1 })")
+                .unwrap();
+            let proxy = Proxy::new(ctx.clone(), target, Double).unwrap();
+            ctx.globals().set("f", proxy).unwrap();
+            assert_eq!(ctx.eval::<i32, _>("f(2)").unwrap(), 6);
+            assert_eq!(ctx.eval::<i32, _>("new f(4).x").unwrap(), 4);
+
+            // Proxies of functions are both functions and proxies.
+            let f: Value = ctx.globals().get("f").unwrap();
+            assert!(f.is_function());
+            assert!(f.into_proxy().is_some());
+        });
+    }
+
+    #[test]
+    fn default_traps() {
+        struct Forward;
+
+        impl<'js> Trace<'js> for Forward {
+            fn trace<'a>(&self, _tracer: Tracer<'a, 'js>) {}
+        }
+
+        unsafe impl<'js> JsLifetime<'js> for Forward {
+            type Changed<'to> = Forward;
+        }
+
+        impl<'js> ProxyHandler<'js> for Forward {}
+
+        /// Installs every trap with the default implementation.
+        struct ForwardAll;
+
+        impl<'js> Trace<'js> for ForwardAll {
+            fn trace<'a>(&self, _tracer: Tracer<'a, 'js>) {}
+        }
+
+        unsafe impl<'js> JsLifetime<'js> for ForwardAll {
+            type Changed<'to> = ForwardAll;
+        }
+
+        impl<'js> ProxyHandler<'js> for ForwardAll {
+            const IMPLEMENTS_GET: bool = true;
+            const IMPLEMENTS_SET: bool = true;
+            const IMPLEMENTS_HAS: bool = true;
+            const IMPLEMENTS_DELETE_PROPERTY: bool = true;
+            const IMPLEMENTS_OWN_KEYS: bool = true;
+            const IMPLEMENTS_GET_OWN_PROPERTY_DESCRIPTOR: bool = true;
+            const IMPLEMENTS_APPLY: bool = true;
+            const IMPLEMENTS_CONSTRUCT: bool = true;
+        }
+
+        test_with(|ctx| {
+            let target: Object = ctx.eval("[1, 2]").unwrap();
+            let proxy = Proxy::new(ctx.clone(), target, Forward).unwrap();
+            assert!(proxy.handler().unwrap().keys::<Atom>().next().is_none());
+            ctx.globals().set("p", proxy).unwrap();
+
+            // The default implementations don't depend on the global `Reflect`.
+            ctx.eval::<(), _>("globalThis.Reflect = undefined").unwrap();
+            let target: Object = ctx.eval("[1, 2]").unwrap();
+            let proxy = Proxy::new(ctx.clone(), target, ForwardAll).unwrap();
+            ctx.globals().set("q", proxy).unwrap();
+            let target: Object = ctx
+                .eval("(function (x) { if (new.target) this.x = x; return x + 1 })")
+                .unwrap();
+            let proxy = Proxy::new(ctx.clone(), target, ForwardAll).unwrap();
+            ctx.globals().set("f", proxy).unwrap();
+
+            let ok: bool = ctx
+                .eval(
+                    r#"
+                    [p, q].every((p) =>
+                        [...p].join() === "1,2" &&
+                        Symbol.iterator in p &&
+                        (p[2] = 3, p.length === 3) &&
+                        Object.keys(p).join() === "0,1,2" &&
+                        Object.getOwnPropertyDescriptor(p, 1).value === 2 &&
+                        delete p[0] && !(0 in p)
+                    ) &&
+                    f(1) === 2 &&
+                    new f(3).x === 3
+                "#,
+                )
+                .catch(&ctx)
+                .unwrap();
+            assert!(ok);
+        });
+    }
+
+    #[test]
+    fn javascript_proxy() {
+        test_with(|ctx| {
+            let value: Value = ctx
+                .eval("Proxy.revocable({ a: 1 }, { get: () => 2 })")
+                .unwrap();
+            let revocable = value.into_object().unwrap();
+            let proxy: Proxy = revocable.get("proxy").unwrap();
+            assert_eq!(proxy.get::<_, i32>("a").unwrap(), 2);
+            assert_eq!(proxy.target().unwrap().get::<_, i32>("a").unwrap(), 1);
+
+            revocable
+                .get::<_, Function>("revoke")
+                .unwrap()
+                .call::<_, ()>(())
+                .unwrap();
+            assert!(proxy.target().is_err());
+            let _ = ctx.catch();
+
+            let object: Object = ctx.eval("({})").unwrap();
+            assert!(!object.is_proxy());
+            assert!(Proxy::from_js(&ctx, object.into_value()).is_err());
+        });
+    }
+}
@@ -0,0 +1,116 @@
+use crate::{atom::PredefinedAtom, qjs, Array, Ctx, IntoJs, Object, Result, StdString};
+
+use super::call;
+
+/// Rust representation of a JavaScript object of class `RegExp`.
+#[derive(Debug, PartialEq, Clone, Eq, Hash)]
+#[repr(transparent)]
+pub struct RegExp<'js>(pub(crate) Object<'js>);
+
+impl<'js> RegExp<'js> {
+    /// Create a new regular expression from a pattern and flags.
+    ///
+    /// Returns a `SyntaxError` exception if the pattern or flags are invalid.
+    pub fn new(ctx: Ctx<'js>, pattern: &str, flags: &str) -> Result<Self> {
+        let (pattern, flags) = (pattern.into_js(&ctx)?, flags.into_js(&ctx)?);
+        call(&ctx, |ctx| unsafe {
+            qjs::rquickjs_regexp_new(ctx, pattern.as_js_value(), flags.as_js_value())
+        })
+    }
+
+    /// Returns the source text of the pattern.
+    pub fn source(&self) -> Result<StdString> {
+        self.0.get(PredefinedAtom::Source)
+    }
+
+    /// Returns the flags of the regular expression.
+    pub fn flags(&self) -> Result<StdString> {
+        self.0.get(PredefinedAtom::Flags)
+    }
+
+    /// Returns the index at which the next match of a global or sticky regular expression starts.
+    pub fn last_index(&self) -> Result<usize> {
+        self.0.get(PredefinedAtom::LastIndex)
+    }
+
+    /// Set the index at which the next match of a global or sticky regular expression starts.
+    pub fn set_last_index(&self, index: usize) -> Result<()> {
+        self.0.set(PredefinedAtom::LastIndex, index)
+    }
+
+    /// Returns whether the regular expression matches the given text.
+    pub fn test(&self, text: &str) -> Result<bool> {
+        self.exec(text).map(|found| found.is_some())
+    }
+
+    /// Match the regular expression against the given text.
+    ///
+    /// Returns the match array with the matched text followed by the captured groups, or `None`
+    /// if there is no match.
+    pub fn exec(&self, text: &str) -> Result<Option<Array<'js>>> {
+        let ctx = self.0.ctx();
+        let (regexp, text) = (self.0.as_js_value(), text.into_js(ctx)?);
+        call(ctx, |ctx| unsafe {
+            qjs::rquickjs_regexp_exec(ctx, regexp, text.as_js_value())
+        })
+    }
+
+    pub fn into_object(self) -> Object<'js> {
+        self.0
+    }
+
+    pub fn as_object(&self) -> &Object<'js> {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn from_javascript() {
+        test_with(|ctx| {
+            let re: RegExp = ctx.eval("/(a+)(b)?/gi").unwrap();
+            assert_eq!(re.source().unwrap(), "(a+)(b)?");
+            assert_eq!(re.flags().unwrap(), "gi");
+            assert!(re.test("xAAb").unwrap());
+            re.set_last_index(0).unwrap();
+
+            let found = re.exec("xaab aa").unwrap().unwrap();
+            assert_eq!(found.get::<StdString>(0).unwrap(), "aab");
+            assert_eq!(found.get::<StdString>(1).unwrap(), "aa");
+            assert_eq!(re.last_index().unwrap(), 4);
+            let found = re.exec("xaab aa").unwrap().unwrap();
+            assert_eq!(found.get::<Option<StdString>>(2).unwrap(), None);
+            assert!(re.exec("xaab aa").unwrap().is_none());
+
+            let value: Value = ctx.eval("'a+'").unwrap();
+            assert!(!value.is_reg_exp());
+        });
+    }
+
+    #[test]
+    fn into_javascript() {
+        test_with(|ctx| {
+            let re = RegExp::new(ctx.clone(), "^\\d+$", "u").unwrap();
+            assert_eq!(re.type_of(), Type::RegExp);
+            ctx.globals().set("re", re).unwrap();
+            let ok: bool = ctx
+                .eval("re instanceof RegExp && re.test('123') && !re.test('12a')")
+                .unwrap();
+            assert!(ok);
+
+            ctx.eval::<(), _>("RegExp.prototype.exec = () => null; globalThis.RegExp = null;")
+                .unwrap();
+            let re = RegExp::new(ctx.clone(), "b+", "").unwrap();
+            assert!(re.test("abb").unwrap());
+            let found = re.exec("abb").unwrap().unwrap();
+            assert_eq!(found.get::<StdString>(0).unwrap(), "bb");
+
+            let err = RegExp::new(ctx.clone(), "(", "").unwrap_err();
+            assert!(err.is_exception());
+            let _ = ctx.catch();
+        });
+    }
+}
@@ -0,0 +1,237 @@
+//! JavaScript set types.
+
+use crate::{qjs, Ctx, FromJs, IntoJs, Object, Result, Value};
+use core::{iter::FusedIterator, marker::PhantomData};
+
+use super::{call, convert::FromIteratorJs, map::RawIter};
+
+/// Rust representation of a JavaScript object of class `Set`.
+///
+/// A set contains unique values of any type and remembers their insertion order.
+///
+/// The methods call the engine directly, so they are not affected by changes to the global `Set`
+/// or its prototype.
+#[derive(Debug, PartialEq, Clone, Eq, Hash)]
+#[repr(transparent)]
+pub struct Set<'js>(pub(crate) Object<'js>);
+
+impl<'js> Set<'js> {
+    /// Create a new JavaScript set.
+    pub fn new(ctx: Ctx<'js>) -> Result<Self> {
+        call(&ctx, |ctx| unsafe { qjs::rquickjs_map_new(ctx, true) }).map(Set)
+    }
+
+    /// Get the number of values in the set.
+    pub fn size(&self) -> Result<usize> {
+        let set = self.0.as_js_value();
+        call(self.0.ctx(), |ctx| unsafe {
+            qjs::rquickjs_map_size(ctx, set, true)
+        })
+    }
+
+    /// Returns whether the set is empty.
+    pub fn is_empty(&self) -> Result<bool> {
+        self.size().map(|size| size == 0)
+    }
+
+    /// Add a value to the set.
+    pub fn add<V: IntoJs<'js>>(&self, value: V) -> Result<()> {
+        let ctx = self.0.ctx();
+        let (set, value) = (self.0.as_js_value(), value.into_js(ctx)?);
+        call::<Value>(ctx, |ctx| unsafe {
+            let value = value.as_js_value();
+            qjs::rquickjs_map_set(ctx, set, value, value, true)
+        })?;
+        Ok(())
+    }
+
+    /// Returns whether the set contains a value.
+    pub fn has<V: IntoJs<'js>>(&self, value: V) -> Result<bool> {
+        let ctx = self.0.ctx();
+        let (set, value) = (self.0.as_js_value(), value.into_js(ctx)?);
+        call(ctx, |ctx| unsafe {
+            qjs::rquickjs_map_has(ctx, set, value.as_js_value(), true)
+        })
+    }
+
+    /// Remove a value from the set, returning whether the value was present.
+    pub fn delete<V: IntoJs<'js>>(&self, value: V) -> Result<bool> {
+        let ctx = self.0.ctx();
+        let (set, value) = (self.0.as_js_value(), value.into_js(ctx)?);
+        call(ctx, |ctx| unsafe {
+            qjs::rquickjs_map_delete(ctx, set, value.as_js_value(), true)
+        })
+    }
+
+    /// Remove all values from the set.
+    pub fn clear(&self) -> Result<()> {
+        let set = self.0.as_js_value();
+        call(self.0.ctx(), |ctx| unsafe {
+            qjs::rquickjs_map_clear(ctx, set, true)
+        })
+    }
+
+    /// Get an iterator over the values of the set in insertion order.
+    ///
+    /// The iterator is live, values added to the set during iteration are visited as well.
+    pub fn iter<T: FromJs<'js>>(&self) -> SetIter<'js, T> {
+        SetIter {
+            iter: RawIter::new(&self.0, true),
+            marker: PhantomData,
+        }
+    }
+
+    pub fn into_object(self) -> Object<'js> {
+        self.0
+    }
+
+    pub fn as_object(&self) -> &Object<'js> {
+        &self.0
+    }
+}
+
+/// The iterator for a set
+pub struct SetIter<'js, T> {
+    iter: RawIter<'js>,
+    marker: PhantomData<T>,
+}
+
+impl<'js, T> Iterator for SetIter<'js, T>
+where
+    T: FromJs<'js>,
+{
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.iter.next_value()?;
+        Some(value.and_then(|value| {
+            let ctx = value.ctx().clone();
+            T::from_js(&ctx, value)
+        }))
+    }
+}
+
+impl<'js, T> FusedIterator for SetIter<'js, T> where T: FromJs<'js> {}
+
+impl<'js> IntoIterator for Set<'js> {
+    type Item = Result<Value<'js>>;
+    type IntoIter = SetIter<'js, Value<'js>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'js, A> FromIteratorJs<'js, A> for Set<'js>
+where
+    A: IntoJs<'js>,
+{
+    type Item = Value<'js>;
+
+    fn from_iter_js<T>(ctx: &Ctx<'js>, iter: T) -> Result<Self>
+    where
+        T: IntoIterator<Item = A>,
+    {
+        let set = Set::new(ctx.clone())?;
+        for value in iter {
+            set.add(value)?;
+        }
+        Ok(set)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn from_javascript() {
+        test_with(|ctx| {
+            let set: Set = ctx.eval(r#"new Set([1, "a", 1, {}])"#).unwrap();
+            assert_eq!(set.size().unwrap(), 3);
+            assert!(set.has(1).unwrap());
+            assert!(set.has("a").unwrap());
+            assert!(!set.has("b").unwrap());
+        });
+    }
+
+    #[test]
+    fn intrinsic() {
+        test_with(|ctx| {
+            ctx.eval::<(), _>("Set.prototype.add = null; globalThis.Set = null;")
+                .unwrap();
+            let set = Set::new(ctx.clone()).unwrap();
+            set.add(1).unwrap();
+            set.add(1).unwrap();
+            assert_eq!(set.size().unwrap(), 1);
+            let values = set.iter::<i32>().collect::<Result<Vec<_>>>().unwrap();
+            assert_eq!(values, [1]);
+        });
+    }
+
+    #[test]
+    fn into_javascript() {
+        test_with(|ctx| {
+            let set = Set::new(ctx.clone()).unwrap();
+            set.add(1).unwrap();
+            set.add("b").unwrap();
+            set.add(1).unwrap();
+            set.add(3).unwrap();
+            assert!(set.delete(3).unwrap());
+            assert!(!set.delete(3).unwrap());
+            ctx.globals().set("s", set.clone()).unwrap();
+            let ok: bool = ctx
+                .eval(r#"s instanceof Set && s.size === 2 && s.has(1) && s.has("b")"#)
+                .unwrap();
+            assert!(ok);
+            set.clear().unwrap();
+            assert!(set.is_empty().unwrap());
+        });
+    }
+
+    #[test]
+    fn iter() {
+        test_with(|ctx| {
+            let set: Set = ctx.eval("new Set([3, 1, 2])").unwrap();
+            let values = set.iter::<i32>().collect::<Result<Vec<_>>>().unwrap();
+            assert_eq!(values, [3, 1, 2]);
+
+            let set: Set = ["x", "y"].into_iter().collect_js(&ctx).unwrap();
+            let values = set.into_iter().collect::<Result<Vec<_>>>().unwrap();
+            assert_eq!(values.len(), 2);
+            assert_eq!(values[1].as_string().unwrap().to_string().unwrap(), "y");
+        });
+    }
+
+    #[test]
+    fn as_set() {
+        use crate::convert::AsSet;
+        use std::collections::BTreeSet;
+
+        test_with(|ctx| {
+            let set = BTreeSet::from([1, 2]);
+            ctx.globals().set("s", AsSet(&set)).unwrap();
+            ctx.globals().set("a", set).unwrap();
+            let ok: bool = ctx
+                .eval("s instanceof Set && s.has(2) && Array.isArray(a)")
+                .unwrap();
+            assert!(ok);
+
+            let AsSet(set): AsSet<BTreeSet<i32>> = ctx.eval("new Set([3, 1])").unwrap();
+            assert_eq!(set, BTreeSet::from([1, 3]));
+            assert!(ctx.eval::<AsSet<Vec<i32>>, _>("[1]").is_err());
+        });
+    }
+
+    #[test]
+    fn type_of() {
+        test_with(|ctx| {
+            let value: Value = ctx.eval("new Set()").unwrap();
+            assert!(value.is_set());
+            assert!(!value.is_map());
+            assert_eq!(value.type_of(), Type::Set);
+            assert!(value.as_set().is_some());
+            assert!(Array::from_js(&ctx, value).is_err());
+        });
+    }
+}
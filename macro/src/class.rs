@@ -17,6 +17,8 @@ use crate::{
 #[derive(Debug, Default, Clone)]
 pub(crate) struct ClassConfig {
     pub frozen: bool,
+    pub exotic: bool,
+    pub extends: Option<syn::Type>,
     pub crate_: Option<String>,
     pub rename: Option<String>,
     pub rename_all: Option<Case>,
@@ -24,6 +26,8 @@ pub(crate) struct ClassConfig {
 
 pub(crate) enum ClassOption {
     Frozen(FlagOption<kw::frozen>),
+    Exotic(FlagOption<kw::exotic>),
+    Extends(ValueOption<kw::extends, Box<syn::Type>>),
     Crate(ValueOption<Token![crate], LitStr>),
     Rename(ValueOption<kw::rename, LitStr>),
     RenameAll(ValueOption<kw::rename_all, Case>),
@@ -33,6 +37,10 @@ impl Parse for ClassOption {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         if input.peek(kw::frozen) {
             input.parse().map(Self::Frozen)
+        } else if input.peek(kw::exotic) {
+            input.parse().map(Self::Exotic)
+        } else if input.peek(kw::extends) {
+            input.parse().map(Self::Extends)
         } else if input.peek(Token![crate]) {
             input.parse().map(Self::Crate)
         } else if input.peek(kw::rename) {
@@ -51,6 +59,12 @@ impl ClassConfig {
             ClassOption::Frozen(ref x) => {
                 self.frozen = x.is_true();
             }
+            ClassOption::Exotic(ref x) => {
+                self.exotic = x.is_true();
+            }
+            ClassOption::Extends(ref x) => {
+                self.extends = Some((*x.value).clone());
+            }
             ClassOption::Crate(ref x) => {
                 self.crate_ = Some(x.value.value());
             }
@@ -258,6 +272,137 @@ impl Class {
         }
     }
 
+    /// Expands the exotic hooks of the class, forwarding them to the `Exotic` trait.
+    pub fn expand_exotic(&self, crate_name: &Ident) -> TokenStream {
+        if !self.config().exotic {
+            return TokenStream::new();
+        }
+
+        quote! {
+            const EXOTIC: bool = true;
+
+            fn get_own_property(
+                this: &#crate_name::class::JsCell<'js, Self>,
+                ctx: &#crate_name::Ctx<'js>,
+                key: #crate_name::Atom<'js>,
+            ) -> #crate_name::Result<Option<#crate_name::class::PropertyDescriptor<'js>>> {
+                let this = this.try_borrow().map_err(#crate_name::Error::ClassBorrow)?;
+                #crate_name::class::Exotic::get_own_property(&*this, ctx, key)
+            }
+
+            fn define_own_property(
+                this: &#crate_name::class::JsCell<'js, Self>,
+                ctx: &#crate_name::Ctx<'js>,
+                key: #crate_name::Atom<'js>,
+                desc: #crate_name::class::PropertyDescriptor<'js>,
+            ) -> #crate_name::Result<Option<bool>> {
+                let mut this = this.try_borrow_mut().map_err(#crate_name::Error::ClassBorrow)?;
+                #crate_name::class::Exotic::define_own_property(&mut *this, ctx, key, desc)
+            }
+
+            fn delete_property(
+                this: &#crate_name::class::JsCell<'js, Self>,
+                ctx: &#crate_name::Ctx<'js>,
+                key: #crate_name::Atom<'js>,
+            ) -> #crate_name::Result<Option<bool>> {
+                let mut this = this.try_borrow_mut().map_err(#crate_name::Error::ClassBorrow)?;
+                #crate_name::class::Exotic::delete_property(&mut *this, ctx, key)
+            }
+
+            fn get_own_property_names(
+                this: &#crate_name::class::JsCell<'js, Self>,
+                ctx: &#crate_name::Ctx<'js>,
+            ) -> #crate_name::Result<#crate_name::alloc::vec::Vec<#crate_name::Atom<'js>>> {
+                let this = this.try_borrow().map_err(#crate_name::Error::ClassBorrow)?;
+                #crate_name::class::Exotic::get_own_property_names(&*this, ctx)
+            }
+
+            fn has_property(
+                this: &#crate_name::class::JsCell<'js, Self>,
+                ctx: &#crate_name::Ctx<'js>,
+                key: #crate_name::Atom<'js>,
+            ) -> #crate_name::Result<Option<bool>> {
+                let this = this.try_borrow().map_err(#crate_name::Error::ClassBorrow)?;
+                #crate_name::class::Exotic::has_property(&*this, ctx, key)
+            }
+
+            fn get_property(
+                this: &#crate_name::class::JsCell<'js, Self>,
+                ctx: &#crate_name::Ctx<'js>,
+                key: #crate_name::Atom<'js>,
+                receiver: #crate_name::Value<'js>,
+            ) -> #crate_name::Result<Option<#crate_name::Value<'js>>> {
+                let this = this.try_borrow().map_err(#crate_name::Error::ClassBorrow)?;
+                #crate_name::class::Exotic::get_property(&*this, ctx, key, receiver)
+            }
+
+            fn set_property(
+                this: &#crate_name::class::JsCell<'js, Self>,
+                ctx: &#crate_name::Ctx<'js>,
+                key: #crate_name::Atom<'js>,
+                value: #crate_name::Value<'js>,
+                receiver: #crate_name::Value<'js>,
+            ) -> #crate_name::Result<Option<bool>> {
+                let mut this = this.try_borrow_mut().map_err(#crate_name::Error::ClassBorrow)?;
+                #crate_name::class::Exotic::set_property(&mut *this, ctx, key, value, receiver)
+            }
+        }
+    }
+
+    /// Expands the parent of the class, if it extends another class.
+    ///
+    /// Returns the `EXTENDS` constant of the class and a function which checks that the first
+    /// field of the class is the parent.
+    pub fn expand_extends(&self, crate_name: &Ident) -> Result<(TokenStream, TokenStream)> {
+        let Some(parent) = self.config().extends.as_ref() else {
+            return Ok((TokenStream::new(), TokenStream::new()));
+        };
+
+        let Class::Struct {
+            ref ident,
+            ref generics,
+            ref fields,
+            ..
+        } = self
+        else {
+            return Err(Error::new(
+                self.ident().span(),
+                "only structs can extend another class",
+            ));
+        };
+
+        let first = match fields {
+            Fields::Named(x) if !x.is_empty() => {
+                let ident = x[0].ident.as_ref().unwrap();
+                quote!(#ident)
+            }
+            Fields::Unnamed(x) if !x.is_empty() => {
+                let index = syn::Index::from(0);
+                quote!(#index)
+            }
+            _ => return Err(Error::new(
+                ident.span(),
+                "a class which extends another class must have the parent class as its first field",
+            )),
+        };
+
+        let generics_with_lifetimes = add_js_lifetime(generics);
+
+        let extends = quote! {
+            const EXTENDS: Option<#crate_name::class::Extends> = Some(unsafe {
+                #crate_name::class::Extends::new::<Self, #parent>()
+            });
+        };
+        let check = quote! {
+            // The class is `#[repr(C)]`, this ensures that its first field is the parent class.
+            #[allow(dead_code)]
+            fn __extends_first_field #generics_with_lifetimes(this: &#ident #generics) -> &#parent {
+                &this.#first
+            }
+        };
+        Ok((extends, check))
+    }
+
     pub fn expand_props(&self, crate_name: &Ident) -> TokenStream {
         let Class::Struct { ref fields, .. } = self else {
             return TokenStream::new();
@@ -307,6 +452,7 @@ impl Class {
                 fields,
                 ..
             } => {
+                let repr = self.config().extends.is_some().then(|| quote!(#[repr(C)]));
                 let fields = match fields {
                     Fields::Named(fields) => {
                         let fields = fields.iter().map(|x| x.expand_field());
@@ -327,6 +473,7 @@ impl Class {
 
                 quote! {
                     #(#attrs)*
+                    #repr
                     #vis #struct_token #ident #generics #fields
                 }
             }
@@ -344,6 +491,8 @@ impl Class {
 
         let mutability = self.mutability();
         let props = self.expand_props(&crate_name);
+        let exotic = self.expand_exotic(&crate_name);
+        let (extends, extends_check) = self.expand_extends(&crate_name)?;
         let reexpand = self.reexpand();
 
         let res = quote! {
@@ -353,11 +502,17 @@ impl Class {
             mod #module_name{
                 pub use super::*;
 
+                #extends_check
+
                 impl #generics_with_lifetimes #crate_name::class::JsClass<'js> for #class_name #generics{
                     const NAME: &'static str = #javascript_name;
 
                     type Mutable = #crate_name::class::#mutability;
 
+                    #exotic
+
+                    #extends
+
                     fn prototype(ctx: &#crate_name::Ctx<'js>) -> #crate_name::Result<Option<#crate_name::Object<'js>>>{
                         use #crate_name::class::impl_::MethodImplementor;
 
@@ -125,6 +125,8 @@ impl<'a> Fold for SelfReplacer<'a> {
 
 pub(crate) mod kw {
     syn::custom_keyword!(frozen);
+    syn::custom_keyword!(exotic);
+    syn::custom_keyword!(extends);
     syn::custom_keyword!(skip_trace);
     syn::custom_keyword!(rename);
     syn::custom_keyword!(rename_all);
@@ -132,6 +134,7 @@ pub(crate) mod kw {
     syn::custom_keyword!(rename_types);
     syn::custom_keyword!(get);
     syn::custom_keyword!(set);
+    syn::custom_keyword!(symbol);
     syn::custom_keyword!(constructor);
     syn::custom_keyword!(skip);
     syn::custom_keyword!(configurable);
@@ -139,4 +142,9 @@ pub(crate) mod kw {
     syn::custom_keyword!(prefix);
     syn::custom_keyword!(declare);
     syn::custom_keyword!(evaluate);
+    syn::custom_keyword!(default);
+    syn::custom_keyword!(optional);
+    syn::custom_keyword!(tag);
+    syn::custom_keyword!(untagged);
+    syn::custom_keyword!(transparent);
 }
@@ -0,0 +1,775 @@
+use convert_case::Casing;
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+use syn::{
+    ext::IdentExt as _,
+    parse::{Parse, ParseStream},
+    parse_quote,
+    spanned::Spanned,
+    Data, DataEnum, DataStruct, DeriveInput, Error, Generics, LitStr, Result, Token, WhereClause,
+};
+
+use crate::{
+    attrs::{take_attributes, FlagOption, OptionList, ValueOption},
+    common::{crate_ident, kw, Case},
+    fields::{Field, Fields},
+};
+
+#[derive(Debug, Default)]
+pub(crate) struct ConvertConfig {
+    pub crate_: Option<String>,
+    pub rename_all: Option<Case>,
+    pub tag: Option<String>,
+    pub untagged: bool,
+    pub transparent: bool,
+}
+
+pub(crate) enum ConvertOption {
+    Crate(ValueOption<Token![crate], LitStr>),
+    RenameAll(ValueOption<kw::rename_all, Case>),
+    Tag(ValueOption<kw::tag, LitStr>),
+    Untagged(FlagOption<kw::untagged>),
+    Transparent(FlagOption<kw::transparent>),
+}
+
+impl Parse for ConvertOption {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(Token![crate]) {
+            input.parse().map(Self::Crate)
+        } else if input.peek(kw::rename_all) {
+            input.parse().map(Self::RenameAll)
+        } else if input.peek(kw::tag) {
+            input.parse().map(Self::Tag)
+        } else if input.peek(kw::untagged) {
+            input.parse().map(Self::Untagged)
+        } else if input.peek(kw::transparent) {
+            input.parse().map(Self::Transparent)
+        } else {
+            Err(syn::Error::new(
+                input.span(),
+                "invalid conversion attribute",
+            ))
+        }
+    }
+}
+
+impl ConvertConfig {
+    pub fn apply(&mut self, option: &ConvertOption) {
+        match option {
+            ConvertOption::Crate(ref x) => {
+                self.crate_ = Some(x.value.value());
+            }
+            ConvertOption::RenameAll(ref x) => {
+                self.rename_all = Some(x.value);
+            }
+            ConvertOption::Tag(ref x) => {
+                self.tag = Some(x.value.value());
+            }
+            ConvertOption::Untagged(ref x) => {
+                self.untagged = x.is_true();
+            }
+            ConvertOption::Transparent(ref x) => {
+                self.transparent = x.is_true();
+            }
+        }
+    }
+
+    pub fn crate_name(&self) -> Result<String> {
+        if let Some(c) = self.crate_.clone() {
+            return Ok(c);
+        }
+        crate_ident()
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct VariantConfig {
+    pub rename: Option<String>,
+    pub rename_all: Option<Case>,
+    pub skip: bool,
+}
+
+pub(crate) enum VariantOption {
+    Rename(ValueOption<kw::rename, LitStr>),
+    RenameAll(ValueOption<kw::rename_all, Case>),
+    Skip(FlagOption<kw::skip>),
+}
+
+impl Parse for VariantOption {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(kw::rename) {
+            input.parse().map(Self::Rename)
+        } else if input.peek(kw::rename_all) {
+            input.parse().map(Self::RenameAll)
+        } else if input.peek(kw::skip) {
+            input.parse().map(Self::Skip)
+        } else {
+            Err(syn::Error::new(input.span(), "invalid variant attribute"))
+        }
+    }
+}
+
+impl VariantConfig {
+    pub fn apply(&mut self, option: &VariantOption) {
+        match option {
+            VariantOption::Rename(ref x) => {
+                self.rename = Some(x.value.value());
+            }
+            VariantOption::RenameAll(ref x) => {
+                self.rename_all = Some(x.value);
+            }
+            VariantOption::Skip(ref x) => {
+                self.skip = x.is_true();
+            }
+        }
+    }
+}
+
+/// How an enum is represented on the JavaScript side.
+enum Repr<'a> {
+    /// `{ "Variant": content }` or `"Variant"` for unit variants.
+    External,
+    /// `{ tag: "Variant", ...fields }`
+    Internal(&'a str),
+    /// Just the content of the variant.
+    Untagged,
+}
+
+pub(crate) struct Variant {
+    config: VariantConfig,
+    ident: Ident,
+    name: String,
+    fields: Fields,
+}
+
+impl Variant {
+    fn from_variant(variant: syn::Variant, case: Option<Case>) -> Result<Self> {
+        let syn::Variant {
+            ident,
+            fields,
+            mut attrs,
+            ..
+        } = variant;
+
+        let mut config = VariantConfig::default();
+        take_attributes(&mut attrs, |attr| {
+            if !attr.path().is_ident("qjs") {
+                return Ok(false);
+            }
+
+            let options: OptionList<VariantOption> = attr.parse_args()?;
+            options.0.iter().for_each(|x| config.apply(x));
+            Ok(true)
+        })?;
+
+        let name = if let Some(rename) = config.rename.clone() {
+            rename
+        } else if let Some(case) = case {
+            ident.unraw().to_string().to_case(case.to_convert_case())
+        } else {
+            ident.unraw().to_string()
+        };
+
+        Ok(Variant {
+            config,
+            ident,
+            name,
+            fields: Fields::from_fields(fields)?,
+        })
+    }
+}
+
+enum ConvertData {
+    Struct(Fields),
+    Enum(Vec<Variant>),
+}
+
+pub(crate) struct Convert {
+    config: ConvertConfig,
+    crate_name: Ident,
+    ident: Ident,
+    name: String,
+    generics: Generics,
+    data: ConvertData,
+}
+
+impl Convert {
+    pub fn from_derive_input(input: DeriveInput) -> Result<Self> {
+        let DeriveInput {
+            ident,
+            generics,
+            data,
+            mut attrs,
+            ..
+        } = input;
+
+        let mut config = ConvertConfig::default();
+        take_attributes(&mut attrs, |attr| {
+            if !attr.path().is_ident("qjs") {
+                return Ok(false);
+            }
+
+            let options: OptionList<ConvertOption> = attr.parse_args()?;
+            options.0.iter().for_each(|x| config.apply(x));
+            Ok(true)
+        })?;
+
+        let crate_name = format_ident!("{}", config.crate_name()?);
+
+        let data = match data {
+            Data::Struct(DataStruct { fields, .. }) => {
+                if config.tag.is_some() || config.untagged {
+                    return Err(Error::new(
+                        ident.span(),
+                        "the `tag` and `untagged` options are only supported on enums",
+                    ));
+                }
+                let fields = Fields::from_fields(fields)?;
+                let converted = match fields {
+                    Fields::Named(ref fields) | Fields::Unnamed(ref fields) => {
+                        fields.iter().filter(|f| !f.config.skip).count()
+                    }
+                    Fields::Unit => 0,
+                };
+                if config.transparent && converted != 1 {
+                    return Err(Error::new(
+                        ident.span(),
+                        "a transparent struct must have exactly one field which isn't skipped",
+                    ));
+                }
+                ConvertData::Struct(fields)
+            }
+            Data::Enum(DataEnum { variants, .. }) => {
+                if config.transparent {
+                    return Err(Error::new(
+                        ident.span(),
+                        "the `transparent` option is only supported on structs",
+                    ));
+                }
+                if config.tag.is_some() && config.untagged {
+                    return Err(Error::new(
+                        ident.span(),
+                        "an enum can't be both internally tagged and untagged",
+                    ));
+                }
+                let variants = variants
+                    .into_iter()
+                    .map(|x| Variant::from_variant(x, config.rename_all))
+                    .collect::<Result<Vec<_>>>()?;
+                if config.tag.is_some() {
+                    for v in variants.iter() {
+                        if matches!(v.fields, Fields::Unnamed(ref f) if f.len() != 1) {
+                            return Err(Error::new(
+                                v.ident.span(),
+                                "internally tagged enums don't support tuple variants",
+                            ));
+                        }
+                    }
+                }
+                ConvertData::Enum(variants)
+            }
+            Data::Union(u) => {
+                return Err(Error::new(
+                    u.union_token.span(),
+                    "deriving conversions for unions is not supported",
+                ))
+            }
+        };
+
+        Ok(Convert {
+            config,
+            crate_name,
+            name: ident.unraw().to_string(),
+            ident,
+            generics,
+            data,
+        })
+    }
+
+    fn repr(&self) -> Repr<'_> {
+        if let Some(tag) = self.config.tag.as_deref() {
+            Repr::Internal(tag)
+        } else if self.config.untagged {
+            Repr::Untagged
+        } else {
+            Repr::External
+        }
+    }
+
+    /// Expand the impl header for the given trait, requiring the trait for all type parameters.
+    fn expand_impl_header(&self, trait_: Ident) -> TokenStream {
+        let crate_name = &self.crate_name;
+        let ident = &self.ident;
+
+        let lifetime_generics = crate::common::add_js_lifetime(&self.generics);
+        let (impl_generics, _, _) = lifetime_generics.split_for_impl();
+        let (_, ty_generics, _) = self.generics.split_for_impl();
+
+        let mut where_clause: WhereClause = self
+            .generics
+            .where_clause
+            .clone()
+            .unwrap_or_else(|| parse_quote!(where));
+        for param in self.generics.type_params() {
+            let param = &param.ident;
+            where_clause
+                .predicates
+                .push(parse_quote!(#param: #crate_name::#trait_<'js>));
+        }
+
+        quote! {
+            impl #impl_generics #crate_name::#trait_<'js> for #ident #ty_generics #where_clause
+        }
+    }
+
+    pub fn expand_from_js(&self) -> TokenStream {
+        let crate_name = &self.crate_name;
+        let header = self.expand_impl_header(format_ident!("FromJs"));
+
+        let body = match self.data {
+            ConvertData::Struct(ref fields) => self.expand_from_js_fields(
+                fields,
+                quote!(Self),
+                self.config.rename_all,
+                self.config.transparent,
+            ),
+            ConvertData::Enum(ref variants) => self.expand_from_js_enum(variants),
+        };
+
+        quote! {
+            #header {
+                fn from_js(ctx: &#crate_name::Ctx<'js>, value: #crate_name::Value<'js>) -> #crate_name::Result<Self> {
+                    let _ = ctx;
+                    #body
+                }
+            }
+        }
+    }
+
+    /// Expand a conversion from `value` into the constructor at `path` with the given fields.
+    ///
+    /// A transparent conversion converts `value` into the single field which isn't skipped.
+    fn expand_from_js_fields(
+        &self,
+        fields: &Fields,
+        path: TokenStream,
+        case: Option<Case>,
+        transparent: bool,
+    ) -> TokenStream {
+        let crate_name = &self.crate_name;
+        let type_name = &self.name;
+        if transparent {
+            let field = |f: &Field| {
+                if f.config.skip {
+                    quote!(::core::default::Default::default())
+                } else {
+                    quote!(#crate_name::FromJs::from_js(ctx, value)?)
+                }
+            };
+            return match fields {
+                Fields::Named(fields) => {
+                    let fields = fields.iter().map(|f| {
+                        let ident = f.ident.as_ref().unwrap();
+                        let value = field(f);
+                        quote!(#ident: #value)
+                    });
+                    quote!(Ok(#path { #(#fields,)* }))
+                }
+                Fields::Unnamed(fields) => {
+                    let fields = fields.iter().map(field);
+                    quote!(Ok(#path(#(#fields,)*)))
+                }
+                Fields::Unit => unreachable!("unit structs have no field to convert"),
+            };
+        }
+        match fields {
+            Fields::Named(fields) => {
+                let object = expect_object(crate_name, type_name);
+                let fields = fields.iter().map(|f| {
+                    let ident = f.ident.as_ref().unwrap();
+                    let name = f.js_name(case);
+                    if f.config.skip {
+                        quote!(#ident: ::core::default::Default::default())
+                    } else if f.config.default || f.config.optional {
+                        quote! {
+                            #ident: {
+                                let value: #crate_name::Value = object.get(#name)?;
+                                if value.is_undefined() {
+                                    ::core::default::Default::default()
+                                } else {
+                                    #crate_name::FromJs::from_js(ctx, value)?
+                                }
+                            }
+                        }
+                    } else {
+                        quote!(#ident: object.get(#name)?)
+                    }
+                });
+                quote! {
+                    #object
+                    Ok(#path { #(#fields,)* })
+                }
+            }
+            Fields::Unnamed(fields) => {
+                let mut idx = 0usize;
+                let fields = fields.iter().map(|f| {
+                    if f.config.skip {
+                        quote!(::core::default::Default::default())
+                    } else {
+                        idx += 1;
+                        let idx = idx - 1;
+                        quote!(array.get(#idx)?)
+                    }
+                });
+                quote! {
+                    let array = value
+                        .as_array()
+                        .cloned()
+                        .ok_or_else(|| #crate_name::Error::new_from_js(value.type_name(), #type_name))?;
+                    Ok(#path(#(#fields,)*))
+                }
+            }
+            Fields::Unit => quote! {
+                if value.type_of().is_void() {
+                    Ok(#path)
+                } else {
+                    Err(#crate_name::Error::new_from_js(value.type_name(), #type_name))
+                }
+            },
+        }
+    }
+
+    fn expand_from_js_enum(&self, variants: &[Variant]) -> TokenStream {
+        let crate_name = &self.crate_name;
+        let type_name = &self.name;
+        let variants = variants.iter().filter(|x| !x.config.skip);
+
+        let unknown_variant = quote! {
+            Err(#crate_name::Error::new_from_js_message(
+                "string",
+                #type_name,
+                ["unknown variant `", tag.as_str(), "`"].concat(),
+            ))
+        };
+
+        match self.repr() {
+            Repr::External => {
+                let (unit, content): (Vec<_>, Vec<_>) =
+                    variants.partition(|x| matches!(x.fields, Fields::Unit));
+
+                let unit = unit.iter().map(|v| {
+                    let ident = &v.ident;
+                    let name = &v.name;
+                    quote!(#name => Ok(Self::#ident),)
+                });
+                let content = content.iter().map(|v| {
+                    let ident = &v.ident;
+                    let name = &v.name;
+                    let body = self.expand_from_js_fields(
+                        &v.fields,
+                        quote!(Self::#ident),
+                        v.config.rename_all,
+                        is_newtype(&v.fields),
+                    );
+                    quote! {
+                        #name => {
+                            let value: #crate_name::Value = object.get(#name)?;
+                            #body
+                        }
+                    }
+                });
+                let object = expect_object(crate_name, type_name);
+
+                quote! {
+                    if let Some(tag) = value.as_string() {
+                        let tag = tag.to_string()?;
+                        return match tag.as_str() {
+                            #(#unit)*
+                            _ => #unknown_variant,
+                        };
+                    }
+                    #object
+                    let mut keys = object.keys::<#crate_name::Atom>();
+                    let (Some(tag), None) = (keys.next(), keys.next()) else {
+                        return Err(#crate_name::Error::new_from_js_message(
+                            value.type_name(),
+                            #type_name,
+                            "Expected a string or an object with a single property",
+                        ));
+                    };
+                    let tag = tag?.to_string()?;
+                    match tag.as_str() {
+                        #(#content)*
+                        _ => #unknown_variant,
+                    }
+                }
+            }
+            Repr::Internal(tag) => {
+                let variants = variants.map(|v| {
+                    let ident = &v.ident;
+                    let name = &v.name;
+                    let body = match v.fields {
+                        Fields::Unit => quote!(Ok(Self::#ident)),
+                        ref fields => self.expand_from_js_fields(
+                            fields,
+                            quote!(Self::#ident),
+                            v.config.rename_all,
+                            is_newtype(fields),
+                        ),
+                    };
+                    quote!(#name => { #body })
+                });
+                let object = expect_object(crate_name, type_name);
+
+                quote! {
+                    #object
+                    let tag: #crate_name::String = object.get(#tag)?;
+                    let tag = tag.to_string()?;
+                    match tag.as_str() {
+                        #(#variants)*
+                        _ => #unknown_variant,
+                    }
+                }
+            }
+            Repr::Untagged => {
+                let variants = variants.map(|v| {
+                    let ident = &v.ident;
+                    let body = self.expand_from_js_fields(
+                        &v.fields,
+                        quote!(Self::#ident),
+                        v.config.rename_all,
+                        is_newtype(&v.fields),
+                    );
+                    quote! {
+                        let attempt = (|| -> #crate_name::Result<Self> {
+                            let value = value.clone();
+                            #body
+                        })();
+                        match attempt {
+                            Ok(x) => return Ok(x),
+                            // Only a value of the wrong type moves on to the next variant, other
+                            // errors like thrown exceptions must not be swallowed.
+                            Err(e) if e.is_from_js() => {}
+                            Err(e) => return Err(e),
+                        }
+                    }
+                });
+
+                quote! {
+                    #(#variants)*
+                    Err(#crate_name::Error::new_from_js_message(
+                        value.type_name(),
+                        #type_name,
+                        "Data did not match any variant of untagged enum",
+                    ))
+                }
+            }
+        }
+    }
+
+    pub fn expand_into_js(&self) -> TokenStream {
+        let crate_name = &self.crate_name;
+        let header = self.expand_impl_header(format_ident!("IntoJs"));
+
+        let body = match self.data {
+            ConvertData::Struct(ref fields) => {
+                let pattern = expand_pattern(fields, quote!(Self));
+                let body = self.expand_into_js_fields(
+                    fields,
+                    self.config.rename_all,
+                    self.config.transparent,
+                );
+                quote! {
+                    let #pattern = self;
+                    #body
+                }
+            }
+            ConvertData::Enum(ref variants) => self.expand_into_js_enum(variants),
+        };
+
+        quote! {
+            #header {
+                fn into_js(self, ctx: &#crate_name::Ctx<'js>) -> #crate_name::Result<#crate_name::Value<'js>> {
+                    #body
+                }
+            }
+        }
+    }
+
+    /// Expand a conversion of the fields, bound by the pattern from [`expand_pattern`], into a
+    /// value.
+    fn expand_into_js_fields(
+        &self,
+        fields: &Fields,
+        case: Option<Case>,
+        transparent: bool,
+    ) -> TokenStream {
+        let crate_name = &self.crate_name;
+        if transparent {
+            let binding = match fields {
+                Fields::Named(fields) => fields.iter().find(|f| !f.config.skip).map(binding_named),
+                Fields::Unnamed(fields) => fields
+                    .iter()
+                    .position(|f| !f.config.skip)
+                    .map(|idx| format_ident!("field_{idx}")),
+                Fields::Unit => None,
+            };
+            let binding = binding.expect("transparent fields have a field to convert");
+            return quote!(#crate_name::IntoJs::into_js(#binding, ctx));
+        }
+        match fields {
+            Fields::Named(fields) => {
+                let sets = fields.iter().filter(|f| !f.config.skip).map(|f| {
+                    let binding = binding_named(f);
+                    let name = f.js_name(case);
+                    if f.config.optional {
+                        quote! {
+                            if let Some(value) = #binding {
+                                object.set(#name, value)?;
+                            }
+                        }
+                    } else {
+                        quote!(object.set(#name, #binding)?;)
+                    }
+                });
+                quote! {
+                    let object = #crate_name::Object::new(ctx.clone())?;
+                    #(#sets)*
+                    Ok(object.into_value())
+                }
+            }
+            Fields::Unnamed(fields) => {
+                let sets = fields
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, f)| !f.config.skip)
+                    .enumerate()
+                    .map(|(idx, (field_idx, _))| {
+                        let binding = format_ident!("field_{field_idx}");
+                        quote!(array.set(#idx, #binding)?;)
+                    });
+                quote! {
+                    let array = #crate_name::Array::new(ctx.clone())?;
+                    #(#sets)*
+                    Ok(array.into_value())
+                }
+            }
+            Fields::Unit => quote!(Ok(#crate_name::Value::new_null(ctx.clone()))),
+        }
+    }
+
+    fn expand_into_js_enum(&self, variants: &[Variant]) -> TokenStream {
+        let crate_name = &self.crate_name;
+        let type_name = &self.name;
+        let repr = self.repr();
+
+        let arms = variants.iter().map(|v| {
+            let ident = &v.ident;
+            let name = &v.name;
+            let pattern = expand_pattern(&v.fields, quote!(Self::#ident));
+
+            if v.config.skip {
+                let message = format!("The variant `{}` can not be converted", v.ident.unraw());
+                return quote! {
+                    #pattern => Err(#crate_name::Error::new_into_js_message(#type_name, "value", #message)),
+                };
+            }
+
+            let content =
+                self.expand_into_js_fields(&v.fields, v.config.rename_all, is_newtype(&v.fields));
+            let body = match (&repr, &v.fields) {
+                (Repr::External, Fields::Unit) => quote!(#crate_name::IntoJs::into_js(#name, ctx)),
+                (Repr::External, _) => quote! {
+                    let content: #crate_name::Result<#crate_name::Value> = { #content };
+                    let content = content?;
+                    let object = #crate_name::Object::new(ctx.clone())?;
+                    object.set(#name, content)?;
+                    Ok(object.into_value())
+                },
+                (Repr::Internal(tag), Fields::Unit) => quote! {
+                    let object = #crate_name::Object::new(ctx.clone())?;
+                    object.set(#tag, #name)?;
+                    Ok(object.into_value())
+                },
+                (Repr::Internal(tag), _) => quote! {
+                    let content: #crate_name::Result<#crate_name::Value> = { #content };
+                    let content = content?;
+                    let object = content.into_object().ok_or_else(|| {
+                        #crate_name::Error::new_into_js_message(
+                            #type_name,
+                            "object",
+                            "The content of an internally tagged variant must be an object",
+                        )
+                    })?;
+                    object.set(#tag, #name)?;
+                    Ok(object.into_value())
+                },
+                (Repr::Untagged, _) => content,
+            };
+
+            quote! {
+                #pattern => { #body }
+            }
+        });
+
+        quote! {
+            match self {
+                #(#arms)*
+            }
+        }
+    }
+}
+
+/// Returns whether the fields are those of a newtype variant, which is converted transparently
+/// like serde does, unlike a struct which needs the `transparent` option.
+fn is_newtype(fields: &Fields) -> bool {
+    matches!(fields, Fields::Unnamed(fields) if fields.len() == 1 && !fields[0].config.skip)
+}
+
+fn binding_named(field: &Field) -> Ident {
+    format_ident!("field_{}", field.ident.as_ref().unwrap())
+}
+
+/// Expand a pattern which binds all non skipped fields to `field_{name}` or `field_{idx}`.
+fn expand_pattern(fields: &Fields, path: TokenStream) -> TokenStream {
+    match fields {
+        Fields::Named(fields) => {
+            let bindings = fields.iter().filter(|f| !f.config.skip).map(|f| {
+                let ident = f.ident.as_ref().unwrap();
+                let binding = binding_named(f);
+                quote!(#ident: #binding)
+            });
+            quote!(#path { #(#bindings,)* .. })
+        }
+        Fields::Unnamed(fields) => {
+            let bindings = fields.iter().enumerate().map(|(idx, f)| {
+                if f.config.skip {
+                    quote!(_)
+                } else {
+                    let binding = format_ident!("field_{idx}");
+                    quote!(#binding)
+                }
+            });
+            quote!(#path(#(#bindings,)*))
+        }
+        Fields::Unit => path,
+    }
+}
+
+/// Expand a statement binding `value` as an object to `object`.
+fn expect_object(crate_name: &Ident, type_name: &str) -> TokenStream {
+    quote! {
+        let object = value
+            .as_object()
+            .cloned()
+            .ok_or_else(|| #crate_name::Error::new_from_js(value.type_name(), #type_name))?;
+    }
+}
+
+pub(crate) fn expand_from_js(input: DeriveInput) -> Result<TokenStream> {
+    Ok(Convert::from_derive_input(input)?.expand_from_js())
+}
+
+pub(crate) fn expand_into_js(input: DeriveInput) -> Result<TokenStream> {
+    Ok(Convert::from_derive_input(input)?.expand_into_js())
+}
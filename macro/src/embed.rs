@@ -3,7 +3,9 @@ use std::{env, path::Path};
 use crate::common::crate_ident;
 use proc_macro2::{Span, TokenStream};
 use quote::{format_ident, quote};
-use rquickjs_core::{Context, Module, Result as JsResult, Runtime, WriteOptions};
+use rquickjs_core::{
+    loader::SourceMap, Context, Module, Result as JsResult, Runtime, WriteOptions,
+};
 use syn::{
     parse::{Parse, ParseStream},
     punctuated::Punctuated,
@@ -41,6 +43,9 @@ impl Parse for EmbedModules {
     }
 }
 
+/// The name, bytecode and source map of a compiled module.
+type CompiledModule = (String, Vec<u8>, Option<String>);
+
 /// Implementation of the macro
 pub fn embed(modules: EmbedModules) -> Result<TokenStream> {
     let mut files = Vec::new();
@@ -88,10 +93,20 @@ pub fn embed(modules: EmbedModules) -> Result<TokenStream> {
                 ));
             }
         };
-        files.push((f.name.value(), source));
+        let source_map = read_source_map(&path, &source).map_err(|e| {
+            Error::new(
+                f.name.span(),
+                format_args!(
+                    "Error loading source map of embedded js module `{}`: {}",
+                    path.display(),
+                    e
+                ),
+            )
+        })?;
+        files.push((f.name.value(), source, source_map));
     }
 
-    let res = (|| -> JsResult<Vec<(String, Vec<u8>)>> {
+    let res = (|| -> JsResult<Vec<CompiledModule>> {
         let rt = Runtime::new()?;
         let ctx = Context::full(&rt)?;
 
@@ -99,9 +114,19 @@ pub fn embed(modules: EmbedModules) -> Result<TokenStream> {
 
         ctx.with(|ctx| -> JsResult<()> {
             for f in files.into_iter() {
+                if let Some(map) = &f.2 {
+                    let res = if map.starts_with("data:") {
+                        SourceMap::from_data_url(&ctx, map)
+                    } else {
+                        SourceMap::parse(&ctx, map)
+                    };
+                    res.map_err(|e| {
+                        rquickjs_core::Error::new_loading_message(&f.0, e.to_string())
+                    })?;
+                }
                 let bc = Module::declare(ctx.clone(), f.0.clone(), f.1)?
                     .write(WriteOptions::default())?;
-                modules.push((f.0, bc));
+                modules.push((f.0, bc, f.2));
             }
             Ok(())
         })?;
@@ -123,9 +148,38 @@ pub fn embed(modules: EmbedModules) -> Result<TokenStream> {
     expand(&res)
 }
 
-fn to_entries(modules: impl Iterator<Item = (String, Vec<u8>)>) -> Vec<(String, TokenStream)> {
+/// Read the source map of a module, from its `//# sourceMappingURL=` comment or a `.map` file
+/// next to it.
+///
+/// Inline maps are kept as data urls.
+fn read_source_map(path: &Path, source: &str) -> std::io::Result<Option<String>> {
+    match SourceMap::find_url(source.as_bytes()) {
+        Some(url) if url.starts_with("data:") => Ok(Some(url.to_string())),
+        Some(url) => {
+            let dir = path.parent().unwrap_or(Path::new(""));
+            std::fs::read_to_string(dir.join(url)).map(Some)
+        }
+        None => {
+            let mut map_path = path.as_os_str().to_owned();
+            map_path.push(".map");
+            match std::fs::read_to_string(map_path) {
+                Ok(x) => Ok(Some(x)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e),
+            }
+        }
+    }
+}
+
+fn to_entries(modules: impl Iterator<Item = CompiledModule>) -> Vec<(String, TokenStream)> {
     modules
-        .map(|(name, data)| (name, quote! { &[#(#data),*] }))
+        .map(|(name, data, map)| {
+            let map = match map {
+                Some(map) => quote! { Some(#map) },
+                None => quote! { None },
+            };
+            (name, quote! { (&[#(#data),*], #map) })
+        })
         .collect::<Vec<_>>()
 }
 
@@ -168,13 +222,13 @@ pub fn expand(modules: &[(String, TokenStream)]) -> Result<TokenStream> {
 
 #[cfg(test)]
 mod test {
-    use super::{expand, to_entries, EmbedModules};
+    use super::{embed, expand, to_entries, EmbedModules};
     use quote::quote;
 
     #[cfg(feature = "phf")]
     #[test]
     fn test_expand() {
-        let data = vec![("test_module".to_string(), vec![1u8, 2, 3, 4])];
+        let data = vec![("test_module".to_string(), vec![1u8, 2, 3, 4], None)];
         let test_data = to_entries(data.into_iter());
         let tokens = expand(&test_data);
         let expected = quote! {
@@ -182,7 +236,7 @@ mod test {
                 key: 16287231350648472473u64,
                 disps: &[(0u32,0u32)],
                 entries: &[
-                    ("test_module", &[1u8, 2u8, 3u8,4u8])
+                    ("test_module", (&[1u8, 2u8, 3u8,4u8], None))
                 ],
             })
         };
@@ -192,12 +246,16 @@ mod test {
     #[cfg(not(feature = "phf"))]
     #[test]
     fn test_expand() {
-        let data = vec![("test_module".to_string(), vec![1u8, 2, 3, 4])];
+        let data = vec![(
+            "test_module".to_string(),
+            vec![1u8, 2, 3, 4],
+            Some("{}".to_string()),
+        )];
         let test_data = to_entries(data.into_iter());
         let tokens = expand(&test_data);
         let expected = quote! {
             rquickjs::loader::bundle::Bundle(&[
-                ("test_module", &[1u8, 2u8, 3u8,4u8])
+                ("test_module", (&[1u8, 2u8, 3u8,4u8], Some("{}")))
             ])
         };
         assert_eq_tokens!(tokens.unwrap(), expected);
@@ -220,4 +278,30 @@ mod test {
         assert!(b.path.is_none());
         assert!(iter.next().is_none());
     }
+
+    #[test]
+    fn source_map() {
+        let dir = std::env::temp_dir().join(format!("rquickjs-embed-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let map = r#"{"version":3,"sources":["a.ts"],"names":[],"mappings":"AAAA"}"#;
+        std::fs::write(dir.join("a.js"), "export const a = 1;\n").unwrap();
+        std::fs::write(dir.join("a.js.map"), map).unwrap();
+        std::fs::write(dir.join("b.js"), "export const b = 1;\n").unwrap();
+        std::fs::write(dir.join("c.js"), "export const c = 1;\n").unwrap();
+        std::fs::write(dir.join("c.js.map"), "{").unwrap();
+
+        let path = |name: &str| dir.join(name).display().to_string();
+        let (a, b) = (path("a.js"), path("b.js"));
+        let tokens = embed(syn::parse_quote! { "a": #a, "b": #b })
+            .unwrap()
+            .to_string();
+        assert!(tokens.contains(&quote!(Some(#map)).to_string()), "{tokens}");
+        assert!(tokens.contains("None"), "{tokens}");
+
+        let c = path("c.js");
+        let error = embed(syn::parse_quote! { "c": #c }).unwrap_err();
+        assert!(error.to_string().contains("source map is not valid JSON"));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
 }
@@ -2,6 +2,7 @@ use convert_case::Casing;
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
 use syn::{
+    ext::IdentExt as _,
     parse::{Parse, ParseStream},
     Attribute, Ident, LitStr, Result, Type, Visibility,
 };
@@ -18,6 +19,9 @@ pub struct FieldConfig {
     pub enumerable: bool,
     pub configurable: bool,
     pub skip_trace: bool,
+    pub skip: bool,
+    pub default: bool,
+    pub optional: bool,
     pub rename: Option<String>,
 }
 
@@ -28,6 +32,9 @@ pub(crate) enum FieldOption {
     Enumerable(FlagOption<kw::enumerable>),
     Configurable(FlagOption<kw::configurable>),
     SkipTrace(FlagOption<kw::skip_trace>),
+    Skip(FlagOption<kw::skip>),
+    Default(FlagOption<kw::default>),
+    Optional(FlagOption<kw::optional>),
     Rename(ValueOption<kw::rename, LitStr>),
 }
 
@@ -43,6 +50,12 @@ impl Parse for FieldOption {
             input.parse().map(Self::Configurable)
         } else if input.peek(kw::skip_trace) {
             input.parse().map(Self::SkipTrace)
+        } else if input.peek(kw::skip) {
+            input.parse().map(Self::Skip)
+        } else if input.peek(kw::default) {
+            input.parse().map(Self::Default)
+        } else if input.peek(kw::optional) {
+            input.parse().map(Self::Optional)
         } else if input.peek(kw::rename) {
             input.parse().map(Self::Rename)
         } else {
@@ -88,6 +101,15 @@ impl FieldConfig {
             FieldOption::SkipTrace(ref x) => {
                 self.skip_trace = x.is_true();
             }
+            FieldOption::Skip(ref x) => {
+                self.skip = x.is_true();
+            }
+            FieldOption::Default(ref x) => {
+                self.default = x.is_true();
+            }
+            FieldOption::Optional(ref x) => {
+                self.optional = x.is_true();
+            }
             FieldOption::Rename(ref x) => {
                 self.rename = Some(x.value.value());
             }
@@ -166,6 +188,18 @@ impl Field {
         res
     }
 
+    /// The name of a named field on the JavaScript side.
+    pub fn js_name(&self, case: Option<Case>) -> String {
+        let field = self.ident.as_ref().unwrap();
+        if let Some(rename) = self.config.rename.clone() {
+            rename
+        } else if let Some(case) = case {
+            field.unraw().to_string().to_case(case.to_convert_case())
+        } else {
+            field.unraw().to_string()
+        }
+    }
+
     pub fn expand_trace_body_named(&self, lib_crate: &Ident) -> TokenStream {
         if self.config.skip_trace {
             return TokenStream::new();
@@ -198,13 +232,7 @@ impl Field {
 
         let accessor = self.expand_accessor(field, crate_name, ty);
         let prop_config = self.expand_prop_config();
-        let name = if let Some(rename) = self.config.rename.clone() {
-            rename
-        } else if let Some(case) = case {
-            field.to_string().to_case(case.to_convert_case())
-        } else {
-            field.to_string()
-        };
+        let name = self.js_name(case);
 
         quote! {
             proto.prop(#name, #accessor #prop_config)?;
@@ -22,6 +22,7 @@ macro_rules! assert_eq_tokens {
 mod attrs;
 mod class;
 mod common;
+mod convert;
 mod embed;
 mod fields;
 mod function;
@@ -46,6 +47,8 @@ mod trace;
 /// | `rename`     | String    | Changes the name of the implemented class on the JavaScript side.                                                                                                                       |
 /// | `rename_all` | Casing    | Converts the case of all the fields of this struct which have implement accessors. Can be one of `lowercase`, `UPPERCASE`, `camelCase`, `PascalCase`,`snake_case`, or `SCREAMING_SNAKE` |
 /// | `frozen`     | Flag      | Changes the class implementation to only allow borrowing immutably.  Trying to borrow mutably will result in an error.                                                                  |
+/// | `exotic`     | Flag      | Forwards the exotic hooks of `JsClass` to the class implementation of the `Exotic` trait, allowing it to intercept property access on its instances.                                    |
+/// | `extends`    | Type      | Makes the class extend another Rust class which must be the first field of the struct. Instances of the class are also instances of the parent class and inherit its methods.           |
 ///
 /// # Field options
 ///
@@ -185,7 +188,8 @@ pub fn function(attr: TokenStream1, item: TokenStream1) -> TokenStream1 {
 /// | `enumerable`   | Flag                                                              | Makes the method, if it is a getter or setter, enumerable in JavaScript.                        |
 /// | `configurable` | Flag                                                              | Makes the method, if it is a getter or setter, configurable in JavaScript.                      |
 /// | `rename`       | String or [`PredefinedAtom`](rquickjs_core::atom::PredefinedAtom) | Changes the name of the field getter and/or setter to the specified name in JavaScript.         |
-/// | `static`       | Flag                                                              | Makes the method a static method i.e. defined on the type constructor instead of the prototype. |
+/// | `symbol`       | String                                                            | Keys the method by a well-known symbol, e.g. `iterator` for `Symbol.iterator`.                  |
+/// | `static`       | Flag                                                              | Makes the method or accessor static, defining it on the constructor instead of the prototype.   |
 /// | `constructor`  | Flag                                                              | Marks this method a the constructor for this type.                                              |
 /// | `skip`         | Flag                                                              | Skips defining this method on the JavaScript class.                                             |
 ///
@@ -254,9 +258,9 @@ pub fn function(attr: TokenStream1, item: TokenStream1) -> TokenStream1 {
 ///     #[qjs(skip)]
 ///     pub fn inner_function(&self) {}
 ///
-///     /// Functions can also be renamed to specific symbols. This allows you to make an Rust type
+///     /// Functions can also be keyed by well-known symbols. This allows you to make an Rust type
 ///     /// act like an iteratable value for example.
-///     #[qjs(rename = PredefinedAtom::SymbolIterator)]
+///     #[qjs(symbol = "iterator")]
 ///     pub fn iterate<'js>(&self, ctx: Ctx<'js>) -> Result<Object<'js>> {
 ///         let res = Object::new(ctx)?;
 ///
@@ -498,6 +502,120 @@ pub fn trace(stream: TokenStream1) -> TokenStream1 {
     }
 }
 
+/// A macro for deriving [`FromJs`](rquickjs_core::FromJs) for structs and enums.
+///
+/// Structs with named fields are converted from objects by reading a property for every field and
+/// tuple structs are converted from arrays, including those with a single field. Structs with the
+/// `transparent` option are converted from the value of their only field instead. Unit structs are
+/// converted from `null` or `undefined`.
+///
+/// Enums are by default externally tagged: a unit variant is converted from a string with the
+/// name of the variant and a variant with content from an object with a single property named
+/// after the variant. Like in serde the content of a newtype variant, a tuple variant with a single
+/// field, is the value of that field. See [`macro@IntoJs`] for the other representations.
+///
+/// # Attribute options
+///
+/// The derive can be configured with a `qjs` attribute on the item: `#[qjs(rename_all =
+/// "camelCase")]`. A option which is a Flag can be set just by adding the attribute:
+/// `#[qjs(flag)]` or by setting it to specific boolean value: `#[qjs(flag = true)]`.
+///
+/// | **Option**   | **Value** | **Description**                                                                                                                                                                           |
+/// |--------------|-----------|-------------------------------------------------------------------------------------------------------------------------------------------------------------------------------------------|
+/// | `crate`      | String    | Changes the name from which the attribute tries to use rquickjs types. Use when the name behind which the rquickjs crate is declared is not properly resolved by the macro.               |
+/// | `rename_all` | Casing    | Converts the case of all the fields of a struct or the variants of an enum. Can be one of `lowercase`, `UPPERCASE`, `camelCase`, `PascalCase`,`snake_case`, or `SCREAMING_SNAKE`          |
+/// | `tag`        | String    | Makes an enum internally tagged, the name of the variant is stored in the property with the given name next to the fields of the variant. Doesn't support tuple variants.                 |
+/// | `untagged`   | Flag      | Makes an enum untagged, the value is converted into the first variant for which the conversion succeeds. Errors other than a type mismatch are returned without trying the next variant. |
+/// | `transparent`| Flag      | Converts a struct from and into the value of its only field which isn't skipped.                                                                                                          |
+///
+/// # Variant options
+///
+/// | **Option**   | **Value** | **Description**                                                                 |
+/// |--------------|-----------|---------------------------------------------------------------------------------|
+/// | `rename`     | String    | Changes the name of the variant on the JavaScript side.                         |
+/// | `rename_all` | Casing    | Converts the case of all the fields of the variant.                             |
+/// | `skip`       | Flag      | Never converts a value into this variant and fails converting this variant to a value. |
+///
+/// # Field options
+///
+/// | **Option** | **Value** | **Description**                                                                                                      |
+/// |------------|-----------|----------------------------------------------------------------------------------------------------------------------|
+/// | `rename`   | String    | Changes the name of the property the field is converted from and to.                                                 |
+/// | `skip`     | Flag      | Skips converting this field, it will be initialized with [`Default::default`].                                       |
+/// | `default`  | Flag      | Uses [`Default::default`] when the property is missing or `undefined` instead of failing.                             |
+/// | `optional` | Flag      | Like `default` but also leaves out the property when converting a field which is `None`. Only for [`Option`] fields. |
+///
+/// # Example
+/// ```
+/// use rquickjs::{Context, FromJs, IntoJs, Runtime};
+///
+/// #[derive(FromJs, IntoJs, Debug, PartialEq)]
+/// #[qjs(rename_all = "camelCase")]
+/// pub struct Config {
+///     server_name: String,
+///     #[qjs(default)]
+///     port: u16,
+///     #[qjs(optional)]
+///     timeout: Option<u32>,
+/// }
+///
+/// #[derive(FromJs, IntoJs, Debug, PartialEq)]
+/// #[qjs(tag = "type", rename_all = "lowercase")]
+/// pub enum Shape {
+///     Circle { radius: f64 },
+///     Square { side: f64 },
+/// }
+///
+/// pub fn main() {
+///     let rt = Runtime::new().unwrap();
+///     let ctx = Context::full(&rt).unwrap();
+///
+///     ctx.with(|ctx| {
+///         let config: Config = ctx.eval("({ serverName: 'localhost' })").unwrap();
+///         assert_eq!(
+///             config,
+///             Config {
+///                 server_name: "localhost".to_string(),
+///                 port: 0,
+///                 timeout: None,
+///             }
+///         );
+///
+///         let shape: Shape = ctx.eval("({ type: 'circle', radius: 2 })").unwrap();
+///         assert_eq!(shape, Shape::Circle { radius: 2.0 });
+///
+///         ctx.globals().set("shape", Shape::Square { side: 3.0 }).unwrap();
+///         assert!(ctx.eval::<bool, _>("shape.type === 'square' && shape.side === 3").unwrap());
+///     })
+/// }
+/// ```
+#[proc_macro_derive(FromJs, attributes(qjs))]
+pub fn from_js(stream: TokenStream1) -> TokenStream1 {
+    let derive_input = parse_macro_input!(stream as DeriveInput);
+    match convert::expand_from_js(derive_input) {
+        Ok(x) => x.into(),
+        Err(e) => e.into_compile_error().into(),
+    }
+}
+
+/// A macro for deriving [`IntoJs`](rquickjs_core::IntoJs) for structs and enums.
+///
+/// The conversion is the inverse of the one derived by [`macro@FromJs`] and takes the same
+/// options. Enums can be represented in one of three ways:
+///
+/// - Externally tagged (the default): `"Unit"`, `{ "Variant": content }`.
+/// - Internally tagged with `#[qjs(tag = "type")]`: `{ "type": "Variant", ...fields }`. The content of
+/// newtype variants must convert to an object.
+/// - Untagged with `#[qjs(untagged)]`: just the content of the variant, unit variants are `null`.
+#[proc_macro_derive(IntoJs, attributes(qjs))]
+pub fn into_js(stream: TokenStream1) -> TokenStream1 {
+    let derive_input = parse_macro_input!(stream as DeriveInput);
+    match convert::expand_into_js(derive_input) {
+        Ok(x) => x.into(),
+        Err(e) => e.into_compile_error().into(),
+    }
+}
+
 /// A macro for embedding JavaScript code into a binary.
 ///
 /// Compiles a JavaScript module to bytecode and then compiles the resulting bytecode into the
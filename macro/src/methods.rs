@@ -141,11 +141,14 @@ pub(crate) fn expand(options: OptionList<ImplOption>, item: ItemImpl) -> Result<
         match item {
             syn::ImplItem::Const(_item) => {}
             syn::ImplItem::Fn(item) => {
-                let function = Method::parse_impl_fn(item, &self_ty)?;
+                let function = Method::parse_impl_fn(item, &self_ty, &crate_name)?;
                 let span = function.attr_span;
                 if function.config.get || function.config.set {
+                    if static_span.is_none() && function.config.r#static {
+                        static_span = Some(function.attr_span);
+                    }
                     let access = accessors
-                        .entry(function.name(config.rename_all))
+                        .entry((function.config.r#static, function.name(config.rename_all)))
                         .or_insert_with(JsAccessor::new);
                     if function.config.get {
                         access.define_get(function, config.rename_all)?;
@@ -208,8 +211,11 @@ pub(crate) fn expand(options: OptionList<ImplOption>, item: ItemImpl) -> Result<
             func.expand_apply_to_object(&prefix, &self_ty, &proto_ident, config.rename_all)
         });
     let accessor_apply_proto = accessors
-        .values()
-        .map(|access| access.expand_apply_to_proto(&crate_name, config.rename_all));
+        .iter()
+        .filter(|((is_static, _), _)| !is_static)
+        .map(|(_, access)| {
+            access.expand_apply_to_object(&crate_name, &proto_ident, config.rename_all)
+        });
 
     let constructor_ident = format_ident!("constr");
 
@@ -230,12 +236,19 @@ pub(crate) fn expand(options: OptionList<ImplOption>, item: ItemImpl) -> Result<
                         config.rename_all,
                     )
                 });
+        let static_accessor_apply = accessors
+            .iter()
+            .filter(|((is_static, _), _)| *is_static)
+            .map(|(_, access)| {
+                access.expand_apply_to_object(&crate_name, &constructor_ident, config.rename_all)
+            });
 
         quote! {
             impl #js_added_generics #crate_name::class::impl_::ConstructorCreator<'js,#self_ty> for #crate_name::class::impl_::ConstructorCreate<#self_ty> {
                 fn create_constructor(&self, ctx: &#crate_name::Ctx<'js>) -> #crate_name::Result<Option<#crate_name::function::Constructor<'js>>>{
                     let constr = #crate_name::function::Constructor::new_class::<#self_ty,_,_>(ctx.clone(),#name)?;
                     #(#static_function_apply)*
+                    #(#static_accessor_apply)*
                     Ok(Some(constr))
                 }
             }
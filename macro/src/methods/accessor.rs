@@ -70,7 +70,12 @@ impl JsAccessor {
         res
     }
 
-    pub fn expand_apply_to_proto(&self, lib_crate: &Ident, case: Option<Case>) -> TokenStream {
+    pub fn expand_apply_to_object(
+        &self,
+        lib_crate: &Ident,
+        object_name: &Ident,
+        case: Option<Case>,
+    ) -> TokenStream {
         match (self.get.as_ref(), self.set.as_ref()) {
             (Some(get), Some(set)) => {
                 let configurable = get.config.configurable || set.config.configurable;
@@ -90,7 +95,7 @@ impl JsAccessor {
                 };
                 let get_name = get.function.expand_carry_type_name(GET_PREFIX);
                 let set_name = set.function.expand_carry_type_name(SET_PREFIX);
-                quote! {_proto.prop(#name,
+                quote! {#object_name.prop(#name,
                         #lib_crate::object::Accessor::new(#get_name,#set_name)
                         #configurable
                         #enumerable
@@ -113,7 +118,7 @@ impl JsAccessor {
                     Default::default()
                 };
                 let get_name = get.function.expand_carry_type_name(GET_PREFIX);
-                quote! {_proto.prop(#name,
+                quote! {#object_name.prop(#name,
                         #lib_crate::object::Accessor::new_get(#get_name)
                         #configurable
                         #enumerable
@@ -136,8 +141,8 @@ impl JsAccessor {
                     Default::default()
                 };
 
-                let set_name = set.function.expand_carry_type_name(GET_PREFIX);
-                quote! {_proto.prop(#name,
+                let set_name = set.function.expand_carry_type_name(SET_PREFIX);
+                quote! {#object_name.prop(#name,
                         #lib_crate::object::Accessor::new_set(#set_name)
                         #configurable
                         #enumerable
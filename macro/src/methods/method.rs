@@ -3,6 +3,7 @@ use proc_macro2::{Ident, Span, TokenStream};
 use quote::quote;
 use syn::{
     parse::{Parse, ParseStream},
+    parse_quote,
     spanned::Spanned,
     Attribute, Block, Error, Expr, ImplItemFn, LitStr, Result, Signature, Token, Type, Visibility,
 };
@@ -23,6 +24,7 @@ pub(crate) struct MethodConfig {
     pub get: bool,
     pub set: bool,
     pub rename: Option<Expr>,
+    pub symbol: Option<LitStr>,
 }
 
 impl MethodConfig {
@@ -52,6 +54,9 @@ impl MethodConfig {
             MethodOption::Rename(x) => {
                 self.rename = Some(x.value.clone());
             }
+            MethodOption::Symbol(x) => {
+                self.symbol = Some(x.value.clone());
+            }
         }
     }
 }
@@ -65,6 +70,7 @@ pub(crate) enum MethodOption {
     Get(FlagOption<kw::get>),
     Set(FlagOption<kw::set>),
     Rename(ValueOption<kw::rename, Expr>),
+    Symbol(ValueOption<kw::symbol, LitStr>),
 }
 
 impl Parse for MethodOption {
@@ -85,6 +91,8 @@ impl Parse for MethodOption {
             input.parse().map(Self::Set)
         } else if input.peek(kw::rename) {
             input.parse().map(Self::Rename)
+        } else if input.peek(kw::symbol) {
+            input.parse().map(Self::Symbol)
         } else {
             Err(syn::Error::new(input.span(), "invalid method attribute"))
         }
@@ -102,10 +110,17 @@ impl MethodConfig {
             ));
         }
 
-        if self.constructor && self.rename.is_some() {
+        if self.constructor && (self.rename.is_some() || self.symbol.is_some()) {
             return Err(Error::new(span, "Can't rename a constructor"));
         }
 
+        if self.rename.is_some() && self.symbol.is_some() {
+            return Err(Error::new(
+                span,
+                "a function can't both be renamed and keyed by a symbol at the same time.",
+            ));
+        }
+
         if self.constructor && self.get {
             return Err(Error::new(
                 span,
@@ -137,6 +152,32 @@ impl MethodConfig {
     }
 }
 
+/// Returns the predefined atom of a well-known symbol like `iterator` for `Symbol.iterator`.
+fn symbol_atom(name: &LitStr) -> Result<Ident> {
+    let atom = match name.value().as_str() {
+        "asyncIterator" => "SymbolAsyncIterator",
+        "hasInstance" => "SymbolHasInstance",
+        "isConcatSpreadable" => "SymbolIsConcatSpreadable",
+        "iterator" => "SymbolIterator",
+        "match" => "SymbolMatch",
+        "matchAll" => "SymbolMatchAll",
+        "replace" => "SymbolReplace",
+        "search" => "SymbolSearch",
+        "species" => "SymbolSpecies",
+        "split" => "SymbolSplit",
+        "toPrimitive" => "SymbolToPrimitive",
+        "toStringTag" => "SymbolToStringTag",
+        "unscopables" => "SymbolUnscopables",
+        x => {
+            return Err(Error::new(
+                name.span(),
+                format_args!("`{x}` is not a well-known symbol"),
+            ))
+        }
+    };
+    Ok(Ident::new(atom, name.span()))
+}
+
 #[derive(Clone)]
 pub(crate) struct Method {
     pub config: MethodConfig,
@@ -149,7 +190,7 @@ pub(crate) struct Method {
 }
 
 impl Method {
-    pub fn parse_impl_fn(func: ImplItemFn, self_ty: &Type) -> Result<Self> {
+    pub fn parse_impl_fn(func: ImplItemFn, self_ty: &Type, lib_crate: &Ident) -> Result<Self> {
         let span = func.span();
         let ImplItemFn {
             mut attrs,
@@ -177,6 +218,11 @@ impl Method {
 
         config.validate(attr_span)?;
 
+        if let Some(symbol) = config.symbol.as_ref() {
+            let atom = symbol_atom(symbol)?;
+            config.rename = Some(parse_quote!(#lib_crate::atom::PredefinedAtom::#atom));
+        }
+
         let attr_span = if attrs.is_empty() {
             span
         } else {
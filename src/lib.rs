@@ -54,6 +54,10 @@
 //! Promises](https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/Promise)
 //! and ES6 Promises can be given back as Rust futures.
 //!
+//! - `timers` adds the [`timers`] module, which installs `setTimeout`, `setInterval`,
+//! `clearTimeout`, `clearInterval` and `queueMicrotask` into contexts of an [`AsyncRuntime`]. It
+//! implies `futures`.
+//!
 //! - `macro` enables some useful procedural macros which gets Rust/JS interop much easy. An
 //! [attribute](#attributes) macros can be applied to functions, constants and modules. An
 //! [derive](#derives) macros can be used with structs and enums.
@@ -71,6 +75,9 @@
 //! - `indexmap` adds [`FromJs`]/[`IntoJs`] implementations for [`IndexSet`](`indexmap::IndexSet`)
 //! and [`IndexMap`](`indexmap_rs::IndexMap`)
 //!
+//! - `serde` adds a [`Serializer`](convert::serde::Serializer) and
+//! [`Deserializer`](convert::serde::Deserializer) for types implementing the serde traits
+//!
 //! ## Bindings
 //!
 //! The bindings are pre-generated for the following platforms:
@@ -124,7 +131,7 @@ pub use rquickjs_core::*;
 
 #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "macro")))]
 #[cfg(feature = "macro")]
-pub use rquickjs_macro::{class, embed, function, methods, module, JsLifetime};
+pub use rquickjs_macro::{class, embed, function, methods, module, FromJs, IntoJs, JsLifetime};
 
 pub mod class {
     //! JavaScript classes defined from Rust.
@@ -143,6 +143,9 @@ fn main() {
         "xsum.c",
     ];
 
+    // Extensions which include `quickjs.c`, so they are compiled instead of `quickjs.c`.
+    let extension_files = ["extensions.c", "extensions.h"];
+
     let mut defines: Vec<(String, Option<&str>)> = vec![("_GNU_SOURCE".into(), None)];
 
     let target_os = env::var("CARGO_CFG_TARGET_OS").unwrap();
@@ -205,6 +208,10 @@ fn main() {
             .expect("Unable to copy source; try 'git submodule update --init'");
     }
     fs::copy("quickjs.bind.h", out_dir.join("quickjs.bind.h")).expect("Unable to copy source");
+    for file in extension_files {
+        println!("cargo:rerun-if-changed={file}");
+        fs::copy(file, out_dir.join(file)).expect("Unable to copy source");
+    }
 
     if target_os == "wasi" {
         let wasi_sdk_path = get_wasi_sdk_path();
@@ -236,9 +243,10 @@ fn main() {
         builder.define(name, *value);
     }
 
-    for src in &source_files {
+    for src in source_files.iter().filter(|x| **x != "quickjs.c") {
         builder.file(out_dir.join(src));
     }
+    builder.file(out_dir.join("extensions.c"));
 
     builder.compile("libquickjs.a");
 }
@@ -0,0 +1,247 @@
+// Extensions to the QuickJS API which need access to the internals of QuickJS.
+//
+// QuickJS is included here so the extensions are compiled in the same translation unit and use
+// the exact same definitions as the engine itself.
+
+#include "quickjs.c"
+
+#include "extensions.h"
+
+int rquickjs_get_closure_vars(JSContext *ctx, JSValueConst func,
+                              rquickjs_closure_var_func *callback, void *opaque)
+{
+    JSObject *p;
+    JSFunctionBytecode *b;
+    int i;
+
+    if (JS_VALUE_GET_TAG(func) != JS_TAG_OBJECT)
+        return -1;
+    p = JS_VALUE_GET_OBJ(func);
+    switch (p->class_id) {
+    case JS_CLASS_BYTECODE_FUNCTION:
+    case JS_CLASS_GENERATOR_FUNCTION:
+    case JS_CLASS_ASYNC_FUNCTION:
+    case JS_CLASS_ASYNC_GENERATOR_FUNCTION:
+        break;
+    default:
+        return -1;
+    }
+
+    b = p->u.func.function_bytecode;
+    if (!b || !p->u.func.var_refs)
+        return 0;
+    for (i = 0; i < b->closure_var_count; i++) {
+        JSVarRef *var_ref = p->u.func.var_refs[i];
+        if (var_ref)
+            callback(opaque, b->closure_var[i].var_name, *var_ref->pvalue);
+    }
+    return b->closure_var_count;
+}
+
+int rquickjs_get_contexts(JSRuntime *rt, JSContext **contexts, int len)
+{
+    struct list_head *el;
+    int count = 0;
+
+    list_for_each(el, &rt->context_list) {
+        if (count < len)
+            contexts[count] = list_entry(el, JSContext, link);
+        count++;
+    }
+    return count;
+}
+
+JSAtom rquickjs_new_private_atom(JSRuntime *rt, const char *name)
+{
+    return __JS_NewAtomInit(rt, name, strlen(name), JS_ATOM_TYPE_PRIVATE);
+}
+
+JSValue rquickjs_get_prepare_stack_trace(JSContext *ctx)
+{
+    return js_dup(ctx->error_prepare_stack);
+}
+
+void rquickjs_set_prepare_stack_trace(JSContext *ctx, JSValue value)
+{
+    JS_FreeValue(ctx, ctx->error_prepare_stack);
+    ctx->error_prepare_stack = value;
+}
+
+int rquickjs_get_call_site(JSValueConst value, rquickjs_call_site *site)
+{
+    JSCallSiteData *csd = JS_GetOpaque(value, JS_CLASS_CALL_SITE);
+    if (!csd)
+        return -1;
+    site->function_name = csd->func_name;
+    site->file_name = csd->filename;
+    site->line = csd->line_num;
+    site->column = csd->col_num;
+    site->is_native = csd->native;
+    site->has_function = !JS_IsNull(csd->func);
+    return 0;
+}
+
+int rquickjs_get_stack(JSRuntime *rt, rquickjs_stack_frame_func *callback, void *opaque)
+{
+    JSStackFrame *sf;
+    JSContext *ctx;
+    JSObject *p;
+    JSFunctionBytecode *b;
+    const char *function, *file;
+    int line, column, count = 0;
+
+    if (list_empty(&rt->context_list))
+        return 0;
+    for (sf = rt->current_stack_frame; sf != NULL; sf = sf->prev_frame) {
+        if (JS_VALUE_GET_TAG(sf->cur_func) != JS_TAG_OBJECT)
+            continue;
+        p = JS_VALUE_GET_OBJ(sf->cur_func);
+        b = js_class_has_bytecode(p->class_id) ? p->u.func.function_bytecode : NULL;
+
+        // Strings are allocated in the realm of the function if it is known.
+        if (b)
+            ctx = b->realm;
+        else if (p->class_id == JS_CLASS_C_FUNCTION)
+            ctx = p->u.cfunc.realm;
+        else
+            ctx = list_entry(rt->context_list.next, JSContext, link);
+
+        function = get_func_name(ctx, sf->cur_func);
+        file = NULL;
+        line = -1;
+        column = -1;
+        if (b) {
+            file = JS_AtomToCString(ctx, b->filename);
+            if (sf->cur_pc)
+                line = find_line_num(ctx, b, sf->cur_pc - b->byte_code_buf - 1, &column);
+        }
+        callback(opaque, function, file, line, column);
+        JS_FreeCString(ctx, function);
+        JS_FreeCString(ctx, file);
+        count++;
+    }
+    return count;
+}
+
+JSModuleDef *rquickjs_find_module(JSContext *ctx, JSAtom name)
+{
+    return js_find_loaded_module(ctx, name);
+}
+
+int rquickjs_get_module_requests(JSModuleDef *m, JSAtom *requests, JSModuleDef **modules,
+                                 int len)
+{
+    int i;
+
+    for (i = 0; i < m->req_module_entries_count && i < len; i++) {
+        requests[i] = m->req_module_entries[i].module_name;
+        modules[i] = m->req_module_entries[i].module;
+    }
+    return m->req_module_entries_count;
+}
+
+int rquickjs_set_module_request(JSModuleDef *m, int index, JSModuleDef *module)
+{
+    if (index < 0 || index >= m->req_module_entries_count)
+        return -1;
+    m->req_module_entries[index].module = module;
+    return 0;
+}
+
+JSValue rquickjs_map_new(JSContext *ctx, bool set)
+{
+    return js_map_constructor(ctx, JS_UNDEFINED, 0, NULL, set ? MAGIC_SET : 0);
+}
+
+JSValue rquickjs_map_size(JSContext *ctx, JSValueConst map, bool set)
+{
+    return js_map_get_size(ctx, map, set ? MAGIC_SET : 0);
+}
+
+JSValue rquickjs_map_get(JSContext *ctx, JSValueConst map, JSValueConst key)
+{
+    return js_map_get(ctx, map, 1, &key, 0);
+}
+
+JSValue rquickjs_map_set(JSContext *ctx, JSValueConst map, JSValueConst key, JSValueConst value,
+                         bool set)
+{
+    JSValueConst args[2] = { key, value };
+    return js_map_set(ctx, map, 2, args, set ? MAGIC_SET : 0);
+}
+
+JSValue rquickjs_map_has(JSContext *ctx, JSValueConst map, JSValueConst key, bool set)
+{
+    return js_map_has(ctx, map, 1, &key, set ? MAGIC_SET : 0);
+}
+
+JSValue rquickjs_map_delete(JSContext *ctx, JSValueConst map, JSValueConst key, bool set)
+{
+    return js_map_delete(ctx, map, 1, &key, set ? MAGIC_SET : 0);
+}
+
+JSValue rquickjs_map_clear(JSContext *ctx, JSValueConst map, bool set)
+{
+    return js_map_clear(ctx, map, 0, NULL, set ? MAGIC_SET : 0);
+}
+
+JSValue rquickjs_map_iterator(JSContext *ctx, JSValueConst map, bool set)
+{
+    int kind = set ? JS_ITERATOR_KIND_VALUE : JS_ITERATOR_KIND_KEY_AND_VALUE;
+    return js_create_map_iterator(ctx, map, 0, NULL, (kind << 2) | (set ? MAGIC_SET : 0));
+}
+
+JSValue rquickjs_map_iterator_next(JSContext *ctx, JSValueConst iter, bool set, bool *done)
+{
+    int pdone;
+    JSValue ret = js_map_iterator_next(ctx, iter, 0, NULL, &pdone, set ? MAGIC_SET : 0);
+    *done = pdone;
+    return ret;
+}
+
+int rquickjs_date_get_time(JSContext *ctx, JSValueConst date, double *time)
+{
+    return JS_ThisTimeValue(ctx, time, date);
+}
+
+JSValue rquickjs_regexp_new(JSContext *ctx, JSValueConst pattern, JSValueConst flags)
+{
+    JSValueConst args[2] = { pattern, flags };
+    return JS_CallConstructor(ctx, ctx->regexp_ctor, 2, args);
+}
+
+JSValue rquickjs_regexp_exec(JSContext *ctx, JSValueConst regexp, JSValueConst text)
+{
+    return js_regexp_exec(ctx, regexp, 1, &text);
+}
+
+JSValue rquickjs_dataview_new(JSContext *ctx, JSValueConst buffer, JSValueConst offset,
+                              JSValueConst length)
+{
+    JSValueConst args[3] = { buffer, offset, length };
+    return js_dataview_constructor(ctx, JS_UNDEFINED, 3, args);
+}
+
+JSValue rquickjs_reflect(JSContext *ctx, int op, int argc, JSValueConst *argv)
+{
+    switch (op) {
+    case RQUICKJS_REFLECT_GET:
+        return js_reflect_get(ctx, JS_UNDEFINED, argc, argv);
+    case RQUICKJS_REFLECT_SET:
+        return js_reflect_set(ctx, JS_UNDEFINED, argc, argv);
+    case RQUICKJS_REFLECT_HAS:
+        return js_reflect_has(ctx, JS_UNDEFINED, argc, argv);
+    case RQUICKJS_REFLECT_DELETE_PROPERTY:
+        return js_reflect_deleteProperty(ctx, JS_UNDEFINED, argc, argv);
+    case RQUICKJS_REFLECT_OWN_KEYS:
+        return js_reflect_ownKeys(ctx, JS_UNDEFINED, argc, argv);
+    case RQUICKJS_REFLECT_GET_OWN_PROPERTY_DESCRIPTOR:
+        return js_object_getOwnPropertyDescriptor(ctx, JS_UNDEFINED, argc, argv, 1);
+    case RQUICKJS_REFLECT_APPLY:
+        return js_reflect_apply(ctx, JS_UNDEFINED, argc, argv);
+    case RQUICKJS_REFLECT_CONSTRUCT:
+        return js_reflect_construct(ctx, JS_UNDEFINED, argc, argv);
+    default:
+        return JS_ThrowInternalError(ctx, "invalid Reflect operation");
+    }
+}
@@ -0,0 +1,132 @@
+// Extensions to the QuickJS API implemented in `extensions.c`.
+
+#ifndef RQUICKJS_EXTENSIONS_H
+#define RQUICKJS_EXTENSIONS_H
+
+#include "quickjs.h"
+
+typedef void rquickjs_closure_var_func(void *opaque, JSAtom name, JSValueConst value);
+
+// Calls `callback` with the name and the current value of every variable captured by the
+// closure `func`, without running any JavaScript.
+//
+// Returns the number of captured variables, or -1 if `func` is not a function defined in
+// JavaScript.
+int rquickjs_get_closure_vars(JSContext *ctx, JSValueConst func,
+                              rquickjs_closure_var_func *callback, void *opaque);
+
+// Stores up to `len` of the contexts of the runtime in `contexts`, in order of creation.
+//
+// Returns the number of contexts of the runtime, which may be larger than `len`.
+int rquickjs_get_contexts(JSRuntime *rt, JSContext **contexts, int len);
+
+// Returns a new private symbol atom, which can be used as a property key which is invisible to
+// JavaScript. Returns `JS_ATOM_NULL` if out of memory.
+JSAtom rquickjs_new_private_atom(JSRuntime *rt, const char *name);
+
+// Returns the function called by the engine to prepare the stack of a new error, which is the
+// value of `Error.prepareStackTrace` unless the accessor was replaced.
+JSValue rquickjs_get_prepare_stack_trace(JSContext *ctx);
+
+// Sets the function called by the engine to prepare the stack of a new error, taking ownership
+// of `value`.
+void rquickjs_set_prepare_stack_trace(JSContext *ctx, JSValue value);
+
+// The data of a `CallSite` object, as passed to `Error.prepareStackTrace`.
+typedef struct rquickjs_call_site {
+    // The name of the function, null for anonymous functions. Not duplicated.
+    JSValue function_name;
+    // The name of the script or module, null for native functions. Not duplicated.
+    JSValue file_name;
+    // The line and column number, starting at 1, -1 if unknown.
+    int line;
+    int column;
+    bool is_native;
+    // Whether the frame is a call, false for the location of a syntax error.
+    bool has_function;
+} rquickjs_call_site;
+
+// Reads the data of a `CallSite` object without running any JavaScript.
+//
+// Returns -1 if `value` is not a `CallSite`.
+int rquickjs_get_call_site(JSValueConst value, rquickjs_call_site *site);
+
+// Called with a frame of the stack. The strings are UTF-8 and null if unknown, the line and
+// column start at 1 and are -1 if unknown.
+typedef void rquickjs_stack_frame_func(void *opaque, const char *function, const char *file,
+                                       int line, int column);
+
+// Calls `callback` with every frame of the currently executing code, innermost frame first,
+// without running any JavaScript and independent of `Error.stackTraceLimit`.
+//
+// Returns the number of frames.
+int rquickjs_get_stack(JSRuntime *rt, rquickjs_stack_frame_func *callback, void *opaque);
+
+// Returns the module named `name` which was declared in the context, or null if there is none.
+JSModuleDef *rquickjs_find_module(JSContext *ctx, JSAtom name);
+
+// Stores up to `len` of the specifiers of the modules requested by the static imports and
+// re-exports of `m` in `requests` and the modules they were resolved to in `modules`, in order
+// of appearance. Requests which are not resolved yet have a null module. The atoms are not
+// duplicated.
+//
+// Returns the number of requests, which may be larger than `len`.
+int rquickjs_get_module_requests(JSModuleDef *m, JSAtom *requests, JSModuleDef **modules,
+                                 int len);
+
+// Sets the module the request at `index` of `m` resolves to. Must be called before `m` is
+// linked.
+//
+// Returns -1 if `m` has no such request.
+int rquickjs_set_module_request(JSModuleDef *m, int index, JSModuleDef *module);
+
+// Operations of the intrinsic `Map` and `Set` classes which call the engine directly instead of
+// looking up the global constructor or the methods of the prototype. `set` selects `Set` instead
+// of `Map`, which uses the key as the value. Throw a `TypeError` if `map` is of the wrong class.
+JSValue rquickjs_map_new(JSContext *ctx, bool set);
+JSValue rquickjs_map_size(JSContext *ctx, JSValueConst map, bool set);
+JSValue rquickjs_map_get(JSContext *ctx, JSValueConst map, JSValueConst key);
+JSValue rquickjs_map_set(JSContext *ctx, JSValueConst map, JSValueConst key, JSValueConst value,
+                         bool set);
+JSValue rquickjs_map_has(JSContext *ctx, JSValueConst map, JSValueConst key, bool set);
+JSValue rquickjs_map_delete(JSContext *ctx, JSValueConst map, JSValueConst key, bool set);
+JSValue rquickjs_map_clear(JSContext *ctx, JSValueConst map, bool set);
+
+// Returns an iterator over the `[key, value]` entries of a map or the values of a set.
+JSValue rquickjs_map_iterator(JSContext *ctx, JSValueConst map, bool set);
+
+// Returns the next item of an iterator returned by `rquickjs_map_iterator`, or sets `done` once
+// the iterator is finished.
+JSValue rquickjs_map_iterator_next(JSContext *ctx, JSValueConst iter, bool set, bool *done);
+
+// Reads the time value of a `Date` in milliseconds since the epoch without calling
+// `Date.prototype.getTime`.
+//
+// Returns -1 and throws a `TypeError` if `date` is not a `Date`.
+int rquickjs_date_get_time(JSContext *ctx, JSValueConst date, double *time);
+
+// Calls the intrinsic `RegExp` constructor, independent of the global `RegExp`.
+JSValue rquickjs_regexp_new(JSContext *ctx, JSValueConst pattern, JSValueConst flags);
+
+// Calls the intrinsic `RegExp.prototype.exec`, independent of the properties of `regexp`.
+JSValue rquickjs_regexp_exec(JSContext *ctx, JSValueConst regexp, JSValueConst text);
+
+// Calls the intrinsic `DataView` constructor, independent of the global `DataView`. `length` may
+// be undefined to cover the rest of the buffer.
+JSValue rquickjs_dataview_new(JSContext *ctx, JSValueConst buffer, JSValueConst offset,
+                              JSValueConst length);
+
+#define RQUICKJS_REFLECT_GET 0
+#define RQUICKJS_REFLECT_SET 1
+#define RQUICKJS_REFLECT_HAS 2
+#define RQUICKJS_REFLECT_DELETE_PROPERTY 3
+#define RQUICKJS_REFLECT_OWN_KEYS 4
+#define RQUICKJS_REFLECT_GET_OWN_PROPERTY_DESCRIPTOR 5
+#define RQUICKJS_REFLECT_APPLY 6
+#define RQUICKJS_REFLECT_CONSTRUCT 7
+
+// Calls the intrinsic `Reflect` function selected by `op` with the given arguments, independent
+// of the global `Reflect`. `argv` must hold the arguments expected by the function.
+JSValue rquickjs_reflect(JSContext *ctx, int op, int argc, JSValueConst *argv);
+
+#endif
@@ -0,0 +1,197 @@
+// Declarations of the extensions to the QuickJS API in `extensions.c`.
+
+pub type rquickjs_closure_var_func = ::core::option::Option<
+    unsafe extern "C" fn(opaque: *mut ::core::ffi::c_void, name: JSAtom, value: JSValue),
+>;
+
+/// The data of a `CallSite` object, as passed to `Error.prepareStackTrace`.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct rquickjs_call_site {
+    /// The name of the function, null for anonymous functions. Not duplicated.
+    pub function_name: JSValue,
+    /// The name of the script or module, null for native functions. Not duplicated.
+    pub file_name: JSValue,
+    /// The line number, starting at 1, -1 if unknown.
+    pub line: ::core::ffi::c_int,
+    /// The column number, starting at 1, -1 if unknown.
+    pub column: ::core::ffi::c_int,
+    pub is_native: bool,
+    /// Whether the frame is a call, false for the location of a syntax error.
+    pub has_function: bool,
+}
+
+/// Called with a frame of the stack. The strings are UTF-8 and null if unknown, the line and
+/// column start at 1 and are -1 if unknown.
+pub type rquickjs_stack_frame_func = ::core::option::Option<
+    unsafe extern "C" fn(
+        opaque: *mut ::core::ffi::c_void,
+        function: *const ::core::ffi::c_char,
+        file: *const ::core::ffi::c_char,
+        line: ::core::ffi::c_int,
+        column: ::core::ffi::c_int,
+    ),
+>;
+
+pub const RQUICKJS_REFLECT_GET: ::core::ffi::c_int = 0;
+pub const RQUICKJS_REFLECT_SET: ::core::ffi::c_int = 1;
+pub const RQUICKJS_REFLECT_HAS: ::core::ffi::c_int = 2;
+pub const RQUICKJS_REFLECT_DELETE_PROPERTY: ::core::ffi::c_int = 3;
+pub const RQUICKJS_REFLECT_OWN_KEYS: ::core::ffi::c_int = 4;
+pub const RQUICKJS_REFLECT_GET_OWN_PROPERTY_DESCRIPTOR: ::core::ffi::c_int = 5;
+pub const RQUICKJS_REFLECT_APPLY: ::core::ffi::c_int = 6;
+pub const RQUICKJS_REFLECT_CONSTRUCT: ::core::ffi::c_int = 7;
+
+extern "C" {
+    /// Calls `callback` with the name and the current value of every variable captured by the
+    /// closure `func`, without running any JavaScript.
+    ///
+    /// Returns the number of captured variables, or -1 if `func` is not a function defined in
+    /// JavaScript.
+    pub fn rquickjs_get_closure_vars(
+        ctx: *mut JSContext,
+        func: JSValue,
+        callback: rquickjs_closure_var_func,
+        opaque: *mut ::core::ffi::c_void,
+    ) -> ::core::ffi::c_int;
+
+    /// Stores up to `len` of the contexts of the runtime in `contexts`, in order of creation.
+    ///
+    /// Returns the number of contexts of the runtime, which may be larger than `len`.
+    pub fn rquickjs_get_contexts(
+        rt: *mut JSRuntime,
+        contexts: *mut *mut JSContext,
+        len: ::core::ffi::c_int,
+    ) -> ::core::ffi::c_int;
+
+    /// Returns a new private symbol atom, which can be used as a property key which is invisible
+    /// to JavaScript. Returns `JS_ATOM_NULL` if out of memory.
+    pub fn rquickjs_new_private_atom(
+        rt: *mut JSRuntime,
+        name: *const ::core::ffi::c_char,
+    ) -> JSAtom;
+
+    /// Returns the function called by the engine to prepare the stack of a new error, which is
+    /// the value of `Error.prepareStackTrace` unless the accessor was replaced.
+    pub fn rquickjs_get_prepare_stack_trace(ctx: *mut JSContext) -> JSValue;
+
+    /// Sets the function called by the engine to prepare the stack of a new error, taking
+    /// ownership of `value`.
+    pub fn rquickjs_set_prepare_stack_trace(ctx: *mut JSContext, value: JSValue);
+
+    /// Reads the data of a `CallSite` object without running any JavaScript.
+    ///
+    /// Returns -1 if `value` is not a `CallSite`.
+    pub fn rquickjs_get_call_site(
+        value: JSValue,
+        site: *mut rquickjs_call_site,
+    ) -> ::core::ffi::c_int;
+
+    /// Calls `callback` with every frame of the currently executing code, innermost frame first,
+    /// without running any JavaScript and independent of `Error.stackTraceLimit`.
+    ///
+    /// Returns the number of frames.
+    pub fn rquickjs_get_stack(
+        rt: *mut JSRuntime,
+        callback: rquickjs_stack_frame_func,
+        opaque: *mut ::core::ffi::c_void,
+    ) -> ::core::ffi::c_int;
+
+    /// Returns the module named `name` which was declared in the context, or null if there is
+    /// none.
+    pub fn rquickjs_find_module(ctx: *mut JSContext, name: JSAtom) -> *mut JSModuleDef;
+
+    /// Stores up to `len` of the specifiers of the modules requested by the static imports and
+    /// re-exports of `m` in `requests` and the modules they were resolved to in `modules`, in
+    /// order of appearance. Requests which are not resolved yet have a null module. The atoms
+    /// are not duplicated.
+    ///
+    /// Returns the number of requests, which may be larger than `len`.
+    pub fn rquickjs_get_module_requests(
+        m: *mut JSModuleDef,
+        requests: *mut JSAtom,
+        modules: *mut *mut JSModuleDef,
+        len: ::core::ffi::c_int,
+    ) -> ::core::ffi::c_int;
+
+    /// Sets the module the request at `index` of `m` resolves to. Must be called before `m` is
+    /// linked.
+    ///
+    /// Returns -1 if `m` has no such request.
+    pub fn rquickjs_set_module_request(
+        m: *mut JSModuleDef,
+        index: ::core::ffi::c_int,
+        module: *mut JSModuleDef,
+    ) -> ::core::ffi::c_int;
+
+    /// Operations of the intrinsic `Map` and `Set` classes which call the engine directly
+    /// instead of looking up the global constructor or the methods of the prototype. `set`
+    /// selects `Set` instead of `Map`, which uses the key as the value. Throw a `TypeError` if
+    /// `map` is of the wrong class.
+    pub fn rquickjs_map_new(ctx: *mut JSContext, set: bool) -> JSValue;
+    pub fn rquickjs_map_size(ctx: *mut JSContext, map: JSValue, set: bool) -> JSValue;
+    pub fn rquickjs_map_get(ctx: *mut JSContext, map: JSValue, key: JSValue) -> JSValue;
+    pub fn rquickjs_map_set(
+        ctx: *mut JSContext,
+        map: JSValue,
+        key: JSValue,
+        value: JSValue,
+        set: bool,
+    ) -> JSValue;
+    pub fn rquickjs_map_has(ctx: *mut JSContext, map: JSValue, key: JSValue, set: bool)
+        -> JSValue;
+    pub fn rquickjs_map_delete(
+        ctx: *mut JSContext,
+        map: JSValue,
+        key: JSValue,
+        set: bool,
+    ) -> JSValue;
+    pub fn rquickjs_map_clear(ctx: *mut JSContext, map: JSValue, set: bool) -> JSValue;
+
+    /// Returns an iterator over the `[key, value]` entries of a map or the values of a set.
+    pub fn rquickjs_map_iterator(ctx: *mut JSContext, map: JSValue, set: bool) -> JSValue;
+
+    /// Returns the next item of an iterator returned by `rquickjs_map_iterator`, or sets `done`
+    /// once the iterator is finished.
+    pub fn rquickjs_map_iterator_next(
+        ctx: *mut JSContext,
+        iter: JSValue,
+        set: bool,
+        done: *mut bool,
+    ) -> JSValue;
+
+    /// Reads the time value of a `Date` in milliseconds since the epoch without calling
+    /// `Date.prototype.getTime`.
+    ///
+    /// Returns -1 and throws a `TypeError` if `date` is not a `Date`.
+    pub fn rquickjs_date_get_time(
+        ctx: *mut JSContext,
+        date: JSValue,
+        time: *mut f64,
+    ) -> ::core::ffi::c_int;
+
+    /// Calls the intrinsic `RegExp` constructor, independent of the global `RegExp`.
+    pub fn rquickjs_regexp_new(ctx: *mut JSContext, pattern: JSValue, flags: JSValue) -> JSValue;
+
+    /// Calls the intrinsic `RegExp.prototype.exec`, independent of the properties of `regexp`.
+    pub fn rquickjs_regexp_exec(ctx: *mut JSContext, regexp: JSValue, text: JSValue) -> JSValue;
+
+    /// Calls the intrinsic `DataView` constructor, independent of the global `DataView`.
+    /// `length` may be undefined to cover the rest of the buffer.
+    pub fn rquickjs_dataview_new(
+        ctx: *mut JSContext,
+        buffer: JSValue,
+        offset: JSValue,
+        length: JSValue,
+    ) -> JSValue;
+
+    /// Calls the intrinsic `Reflect` function selected by `op` with the given arguments,
+    /// independent of the global `Reflect`. `argv` must hold the arguments expected by the
+    /// function.
+    pub fn rquickjs_reflect(
+        ctx: *mut JSContext,
+        op: ::core::ffi::c_int,
+        argc: ::core::ffi::c_int,
+        argv: *mut JSValue,
+    ) -> JSValue;
+}
@@ -9,7 +9,7 @@ pub async fn test() {
     async_with!(ctx => |ctx|{
         ctx.spawn(async move {
             *var_ref += 1;
-        })
+        });
     })
     .await
 }
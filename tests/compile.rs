@@ -2,6 +2,18 @@
 #[path = "macros/pass_class.rs"]
 pub mod pass_class;
 
+#[cfg(target_arch = "wasm32")]
+#[path = "macros/pass_convert.rs"]
+pub mod pass_convert;
+
+#[cfg(target_arch = "wasm32")]
+#[path = "macros/pass_exotic.rs"]
+pub mod pass_exotic;
+
+#[cfg(target_arch = "wasm32")]
+#[path = "macros/pass_extends.rs"]
+pub mod pass_extends;
+
 #[cfg(target_arch = "wasm32")]
 #[path = "macros/pass_method.rs"]
 pub mod pass_method;
@@ -17,7 +29,9 @@ pub mod pass_trace;
 #[cfg(feature = "macro")]
 mod macro_tests {
     #[cfg(target_arch = "wasm32")]
-    use crate::{pass_class, pass_method, pass_module, pass_trace};
+    use crate::{
+        pass_class, pass_convert, pass_exotic, pass_extends, pass_method, pass_module, pass_trace,
+    };
 
     #[cfg(not(target_arch = "wasm32"))]
     #[test]
@@ -38,6 +52,24 @@ mod macro_tests {
         pass_class::main();
     }
 
+    #[cfg(target_arch = "wasm32")]
+    #[test]
+    fn macros_pass_convert() {
+        pass_convert::main();
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    #[test]
+    fn macros_pass_exotic() {
+        pass_exotic::main();
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    #[test]
+    fn macros_pass_extends() {
+        pass_extends::main();
+    }
+
     #[cfg(target_arch = "wasm32")]
     #[test]
     fn macros_pass_method() {
@@ -0,0 +1,231 @@
+use rquickjs::{CatchResultExt, Context, FromJs, IntoJs, Object, Runtime, Value};
+
+#[derive(FromJs, IntoJs, Debug, PartialEq)]
+#[qjs(rename_all = "camelCase")]
+pub struct Person {
+    first_name: String,
+    #[qjs(rename = "years")]
+    age: u32,
+    #[qjs(default)]
+    tags: Vec<String>,
+    #[qjs(optional)]
+    nickname: Option<String>,
+    #[qjs(skip)]
+    cached: u64,
+}
+
+#[derive(FromJs, IntoJs, Debug, PartialEq)]
+pub struct Point(f64, f64);
+
+#[derive(FromJs, IntoJs, Debug, PartialEq)]
+#[qjs(transparent)]
+pub struct Id(u32);
+
+#[derive(FromJs, IntoJs, Debug, PartialEq)]
+pub struct Single(u32);
+
+#[derive(FromJs, IntoJs, Debug, PartialEq)]
+#[qjs(transparent)]
+pub struct Name {
+    value: String,
+    #[qjs(skip)]
+    cached: u64,
+}
+
+/// Fails with an error which isn't a type mismatch for negative numbers.
+#[derive(Debug, PartialEq)]
+pub struct Positive(f64);
+
+impl<'js> FromJs<'js> for Positive {
+    fn from_js(ctx: &rquickjs::Ctx<'js>, value: Value<'js>) -> rquickjs::Result<Self> {
+        let value = f64::from_js(ctx, value)?;
+        if value < 0.0 {
+            return Err(rquickjs::Error::Unknown);
+        }
+        Ok(Positive(value))
+    }
+}
+
+#[derive(FromJs, Debug, PartialEq)]
+#[qjs(untagged)]
+pub enum Amount {
+    Positive(Positive),
+    Text(String),
+}
+
+#[derive(FromJs, IntoJs, Debug, PartialEq)]
+pub struct Wrapper<T> {
+    inner: T,
+}
+
+#[derive(FromJs, IntoJs)]
+pub struct WithObject<'js> {
+    object: Object<'js>,
+}
+
+#[derive(FromJs, IntoJs, Debug, PartialEq)]
+pub enum External {
+    Unit,
+    Newtype(u32),
+    Tuple(u32, String),
+    Struct {
+        value: bool,
+    },
+    #[qjs(rename = "renamed")]
+    Renamed,
+}
+
+#[derive(FromJs, IntoJs, Debug, PartialEq)]
+#[qjs(tag = "type", rename_all = "camelCase")]
+pub enum Internal {
+    Unit,
+    Newtype(Person),
+    #[qjs(rename_all = "camelCase")]
+    StructVariant {
+        some_value: u32,
+    },
+}
+
+#[derive(FromJs, IntoJs, Debug, PartialEq)]
+#[qjs(untagged)]
+pub enum Untagged {
+    Number(f64),
+    Text(String),
+    Point { x: f64, y: f64 },
+    Nothing,
+}
+
+pub fn main() {
+    let rt = Runtime::new().unwrap();
+    let ctx = Context::full(&rt).unwrap();
+
+    ctx.with(|ctx| {
+        let person: Person = ctx
+            .eval("({ firstName: 'Ada', years: 36, cached: 10 })")
+            .catch(&ctx)
+            .unwrap();
+        assert_eq!(
+            person,
+            Person {
+                first_name: "Ada".to_string(),
+                age: 36,
+                tags: Vec::new(),
+                nickname: None,
+                cached: 0,
+            }
+        );
+
+        ctx.globals().set("person", person).unwrap();
+        let ok: bool = ctx
+            .eval(
+                r#"
+                person.firstName === "Ada" &&
+                person.years === 36 &&
+                person.tags.length === 0 &&
+                !("nickname" in person) &&
+                !("cached" in person)
+                "#,
+            )
+            .catch(&ctx)
+            .unwrap();
+        assert!(ok);
+
+        let res = Person::from_js(&ctx, ctx.eval("({ firstName: 'Ada' })").unwrap());
+        assert!(res.is_err());
+
+        let point: Point = ctx.eval("[1, 2]").unwrap();
+        assert_eq!(point, Point(1.0, 2.0));
+        let id: Id = ctx.eval("7").unwrap();
+        assert_eq!(id, Id(7));
+        let value = Id(7).into_js(&ctx).unwrap();
+        assert_eq!(value.as_int(), Some(7));
+
+        let single: Single = ctx.eval("[7]").unwrap();
+        assert_eq!(single, Single(7));
+        assert!(ctx.eval::<Single, _>("7").is_err());
+        let value = Single(7).into_js(&ctx).unwrap();
+        assert_eq!(value.as_array().unwrap().get::<u32>(0).unwrap(), 7);
+
+        let name: Name = ctx.eval("'ada'").unwrap();
+        assert_eq!(
+            name,
+            Name {
+                value: "ada".to_string(),
+                cached: 0,
+            }
+        );
+        let value = name.into_js(&ctx).unwrap();
+        assert_eq!(value.as_string().unwrap().to_string().unwrap(), "ada");
+
+        let wrapper: Wrapper<u32> = ctx.eval("({ inner: 3 })").unwrap();
+        assert_eq!(wrapper, Wrapper { inner: 3 });
+
+        let with_object: WithObject = ctx.eval("({ object: { a: 1 } })").unwrap();
+        assert_eq!(with_object.object.get::<_, i32>("a").unwrap(), 1);
+
+        for (source, expected) in [
+            ("'Unit'", External::Unit),
+            ("({ Newtype: 1 })", External::Newtype(1)),
+            ("({ Tuple: [2, 'a'] })", External::Tuple(2, "a".to_string())),
+            (
+                "({ Struct: { value: true } })",
+                External::Struct { value: true },
+            ),
+            ("'renamed'", External::Renamed),
+        ] {
+            let value: External = ctx.eval(source).catch(&ctx).unwrap();
+            assert_eq!(value, expected);
+            let value = expected.into_js(&ctx).unwrap();
+            assert_eq!(
+                External::from_js(&ctx, value).unwrap(),
+                External::from_js(&ctx, ctx.eval(source).unwrap()).unwrap()
+            );
+        }
+        assert!(ctx.eval::<External, _>("'Other'").is_err());
+        assert!(ctx
+            .eval::<External, _>("({ Newtype: 1, Unit: null })")
+            .is_err());
+
+        let internal: Internal = ctx
+            .eval("({ type: 'structVariant', someValue: 5 })")
+            .catch(&ctx)
+            .unwrap();
+        assert_eq!(internal, Internal::StructVariant { some_value: 5 });
+        let internal: Internal = ctx
+            .eval("({ type: 'newtype', firstName: 'Bob', years: 1 })")
+            .catch(&ctx)
+            .unwrap();
+        ctx.globals().set("internal", internal).unwrap();
+        let ok: bool = ctx
+            .eval("internal.type === 'newtype' && internal.firstName === 'Bob'")
+            .unwrap();
+        assert!(ok);
+        let unit = Internal::Unit.into_js(&ctx).unwrap();
+        let unit = unit.into_object().unwrap();
+        assert_eq!(unit.get::<_, String>("type").unwrap(), "unit");
+
+        let values: Vec<Untagged> = ctx
+            .eval("[1.5, 'text', { x: 1, y: 2 }, null]")
+            .catch(&ctx)
+            .unwrap();
+        assert_eq!(
+            values,
+            vec![
+                Untagged::Number(1.5),
+                Untagged::Text("text".to_string()),
+                Untagged::Point { x: 1.0, y: 2.0 },
+                Untagged::Nothing,
+            ]
+        );
+        let value: Value = Untagged::Nothing.into_js(&ctx).unwrap();
+        assert!(value.is_null());
+        assert!(ctx.eval::<Untagged, _>("true").is_err());
+
+        let amount: Amount = ctx.eval("'all'").unwrap();
+        assert_eq!(amount, Amount::Text("all".to_string()));
+        let amount: Amount = ctx.eval("2").unwrap();
+        assert_eq!(amount, Amount::Positive(Positive(2.0)));
+        let err = ctx.eval::<Amount, _>("-2").unwrap_err();
+        assert!(matches!(err, rquickjs::Error::Unknown), "{err}");
+    })
+}
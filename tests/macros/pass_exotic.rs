@@ -0,0 +1,129 @@
+use rquickjs::{
+    atom::PredefinedAtom,
+    class::{Exotic, PropertyDescriptor, Trace},
+    Atom, CatchResultExt, Class, Context, Ctx, IntoJs, JsLifetime, Result, Runtime, Value,
+};
+
+#[derive(Trace, JsLifetime)]
+#[rquickjs::class(exotic)]
+pub struct Bytes {
+    #[qjs(skip_trace)]
+    data: Vec<u8>,
+}
+
+#[rquickjs::methods]
+impl Bytes {
+    #[qjs(get)]
+    pub fn length(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn sum(&self) -> u32 {
+        self.data.iter().map(|x| *x as u32).sum()
+    }
+}
+
+impl Bytes {
+    fn index(&self, key: &Atom) -> Result<Option<usize>> {
+        let idx = key.to_string()?.parse::<usize>().ok();
+        Ok(idx.filter(|idx| *idx < self.data.len()))
+    }
+}
+
+impl<'js> Exotic<'js> for Bytes {
+    fn get_own_property(
+        &self,
+        ctx: &Ctx<'js>,
+        key: Atom<'js>,
+    ) -> Result<Option<PropertyDescriptor<'js>>> {
+        let Some(idx) = self.index(&key)? else {
+            return Ok(None);
+        };
+        let value = self.data[idx].into_js(ctx)?;
+        Ok(Some(PropertyDescriptor::value(value)))
+    }
+
+    fn get_own_property_names(&self, ctx: &Ctx<'js>) -> Result<Vec<Atom<'js>>> {
+        (0..self.data.len())
+            .map(|idx| Atom::from_u32(ctx.clone(), idx as u32))
+            .collect()
+    }
+
+    fn set_property(
+        &mut self,
+        _ctx: &Ctx<'js>,
+        key: Atom<'js>,
+        value: Value<'js>,
+        _receiver: Value<'js>,
+    ) -> Result<Option<bool>> {
+        let Some(idx) = self.index(&key)? else {
+            return Ok(None);
+        };
+        let Some(value) = value.as_int().and_then(|x| u8::try_from(x).ok()) else {
+            return Ok(Some(false));
+        };
+        self.data[idx] = value;
+        Ok(Some(true))
+    }
+
+    fn delete_property(&mut self, _ctx: &Ctx<'js>, key: Atom<'js>) -> Result<Option<bool>> {
+        Ok(self.index(&key)?.map(|_| false))
+    }
+
+    fn has_property(&self, ctx: &Ctx<'js>, key: Atom<'js>) -> Result<Option<bool>> {
+        if key == Atom::from_predefined(ctx.clone(), PredefinedAtom::SymbolIterator) {
+            return Ok(Some(false));
+        }
+        Ok(None)
+    }
+}
+
+pub fn main() {
+    let rt = Runtime::new().unwrap();
+    let ctx = Context::full(&rt).unwrap();
+
+    ctx.with(|ctx| {
+        let cls = Class::instance(
+            ctx.clone(),
+            Bytes {
+                data: vec![1, 2, 3],
+            },
+        )
+        .unwrap();
+        ctx.globals().set("b", cls.clone()).unwrap();
+        ctx.eval::<(), _>(
+            r#"
+            if(b[0] !== 1 || b[2] !== 3 || b[3] !== undefined){
+                throw new Error(1)
+            }
+            if(b.length !== 3 || b.sum() !== 6){
+                throw new Error(2)
+            }
+            if(Object.keys(b).join() !== "0,1,2" || !(1 in b) || (5 in b)){
+                throw new Error(3)
+            }
+            b[1] = 20;
+            if(Reflect.set(b, 2, "not a byte")){
+                throw new Error(4)
+            }
+            if(b[1] !== 20 || b[2] !== 3){
+                throw new Error(4)
+            }
+            if(Reflect.deleteProperty(b, 0) || b[0] !== 1){
+                throw new Error(5)
+            }
+            b.other = "ordinary";
+            if(b.other !== "ordinary" || !Object.keys(b).includes("other")){
+                throw new Error(6)
+            }
+            if(Symbol.iterator in b){
+                throw new Error(7)
+            }
+        "#,
+        )
+        .catch(&ctx)
+        .unwrap();
+
+        assert_eq!(cls.borrow().data, [1, 20, 3]);
+    });
+}
@@ -0,0 +1,96 @@
+use rquickjs::{class::Trace, CatchResultExt, Class, Context, JsLifetime, Runtime};
+
+#[derive(Trace, JsLifetime)]
+#[rquickjs::class]
+pub struct Animal {
+    #[qjs(get)]
+    name: String,
+    legs: u32,
+}
+
+#[rquickjs::methods]
+impl Animal {
+    #[qjs(constructor)]
+    pub fn new(name: String) -> Self {
+        Animal { name, legs: 4 }
+    }
+
+    pub fn describe(&self) -> String {
+        format!("{} has {} legs", self.name, self.legs)
+    }
+}
+
+#[derive(Trace, JsLifetime)]
+#[rquickjs::class(extends = Animal)]
+pub struct Dog {
+    animal: Animal,
+    #[qjs(get, set)]
+    good: bool,
+}
+
+#[rquickjs::methods]
+impl Dog {
+    #[qjs(constructor)]
+    pub fn new(name: String) -> Self {
+        Dog {
+            animal: Animal::new(name),
+            good: true,
+        }
+    }
+
+    pub fn bark(&self) -> String {
+        format!("{} barks", self.animal.name)
+    }
+}
+
+pub fn main() {
+    let rt = Runtime::new().unwrap();
+    let ctx = Context::full(&rt).unwrap();
+
+    ctx.with(|ctx| {
+        Class::<Animal>::define(&ctx.globals()).unwrap();
+        Class::<Dog>::define(&ctx.globals()).unwrap();
+
+        let dog = ctx
+            .eval::<Class<Dog>, _>(
+                r#"
+                const dog = new Dog("Rex");
+                if(!(dog instanceof Dog) || !(dog instanceof Animal)){
+                    throw new Error(1)
+                }
+                if(dog.name !== "Rex" || !dog.good || dog.bark() !== "Rex barks"){
+                    throw new Error(2)
+                }
+                if(dog.describe() !== "Rex has 4 legs"){
+                    throw new Error(3)
+                }
+                if(new Animal("Tom") instanceof Dog){
+                    throw new Error(4)
+                }
+                class Puppy extends Dog {
+                    play(){
+                        return this.bark() + " and plays"
+                    }
+                }
+                const puppy = new Puppy("Max");
+                if(!(puppy instanceof Animal) || puppy.play() !== "Max barks and plays"){
+                    throw new Error(5)
+                }
+                dog
+            "#,
+            )
+            .catch(&ctx)
+            .unwrap();
+
+        let animal = Class::<Animal>::from_value(&dog.clone().into_value()).unwrap();
+        animal.borrow_mut().legs = 3;
+        assert_eq!(dog.borrow().animal.describe(), "Rex has 3 legs");
+
+        let animal = dog.clone().upcast::<Animal>().unwrap();
+        assert_eq!(animal.borrow().name, "Rex");
+        assert!(dog.as_class::<Animal>().is_some());
+
+        let puppy: Class<Dog> = ctx.eval("puppy").catch(&ctx).unwrap();
+        assert_eq!(puppy.borrow().animal.name, "Max");
+    });
+}
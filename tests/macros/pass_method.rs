@@ -40,11 +40,32 @@ impl TestClass {
         self.another_value = v
     }
 
+    #[qjs(set, rename = "both")]
+    pub fn set_both(&mut self, v: u32) {
+        self.value = v;
+        self.another_value = v;
+    }
+
     #[qjs(static)]
     pub fn compare(a: &Self, b: &Self) -> bool {
         a.value == b.value && a.another_value == b.another_value
     }
 
+    #[qjs(static, rename = "fromJSON")]
+    pub fn from_json(json: String) -> Self {
+        TestClass::new(json.parse().unwrap())
+    }
+
+    #[qjs(static, get, rename = "DEFAULT_VALUE")]
+    pub fn default_value() -> u32 {
+        7
+    }
+
+    #[qjs(symbol = "toPrimitive")]
+    pub fn to_primitive(&self, _hint: String) -> u32 {
+        self.value
+    }
+
     #[qjs(skip)]
     pub fn inner_function(&self) {}
 
@@ -113,6 +134,19 @@ pub fn main() {
             for(const v of t){
                 throw new Error("iterator should be done immediately")
             }
+            if(TestClass.DEFAULT_VALUE !== 7 || TestClass.fromJSON("3").value !== 3){
+                throw new Error(9)
+            }
+            if(TestClass.prototype.DEFAULT_VALUE !== undefined || t.fromJSON !== undefined){
+                throw new Error(10)
+            }
+            if(+t !== 5 || typeof t[Symbol.toPrimitive] !== "function"){
+                throw new Error(11)
+            }
+            nv.both = 8;
+            if(nv.value !== 8 || nv.anotherValue !== 8){
+                throw new Error(12)
+            }
         "#,
         )
         .catch(&ctx)